@@ -38,9 +38,11 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 extern crate failure;
+extern crate toml_query;
 
 extern crate libimagentryannotation;
 extern crate libimagentryedit;
+extern crate libimagentryquery;
 extern crate libimagerror;
 #[macro_use] extern crate libimagrt;
 extern crate libimagstore;
@@ -49,10 +51,19 @@ extern crate libimagutil;
 use std::io::Write;
 
 use failure::Error;
+use clap::ArgMatches;
+use toml_query::read::TomlValueReadTypeExt;
 
+use libimagentryannotation::anchor::Anchor;
+use libimagentryannotation::anchor::AnchoredAnnotation;
+use libimagentryannotation::anchor::relocate_anchors;
 use libimagentryannotation::annotateable::*;
 use libimagentryannotation::annotation_fetcher::*;
+use libimagentryannotation::annotation::Annotation;
+use libimagentryannotation::annotation::sort_into_threads;
 use libimagentryedit::edit::*;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
@@ -77,6 +88,7 @@ fn main() {
                 "add"    => add(&rt),
                 "remove" => remove(&rt),
                 "list"   => list(&rt),
+                "reply"  => reply(&rt),
                 other    => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-annotation", other, rt.cli())
@@ -91,23 +103,101 @@ fn main() {
 fn add(rt: &Runtime) {
     let scmd            = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
     let annotation_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
+    let author          = get_author(rt, scmd);
     let ids             = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
 
     ids.into_iter().for_each(|id| {
-        let _ = rt.store()
+        let mut entry = rt.store()
             .get(id.clone())
             .map_err_trace_exit_unwrap(1)
             .ok_or_else(|| EM::EntryNotFound(id.local_display_string()))
             .map_err(Error::from)
-            .map_err_trace_exit_unwrap(1)
+            .map_err_trace_exit_unwrap(1);
+
+        let anchor = if let Some(range) = scmd.value_of("lines") {
+            let (start, end) = ::ui::parse_line_range(range).unwrap(); // safed by clap
+            Some(Anchor::for_lines(&entry, start, end).map_err_trace_exit_unwrap(1))
+        } else if let Some(quote) = scmd.value_of("quote") {
+            Some(Anchor::for_quote(&entry, quote).map_err_trace_exit_unwrap(1))
+        } else {
+            None
+        };
+
+        let mut annotation = entry
             .annotate(rt.store(), annotation_name)
-            .map_err_trace_exit_unwrap(1)
-            .edit_content(&rt)
             .map_err_trace_exit_unwrap(1);
+
+        if let Some(anchor) = anchor {
+            let _ = annotation.set_anchor(&anchor).map_err_trace_exit_unwrap(1);
+        }
+
+        edit_annotation(rt, &mut annotation, author.as_ref());
     })
 
 }
 
+fn reply(rt: &Runtime) {
+    use libimagentryannotation::module_path::ModuleEntryPath;
+    use libimagstore::storeid::IntoStoreId;
+
+    let scmd       = rt.cli().subcommand_matches("reply").unwrap(); // safed by main()
+    let parent     = scmd.value_of("reply-to").unwrap(); // safed by clap
+    let reply_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
+    let author     = get_author(rt, scmd);
+
+    let parent_id  = ModuleEntryPath::new(parent).into_storeid().map_err_trace_exit_unwrap(1);
+    let mut reply  = rt.store()
+        .get(parent_id.clone())
+        .map_err_trace_exit_unwrap(1)
+        .ok_or_else(|| EM::EntryNotFound(parent_id.local_display_string()))
+        .map_err(Error::from)
+        .map_err_trace_exit_unwrap(1)
+        .reply(rt.store(), reply_name)
+        .map_err_trace_exit_unwrap(1);
+
+    edit_annotation(rt, &mut reply, author.as_ref());
+}
+
+/// Set the author of a (new) annotation and let the user edit the text of it
+fn edit_annotation(rt: &Runtime, annotation: &mut FileLockEntry, author: Option<&String>) {
+    if let Some(author) = author {
+        let _ = annotation.set_author(author).map_err_trace_exit_unwrap(1);
+    }
+
+    let _ = annotation
+        .edit_content_with_hook(&rt, |a| {
+            let lost = relocate_anchors(rt.store(), a)?;
+            if lost != 0 {
+                warn!("{} annotation anchor(s) in {} could not be found anymore", lost, a.get_location());
+            }
+            Ok(())
+        })
+        .map_err_trace_exit_unwrap(1);
+    let _ = annotation.touch().map_err_trace_exit_unwrap(1);
+
+    let _ = rt
+        .report_touched(annotation.get_location())
+        .map_err_trace_exit_unwrap(1);
+}
+
+/// Get the author for new annotations
+///
+/// Taken from the commandline, the `annotate.author` configuration setting or the `USER`
+/// environment variable, in this order.
+fn get_author(rt: &Runtime, scmd: &ArgMatches) -> Option<String> {
+    scmd.value_of("author")
+        .map(String::from)
+        .or_else(|| {
+            rt.config()
+                .and_then(|cfg| {
+                    cfg.read_string("annotate.author")
+                        .map_err(Error::from)
+                        .map_err_trace_exit_unwrap(1)
+                })
+        })
+        .or_else(|| ::std::env::var("USER").ok())
+}
+
 fn remove(rt: &Runtime) {
     let scmd            = rt.cli().subcommand_matches("remove").unwrap(); // safed by main()
     let annotation_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
@@ -149,13 +239,14 @@ fn remove(rt: &Runtime) {
 fn list(rt: &Runtime) {
     let scmd      = rt.cli().subcommand_matches("list").unwrap(); // safed by clap
     let with_text = scmd.is_present("list-with-text");
+    let query     = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
     let ids       = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
 
     if ids.len() != 0 {
         let _ = ids
             .into_iter()
             .for_each(|id| {
-                let _ = rt
+                let annotations = rt
                     .store()
                     .get(id.clone())
                     .map_err_trace_exit_unwrap(1)
@@ -164,37 +255,74 @@ fn list(rt: &Runtime) {
                     .map_err_trace_exit_unwrap(1)
                     .annotations(rt.store())
                     .map_err_trace_exit_unwrap(1)
-                    .enumerate()
-                    .map(|(i, a)| {
-                        list_annotation(&rt, i, a.map_err_trace_exit_unwrap(1), with_text)
-                    })
+                    .map(|a| a.map_err_trace_exit_unwrap(1))
+                    .filter(|a| entry_matches(query.as_ref(), a).map_err_trace_exit_unwrap(1))
                     .collect::<Vec<_>>();
+
+                list_annotations(&rt, annotations, with_text)
             });
     } else { // ids.len() == 0
         // show them all
-        let _ = rt
+        let annotations = rt
             .store()
             .all_annotations()
             .map_err_trace_exit_unwrap(1)
-            .enumerate()
-            .map(|(i, a)| {
-                list_annotation(&rt, i, a.map_err_trace_exit_unwrap(1), with_text)
-            })
+            .map(|a| a.map_err_trace_exit_unwrap(1))
+            .filter(|a| entry_matches(query.as_ref(), a).map_err_trace_exit_unwrap(1))
             .collect::<Vec<_>>();
+
+        list_annotations(&rt, annotations, with_text)
     }
 }
 
-fn list_annotation<'a>(rt: &Runtime, i: usize, a: FileLockEntry<'a>, with_text: bool) {
+/// List annotations as threads, in chronological order
+fn list_annotations<'a>(rt: &Runtime, annotations: Vec<FileLockEntry<'a>>, with_text: bool) {
+    sort_into_threads(annotations)
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .enumerate()
+        .for_each(|(i, (depth, a))| list_annotation(rt, i, depth, a, with_text))
+}
+
+fn list_annotation<'a>(rt: &Runtime, i: usize, depth: usize, a: FileLockEntry<'a>, with_text: bool) {
+    let indent  = "    ".repeat(depth);
+    let author  = a.get_author().map_err_trace_exit_unwrap(1).unwrap_or_else(|| String::from("unknown"));
+    let created = a.get_created()
+        .map_err_trace_exit_unwrap(1)
+        .map(|dt| dt.to_string())
+        .unwrap_or_else(|| String::from("unknown date"));
+    let anchor  = a.get_anchor()
+        .map_err_trace_exit_unwrap(1)
+        .map(|anchor| if anchor.is_lost() {
+            String::from(" | anchor lost")
+        } else {
+            format!(" | lines {}-{}", anchor.start(), anchor.end())
+        })
+        .unwrap_or_else(String::new);
+
     let _ = if with_text {
+        let text = a.get_content()
+            .lines()
+            .map(|line| format!("{}{}", indent, line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
         writeln!(rt.stdout(),
-                 "--- {i: >5} | {id}\n{text}\n\n",
+                 "{indent}--- {i: >5} | {id} | {author} | {created}{anchor}\n{text}\n\n",
+                 indent = indent,
                  i = i,
                  id = a.get_location(),
-                 text = a.get_content())
+                 author = author,
+                 created = created,
+                 anchor = anchor,
+                 text = text)
     } else {
-        writeln!(rt.stdout(), "{: >5} | {}", i, a.get_location())
+        writeln!(rt.stdout(), "{: >5} | {}{} | {} | {}{}", i, indent, a.get_location(), author, created, anchor)
     }
     .to_exit_code()
     .unwrap_or_exit();
-}
 
+    let _ = rt
+        .report_touched(a.get_location())
+        .map_err_trace_exit_unwrap(1);
+}
@@ -25,6 +25,8 @@ use libimagstore::storeid::StoreId;
 use libimagstore::storeid::IntoStoreId;
 use libimagrt::runtime::IdPathProvider;
 use libimagerror::trace::MapErrTrace;
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -45,6 +47,59 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .multiple(false)
                          .help("Name of the new annotation")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("author")
+                         .long("author")
+                         .short("a")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("The author of the annotation. Defaults to the 'annotate.author' setting or $USER")
+                         .value_name("AUTHOR"))
+                    .arg(Arg::with_name("lines")
+                         .long("lines")
+                         .short("l")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .conflicts_with("quote")
+                         .validator(is_line_range)
+                         .help("Anchor the annotation to a range of lines of the entry content, e.g. '3-5' or '7'")
+                         .value_name("START-END"))
+                    .arg(Arg::with_name("quote")
+                         .long("quote")
+                         .short("q")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Anchor the annotation to a text in the entry content")
+                         .value_name("TEXT"))
+                   )
+
+        .subcommand(SubCommand::with_name("reply")
+                    .about("Reply to an annotation")
+                    .version("0.1")
+                    .arg(Arg::with_name("reply-to")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("Name of the annotation to reply to")
+                         .value_name("ANNOTATION"))
+                    .arg(Arg::with_name("annotation_name")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("Name of the new annotation")
+                         .value_name("NAME"))
+                    .arg(Arg::with_name("author")
+                         .long("author")
+                         .short("a")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("The author of the annotation. Defaults to the 'annotate.author' setting or $USER")
+                         .value_name("AUTHOR"))
                    )
 
         .subcommand(SubCommand::with_name("remove")
@@ -90,9 +145,31 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .required(false)
                          .multiple(false)
                          .help("List annotations with text"))
+                    .arg(where_arg())
+                    .arg(saved_arg())
                    )
 }
 
+/// Parse a line range like "3-5" or "7" into a (start, end) tuple
+pub fn parse_line_range(s: &str) -> Option<(usize, usize)> {
+    use std::str::FromStr;
+
+    let mut parts = s.splitn(2, '-');
+    let start     = parts.next().and_then(|p| usize::from_str(p.trim()).ok())?;
+    let end       = match parts.next() {
+        Some(p) => usize::from_str(p.trim()).ok()?,
+        None    => start,
+    };
+
+    Some((start, end))
+}
+
+fn is_line_range(s: String) -> Result<(), String> {
+    parse_line_range(&s)
+        .map(|_| ())
+        .ok_or_else(|| format!("'{}' is not a line range like '3-5'", s))
+}
+
 pub struct PathProvider;
 impl IdPathProvider for PathProvider {
     fn get_ids(matches: &ArgMatches) -> Vec<StoreId> {
@@ -139,6 +216,11 @@ impl IdPathProvider for PathProvider {
                     .map_err_trace_exit_unwrap(1)
             },
 
+            ("reply", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
             (other, _) => {
                 error!("Not a known command: {}", other);
                 ::std::process::exit(1)
@@ -43,8 +43,11 @@ extern crate libimagerror;
 #[macro_use] extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimaginteraction;
+extern crate libimagentrylink;
+extern crate libimagentryquery;
 
 use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagrt::runtime::Runtime;
@@ -60,6 +63,10 @@ use libimagstore::iter::get::StoreIdGetIteratorExtension;
 use libimagerror::iter::TraceIterator;
 use libimagentrycategory::entry::EntryCategory;
 use libimagentrycategory::category::Category;
+use libimagentrycategory::iter::CategoryMatch;
+use libimagstore::store::FileLockEntry;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 fn main() {
     let version = make_imag_version!();
@@ -78,6 +85,11 @@ fn main() {
                 "create-category"   => create_category(&rt),
                 "delete-category"   => delete_category(&rt),
                 "list-categories"   => list_categories(&rt),
+                "reparent"          => reparent(&rt),
+                "rename"            => rename(&rt),
+                "add"               => add(&rt),
+                "remove"            => remove(&rt),
+                "migrate"           => migrate(&rt),
                 other               => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-category", other, rt.cli())
@@ -120,30 +132,138 @@ fn get(rt: &Runtime) {
             error!("Did not find one entry");
             ::std::process::exit(1)
         }))
-        .map(|entry| entry.get_category().map_err_trace_exit_unwrap(1))
-        .for_each(|name| {
-            let _ = writeln!(outlock, "{}", name).to_exit_code().unwrap_or_exit();
+        .map(|entry| entry.get_categories().map_err_trace_exit_unwrap(1))
+        .for_each(|names| {
+            let _ = writeln!(outlock, "{}", names.join(", ")).to_exit_code().unwrap_or_exit();
+        })
+}
+
+fn add(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
+    let name = scmd.value_of("add-name").map(String::from).unwrap(); // safed by clap
+    let sids = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    StoreIdIterator::new(Box::new(sids.into_iter().map(Ok)))
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Did not find one entry");
+            ::std::process::exit(1)
+        }))
+        .for_each(|mut entry| {
+            let _ = entry
+                .add_category_checked(rt.store(), &name)
+                .map_err_trace_exit_unwrap(1);
+        })
+}
+
+fn remove(rt: &Runtime) {
+    use libimagentrylink::internal::InternalLinker;
+
+    let scmd = rt.cli().subcommand_matches("remove").unwrap(); // safed by main()
+    let name = scmd.value_of("remove-name").map(String::from).unwrap(); // safed by clap
+    let sids = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    let mut category = rt.store()
+        .get_category_by_name(&name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No category named '{}'", name);
+            ::std::process::exit(1)
+        });
+
+    StoreIdIterator::new(Box::new(sids.into_iter().map(Ok)))
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Did not find one entry");
+            ::std::process::exit(1)
+        }))
+        .for_each(|mut entry| {
+            let _ = entry
+                .remove_category_value(&name)
+                .map_err_trace_exit_unwrap(1);
+            let _ = category
+                .remove_internal_link(&mut entry)
+                .map_err_trace_exit_unwrap(1);
         })
 }
 
+fn migrate(rt: &Runtime) {
+    let mut count = 0;
+
+    rt.store()
+        .entries()
+        .map_err_trace_exit_unwrap(1)
+        .without_store()
+        .trace_unwrap_exit(1)
+        .for_each(|id| {
+            // Migrate a copy first, so that only the entries which change are written back
+            let mut copy = rt.store().get_copy(id.clone()).map_err_trace_exit_unwrap(1);
+            if !copy.migrate_to_multiple_categories().map_err_trace_exit_unwrap(1) {
+                return;
+            }
+
+            let mut entry = rt.store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| {
+                    error!("Entry '{}' vanished while migrating", id);
+                    ::std::process::exit(1)
+                });
+
+            let _ = entry.migrate_to_multiple_categories().map_err_trace_exit_unwrap(1);
+            debug!("Migrated {}", entry.get_location());
+            count += 1;
+        });
+
+    info!("Migrated {} entries", count);
+}
+
 fn list_category(rt: &Runtime) {
-    let scmd = rt.cli().subcommand_matches("list-category").unwrap(); // safed by main()
-    let name = scmd.value_of("list-category-name").map(String::from).unwrap(); // safed by clap
+    let scmd  = rt.cli().subcommand_matches("list-category").unwrap(); // safed by main()
+    let names = scmd.values_of("list-category-name").unwrap().collect::<Vec<_>>(); // safed by clap
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
 
-    if let Some(category) = rt.store().get_category_by_name(&name).map_err_trace_exit_unwrap(1) {
-        let out         = rt.stdout();
-        let mut outlock = out.lock();
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+    let print       = |entry: Result<FileLockEntry, _>| {
+        let entry = entry.map_err_trace_exit_unwrap(1);
+        if entry_matches(query.as_ref(), &entry).map_err_trace_exit_unwrap(1) {
+            writeln!(outlock, "{}", entry.get_location())
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    };
 
+    if scmd.is_present("list-category-recursive") {
+        if names.len() != 1 {
+            error!("--recursive can only be used with exactly one category");
+            ::std::process::exit(1)
+        }
+
+        rt.store()
+            .get_entries_with_subcategories(names[0])
+            .map_err_trace_exit_unwrap(1)
+            .for_each(print)
+    } else if names.len() > 1 || scmd.is_present("list-category-all") {
+        let matching = if scmd.is_present("list-category-all") {
+            CategoryMatch::All
+        } else {
+            CategoryMatch::Any
+        };
+
+        rt.store()
+            .get_entries_in_categories(&names, matching)
+            .map_err_trace_exit_unwrap(1)
+            .for_each(print)
+    } else if let Some(category) = rt.store().get_category_by_name(names[0]).map_err_trace_exit_unwrap(1) {
         category
             .get_entries(rt.store())
             .map_err_trace_exit_unwrap(1)
-            .for_each(|entry| {
-                writeln!(outlock, "{}", entry.map_err_trace_exit_unwrap(1).get_location())
-                    .to_exit_code()
-                    .unwrap_or_exit();
-            })
+            .for_each(print)
     } else {
-        info!("No category named '{}'", name);
+        info!("No category named '{}'", names[0]);
         ::std::process::exit(1)
     }
 }
@@ -152,10 +272,31 @@ fn create_category(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("create-category").unwrap(); // safed by main()
     let name = scmd.value_of("create-category-name").map(String::from).unwrap(); // safed by clap
 
+    let parent = scmd.value_of("create-category-parent");
+
+    // Check the parent before creating anything, so we do not leave a category behind
+    if let Some(parent) = parent {
+        if !rt.store().category_exists(parent).map_err_trace_exit_unwrap(1) {
+            error!("Category does not exist: '{}'", parent);
+            ::std::process::exit(1)
+        }
+    }
+
     let _ = rt
         .store()
         .create_category(&name)
         .map_err_trace_exit_unwrap(1);
+
+    if let Some(parent) = parent {
+        if let Err(e) = rt.store().set_category_parent(&name, Some(parent)) {
+            trace_error(&e);
+            let _ = rt
+                .store()
+                .delete_category(&name)
+                .map_err_trace_exit_unwrap(1);
+            ::std::process::exit(1)
+        }
+    }
 }
 
 fn delete_category(rt: &Runtime) {
@@ -197,3 +338,25 @@ fn list_categories(rt: &Runtime) {
         })
 }
 
+fn reparent(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("reparent").unwrap(); // safed by main()
+    let name   = scmd.value_of("reparent-name").map(String::from).unwrap(); // safed by clap
+    let parent = scmd.value_of("reparent-parent");
+
+    let _ = rt
+        .store()
+        .set_category_parent(&name, parent)
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn rename(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("rename").unwrap(); // safed by main()
+    let old  = scmd.value_of("rename-old-name").map(String::from).unwrap(); // safed by clap
+    let new  = scmd.value_of("rename-new-name").map(String::from).unwrap(); // safed by clap
+
+    info!("Renaming category '{}' to '{}'", old, new);
+    let _ = rt
+        .store()
+        .rename_category(&old, &new)
+        .map_err_trace_exit_unwrap(1);
+}
@@ -25,6 +25,8 @@ use libimagstore::storeid::StoreId;
 use libimagstore::storeid::IntoStoreId;
 use libimagrt::runtime::IdPathProvider;
 use libimagerror::trace::MapErrTrace;
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -38,6 +40,14 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .multiple(false)
                          .help("The name of the new category")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("create-category-parent")
+                         .long("parent")
+                         .short("p")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Create the category as subcategory of this category")
+                         .value_name("PARENT"))
                    )
 
         .subcommand(SubCommand::with_name("delete-category")
@@ -63,9 +73,64 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .index(1)
                          .takes_value(true)
                          .required(true)
+                         .multiple(true)
+                         .help("The name of the category to list all entries for. If multiple categories are passed, entries in any of them are listed")
+                         .value_name("NAME"))
+                    .arg(Arg::with_name("list-category-all")
+                         .long("all")
+                         .short("a")
+                         .takes_value(false)
+                         .required(false)
                          .multiple(false)
-                         .help("The name of the category to list all entries for")
+                         .conflicts_with("list-category-recursive")
+                         .help("Only list entries which are in all of the passed categories"))
+                    .arg(Arg::with_name("list-category-recursive")
+                         .long("recursive")
+                         .short("r")
+                         .takes_value(false)
+                         .required(false)
+                         .multiple(false)
+                         .help("Also list the entries of all subcategories"))
+                    .arg(where_arg())
+                    .arg(saved_arg())
+                   )
+
+        .subcommand(SubCommand::with_name("reparent")
+                    .about("Move a category below another category")
+                    .version("0.1")
+                    .arg(Arg::with_name("reparent-name")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The name of the category to move")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("reparent-parent")
+                         .index(2)
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("The new parent category. If not passed, the category becomes a top-level category")
+                         .value_name("PARENT"))
+                   )
+
+        .subcommand(SubCommand::with_name("rename")
+                    .about("Rename a category and update all entries in it")
+                    .version("0.1")
+                    .arg(Arg::with_name("rename-old-name")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The name of the category to rename")
+                         .value_name("OLD"))
+                    .arg(Arg::with_name("rename-new-name")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The new name of the category")
+                         .value_name("NEW"))
                    )
 
         .subcommand(SubCommand::with_name("set")
@@ -88,6 +153,50 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .value_name("ID"))
                    )
 
+        .subcommand(SubCommand::with_name("add")
+                    .about("Add a category to entries, keeping their other categories")
+                    .version("0.1")
+                    .arg(Arg::with_name("add-name")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The name of the category to add")
+                         .value_name("NAME"))
+
+                    .arg(Arg::with_name("add-ids")
+                         .index(2)
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .help("The entries to add the category to")
+                         .value_name("ID"))
+                   )
+
+        .subcommand(SubCommand::with_name("remove")
+                    .about("Remove a category from entries, keeping their other categories")
+                    .version("0.1")
+                    .arg(Arg::with_name("remove-name")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The name of the category to remove")
+                         .value_name("NAME"))
+
+                    .arg(Arg::with_name("remove-ids")
+                         .index(2)
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .help("The entries to remove the category from")
+                         .value_name("ID"))
+                   )
+
+        .subcommand(SubCommand::with_name("migrate")
+                    .about("Migrate all entries from the single-category header to the multi-category header")
+                    .version("0.1"))
+
         .subcommand(SubCommand::with_name("get")
                     .about("Get the category of the entry")
                     .version("0.1")
@@ -125,6 +234,16 @@ impl IdPathProvider for PathProvider {
                 ::std::process::exit(1)
             },
 
+            ("reparent", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
+            ("rename", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
             ("set", Some(subm)) => {
                 subm.values_of("set-ids")
                     .ok_or_else(|| {
@@ -139,6 +258,39 @@ impl IdPathProvider for PathProvider {
                     .map_err_trace_exit_unwrap(1)
             },
 
+            ("migrate", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
+            ("add", Some(subm)) => {
+                subm.values_of("add-ids")
+                    .ok_or_else(|| {
+                        error!("No StoreId found");
+                        ::std::process::exit(1)
+                    })
+                    .unwrap()
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .map(|pb| pb.into_storeid())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err_trace_exit_unwrap(1)
+            },
+
+            ("remove", Some(subm)) => {
+                subm.values_of("remove-ids")
+                    .ok_or_else(|| {
+                        error!("No StoreId found");
+                        ::std::process::exit(1)
+                    })
+                    .unwrap()
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .map(|pb| pb.into_storeid())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err_trace_exit_unwrap(1)
+            },
+
             ("get", Some(subm)) => {
                 subm.values_of("get-ids")
                     .ok_or_else(|| {
@@ -37,7 +37,11 @@
 extern crate clap;
 #[macro_use] extern crate log;
 
+extern crate failure;
+
 extern crate libimagentryedit;
+extern crate libimagentryannotation;
+extern crate libimagentrymarkdown;
 extern crate libimagerror;
 #[macro_use] extern crate libimagrt;
 extern crate libimagstore;
@@ -45,12 +49,19 @@ extern crate libimagutil;
 
 use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
-use libimagentryedit::edit::Edit;
 use libimagentryedit::edit::EditHeader;
+use libimagentryedit::edit::EditWithHook;
+use libimagentryannotation::anchor::relocate_anchors;
+use libimagentrymarkdown::wikilink::link_wiki_links;
+use libimagentrymarkdown::wikilink::StoreResolver;
+use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
+use libimagstore::store::Entry;
 use libimagstore::storeid::StoreIdIterator;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
 
+use failure::Fallible as Result;
+
 mod ui;
 
 fn main() {
@@ -75,7 +86,7 @@ fn main() {
         .for_each(|mut entry| {
             if edit_header {
                 let _ = entry
-                    .edit_header_and_content(&rt)
+                    .edit_header_and_content_with_hook(&rt, |e| after_edit(&rt, e))
                     .map_err_trace_exit_unwrap(1);
             } else if edit_header_only {
                 let _ = entry
@@ -83,9 +94,26 @@ fn main() {
                     .map_err_trace_exit_unwrap(1);
             } else {
                 let _ = entry
-                    .edit_content(&rt)
+                    .edit_content_with_hook(&rt, |e| after_edit(&rt, e))
                     .map_err_trace_exit_unwrap(1);
             }
         });
 }
 
+/// Keep the annotation anchors and the wiki links of `entry` up to date after its content changed
+///
+/// Anchors which cannot be found anymore and wiki links to entries which do not exist are
+/// reported.
+fn after_edit(rt: &Runtime, entry: &mut Entry) -> Result<()> {
+    let lost = relocate_anchors(rt.store(), entry)?;
+    if lost != 0 {
+        warn!("{} annotation anchor(s) in {} could not be found anymore", lost, entry.get_location());
+    }
+
+    for link in link_wiki_links(entry, rt.store(), &StoreResolver::new())? {
+        warn!("Wiki link target '{}' in {} does not exist", link.target, entry.get_location());
+    }
+
+    Ok(())
+}
+
@@ -0,0 +1,470 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Export the store as a static HTML site
+//!
+//! Every exported entry is rendered to `<id>.html` below the output directory. Links between
+//! exported entries are rewritten to relative URLs, so the site can be browsed from any location.
+//! `tags/<tag>.html` and `categories/<category>.html` list the entries with a tag or category,
+//! `index.html` lists all exported entries.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs::create_dir_all;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use handlebars::Handlebars;
+use url::Url;
+use url::percent_encoding::percent_decode;
+use url::percent_encoding::utf8_percent_encode;
+use url::percent_encoding::DEFAULT_ENCODE_SET;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrymarkdown::html::to_html;
+use libimagentrymarkdown::wikilink::backlinks;
+use libimagentrymarkdown::wikilink::replace_wiki_links;
+use libimagentrymarkdown::wikilink::StoreResolver;
+use libimagentrymarkdown::wikilink::WikiLinkResolver;
+use libimagentrytag::tagable::Tagable;
+use libimagentrycategory::entry::EntryCategory;
+
+pub const DEFAULT_PAGE_TEMPLATE  : &str = include_str!("../static/page.hbs");
+pub const DEFAULT_INDEX_TEMPLATE : &str = include_str!("../static/index.hbs");
+
+#[derive(Serialize, Debug)]
+struct Link {
+    title: String,
+    url: String,
+}
+
+/// The data the page template is rendered with
+#[derive(Serialize, Debug)]
+struct Page<'a> {
+    root: &'a str,
+    id: String,
+    title: String,
+    content: String,
+    tags: Vec<Link>,
+    categories: Vec<Link>,
+    backlinks: Vec<Link>,
+}
+
+#[derive(Serialize, Debug)]
+struct Section {
+    name: String,
+    links: Vec<Link>,
+}
+
+/// The data the index template is rendered with
+#[derive(Serialize, Debug)]
+struct Index<'a> {
+    root: &'a str,
+    title: String,
+    sections: Vec<Section>,
+}
+
+/// What was exported
+pub struct Stats {
+    pub entries: Vec<StoreId>,
+    pub tags: usize,
+    pub categories: usize,
+}
+
+pub struct HtmlExport<'a> {
+    store: &'a Store,
+    handlebars: Handlebars,
+
+    /// The local paths of the exported entries
+    ids: BTreeSet<PathBuf>,
+
+    /// Shared by all pages, so the names of the entries are only indexed once
+    resolver: StoreResolver,
+}
+
+impl<'a> HtmlExport<'a> {
+
+    /// Prepare the export of all entries in `collections`
+    pub fn new(store: &'a Store, collections: &[String], page_template: &str, index_template: &str)
+        -> Result<HtmlExport<'a>>
+    {
+        let mut handlebars = Handlebars::new();
+        let _ = handlebars
+            .register_template_string("page", page_template)
+            .map_err(Error::from)
+            .context(err_msg("Handlebars template error: page"))?;
+        let _ = handlebars
+            .register_template_string("index", index_template)
+            .map_err(Error::from)
+            .context(err_msg("Handlebars template error: index"))?;
+
+        let mut ids = BTreeSet::new();
+        for id in store.entries()? {
+            let id = id?;
+            if collections.iter().any(|c| id.is_in_collection(&[c])) {
+                ids.insert(id.local().clone());
+            }
+        }
+
+        Ok(HtmlExport {
+            store: store,
+            handlebars: handlebars,
+            ids: ids,
+            resolver: StoreResolver::new(),
+        })
+    }
+
+    /// Write the site to `outdir`
+    pub fn export(&self, outdir: &Path) -> Result<Stats> {
+        let mut tags       : BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+        let mut categories : BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+        let mut exported   = vec![];
+
+        for path in self.ids.iter() {
+            let id    = StoreId::new_baseless(path.clone())?;
+            let entry = self
+                .store
+                .get(id.clone())?
+                .ok_or_else(|| format_err!("Entry not found: {}", id))?;
+
+            let page = page_path(path);
+            let root = root_of(&page);
+
+            let entry_tags       = entry.get_tags()?;
+            let entry_categories = entry.get_categories()?;
+
+            for tag in entry_tags.iter() {
+                tags.entry(tag.clone()).or_insert_with(Vec::new).push(path);
+            }
+
+            for category in entry_categories.iter() {
+                categories.entry(category.clone()).or_insert_with(Vec::new).push(path);
+            }
+
+            let backlinks = backlinks(&entry, self.store, &self.resolver)?
+                .into_iter()
+                .filter(|id| self.ids.contains(id.local()))
+                .map(|id| entry_link(&root, id.local()))
+                .collect();
+
+            let data = Page {
+                root: &root,
+                id: format!("{}", id),
+                title: title_of(path),
+                content: self.render_content(&entry, &page)?,
+                tags: entry_tags.iter().map(|t| link(&root, t.clone(), &tag_page(t))).collect(),
+                categories: entry_categories
+                    .iter()
+                    .map(|c| link(&root, c.clone(), &category_page(c)))
+                    .collect(),
+                backlinks: backlinks,
+            };
+
+            let _ = write_page(outdir, &page, self.render("page", &data)?)?;
+            exported.push(id);
+        }
+
+        for (tag, paths) in tags.iter() {
+            let _ = self.write_index(outdir, &tag_page(tag), format!("Tag: {}", tag), |root| {
+                vec![entries_section("Entries", root, paths.iter().cloned())]
+            })?;
+        }
+
+        for (category, paths) in categories.iter() {
+            let _ = self.write_index(outdir, &category_page(category), format!("Category: {}", category), |root| {
+                vec![entries_section("Entries", root, paths.iter().cloned())]
+            })?;
+        }
+
+        let _ = self.write_index(outdir, &PathBuf::from("index.html"), String::from("Index"), |root| {
+            let mut collections : BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+            for path in self.ids.iter() {
+                let collection = path
+                    .components()
+                    .next()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .unwrap_or_else(String::new);
+                collections.entry(collection).or_insert_with(Vec::new).push(path);
+            }
+
+            let mut sections : Vec<Section> = collections
+                .into_iter()
+                .map(|(name, paths)| entries_section(&name, root, paths.into_iter()))
+                .collect();
+
+            if !tags.is_empty() {
+                sections.push(Section {
+                    name: String::from("Tags"),
+                    links: tags.keys().map(|t| link(root, t.clone(), &tag_page(t))).collect(),
+                });
+            }
+
+            if !categories.is_empty() {
+                sections.push(Section {
+                    name: String::from("Categories"),
+                    links: categories.keys().map(|c| link(root, c.clone(), &category_page(c))).collect(),
+                });
+            }
+
+            sections
+        })?;
+
+        Ok(Stats {
+            entries: exported,
+            tags: tags.len(),
+            categories: categories.len(),
+        })
+    }
+
+    /// Render the content of an entry to HTML
+    ///
+    /// Wiki links are converted to markdown links before rendering, links to exported entries are
+    /// rewritten to point to their pages.
+    fn render_content(&self, entry: &Entry, page: &Path) -> Result<String> {
+        let from     = entry.get_location();
+        let markdown = replace_wiki_links(entry.get_content(), |link| {
+            Ok(match self.resolver.resolve(link, from, self.store) {
+                Ok(Some(id)) => format!("[{}]({})", link.title(), encode(&id.local().display().to_string())),
+                Ok(None)     => String::from(link.title()),
+                Err(e)       => {
+                    warn!("Cannot resolve '{}' in {}: {}", link.target, from, e);
+                    String::from(link.title())
+                },
+            })
+        })?;
+
+        to_html(&markdown).map(|html| rewrite_links(&html, page, &self.ids))
+    }
+
+    fn write_index<F>(&self, outdir: &Path, page: &Path, title: String, sections: F) -> Result<()>
+        where F: FnOnce(&str) -> Vec<Section>
+    {
+        let root = root_of(page);
+        let data = Index {
+            sections: sections(&root),
+            root: &root,
+            title: title,
+        };
+
+        write_page(outdir, page, self.render("index", &data)?)
+    }
+
+    fn render<T: ::serde::Serialize>(&self, template: &str, data: &T) -> Result<String> {
+        self.handlebars
+            .render(template, data)
+            .map_err(Error::from)
+            .context(format_err!("Failed to render template: {}", template))
+            .map_err(Error::from)
+    }
+
+}
+
+fn write_page(outdir: &Path, page: &Path, html: String) -> Result<()> {
+    let path = outdir.join(page);
+    debug!("Writing {}", path.display());
+
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent)
+            .context(format_err!("Cannot create directory: {}", parent.display()))?;
+    }
+
+    File::create(&path)
+        .and_then(|mut file| file.write_all(html.as_bytes()))
+        .context(format_err!("Cannot write {}", path.display()))
+        .map_err(Error::from)
+}
+
+fn entries_section<'b, I>(name: &str, root: &str, paths: I) -> Section
+    where I: Iterator<Item = &'b PathBuf>
+{
+    Section {
+        name: String::from(name),
+        links: paths.map(|path| entry_link(root, path)).collect(),
+    }
+}
+
+fn entry_link(root: &str, path: &Path) -> Link {
+    link(root, path.display().to_string(), &page_path(path))
+}
+
+fn link(root: &str, title: String, page: &Path) -> Link {
+    Link {
+        title: title,
+        url: format!("{}{}", root, encode(&page.display().to_string())),
+    }
+}
+
+/// The page of the entry with the local path `path`, relative to the output directory
+fn page_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.html", path.display()))
+}
+
+fn tag_page(tag: &str) -> PathBuf {
+    PathBuf::from("tags").join(name_page(tag))
+}
+
+fn category_page(category: &str) -> PathBuf {
+    PathBuf::from("categories").join(name_page(category))
+}
+
+/// The file name of the page for a tag or category name
+///
+/// Everything but ASCII alphanumerics, `-` and `_` is percent-encoded, so the page of a name like
+/// `../x` or `a/b` cannot end up outside of its directory.
+fn name_page(name: &str) -> String {
+    let mut page = String::with_capacity(name.len() + ".html".len());
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            page.push(b as char);
+        } else {
+            page.push_str(&format!("%{:02X}", b));
+        }
+    }
+
+    page.push_str(".html");
+    page
+}
+
+/// The relative URL of the output directory from the page `page`, either empty or ending in `/`
+fn root_of(page: &Path) -> String {
+    "../".repeat(page.components().count().saturating_sub(1))
+}
+
+fn title_of(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Percent-encode a path for a URL
+///
+/// `%` is encoded as well, as it occurs in the file names of tag and category pages.
+fn encode(s: &str) -> String {
+    utf8_percent_encode(&s.replace('%', "%25"), DEFAULT_ENCODE_SET).to_string()
+}
+
+/// Rewrite the `href`s in `html`, which point to entries in `ids`, to the pages of these entries
+///
+/// `page` is the page the HTML is placed on.
+fn rewrite_links(html: &str, page: &Path, ids: &BTreeSet<PathBuf>) -> String {
+    let root       = root_of(page);
+    let mut result = String::with_capacity(html.len());
+    let mut rest   = html;
+
+    while let Some(start) = rest.find("href=\"") {
+        let value_start = start + "href=\"".len();
+        let value_end   = match rest[value_start..].find('"') {
+            Some(end) => value_start + end,
+            None      => break,
+        };
+
+        let href = &rest[value_start..value_end];
+        result.push_str(&rest[..value_start]);
+
+        match internal_target(href).filter(|target| ids.contains(target)) {
+            Some(target) => result.push_str(&entry_link(&root, &target).url.replace('&', "&amp;")),
+            None         => result.push_str(href),
+        }
+
+        rest = &rest[value_end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// The store path an `href` points to, if it is neither an URL nor an anchor
+fn internal_target(href: &str) -> Option<PathBuf> {
+    if href.is_empty() || href.starts_with('#') || Url::parse(href).is_ok() {
+        return None
+    }
+
+    let href = href.replace("&amp;", "&");
+    percent_decode(href.as_bytes())
+        .decode_utf8()
+        .ok()
+        .map(|decoded| PathBuf::from(decoded.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_of() {
+        assert_eq!(root_of(&PathBuf::from("index.html")), "");
+        assert_eq!(root_of(&PathBuf::from("notes/foo.html")), "../");
+        assert_eq!(root_of(&PathBuf::from("diary/default/2018/01.html")), "../../../");
+    }
+
+    #[test]
+    fn test_name_pages_stay_in_their_directory() {
+        assert_eq!(tag_page("work"), PathBuf::from("tags/work.html"));
+        assert_eq!(tag_page("to-do_2"), PathBuf::from("tags/to-do_2.html"));
+        assert_eq!(category_page("a/b"), PathBuf::from("categories/a%2Fb.html"));
+        assert_eq!(category_page(".."), PathBuf::from("categories/%2E%2E.html"));
+        assert_eq!(tag_page("../../etc/passwd"), PathBuf::from("tags/%2E%2E%2F%2E%2E%2Fetc%2Fpasswd.html"));
+        assert_eq!(category_page("Bücher"), PathBuf::from("categories/B%C3%BCcher.html"));
+
+        for page in vec![tag_page(".."), category_page("/abs"), category_page("a/../b")] {
+            assert_eq!(page.components().count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_links_to_name_pages() {
+        let l = link("../", String::from("a/b c"), &category_page("a/b c"));
+        assert_eq!(l.url, "../categories/a%252Fb%2520c.html");
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let ids : BTreeSet<PathBuf> = vec![
+            PathBuf::from("notes/foo"),
+            PathBuf::from("wiki/default/Some Page"),
+        ].into_iter().collect();
+
+        let html = concat!(
+            r#"<a href="notes/foo">foo</a> "#,
+            r#"<a href="wiki/default/Some%20Page">page</a> "#,
+            r#"<a href="notes/missing">missing</a> "#,
+            r#"<a href="https://imag-pim.org">web</a> "#,
+            r##"<a href="#top">top</a>"##,
+        );
+
+        let expected = concat!(
+            r#"<a href="../../notes/foo.html">foo</a> "#,
+            r#"<a href="../../wiki/default/Some%20Page.html">page</a> "#,
+            r#"<a href="notes/missing">missing</a> "#,
+            r#"<a href="https://imag-pim.org">web</a> "#,
+            r##"<a href="#top">top</a>"##,
+        );
+
+        let page = PathBuf::from("wiki/default/index.html");
+        assert_eq!(rewrite_links(html, &page, &ids), expected);
+    }
+}
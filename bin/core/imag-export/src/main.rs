@@ -0,0 +1,170 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate log;
+#[macro_use] extern crate failure;
+#[macro_use] extern crate serde_derive;
+extern crate clap;
+extern crate handlebars;
+extern crate serde;
+extern crate toml_query;
+extern crate url;
+
+extern crate libimagstore;
+#[macro_use] extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagentrymarkdown;
+extern crate libimagentrytag;
+extern crate libimagentrycategory;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use failure::Fallible as Result;
+use failure::ResultExt;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+
+mod html;
+mod ui;
+
+use html::HtmlExport;
+
+fn main() {
+    let version = make_imag_version!();
+    let rt = generate_runtime_setup("imag-export",
+                                    &version,
+                                    "Export entries to other formats",
+                                    ui::build_ui);
+
+    rt.cli()
+        .subcommand_name()
+        .map(|name| {
+            match name {
+                "html" => export_html(&rt),
+                other  => {
+                    debug!("Unknown command");
+                    let _ = rt.handle_unknown_subcommand("imag-export", other, rt.cli())
+                        .map_err_trace_exit_unwrap(1)
+                        .code()
+                        .map(::std::process::exit);
+                }
+            }
+        });
+}
+
+fn export_html(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("html").unwrap(); // safed by main()
+    let outdir = PathBuf::from(scmd.value_of("directory").unwrap()); // safed by clap
+
+    let collections = match scmd.values_of("collection") {
+        Some(values) => values.map(String::from).collect(),
+        None         => configured_collections(rt)
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or_else(|| vec![
+                String::from("wiki"),
+                String::from("notes"),
+                String::from("diary"),
+            ]),
+    };
+    debug!("Exporting collections: {:?}", collections);
+
+    let page_template  = template(rt, "export.html.page_template", html::DEFAULT_PAGE_TEMPLATE)
+        .map_err_trace_exit_unwrap(1);
+    let index_template = template(rt, "export.html.index_template", html::DEFAULT_INDEX_TEMPLATE)
+        .map_err_trace_exit_unwrap(1);
+
+    let stats = HtmlExport::new(rt.store(), &collections, &page_template, &index_template)
+        .and_then(|export| export.export(&outdir))
+        .map_err_trace_exit_unwrap(1);
+
+    for id in stats.entries.iter() {
+        let _ = rt.report_touched(id).map_err_trace_exit_unwrap(1);
+    }
+
+    info!("Exported {} entries, {} tags and {} categories to {}",
+          stats.entries.len(),
+          stats.tags,
+          stats.categories,
+          outdir.display());
+}
+
+/// Read `export.html.collections` from the configuration
+fn configured_collections(rt: &Runtime) -> Result<Option<Vec<String>>> {
+    let value = match rt.config() {
+        Some(config) => config.read("export.html.collections")?,
+        None         => return Ok(None),
+    };
+
+    match value {
+        None        => Ok(None),
+        Some(value) => value
+            .as_array()
+            .ok_or_else(|| format_err!("'export.html.collections' must be an array of strings"))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(String::from)
+                    .ok_or_else(|| format_err!("'export.html.collections' must be an array of strings"))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some),
+    }
+}
+
+/// Read the template from the file configured at `key`, or use `default`
+fn template(rt: &Runtime, key: &str, default: &str) -> Result<String> {
+    let path = match rt.config() {
+        Some(config) => config.read_string(key)?,
+        None         => None,
+    };
+
+    match path {
+        None       => Ok(String::from(default)),
+        Some(path) => {
+            let mut template = String::new();
+            let _ = File::open(&path)
+                .and_then(|mut file| file.read_to_string(&mut template))
+                .context(format_err!("Cannot read template {}", path))?;
+            Ok(template)
+        },
+    }
+}
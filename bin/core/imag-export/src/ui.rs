@@ -0,0 +1,43 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use clap::{Arg, App, SubCommand};
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app
+        .subcommand(SubCommand::with_name("html")
+                    .about("Export entries as a static HTML site")
+                    .version("0.1")
+                    .arg(Arg::with_name("directory")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .value_name("DIR")
+                         .help("The directory the site is written to. Existing files are overwritten."))
+                    .arg(Arg::with_name("collection")
+                         .long("collection")
+                         .short("c")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .value_name("COLLECTION")
+                         .help("Export the entries of these collections. Default: 'export.html.collections' from the configuration, or wiki, notes and diary"))
+                    )
+}
@@ -38,27 +38,63 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 extern crate failure;
+extern crate serde_json;
+extern crate chrono;
 
 extern crate libimagentrygps;
 #[macro_use] extern crate libimagrt;
 extern crate libimagutil;
 extern crate libimagerror;
 extern crate libimagstore;
+#[macro_use] extern crate libimagentryref;
+extern crate libimagdiary;
+extern crate libimagtimetrack;
 
+use std::fs::File;
+use std::io::BufReader;
 use std::io::Write;
+use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
 
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Local;
+use chrono::LocalResult;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Utc;
 use failure::Error;
+use failure::Fallible as Result;
 use failure::err_msg;
+use clap::ArgMatches;
 
 use libimagentrygps::types::*;
 use libimagentrygps::entry::*;
+use libimagentrygps::geojson::to_geojson;
+use libimagentrygps::gpx;
+use libimagentrygps::photo;
+use libimagentrygps::query::BoundingBox;
+use libimagentrygps::query::GPSStore;
+use libimagentrygps::query::format_distance;
+use libimagentrygps::query::parse_distance;
 use libimagrt::setup::generate_runtime_setup;
 use libimagrt::runtime::Runtime;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
+use libimagerror::iter::TraceIterator;
+use libimagerror::trace::trace_error;
+use libimagstore::store::Entry;
+use libimagentryref::config::Config as RefConfig;
+use libimagentryref::generators::DEFAULT_GENERATOR;
+use libimagentryref::generators::GENERATOR_COLLECTION;
+use libimagentryref::reference::Ref;
+use libimagentryref::refstore::RefStore;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+use libimagdiary::entry::DiaryEntry;
+use libimagtimetrack::timetracking::TimeTracking;
+use libimagstore::iter::get::StoreIdGetIteratorExtension;
 
 mod ui;
 
@@ -73,10 +109,16 @@ fn main() {
         .subcommand_name()
         .map(|name| {
             match name {
-                "add"    => add(&rt),
-                "remove" => remove(&rt),
-                "get"    => get(&rt),
-                other    => {
+                "add"         => add(&rt),
+                "remove"      => remove(&rt),
+                "get"         => get(&rt),
+                "near"        => near(&rt),
+                "within"      => within(&rt),
+                "geojson"     => geojson(&rt),
+                "import-exif" => import_exif(&rt),
+                "import-gpx"  => import_gpx(&rt),
+                "migrate"     => migrate(&rt),
+                other         => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-gps", other, rt.cli())
                         .map_err_trace_exit_unwrap(1)
@@ -88,40 +130,43 @@ fn main() {
 }
 
 fn add(rt: &Runtime) {
-    let c = {
-        let parse = |value: &str| -> (i64, i64, i64) {
-            debug!("Parsing '{}' into degree, minute and second", value);
-            let ary = value.split(".")
-                .map(|v| {debug!("Parsing = {}", v); v})
-                .map(FromStr::from_str)
-                .map(|elem| {
-                    elem.or_else(|_| Err(Error::from(err_msg("Error while converting number"))))
-                        .map_err_trace_exit_unwrap(1)
-                })
-                .collect::<Vec<i64>>();
-
-            let degree = ary.get(0).unwrap_or_else(|| {
-                error!("Degree missing. This value is required.");
-                exit(1)
-            });
-            let minute = ary.get(1).unwrap_or_else(|| {
-                error!("Degree missing. This value is required.");
-                exit(1)
-            });
-            let second = ary.get(2).unwrap_or(&0);
+    let scmd = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
 
-            (*degree, *minute, *second)
-        };
+    let c = match scmd.value_of("coordinates") {
+        Some(c) => Coordinates::from_str(c).map_err_trace_exit_unwrap(1),
+        None    => {
+            // unwraps safed by clap
+            let long = GPSValue::parse_longitude(scmd.value_of("longitude").unwrap())
+                .map_err_trace_exit_unwrap(1);
+            let lati = GPSValue::parse_latitude(scmd.value_of("latitude").unwrap())
+                .map_err_trace_exit_unwrap(1);
 
-        let scmd = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
+            Coordinates::new(long, lati)
+        },
+    };
 
-        let long = parse(scmd.value_of("longitude").unwrap()); // unwrap safed by clap
-        let lati = parse(scmd.value_of("latitude").unwrap()); // unwrap safed by clap
+    let parse_meters = |value: &str, what: &str| -> f64 {
+        debug!("Parsing '{}' into {}", value, what);
+        f64::from_str(value)
+            .map_err(|_| Error::from(err_msg(format!("{} is not a number: {}", what, value))))
+            .map_err_trace_exit_unwrap(1)
+    };
 
-        let long = GPSValue::new(long.0, long.1, long.2);
-        let lati = GPSValue::new(lati.0, lati.1, lati.2);
+    let c = match scmd.value_of("altitude") {
+        Some(altitude) => c.with_altitude(parse_meters(altitude, "Altitude")),
+        None           => c,
+    };
 
-        Coordinates::new(long, lati)
+    let c = match scmd.value_of("accuracy") {
+        Some(accuracy) => {
+            let accuracy = parse_meters(accuracy, "Accuracy");
+            if accuracy < 0.0 {
+                error!("Accuracy must not be negative: {}", accuracy);
+                exit(1)
+            }
+            c.with_accuracy(accuracy)
+        },
+        None => c,
     };
 
     rt.ids::<::ui::PathProvider>()
@@ -182,6 +227,12 @@ fn remove(rt: &Runtime) {
 }
 
 fn get(rt: &Runtime) {
+    let dms = rt
+        .cli()
+        .subcommand_matches("get")
+        .unwrap()
+        .is_present("dms"); // safed by main()
+
     let mut stdout = rt.stdout();
     rt.ids::<::ui::PathProvider>()
         .map_err_trace_exit_unwrap(1)
@@ -202,7 +253,11 @@ fn get(rt: &Runtime) {
                     exit(1)
                 });
 
-            let _ = writeln!(stdout, "{}", value).to_exit_code().unwrap_or_exit();
+            let _ = if dms {
+                writeln!(stdout, "{}", value.to_dms_string())
+            } else {
+                writeln!(stdout, "{}", value)
+            }.to_exit_code().unwrap_or_exit();
 
             let _ = rt
                 .report_touched(&id)
@@ -211,3 +266,254 @@ fn get(rt: &Runtime) {
 
 }
 
+fn near(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("near").unwrap(); // safed by main()
+
+    // unwraps safed by clap
+    let lati   = GPSValue::parse_latitude(scmd.value_of("latitude").unwrap()).map_err_trace_exit_unwrap(1);
+    let long   = GPSValue::parse_longitude(scmd.value_of("longitude").unwrap()).map_err_trace_exit_unwrap(1);
+    let radius = parse_distance(scmd.value_of("radius").unwrap()).map_err_trace_exit_unwrap(1);
+    let center = Coordinates::new(long, lati);
+
+    let out      = rt.stdout();
+    let mut lock = out.lock();
+
+    rt.store()
+        .entries_near(&center, radius)
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .for_each(|(located, distance)| {
+            let _ = writeln!(lock, "{}\t{}", located.id, format_distance(distance))
+                .to_exit_code()
+                .unwrap_or_exit();
+
+            let _ = rt
+                .report_touched(&located.id)
+                .map_err_trace_exit_unwrap(1);
+        });
+}
+
+fn within(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("within").unwrap(); // safed by main()
+
+    // unwraps safed by clap
+    let lati = |name: &str| GPSValue::parse_latitude(scmd.value_of(name).unwrap()).map_err_trace_exit_unwrap(1);
+    let long = |name: &str| GPSValue::parse_longitude(scmd.value_of(name).unwrap()).map_err_trace_exit_unwrap(1);
+    let bbox = BoundingBox::new(lati("south"), long("west"), lati("north"), long("east"));
+
+    if bbox.south.decimal() > bbox.north.decimal() {
+        error!("The southern latitude must not be north of the northern latitude");
+        exit(1)
+    }
+
+    let out      = rt.stdout();
+    let mut lock = out.lock();
+
+    rt.store()
+        .entries_within(&bbox)
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .for_each(|located| {
+            let _ = writeln!(lock, "{}", located.id).to_exit_code().unwrap_or_exit();
+
+            let _ = rt
+                .report_touched(&located.id)
+                .map_err_trace_exit_unwrap(1);
+        });
+}
+
+fn geojson(rt: &Runtime) {
+    let located = rt.store().located_entries().map_err_trace_exit_unwrap(1);
+    let json    = serde_json::to_string_pretty(&to_geojson(&located))
+        .map_err(Error::from)
+        .map_err_trace_exit_unwrap(1);
+
+    let output = rt
+        .cli()
+        .subcommand_matches("geojson")
+        .unwrap() // safed by main()
+        .value_of("output");
+
+    let _ = match output {
+        Some(path) => File::create(path).and_then(|mut f| writeln!(f, "{}", json)),
+        None       => writeln!(rt.stdout(), "{}", json),
+    }.to_exit_code().unwrap_or_exit();
+
+    for located in located.iter() {
+        let _ = rt
+            .report_touched(&located.id)
+            .map_err_trace_exit_unwrap(1);
+    }
+}
+
+/// Create refs for photos and set their coordinates from the EXIF data
+///
+/// The refs are created in the `ref` collection with the generator configured for it in
+/// `ref.generators`, as `imag ref create` does, and store its name, so `imag ref check` re-hashes
+/// them with the same generator.
+fn import_exif(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("import-exif").unwrap(); // safed by main()
+    let config = rt
+        .config()
+        .map(RefConfig::from_config)
+        .unwrap_or_else(|| Ok(RefConfig::default()))
+        .map_err_trace_exit_unwrap(1);
+
+    let collection = GENERATOR_COLLECTION;
+    let generator  = config
+        .generator_for(collection)
+        .unwrap_or(DEFAULT_GENERATOR);
+
+    let _ = with_generator!(generator, import_exif_with(rt, scmd, &config, collection, generator))
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn import_exif_with<RPG>(rt: &Runtime,
+                         scmd: &ArgMatches,
+                         config: &RefConfig,
+                         collection: &str,
+                         generator: &str)
+    where RPG: UniqueRefPathGenerator
+{
+    let mut out = rt.stdout();
+
+    for path in scmd.values_of("files").unwrap() { // safed by clap
+        let path = match PathBuf::from(path).canonicalize() {
+            Ok(path) => path,
+            Err(e)   => {
+                warn!("Cannot read {}: {}", path, e);
+                continue
+            },
+        };
+
+        let coordinates = File::open(&path)
+            .map_err(Error::from)
+            .and_then(|file| photo::read_coordinates(&mut BufReader::new(file)));
+
+        let coordinates = match coordinates {
+            Ok(Some(c)) => c,
+            Ok(None)    => {
+                warn!("No GPS position in the EXIF data of {}", path.display());
+                continue
+            },
+            Err(e) => {
+                warn!("Cannot read the EXIF data of {}: {}", path.display(), e);
+                continue
+            },
+        };
+
+        let mut entry = rt
+            .store()
+            .retrieve_ref_in::<RPG, _>(collection, &path, config)
+            .map_err_trace_exit_unwrap(1);
+
+        let _ = entry.set_generator(generator).map_err_trace_exit_unwrap(1);
+        let _ = entry.set_coordinates(coordinates).map_err_trace_exit_unwrap(1);
+
+        let _ = writeln!(out, "{}", entry.get_location()).to_exit_code().unwrap_or_exit();
+        let _ = rt.report_touched(entry.get_location()).map_err_trace_exit_unwrap(1);
+    }
+}
+
+/// Set the coordinates of diary and timetracking entries from the track point closest in time
+fn import_gpx(rt: &Runtime) {
+    let scmd       = rt.cli().subcommand_matches("import-gpx").unwrap(); // safed by main()
+    let force      = scmd.is_present("force");
+    let max_offset = scmd
+        .value_of("max-offset")
+        .map(i64::from_str)
+        .unwrap() // default value by clap
+        .map_err(|_| Error::from(err_msg("Maximum offset must be a number of minutes")))
+        .map_err_trace_exit_unwrap(1);
+    let max_offset = Duration::minutes(max_offset);
+
+    let points = File::open(scmd.value_of("file").unwrap()) // safed by clap
+        .map_err(Error::from)
+        .and_then(|file| gpx::read_track(BufReader::new(file)))
+        .map_err_trace_exit_unwrap(1);
+
+    if points.is_empty() {
+        warn!("The track has no points with a time");
+        return
+    }
+
+    let ids = rt
+        .store()
+        .entries()
+        .map_err_trace_exit_unwrap(1)
+        .trace_unwrap_exit(1)
+        .filter(|id| id.is_in_collection(&["diary"]) || id.is_in_collection(&["timetrack"]))
+        .collect::<Vec<_>>();
+
+    let mut out = rt.stdout();
+
+    for id in ids {
+        let mut entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        if !force && entry.get_coordinates().map_err_trace_exit_unwrap(1).is_some() {
+            debug!("{} has coordinates already", id);
+            continue
+        }
+
+        let time = match entry_time(&entry) {
+            Ok(Some(time)) => local_to_utc(time),
+            Ok(None)       => None,
+            Err(e)         => {
+                trace_error(&e);
+                None
+            },
+        };
+
+        let point = time.and_then(|time| gpx::nearest_point(&points, &time, max_offset));
+
+        if let Some(point) = point {
+            let _ = entry.set_coordinates(point.coordinates.clone()).map_err_trace_exit_unwrap(1);
+
+            let _ = writeln!(out, "{}", id).to_exit_code().unwrap_or_exit();
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        }
+    }
+}
+
+/// The time of a diary entry or the start of a timetracking
+fn entry_time(entry: &Entry) -> Result<Option<NaiveDateTime>> {
+    let id = entry.get_location();
+
+    if id.is_in_collection(&["diary"]) {
+        entry.diary_id().map(|diary_id| Some(diary_id.into()))
+    } else if id.is_in_collection(&["timetrack"]) {
+        entry.get_start_datetime()
+    } else {
+        Ok(None)
+    }
+}
+
+/// Diary and timetracking entries are in local time, track points in UTC
+fn local_to_utc(time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match Local.from_local_datetime(&time) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.with_timezone(&Utc)),
+        LocalResult::None                                       => None,
+    }
+}
+
+fn migrate(rt: &Runtime) {
+    let mut count = 0;
+
+    rt.store()
+        .entries()
+        .map_err_trace_exit_unwrap(1)
+        .into_get_iter()
+        .trace_unwrap_exit(1)
+        .filter_map(|e| e)
+        .for_each(|mut entry| {
+            if entry.migrate_coordinates().map_err_trace_exit_unwrap(1) {
+                debug!("Migrated {}", entry.get_location());
+                count += 1;
+            }
+        });
+
+    info!("Migrated {} entries", count);
+}
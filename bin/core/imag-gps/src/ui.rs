@@ -34,17 +34,42 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                     .arg(Arg::with_name("longitude")
                          .long("long")
                          .takes_value(true)
-                         .required(true)
+                         .required_unless("coordinates")
+                         .requires("latitude")
                          .multiple(false)
-                         .help("Set the longitude value. Format: <degrees>.<minutes>.<seconds>")
+                         .help("Set the longitude value. Format: Decimal degrees (11.575) or degrees, minutes and seconds (11°34'30\"E)")
                          .value_name("LONGITUDE"))
                     .arg(Arg::with_name("latitude")
                          .long("lat")
                          .takes_value(true)
-                         .required(true)
+                         .required_unless("coordinates")
+                         .requires("longitude")
                          .multiple(false)
-                         .help("Set the latitude. Format: <degrees>.<minutes>.<seconds>")
+                         .help("Set the latitude. Format: Decimal degrees (48.137) or degrees, minutes and seconds (48°8'13.2\"N)")
                          .value_name("LATITUDE"))
+                    .arg(Arg::with_name("coordinates")
+                         .long("coordinates")
+                         .short("c")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .conflicts_with_all(&["longitude", "latitude"])
+                         .help("Set latitude and longitude at once, for example '48.137, 11.575'")
+                         .value_name("COORDINATES"))
+                    .arg(Arg::with_name("altitude")
+                         .long("altitude")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Set the altitude in meters")
+                         .value_name("METERS"))
+                    .arg(Arg::with_name("accuracy")
+                         .long("accuracy")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Set the accuracy of the coordinates in meters")
+                         .value_name("METERS"))
                     .arg(Arg::with_name("entry")
                          .index(1)
                          .takes_value(true)
@@ -94,6 +119,129 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                      .required(false)
                      .multiple(false)
                      .help("Print as <key>=<value> pairs (2 lines, default)"))
+                .arg(Arg::with_name("dms")
+                     .long("dms")
+                     .takes_value(false)
+                     .required(false)
+                     .multiple(false)
+                     .help("Print latitude and longitude as degrees, minutes and seconds"))
+                )
+
+        .subcommand(SubCommand::with_name("near")
+                .about("List entries within a radius around a point, the closest first")
+                .version("0.1")
+                .arg(Arg::with_name("latitude")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The latitude of the point")
+                     .value_name("LATITUDE"))
+                .arg(Arg::with_name("longitude")
+                     .index(2)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The longitude of the point")
+                     .value_name("LONGITUDE"))
+                .arg(Arg::with_name("radius")
+                     .long("radius")
+                     .short("r")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .default_value("1km")
+                     .help("The radius, in meters or with unit, for example '500m' or '5km'")
+                     .value_name("DISTANCE"))
+                )
+
+        .subcommand(SubCommand::with_name("within")
+                .about("List entries within a bounding box")
+                .version("0.1")
+                .arg(Arg::with_name("south")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The southern latitude of the box")
+                     .value_name("SOUTH"))
+                .arg(Arg::with_name("west")
+                     .index(2)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The western longitude of the box")
+                     .value_name("WEST"))
+                .arg(Arg::with_name("north")
+                     .index(3)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The northern latitude of the box")
+                     .value_name("NORTH"))
+                .arg(Arg::with_name("east")
+                     .index(4)
+                     .takes_value(true)
+                     .required(true)
+                     .allow_hyphen_values(true)
+                     .help("The eastern longitude of the box")
+                     .value_name("EAST"))
+                )
+
+        .subcommand(SubCommand::with_name("geojson")
+                .about("Export all entries with coordinates as GeoJSON")
+                .version("0.1")
+                .arg(Arg::with_name("output")
+                     .long("output")
+                     .short("o")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Write to this file instead of stdout")
+                     .value_name("FILE"))
+                )
+
+        .subcommand(SubCommand::with_name("import-exif")
+                .about("Create refs for photos and set their coordinates from the EXIF data")
+                .version("0.1")
+                .arg(Arg::with_name("files")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(true)
+                     .help("The photos to import")
+                     .value_name("FILE"))
+                )
+
+        .subcommand(SubCommand::with_name("import-gpx")
+                .about("Set the coordinates of diary and timetracking entries from a GPX track, matching by time")
+                .version("0.1")
+                .arg(Arg::with_name("file")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(false)
+                     .help("The GPX file to import")
+                     .value_name("FILE"))
+                .arg(Arg::with_name("max-offset")
+                     .long("max-offset")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .default_value("15")
+                     .help("Only use track points recorded at most this many minutes before or after the entry")
+                     .value_name("MINUTES"))
+                .arg(Arg::with_name("force")
+                     .long("force")
+                     .short("f")
+                     .takes_value(false)
+                     .required(false)
+                     .help("Overwrite coordinates the entries have already"))
+                )
+
+        .subcommand(SubCommand::with_name("migrate")
+                .about("Migrate the coordinates of all entries from integer degrees, minutes and seconds to decimal degrees")
+                .version("0.1")
                 )
 }
 
@@ -130,7 +278,7 @@ impl IdPathProvider for PathProvider {
             },
 
             ("get", Some(subm)) => {
-                subm.values_of("get-ids")
+                subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
                         ::std::process::exit(1)
@@ -143,6 +291,11 @@ impl IdPathProvider for PathProvider {
                     .map_err_trace_exit_unwrap(1)
             },
 
+            ("near", _) | ("within", _) | ("geojson", _) | ("import-exif", _) | ("import-gpx", _) | ("migrate", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
             (other, _) => {
                     error!("Not a known command: {}", other);
                     ::std::process::exit(1)
@@ -37,14 +37,19 @@
 #[macro_use] extern crate log;
 extern crate clap;
 extern crate regex;
+extern crate ansi_term;
 
 extern crate libimagstore;
 #[macro_use] extern crate libimagrt;
 extern crate libimagerror;
+extern crate libimagfulltext;
+extern crate libimagutil;
 
 use std::io::Write;
+use std::path::PathBuf;
 
 use regex::Regex;
+use ansi_term::Style;
 
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
@@ -52,12 +57,19 @@ use libimagstore::store::Entry;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
+use libimagfulltext::config::Config as IndexConfig;
+use libimagfulltext::index::Index;
+use libimagfulltext::snippet::snippet;
 
 mod ui;
 
-struct Options {
+/// Number of terms shown before and after a match in a snippet
+const SNIPPET_CONTEXT: usize = 8;
+
+struct Options<'a> {
     files_with_matches: bool,
     count: bool,
+    collections: Option<Vec<&'a str>>,
 }
 
 fn main() {
@@ -70,8 +82,13 @@ fn main() {
     let opts = Options {
         files_with_matches    : rt.cli().is_present("files-with-matches"),
         count                 : rt.cli().is_present("count"),
+        collections           : rt.cli().values_of("collection").map(|v| v.collect()),
     };
 
+    if rt.cli().is_present("index") || rt.cli().is_present("update-index") || rt.cli().is_present("rebuild-index") {
+        return indexed_search(&rt, &opts)
+    }
+
     let mut count : usize = 0;
 
     let pattern = rt
@@ -90,6 +107,10 @@ fn main() {
         .map_err_trace_exit_unwrap(1)
         .into_get_iter()
         .filter_map(|res| res.map_err_trace_exit_unwrap(1))
+        .filter(|entry| match opts.collections {
+            Some(ref colls) => entry.get_location().is_in_collection(colls),
+            None            => true,
+        })
         .filter(|entry| pattern.is_match(entry.get_content()))
         .map(|entry| show(&rt, &entry, &pattern, &opts, &mut count))
         .count();
@@ -130,3 +151,80 @@ fn show(rt: &Runtime, e: &Entry, re: &Regex, opts: &Options, count: &mut usize)
         .map_err_trace_exit_unwrap(1);
 }
 
+fn indexed_search(rt: &Runtime, opts: &Options) {
+    let config = rt
+        .config()
+        .map(IndexConfig::from_config)
+        .unwrap_or_else(|| Ok(IndexConfig::default()))
+        .map_err_trace_exit_unwrap(1);
+
+    let path = config
+        .path()
+        .cloned()
+        .unwrap_or_else(|| rt.rtp().join("index").join("fulltext.json"));
+
+    let mut index = if rt.cli().is_present("rebuild-index") {
+        Index::default()
+    } else {
+        Index::load(&path).map_err_trace_exit_unwrap(1)
+    };
+
+    let stats = index.update(rt.store(), &config).map_err_trace_exit_unwrap(1);
+    debug!("Index updated: {:?}", stats);
+
+    if stats.added + stats.updated + stats.removed > 0 {
+        let _ = index.save(&path).map_err_trace_exit_unwrap(1);
+    }
+
+    let pattern = match rt.cli().value_of("pattern") {
+        Some(p) => p,
+        None    => {
+            info!("Index at {}: {} added, {} updated, {} removed, {} unchanged",
+                  path.display(), stats.added, stats.updated, stats.removed, stats.unchanged);
+            return
+        },
+    };
+
+    let clauses     = ::libimagfulltext::query::parse(pattern).map_err_trace_exit_unwrap(1);
+    let collections = opts.collections.as_ref().map(|c| &c[..]);
+    let limit       = rt
+        .cli()
+        .value_of("limit")
+        .map(|l| l.parse::<usize>().unwrap()) // safe by clap validator
+        .unwrap_or(::std::usize::MAX);
+
+    let hits = index.search(&clauses, collections);
+
+    if opts.count {
+        let _ = writeln!(rt.stdout(), "{}", hits.len()).to_exit_code().unwrap_or_exit();
+        return
+    }
+
+    let highlight = |s: &str| if rt.output_is_pipe() {
+        String::from(s)
+    } else {
+        Style::new().bold().paint(s).to_string()
+    };
+
+    let mut out = rt.stdout();
+    for hit in hits.into_iter().take(limit) {
+        let entry = match rt.store().get(PathBuf::from(&hit.id)).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        if opts.files_with_matches {
+            let _ = writeln!(out, "{}", entry.get_location()).to_exit_code().unwrap_or_exit();
+        } else {
+            let _ = writeln!(out, "{} ({:.2})", entry.get_location(), hit.score)
+                .to_exit_code()
+                .unwrap_or_exit();
+
+            if let Some(s) = snippet(entry.get_content(), &hit.positions, SNIPPET_CONTEXT, &highlight) {
+                let _ = writeln!(out, "    {}", s).to_exit_code().unwrap_or_exit();
+            }
+        }
+
+        let _ = rt.report_touched(entry.get_location()).map_err_trace_exit_unwrap(1);
+    }
+}
@@ -37,10 +37,51 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
              .multiple(false)
              .help("Count matches"))
 
+        .arg(Arg::with_name("collection")
+             .long("collection")
+             .takes_value(true)
+             .required(false)
+             .multiple(true)
+             .value_name("COLLECTION")
+             .help("Only search entries in these collections"))
+
+        .arg(Arg::with_name("index")
+             .long("index")
+             .short("i")
+             .takes_value(false)
+             .required(false)
+             .multiple(false)
+             .help("Search the full-text index instead of scanning with a regex. The pattern is a list of terms, \"phrases\" and fuzzy terms (term~ or term~2), all of which have to match. Results are ranked."))
+
+        .arg(Arg::with_name("limit")
+             .long("limit")
+             .short("n")
+             .takes_value(true)
+             .required(false)
+             .multiple(false)
+             .value_name("N")
+             .requires("index")
+             .validator(::libimagutil::cli_validators::is_integer)
+             .help("Show only the N best results"))
+
+        .arg(Arg::with_name("update-index")
+             .long("update-index")
+             .takes_value(false)
+             .required(false)
+             .multiple(false)
+             .help("Update the full-text index with the entries which changed since the last update. This is done before each search with --index as well."))
+
+        .arg(Arg::with_name("rebuild-index")
+             .long("rebuild-index")
+             .takes_value(false)
+             .required(false)
+             .multiple(false)
+             .help("Build the full-text index from scratch"))
+
         .arg(Arg::with_name("pattern")
              .index(1)
              .takes_value(false)
-             .required(true)
+             .required_unless_one(&["update-index", "rebuild-index"])
              .multiple(false)
              .value_name("PATTERN")
              .help("Pattern to search for. Regex is supported, multiple patterns are not."))
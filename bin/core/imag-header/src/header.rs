@@ -0,0 +1,357 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::str::FromStr;
+
+use toml::Value;
+use toml::value::Datetime;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+
+/// The type a value from the commandline is parsed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// Booleans, integers and floats are detected, everything else is a string
+    Auto,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Datetime,
+    /// The value is parsed as TOML
+    Toml,
+}
+
+impl ValueType {
+    pub fn from_name(name: &str) -> Option<ValueType> {
+        match name {
+            "auto"     => Some(ValueType::Auto),
+            "string"   => Some(ValueType::String),
+            "int"      => Some(ValueType::Integer),
+            "float"    => Some(ValueType::Float),
+            "bool"     => Some(ValueType::Boolean),
+            "datetime" => Some(ValueType::Datetime),
+            "toml"     => Some(ValueType::Toml),
+            _          => None,
+        }
+    }
+}
+
+pub fn parse_value(s: &str, ty: ValueType) -> Result<Value> {
+    match ty {
+        ValueType::Auto => Ok({
+            if let Ok(b) = bool::from_str(s) {
+                Value::Boolean(b)
+            } else if let Ok(i) = i64::from_str(s) {
+                Value::Integer(i)
+            } else if let Ok(f) = f64::from_str(s) {
+                Value::Float(f)
+            } else {
+                Value::String(String::from(s))
+            }
+        }),
+        ValueType::String   => Ok(Value::String(String::from(s))),
+        ValueType::Integer  => i64::from_str(s)
+            .map(Value::Integer)
+            .context(format_err!("Not an integer: '{}'", s))
+            .map_err(Error::from),
+        ValueType::Float    => f64::from_str(s)
+            .map(Value::Float)
+            .context(format_err!("Not a float: '{}'", s))
+            .map_err(Error::from),
+        ValueType::Boolean  => bool::from_str(s)
+            .map(Value::Boolean)
+            .context(format_err!("Not a bool: '{}'", s))
+            .map_err(Error::from),
+        ValueType::Datetime => Datetime::from_str(s)
+            .map(Value::Datetime)
+            .context(format_err!("Not a datetime: '{}'", s))
+            .map_err(Error::from),
+        ValueType::Toml     => {
+            let mut table : Value = ::toml::de::from_str(&format!("value = {}", s))
+                .context(format_err!("Not a TOML value: '{}'", s))?;
+
+            let value = match table {
+                Value::Table(ref mut t) => t.remove("value"),
+                _                       => None,
+            };
+
+            value.ok_or_else(|| format_err!("Not a TOML value: '{}'", s))
+        },
+    }
+}
+
+/// An operation on the header of an entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Get(String),
+    Set {
+        path: String,
+        value: Value,
+        force: bool,
+    },
+    Unset(String),
+    AppendToArray {
+        path: String,
+        value: Value,
+    },
+    RenameKey {
+        from: String,
+        to: String,
+        force: bool,
+    },
+}
+
+impl Operation {
+
+    /// Whether the operation alters the header
+    pub fn is_modifying(&self) -> bool {
+        match *self {
+            Operation::Get(_) => false,
+            _                 => true,
+        }
+    }
+
+    /// Apply the operation to `header`
+    ///
+    /// Returns the read value for `Operation::Get`, `None` otherwise. If an error is returned,
+    /// `header` might be partially modified, so callers should operate on a copy.
+    pub fn apply(&self, header: &mut Value) -> Result<Option<Value>> {
+        match *self {
+            Operation::Get(ref path) => header
+                .read(path)?
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| format_err!("No value at '{}'", path)),
+
+            Operation::Set { ref path, ref value, force } => {
+                if let Some(old) = header.read(path)? {
+                    if !force {
+                        let _ = check_type(path, old, value)?;
+                    }
+                }
+
+                let _ = header.insert(path, value.clone())?;
+                Ok(None)
+            },
+
+            Operation::Unset(ref path) => remove(header, path)?
+                .map(|_| None)
+                .ok_or_else(|| format_err!("No value at '{}'", path)),
+
+            Operation::AppendToArray { ref path, ref value } => {
+                let array = match header.read(path)? {
+                    None                               => vec![value.clone()],
+                    Some(&Value::Array(ref array))     => {
+                        if let Some(first) = array.first() {
+                            let _ = check_type(path, first, value)?;
+                        }
+
+                        let mut array = array.clone();
+                        array.push(value.clone());
+                        array
+                    },
+                    Some(other) => {
+                        return Err(format_err!("Type mismatch at '{}': expected array, found {}",
+                                               path,
+                                               other.type_str()))
+                    },
+                };
+
+                let _ = header.insert(path, Value::Array(array))?;
+                Ok(None)
+            },
+
+            Operation::RenameKey { ref from, ref to, force } => {
+                if !force && header.read(to)?.is_some() {
+                    return Err(format_err!("Cannot rename '{}': '{}' exists already", from, to))
+                }
+
+                let value = remove(header, from)?
+                    .ok_or_else(|| format_err!("No value at '{}'", from))?;
+
+                let _ = header.insert(to, value)?;
+                Ok(None)
+            },
+        }
+    }
+
+}
+
+fn check_type(path: &str, old: &Value, new: &Value) -> Result<()> {
+    if old.same_type(new) {
+        Ok(())
+    } else {
+        Err(format_err!("Type mismatch at '{}': expected {}, found {}",
+                        path,
+                        old.type_str(),
+                        new.type_str()))
+    }
+}
+
+/// Remove the value at `path`, which might be a table
+fn remove(header: &mut Value, path: &str) -> Result<Option<Value>> {
+    let (parent_path, key) = match path.rfind('.') {
+        Some(pos) => (Some(&path[..pos]), &path[pos + 1..]),
+        None      => (None, path),
+    };
+
+    let parent = match parent_path {
+        Some(parent_path) => header.read_mut(parent_path)?,
+        None              => Some(header),
+    };
+
+    match parent {
+        Some(&mut Value::Table(ref mut table)) => Ok(table.remove(key)),
+        Some(_) => Err(format_err!("Cannot remove '{}': '{}' is not a table",
+                                   path,
+                                   parent_path.unwrap_or(""))),
+        None    => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Value {
+        ::toml::de::from_str(r#"
+            [note]
+            name = "foo"
+            tags = ["a", "b"]
+            count = 1
+        "#).unwrap()
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("true", ValueType::Auto).unwrap(), Value::Boolean(true));
+        assert_eq!(parse_value("12", ValueType::Auto).unwrap(), Value::Integer(12));
+        assert_eq!(parse_value("1.5", ValueType::Auto).unwrap(), Value::Float(1.5));
+        assert_eq!(parse_value("foo", ValueType::Auto).unwrap(), Value::String(String::from("foo")));
+        assert_eq!(parse_value("12", ValueType::String).unwrap(), Value::String(String::from("12")));
+        assert!(parse_value("foo", ValueType::Integer).is_err());
+
+        let ary = parse_value("[1, 2]", ValueType::Toml).unwrap();
+        assert_eq!(ary, Value::Array(vec![Value::Integer(1), Value::Integer(2)]));
+    }
+
+    #[test]
+    fn test_get() {
+        let mut h = header();
+        let value = Operation::Get(String::from("note.name")).apply(&mut h).unwrap();
+        assert_eq!(value, Some(Value::String(String::from("foo"))));
+        assert!(Operation::Get(String::from("note.missing")).apply(&mut h).is_err());
+    }
+
+    #[test]
+    fn test_set() {
+        let mut h = header();
+        let op = Operation::Set {
+            path: String::from("note.meta.rating"),
+            value: Value::Integer(5),
+            force: false,
+        };
+        assert!(op.apply(&mut h).is_ok());
+        assert_eq!(h.read("note.meta.rating").unwrap(), Some(&Value::Integer(5)));
+    }
+
+    #[test]
+    fn test_set_type_mismatch() {
+        let mut h = header();
+        let mut op = Operation::Set {
+            path: String::from("note.count"),
+            value: Value::String(String::from("many")),
+            force: false,
+        };
+        assert!(op.apply(&mut h).is_err());
+
+        if let Operation::Set { ref mut force, .. } = op {
+            *force = true;
+        }
+        assert!(op.apply(&mut h).is_ok());
+        assert_eq!(h.read("note.count").unwrap(), Some(&Value::String(String::from("many"))));
+    }
+
+    #[test]
+    fn test_unset() {
+        let mut h = header();
+        assert!(Operation::Unset(String::from("note.count")).apply(&mut h).is_ok());
+        assert_eq!(h.read("note.count").unwrap(), None);
+        assert!(Operation::Unset(String::from("note.count")).apply(&mut h).is_err());
+
+        assert!(Operation::Unset(String::from("note")).apply(&mut h).is_ok());
+        assert_eq!(h.read("note").unwrap(), None);
+    }
+
+    #[test]
+    fn test_append_to_array() {
+        let mut h = header();
+        let op = Operation::AppendToArray {
+            path: String::from("note.tags"),
+            value: Value::String(String::from("c")),
+        };
+        assert!(op.apply(&mut h).is_ok());
+
+        let tags = h.read("note.tags").unwrap().unwrap().as_array().unwrap().clone();
+        assert_eq!(tags.len(), 3);
+
+        let mismatch = Operation::AppendToArray {
+            path: String::from("note.tags"),
+            value: Value::Integer(1),
+        };
+        assert!(mismatch.apply(&mut h).is_err());
+
+        let not_an_array = Operation::AppendToArray {
+            path: String::from("note.name"),
+            value: Value::String(String::from("c")),
+        };
+        assert!(not_an_array.apply(&mut h).is_err());
+
+        let new_array = Operation::AppendToArray {
+            path: String::from("note.refs"),
+            value: Value::Integer(1),
+        };
+        assert!(new_array.apply(&mut h).is_ok());
+        assert_eq!(h.read("note.refs").unwrap(), Some(&Value::Array(vec![Value::Integer(1)])));
+    }
+
+    #[test]
+    fn test_rename_key() {
+        let mut h = header();
+        let op = Operation::RenameKey {
+            from: String::from("note.name"),
+            to: String::from("note.title"),
+            force: false,
+        };
+        assert!(op.apply(&mut h).is_ok());
+        assert_eq!(h.read("note.name").unwrap(), None);
+        assert_eq!(h.read("note.title").unwrap(), Some(&Value::String(String::from("foo"))));
+
+        let conflict = Operation::RenameKey {
+            from: String::from("note.title"),
+            to: String::from("note.count"),
+            force: false,
+        };
+        assert!(conflict.apply(&mut h).is_err());
+    }
+}
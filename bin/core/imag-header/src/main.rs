@@ -0,0 +1,197 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate log;
+#[macro_use] extern crate failure;
+extern crate clap;
+extern crate toml;
+extern crate toml_query;
+
+extern crate libimagstore;
+#[macro_use] extern crate libimagrt;
+extern crate libimagerror;
+
+use std::io::Write;
+use std::process::exit;
+
+use failure::Fallible as Result;
+use toml::Value;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagstore::storeid::StoreId;
+
+mod header;
+mod ui;
+
+use header::Operation;
+use header::ValueType;
+use header::parse_value;
+
+fn main() {
+    let version = make_imag_version!();
+    let rt = generate_runtime_setup("imag-header",
+                                    &version,
+                                    "Read and modify header values of entries",
+                                    ui::build_ui);
+
+    let operation = rt.cli()
+        .subcommand_name()
+        .map(|name| match name {
+            "get"             => Operation::Get(header_path(&rt, name)),
+            "set"             => Operation::Set {
+                path: header_path(&rt, name),
+                value: value(&rt, name),
+                force: rt.cli().subcommand_matches(name).unwrap().is_present("force"),
+            },
+            "unset"           => Operation::Unset(header_path(&rt, name)),
+            "append-to-array" => Operation::AppendToArray {
+                path: header_path(&rt, name),
+                value: value(&rt, name),
+            },
+            "rename-key"      => Operation::RenameKey {
+                from: header_path(&rt, name),
+                to: String::from(rt.cli().subcommand_matches(name).unwrap().value_of("to").unwrap()), // safe by clap
+                force: rt.cli().subcommand_matches(name).unwrap().is_present("force"),
+            },
+            other => {
+                debug!("Unknown command");
+                let _ = rt.handle_unknown_subcommand("imag-header", other, rt.cli())
+                    .map_err_trace_exit_unwrap(1)
+                    .code()
+                    .map(::std::process::exit);
+                exit(1)
+            },
+        })
+        .unwrap_or_else(|| {
+            error!("No command given");
+            exit(1)
+        });
+
+    let with_id = rt.cli()
+        .subcommand_matches("get")
+        .map(|scmd| scmd.is_present("with-id"))
+        .unwrap_or(false);
+
+    let ids        = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+    let mut failed = 0;
+
+    for id in ids.iter() {
+        match process(&rt, id, &operation) {
+            Ok(value) => {
+                if let Some(value) = value {
+                    print_value(&rt, id, &value, with_id);
+                }
+
+                rt.report_touched(id).map_err_trace_exit_unwrap(1);
+            },
+            Err(e) => {
+                failed += 1;
+                error!("Failed to process {}", id);
+                trace_error(&e);
+            },
+        }
+    }
+
+    if failed != 0 {
+        error!("{} of {} entries failed", failed, ids.len());
+        exit(1)
+    }
+}
+
+fn header_path(rt: &Runtime, scmd: &str) -> String {
+    rt.cli()
+        .subcommand_matches(scmd)
+        .unwrap() // safe by main()
+        .value_of("header-path")
+        .map(String::from)
+        .unwrap() // safe by clap
+}
+
+fn value(rt: &Runtime, scmd: &str) -> Value {
+    let scmd = rt.cli().subcommand_matches(scmd).unwrap(); // safe by main()
+    let ty   = scmd
+        .value_of("type")
+        .and_then(ValueType::from_name)
+        .unwrap(); // safe by clap
+
+    parse_value(scmd.value_of("value").unwrap(), ty) // safe by clap
+        .map_err_trace_exit_unwrap(1)
+}
+
+/// Apply `operation` to the header of the entry `id`
+///
+/// The entry is only altered if the operation succeeds and the resulting header verifies.
+fn process(rt: &Runtime, id: &StoreId, operation: &Operation) -> Result<Option<Value>> {
+    let mut entry = rt.store()
+        .get(id.clone())?
+        .ok_or_else(|| format_err!("Entry not found: {}", id))?;
+
+    let mut header = entry.get_header().clone();
+    let value      = operation.apply(&mut header)?;
+
+    if operation.is_modifying() {
+        let mut check = (*entry).clone();
+        *check.get_header_mut() = header.clone();
+        let _ = check.verify()?;
+
+        *entry.get_header_mut() = header;
+    }
+
+    Ok(value)
+}
+
+fn print_value(rt: &Runtime, id: &StoreId, value: &Value, with_id: bool) {
+    let value = match *value {
+        Value::String(ref s) => s.clone(),
+        Value::Table(_)      => ::toml::ser::to_string_pretty(value).map_err_trace_exit_unwrap(1),
+        _                    => value.to_string(),
+    };
+
+    let mut out = rt.stdout();
+
+    let result  = if with_id {
+        writeln!(out, "{} {}", id, value)
+    } else {
+        writeln!(out, "{}", value)
+    };
+
+    result.to_exit_code().unwrap_or_exit();
+}
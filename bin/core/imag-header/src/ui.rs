@@ -0,0 +1,143 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+
+use clap::{Arg, ArgMatches, App, SubCommand};
+
+use libimagstore::storeid::StoreId;
+use libimagstore::storeid::IntoStoreId;
+use libimagrt::runtime::IdPathProvider;
+use libimagerror::trace::MapErrTrace;
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app.arg(Arg::with_name("id")
+                .index(1)
+                .takes_value(true)
+                .required(false)
+                .multiple(true)
+                .value_name("ID")
+                .help("Entries to work on. If not passed, the ids are read from stdin"))
+
+        .subcommand(SubCommand::with_name("get")
+                    .about("Print a header value")
+                    .version("0.1")
+                    .arg(header_path_arg("The header path to read, for example 'note.name'"))
+                    .arg(Arg::with_name("with-id")
+                         .long("with-id")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Print the id of the entry in front of the value"))
+                    )
+
+        .subcommand(SubCommand::with_name("set")
+                    .about("Set a header value, creating missing tables on the way")
+                    .version("0.1")
+                    .arg(header_path_arg("The header path to set"))
+                    .arg(value_arg("The value to set"))
+                    .arg(type_arg())
+                    .arg(force_arg("Overwrite values of another type"))
+                    )
+
+        .subcommand(SubCommand::with_name("unset")
+                    .about("Remove a header value")
+                    .version("0.1")
+                    .arg(header_path_arg("The header path to remove"))
+                    )
+
+        .subcommand(SubCommand::with_name("append-to-array")
+                    .about("Append a value to an array, creating the array if it does not exist")
+                    .version("0.1")
+                    .arg(header_path_arg("The header path of the array"))
+                    .arg(value_arg("The value to append"))
+                    .arg(type_arg())
+                    )
+
+        .subcommand(SubCommand::with_name("rename-key")
+                    .about("Move a header value to another path")
+                    .version("0.1")
+                    .arg(header_path_arg("The header path to move"))
+                    .arg(Arg::with_name("to")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .value_name("TO")
+                         .help("The new header path"))
+                    .arg(force_arg("Overwrite an existing value at the new path"))
+                    )
+
+        .after_help("Errors, for example type mismatches, are reported per entry. \
+                    The other entries are processed nonetheless.")
+}
+
+fn header_path_arg<'a>(help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name("header-path")
+        .index(1)
+        .takes_value(true)
+        .required(true)
+        .multiple(false)
+        .value_name("PATH")
+        .help(help)
+}
+
+fn value_arg<'a>(help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name("value")
+        .index(2)
+        .takes_value(true)
+        .required(true)
+        .multiple(false)
+        .value_name("VALUE")
+        .help(help)
+}
+
+fn type_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("type")
+        .long("type")
+        .short("t")
+        .takes_value(true)
+        .required(false)
+        .multiple(false)
+        .value_name("TYPE")
+        .possible_values(&["auto", "string", "int", "float", "bool", "datetime", "toml"])
+        .default_value("auto")
+        .help("The type of the value. 'auto' guesses bools, integers and floats, everything else is a string. 'toml' parses the value as TOML, for example '[1, 2]'")
+}
+
+fn force_arg<'a>(help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name("force")
+        .long("force")
+        .short("f")
+        .takes_value(false)
+        .required(false)
+        .help(help)
+}
+
+pub struct PathProvider;
+impl IdPathProvider for PathProvider {
+    fn get_ids(matches: &ArgMatches) -> Vec<StoreId> {
+        matches.values_of("id")
+            .unwrap_or_else(|| {
+                error!("No ids passed, neither as arguments nor on stdin");
+                ::std::process::exit(1)
+            })
+            .map(|s| PathBuf::from(s).into_storeid().map_err_trace_exit_unwrap(1))
+            .collect()
+    }
+}
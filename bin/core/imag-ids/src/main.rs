@@ -36,18 +36,12 @@
 
 extern crate clap;
 extern crate filters;
-#[macro_use] extern crate nom;
 #[macro_use] extern crate log;
-#[macro_use] extern crate is_match;
-extern crate toml;
-extern crate toml_query;
 extern crate failure;
 
-#[cfg(test)]
-extern crate env_logger;
-
 extern crate libimagerror;
 extern crate libimagstore;
+extern crate libimagentryquery;
 #[macro_use] extern crate libimagrt;
 
 use std::io::Write;
@@ -57,10 +51,15 @@ use filters::filter::Filter;
 
 use libimagstore::storeid::StoreId;
 use libimagrt::setup::generate_runtime_setup;
+use libimagrt::runtime::Runtime;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
+use libimagentryquery::query::Query;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
+use libimagentryquery::saved::SavedSearchStore;
 
 mod id_filters;
 mod ui;
@@ -75,6 +74,12 @@ fn main() {
                                     "print all ids",
                                     build_ui);
 
+    match rt.cli().subcommand_name() {
+        Some("list-saved")   => return list_saved(&rt),
+        Some("delete-saved") => return delete_saved(&rt),
+        _ => {},
+    }
+
     let print_storepath = rt.cli().is_present("print-storepath");
 
     let values = rt
@@ -83,13 +88,21 @@ fn main() {
         .map(|v| v.collect::<Vec<&str>>());
 
     let collection_filter = IsInCollectionsFilter::new(values);
-    let query_filter      : Option<id_filters::header_filter_lang::Query> = rt
+    let query_filter      : Option<Query> = rt
         .cli()
         .subcommand_matches("where")
         .map(|matches| {
             let query = matches.value_of("where-filter").unwrap(); // safe by clap
-            id_filters::header_filter_lang::parse(&query)
+
+            if let Some(name) = matches.value_of("where-save") {
+                let _ = rt.store().save_search(name, query).map_err_trace_exit_unwrap(1);
+            }
+
+            Query::compile(&query, rt.store()).map_err_trace_exit_unwrap(1)
         });
+    let saved_filter      : Option<Query> = query_from_cli(rt.cli(), rt.store())
+        .map_err_trace_exit_unwrap(1);
+    let queries           : Vec<&Query> = query_filter.iter().chain(saved_filter.iter()).collect();
 
     let iterator = if rt.ids_from_stdin() {
         debug!("Fetching IDs from stdin...");
@@ -102,20 +115,19 @@ fn main() {
     }
     .trace_unwrap_exit(1)
     .filter(|id| collection_filter.filter(id))
-    .filter(|id| match query_filter.as_ref() {
-        None     => true,
-        Some(qf) => {
-            let entry = rt
-                .store()
-                .get(id.clone())
-                .map_err_trace_exit_unwrap(1)
-                .unwrap_or_else(|| {
-                    error!("Tried to get '{}', but it does not exist!", id);
-                    exit(1)
-                });
-
-            qf.filter(&entry)
-        }
+    .filter(|id| if queries.is_empty() {
+        true
+    } else {
+        let entry = rt
+            .store()
+            .get(id.clone())
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or_else(|| {
+                error!("Tried to get '{}', but it does not exist!", id);
+                exit(1)
+            });
+
+        queries.iter().all(|q| entry_matches(Some(q), &entry).map_err_trace_exit_unwrap(1))
     })
     .map(|id| if print_storepath {
         id
@@ -138,3 +150,32 @@ fn main() {
     })
 }
 
+fn list_saved(rt: &Runtime) {
+    let mut names = rt.store().all_saved_search_names().map_err_trace_exit_unwrap(1);
+    names.sort();
+
+    let mut stdout = rt.stdout();
+    for name in names {
+        let query = rt
+            .store()
+            .get_saved_search(&name)
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or_default();
+
+        let _ = writeln!(stdout, "{}: {}", name, query)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+}
+
+fn delete_saved(rt: &Runtime) {
+    let name = rt
+        .cli()
+        .subcommand_matches("delete-saved")
+        .unwrap() // safe by main()
+        .value_of("name")
+        .unwrap(); // safe by clap
+
+    let _ = rt.store().delete_saved_search(name).map_err_trace_exit_unwrap(1);
+    info!("Deleted saved search '{}'", name);
+}
@@ -21,6 +21,7 @@ use clap::{Arg, ArgMatches, App, SubCommand};
 
 use libimagstore::storeid::StoreId;
 use libimagrt::runtime::IdPathProvider;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -40,6 +41,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
              .value_names(&["COLLECTION"])
              .help("Filter for ids which are only in these collections"))
 
+        .arg(saved_arg())
+
         .subcommand(SubCommand::with_name("where")
                     .arg(Arg::with_name("where-filter")
                          .index(1)
@@ -47,9 +50,30 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .takes_value(true)
                          .multiple(false)
                          .value_names(&["QUERY"])
-                         .help("Query the header of the entries and filter them"))
+                         .help("Query the entries and filter them"))
+                    .arg(Arg::with_name("where-save")
+                         .long("save")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .value_name("NAME")
+                         .help("Save the query as saved search with this name, so it can be used with --saved"))
+                   )
+
+        .subcommand(SubCommand::with_name("list-saved")
+                    .about("List the saved searches and their queries"))
+
+        .subcommand(SubCommand::with_name("delete-saved")
+                    .about("Delete a saved search")
+                    .arg(Arg::with_name("name")
+                         .index(1)
+                         .required(true)
+                         .takes_value(true)
+                         .multiple(false)
+                         .value_name("NAME")
+                         .help("The name of the saved search to delete"))
                    )
-        .after_help(include_str!("../static/language-doc.md"))
+        .after_help(::libimagentryquery::LANGUAGE_DOC)
 }
 
 pub struct PathProvider;
@@ -41,7 +41,9 @@ extern crate clap;
 extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagentrylink;
+extern crate libimagentrymarkdown;
 
+use std::io::Write;
 use std::process::exit;
 
 mod ui;
@@ -52,10 +54,13 @@ use std::path::PathBuf;
 use libimagrt::setup::generate_runtime_setup;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
 use libimagstore::storeid::StoreId;
 use libimagstore::store::Store;
 use libimagstore::store::FileLockEntry;
 use libimagentrylink::internal::InternalLinker;
+use libimagentrymarkdown::rewrite::rewrite_links_in;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
 
 fn main() {
@@ -135,7 +140,26 @@ fn main() {
         .map_err_trace_exit_unwrap(1);
 
     // re-add links to moved entry
-    relink(rt.store(), destname, &mut linked_entries);
+    relink(rt.store(), destname.clone(), &mut linked_entries);
+
+    let linked_ids = linked_entries
+        .iter()
+        .map(|e| e.get_location().clone())
+        .collect::<Vec<_>>();
+    drop(linked_entries);
+
+    // rewrite links to the old id in the content of the linked entries
+    let touched = rewrite_links_in(rt.store(), linked_ids, &sourcename, &destname)
+        .map_err_trace_exit_unwrap(1);
+
+    for id in touched {
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        if !rt.output_is_pipe() {
+            let _ = writeln!(rt.stdout(), "Rewrote links in {}", id)
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    }
 
     info!("Ok.");
 }
@@ -21,6 +21,7 @@ use clap::{Arg, App};
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
+        .after_help("Links to the moved entry are updated: internal links as well as markdown links and [[wiki links]] in the content of the entries linked with it through internal links. The entries whose content was changed are reported.")
         .arg(Arg::with_name("source")
              .index(1)
              .takes_value(true)
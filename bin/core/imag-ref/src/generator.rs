@@ -0,0 +1,60 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Selection of the `UniqueRefPathGenerator` by name
+
+use clap::ArgMatches;
+
+use libimagerror::trace::MapErrTrace;
+use libimagstore::store::Entry;
+use libimagentryref::config::Config as RefConfig;
+use libimagentryref::reference::Ref;
+use libimagentryref::generators::DEFAULT_GENERATOR;
+use libimagentryref::generators::GENERATOR_COLLECTION;
+
+/// Get the name of the generator to use for refs in `collection`
+///
+/// The `--generator` argument wins over the generator configured for the collection in
+/// `ref.generators`, which wins over `DEFAULT_GENERATOR`.
+pub fn generator_name<'a>(cmd: &'a ArgMatches, config: &'a RefConfig, collection: &str) -> &'a str {
+    cmd.value_of("generator")
+        .or_else(|| config.generator_for(collection))
+        .unwrap_or(DEFAULT_GENERATOR)
+}
+
+/// Get the name of the generator `entry` was created with
+///
+/// Refs store the name of their generator in `ref.generator`. For refs created before that, the
+/// generator from `generator_name()` for the collection of the ref is used.
+pub fn stored_generator_name(entry: &Entry, cmd: &ArgMatches, config: &RefConfig) -> String {
+    entry.get_generator()
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| String::from(generator_name(cmd, config, &collection_of(entry))))
+}
+
+/// Get the collection `entry` lives in
+fn collection_of(entry: &Entry) -> String {
+    entry.get_location()
+        .local()
+        .components()
+        .next()
+        .and_then(|c| c.as_os_str().to_str())
+        .map(String::from)
+        .unwrap_or_else(|| String::from(GENERATOR_COLLECTION))
+}
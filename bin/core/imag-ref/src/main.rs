@@ -36,25 +36,50 @@
 
 #[macro_use] extern crate log;
 extern crate clap;
+extern crate walkdir;
+extern crate failure;
 
 extern crate libimagstore;
 #[macro_use] extern crate libimagrt;
-extern crate libimagentryref;
+#[macro_use] extern crate libimagentryref;
+extern crate libimagentryquery;
 extern crate libimagerror;
 extern crate libimaginteraction;
 extern crate libimagutil;
 
+mod generator;
 mod ui;
 use ui::build_ui;
 
 use std::path::PathBuf;
+use std::collections::BTreeMap;
 use std::process::exit;
+use std::io::Write;
+
+use clap::ArgMatches;
+use failure::Error;
+use walkdir::WalkDir;
 
 use libimagerror::trace::MapErrTrace;
+use libimagerror::iter::TraceIterator;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
 use libimagrt::setup::generate_runtime_setup;
 use libimagrt::runtime::Runtime;
 use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+use libimagstore::store::Entry;
+use libimagentryref::config::Config as RefConfig;
 use libimagentryref::reference::Ref;
+use libimagentryref::reference::hash_candidates;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+use libimagentryref::refstore::RefStore;
+use libimagentryref::generators::GENERATOR_COLLECTION;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
+
+use generator::generator_name;
+use generator::stored_generator_name;
 
 fn main() {
     let version = make_imag_version!();
@@ -67,8 +92,13 @@ fn main() {
         .map(|name| {
             debug!("Call: {}", name);
             match name {
-                "deref"  => deref(&rt),
-                "remove" => remove(&rt),
+                "create"   => create(&rt),
+                "deref"    => deref(&rt),
+                "list"     => list(&rt),
+                "check"    => check(&rt),
+                "remove"   => remove(&rt),
+                "relocate" => relocate(&rt),
+                "migrate"  => migrate(&rt),
                 other => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-ref", other, rt.cli())
@@ -80,6 +110,191 @@ fn main() {
         });
 }
 
+fn create(rt: &Runtime) {
+    let cmd    = rt.cli().subcommand_matches("create").unwrap();
+    let config = ref_config(rt);
+
+    let collection = cmd.value_of("collection").unwrap_or(GENERATOR_COLLECTION);
+    let name       = generator_name(cmd, &config, collection);
+
+    let _ = with_generator!(name, create_with(rt, cmd, &config, collection, name))
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn create_with<RPG>(rt: &Runtime,
+                    cmd: &ArgMatches,
+                    config: &RefConfig,
+                    collection: &str,
+                    generator: &str)
+    where RPG: UniqueRefPathGenerator
+{
+    let mut out = rt.stdout();
+
+    let pathes = files_in(cmd.values_of("path").unwrap()); // saved by clap
+
+    for path in pathes {
+        let path = path
+            .canonicalize()
+            .map_err(Error::from)
+            .map_err_trace_exit_unwrap(1);
+
+        let mut entry = rt
+            .store()
+            .retrieve_ref_in::<RPG, _>(collection, &path, config)
+            .map_err_trace_exit_unwrap(1);
+
+        let _ = entry.set_generator(generator).map_err_trace_exit_unwrap(1);
+
+        let _ = writeln!(out, "{}", entry.get_location())
+            .to_exit_code()
+            .unwrap_or_exit();
+        let _ = rt.report_touched(entry.get_location()).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn list(rt: &Runtime) {
+    let cmd    = rt.cli().subcommand_matches("list").unwrap();
+    let config = ref_config(rt);
+
+    let only_dangling = cmd.is_present("dangling");
+    let only_changed  = cmd.is_present("changed");
+    let query         = query_from_cli(cmd, rt.store()).map_err_trace_exit_unwrap(1);
+    let collections   = ref_collections(&config);
+    let mut out       = rt.stdout();
+
+    for id in ids_from_cli(rt, cmd, Some(&collections[..])) {
+        let entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None => {
+                error!("No entry for id '{}' found", id);
+                exit(1)
+            },
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        if !entry_matches(query.as_ref(), &entry).map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        if only_dangling && RefState::of_path(&entry, &config) != RefState::Dangling {
+            continue;
+        }
+
+        // Hashing the referenced file is expensive, so it is only done for `--changed`
+        if only_changed && ref_state(&entry, cmd, &config) != RefState::Changed {
+            continue;
+        }
+
+        let path = match entry.get_path(&config) {
+            Ok(path) => path,
+            Err(e)   => {
+                warn!("Cannot resolve the path of {}: {}", id, e);
+                continue;
+            },
+        };
+
+        let _ = writeln!(out, "{} -> {}", id, path.display())
+            .to_exit_code()
+            .unwrap_or_exit();
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn check(rt: &Runtime) {
+    let cmd    = rt.cli().subcommand_matches("check").unwrap();
+    let config = ref_config(rt);
+
+    let collections = ref_collections(&config);
+    let mut out     = rt.stdout();
+    let mut broken  = 0;
+
+    for id in ids_from_cli(rt, cmd, Some(&collections[..])) {
+        let entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None => {
+                error!("No entry for id '{}' found", id);
+                exit(1)
+            },
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        let state = match ref_state(&entry, cmd, &config) {
+            RefState::Ok             => continue,
+            RefState::Dangling       => String::from("dangling"),
+            RefState::Changed        => String::from("changed"),
+            RefState::Broken(reason) => format!("broken ({})", reason),
+        };
+
+        broken += 1;
+        let _ = writeln!(out, "{}: {}", state, id)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    if broken != 0 {
+        info!("{} refs are dangling, changed or broken", broken);
+        exit(1)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RefState {
+    Ok,
+    Dangling,
+    Changed,
+
+    /// The path of the ref cannot be resolved or the file cannot be hashed, for example because
+    /// the base path of the ref is not configured on this machine
+    Broken(String),
+}
+
+impl RefState {
+    /// Get the state of `entry` without hashing the referenced file
+    fn of_path(entry: &Entry, config: &RefConfig) -> RefState {
+        match entry.is_dangling(config) {
+            Ok(true)  => RefState::Dangling,
+            Ok(false) => RefState::Ok,
+            Err(e)    => RefState::Broken(e.to_string()),
+        }
+    }
+
+    fn of<RPG: UniqueRefPathGenerator>(entry: &Entry, config: &RefConfig) -> RefState {
+        match RefState::of_path(entry, config) {
+            RefState::Ok => match entry.hash_valid::<RPG>(config) {
+                Ok(true)  => RefState::Ok,
+                Ok(false) => RefState::Changed,
+                Err(e)    => RefState::Broken(e.to_string()),
+            },
+            other => other,
+        }
+    }
+}
+
+/// Get the state of the ref `entry`, hashing with the generator it was created with
+///
+/// Refs whose generator is not known here (for example mails imported with the built-in generator
+/// of imag-mail) are not hashed.
+fn ref_state(entry: &Entry, cmd: &ArgMatches, config: &RefConfig) -> RefState {
+    let name = stored_generator_name(entry, cmd, config);
+    match with_generator!(name.as_str(), ref_state_with(entry, config)) {
+        Ok(state) => state,
+        Err(e)    => {
+            debug!("Not hashing {}: {}", entry.get_location(), e);
+            RefState::of_path(entry, config)
+        },
+    }
+}
+
+fn ref_state_with<RPG: UniqueRefPathGenerator>(entry: &Entry, config: &RefConfig) -> RefState {
+    RefState::of::<RPG>(entry, config)
+}
+
 fn deref(rt: &Runtime) {
     let cmd  = rt.cli().subcommand_matches("deref").unwrap();
     let id   = cmd.value_of("ID")
@@ -92,7 +307,7 @@ fn deref(rt: &Runtime) {
     match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
         Some(entry) => {
             entry
-                .get_path()
+                .get_path(&ref_config(rt))
                 .map_err_trace_exit_unwrap(1)
                 .to_str()
                 .ok_or_else(|| {
@@ -148,3 +363,189 @@ fn remove(rt: &Runtime) {
     };
 }
 
+fn relocate(rt: &Runtime) {
+    let cmd    = rt.cli().subcommand_matches("relocate").unwrap();
+    let config = ref_config(rt);
+
+    let candidates = files_in(cmd.values_of("search").unwrap()); // saved by clap
+
+    debug!("Found {} candidate files", candidates.len());
+
+    // The candidates hashed with each generator used by a broken ref, hashed once per generator
+    let mut hashes : BTreeMap<String, BTreeMap<String, PathBuf>> = BTreeMap::new();
+
+    let collections = ref_collections(&config);
+    let ids         = ids_from_cli(rt, cmd, Some(&collections[..]));
+
+    let mut out        = rt.stdout();
+    let mut unresolved = vec![];
+
+    for id in ids {
+        let mut entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None => {
+                error!("No entry for id '{}' found", id);
+                exit(1)
+            },
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            debug!("Not a ref: {}", id);
+            continue;
+        }
+
+        let name = stored_generator_name(&entry, cmd, &config);
+
+        if ref_state(&entry, cmd, &config) == RefState::Ok {
+            debug!("Ref is fine: {}", id);
+            continue;
+        }
+
+        if !hashes.contains_key(&name) {
+            match with_generator!(name.as_str(), hash_candidates_with(&candidates)) {
+                Ok(generator_hashes) => {
+                    let _ = hashes.insert(name.clone(), generator_hashes);
+                },
+                Err(e) => {
+                    warn!("Cannot relocate {}: {}", id, e);
+                    unresolved.push(id);
+                    continue;
+                },
+            }
+        }
+
+        match entry.relocate_by_hash(&hashes[&name], &config) {
+            Ok(Some(path)) => {
+                let _ = writeln!(out, "{} -> {}", id, path.display())
+                    .to_exit_code()
+                    .unwrap_or_exit();
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            },
+            Ok(None) => unresolved.push(id),
+            Err(e)   => {
+                warn!("Cannot relocate {}: {}", id, e);
+                unresolved.push(id);
+            },
+        }
+    }
+
+    if !unresolved.is_empty() {
+        let _ = writeln!(out, "Unresolved refs:")
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        for id in unresolved {
+            let _ = writeln!(out, "{}", id)
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+
+        exit(1)
+    }
+}
+
+fn hash_candidates_with<RPG>(candidates: &[PathBuf]) -> BTreeMap<String, PathBuf>
+    where RPG: UniqueRefPathGenerator
+{
+    hash_candidates::<RPG, _, _>(candidates.iter())
+}
+
+fn migrate(rt: &Runtime) {
+    let cmd    = rt.cli().subcommand_matches("migrate").unwrap();
+    let config = ref_config(rt);
+
+    let ids = ids_from_cli(rt, cmd, None);
+
+    let mut out = rt.stdout();
+
+    for id in ids {
+        let mut entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None => {
+                error!("No entry for id '{}' found", id);
+                exit(1)
+            },
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        if entry.make_portable(&config).map_err_trace_exit_unwrap(1) {
+            let _ = writeln!(out, "{}", id)
+                .to_exit_code()
+                .unwrap_or_exit();
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        }
+    }
+}
+
+/// Get the ids passed on the commandline, or all ids (of `collections`, if passed) if none were
+/// passed
+fn ids_from_cli(rt: &Runtime, cmd: &ArgMatches, collections: Option<&[String]>) -> Vec<StoreId> {
+    match cmd.values_of("ID") {
+        Some(ids) => ids
+            .map(PathBuf::from)
+            .map(|pb| pb.into_storeid().map_err_trace_exit_unwrap(1))
+            .collect(),
+        None => match collections {
+            Some(collections) => collections
+                .iter()
+                .flat_map(|c| {
+                    rt.store()
+                        .entries()
+                        .map_err_trace_exit_unwrap(1)
+                        .in_collection(c)
+                        .without_store()
+                        .trace_unwrap_exit(1)
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            None => rt.store()
+                .entries()
+                .map_err_trace_exit_unwrap(1)
+                .without_store()
+                .trace_unwrap_exit(1)
+                .collect(),
+        },
+    }
+}
+
+/// Get the collections refs are looked up in: the one of the provided generators and all
+/// collections a generator is configured for in `ref.generators`
+fn ref_collections(config: &RefConfig) -> Vec<String> {
+    let mut collections = vec![String::from(GENERATOR_COLLECTION)];
+
+    for collection in config.generator_collections() {
+        if !collections.iter().any(|c| c == collection) {
+            collections.push(String::from(collection));
+        }
+    }
+
+    collections
+}
+
+/// Get all files in the passed pathes, recursing into directories
+fn files_in<'a, I: Iterator<Item = &'a str>>(pathes: I) -> Vec<PathBuf> {
+    pathes
+        .flat_map(|path| WalkDir::new(path).follow_links(false).into_iter())
+        .filter_map(|dirent| match dirent {
+            Ok(dirent) => if dirent.file_type().is_file() {
+                Some(dirent.path().to_path_buf())
+            } else {
+                None
+            },
+            Err(e) => {
+                warn!("Cannot read: {}", e);
+                None
+            },
+        })
+        .collect()
+}
+
+fn ref_config(rt: &Runtime) -> RefConfig {
+    rt.config()
+        .map(RefConfig::from_config)
+        .unwrap_or_else(|| Ok(RefConfig::default()))
+        .map_err_trace_exit_unwrap(1)
+}
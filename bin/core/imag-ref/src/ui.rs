@@ -19,6 +19,26 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
+/// The names of the generators which can be selected
+///
+/// These are the generators `with_generator!()` from libimagentryref dispatches on. Defined here,
+/// because this file is also included by the build script of `imag`, which does not depend on
+/// libimagentryref.
+pub const GENERATORS : &'static [&'static str] = &[
+    "sha1",
+    "sha224",
+    "sha256",
+    "sha384",
+    "sha512",
+    "sha3",
+    "partial-sha256",
+    "path",
+    "inode-mtime",
+];
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("deref")
@@ -32,6 +52,94 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .value_name("ID"))
                     )
 
+        .subcommand(SubCommand::with_name("create")
+                .about("Create refs to files")
+                .version("0.1")
+                .arg(Arg::with_name("path")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(true)
+                     .help("The files to create refs for. Directories are traversed recursively")
+                     .value_name("PATH"))
+
+                .arg(Arg::with_name("generator")
+                     .long("generator")
+                     .short("g")
+                     .takes_value(true)
+                     .required(false)
+                     .possible_values(GENERATORS)
+                     .help("The generator to create the refs with (default: from config for the collection, or sha512)")
+                     .value_name("GENERATOR"))
+
+                .arg(Arg::with_name("collection")
+                     .long("collection")
+                     .short("c")
+                     .takes_value(true)
+                     .required(false)
+                     .help("The collection to create the refs in (default: ref)")
+                     .value_name("COLLECTION"))
+                )
+
+        .subcommand(SubCommand::with_name("list")
+                .about("List refs and the files they reference")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(true)
+                     .help("List only these entries (default: all refs in the ref collection and the collections configured in ref.generators)")
+                     .value_name("ENTRIES"))
+
+                .arg(Arg::with_name("dangling")
+                     .long("dangling")
+                     .takes_value(false)
+                     .required(false)
+                     .conflicts_with("changed")
+                     .help("List only refs whose file does not exist anymore"))
+
+                .arg(Arg::with_name("changed")
+                     .long("changed")
+                     .takes_value(false)
+                     .required(false)
+                     .help("List only refs whose file does not match the hash anymore"))
+
+                .arg(Arg::with_name("generator")
+                     .long("generator")
+                     .short("g")
+                     .takes_value(true)
+                     .required(false)
+                     .possible_values(GENERATORS)
+                     .help("The generator for refs which do not store the generator they were created with (default: from config, or sha512)")
+                     .value_name("GENERATOR"))
+
+                .arg(where_arg())
+
+                .arg(saved_arg())
+                )
+
+        .subcommand(SubCommand::with_name("check")
+                .about("Report refs which are dangling or whose file does not match the hash anymore")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(true)
+                     .help("Check only these entries (default: all refs in the ref collection and the collections configured in ref.generators)")
+                     .value_name("ENTRIES"))
+
+                .arg(Arg::with_name("generator")
+                     .long("generator")
+                     .short("g")
+                     .takes_value(true)
+                     .required(false)
+                     .possible_values(GENERATORS)
+                     .help("The generator for refs which do not store the generator they were created with (default: from config, or sha512)")
+                     .value_name("GENERATOR"))
+                )
+
         .subcommand(SubCommand::with_name("remove")
                 .about("Remove a reference from an entry")
                 .version("0.1")
@@ -48,4 +156,46 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                      .short("y")
                      .help("Don't ask whether this really should be done"))
                 )
+
+        .subcommand(SubCommand::with_name("relocate")
+                .about("Re-find moved or renamed referenced files by their hash and update the refs")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(true)
+                     .help("Relocate only these entries (default: all refs in the ref collection and the collections configured in ref.generators)")
+                     .value_name("ENTRIES"))
+
+                .arg(Arg::with_name("search")
+                     .long("search")
+                     .short("s")
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(true)
+                     .help("Directories to search (recursively) for the referenced files")
+                     .value_name("DIR"))
+
+                .arg(Arg::with_name("generator")
+                     .long("generator")
+                     .short("g")
+                     .takes_value(true)
+                     .required(false)
+                     .possible_values(GENERATORS)
+                     .help("The generator for refs which do not store the generator they were created with (default: from config, or sha512)")
+                     .value_name("GENERATOR"))
+                )
+
+        .subcommand(SubCommand::with_name("migrate")
+                .about("Convert refs with absolute pathes into refs relative to the configured base pathes")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(true)
+                     .help("Migrate only these entries (default: all refs)")
+                     .value_name("ENTRIES"))
+                )
 }
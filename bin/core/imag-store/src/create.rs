@@ -33,6 +33,8 @@ use libimagstore::store::Entry;
 use libimagstore::storeid::StoreId;
 use libimagerror::trace::MapErrTrace;
 use libimagutil::debug_result::*;
+use libimagentrytemplate::cli::apply_template_from_cli;
+use libimagentrytemplate::cli::TEMPLATE_ARG_NAME;
 
 use util::build_toml_header;
 
@@ -63,6 +65,17 @@ pub fn create(rt: &Runtime) {
     }
     .map_err_trace_exit_unwrap(1);
 
+    if scmd.is_present(TEMPLATE_ARG_NAME) {
+        let mut entry = rt
+            .store()
+            .get(path.clone())
+            .map_err_trace_exit_unwrap(1)
+            .ok_or_else(|| err_msg("Created entry does not exist"))
+            .map_err_trace_exit_unwrap(1);
+
+        let _ = apply_template_from_cli(rt, scmd, &mut entry).map_err_trace_exit_unwrap(1);
+    }
+
     let _ = rt.report_touched(&path).map_err_trace_exit_unwrap(1);
 }
 
@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, ArgGroup, SubCommand};
 
+use libimagentrytemplate::cli::template_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app.subcommand(SubCommand::with_name("create")
                    .about("Create an entry from the store")
@@ -34,6 +36,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(true)
                         .help("Create a new entry by reading this file ('-' for stdin)")
                         .value_name("FILE"))
+                   .arg(template_arg())
 
                    .subcommand(SubCommand::with_name("entry")
                                .about("Create an entry via commandline")
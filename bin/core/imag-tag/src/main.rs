@@ -44,6 +44,8 @@ extern crate libimagstore;
 #[macro_use] extern crate libimagrt;
 extern crate libimagentrytag;
 extern crate libimagerror;
+extern crate libimaginteraction;
+extern crate libimagentryquery;
 
 #[cfg(test)]
 #[macro_use]
@@ -64,12 +66,19 @@ use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagentrytag::tagable::Tagable;
 use libimagentrytag::tag::Tag;
+use libimagentrytag::registry::TagStore;
+use libimagentrytag::registry::RegisteredTag;
+use libimaginteraction::format::colour_from_name;
 use libimagerror::trace::trace_error;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagstore::storeid::StoreId;
 use libimagutil::warn_exit::warn_exit;
+use libimagentryquery::cli::ids_from_saved_search;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
+use libimagentryquery::query::Query;
 
 use clap::ArgMatches;
 
@@ -84,26 +93,35 @@ fn main() {
                                     "Direct interface to the store. Use with great care!",
                                     build_ui);
 
-    let ids = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
-
     rt.cli()
         .subcommand_name()
         .map(|name| match name {
-            "list" => for id in ids {
-                list(id, &rt)
+            "list" => {
+                let scmd = rt.cli().subcommand_matches("list").unwrap(); // safe by clap
+                let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
+                let ids   = ids_from_saved_search(scmd, rt.store())
+                    .map_err_trace_exit_unwrap(1)
+                    .map(Ok)
+                    .unwrap_or_else(|| rt.ids::<::ui::PathProvider>())
+                    .map_err_trace_exit_unwrap(1);
+
+                for id in ids {
+                    list(id, &rt, query.as_ref())
+                }
             },
-            "remove" => for id in ids {
+            "remove" => for id in rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1) {
                 let add = None;
                 let rem = get_remove_tags(rt.cli());
                 debug!("id = {:?}, add = {:?}, rem = {:?}", id, add, rem);
                 alter(&rt, id, add, rem);
             },
-            "add" => for id in ids {
+            "add" => for id in rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1) {
                 let add = get_add_tags(rt.cli());
                 let rem = None;
                 debug!("id = {:?}, add = {:?}, rem = {:?}", id, add, rem);
                 alter(&rt, id, add, rem);
             },
+            "registry" => registry(&rt),
             other => {
                 debug!("Unknown command");
                 let _ = rt.handle_unknown_subcommand("imag-tag", other, rt.cli())
@@ -123,7 +141,7 @@ fn alter(rt: &Runtime, path: StoreId, add: Option<Vec<Tag>>, rem: Option<Vec<Tag
                     debug!("Adding tags = '{:?}'", tags);
                     for tag in tags {
                         debug!("Adding tag '{:?}'", tag);
-                        if let Err(e) = e.add_tag(tag) {
+                        if let Err(e) = e.add_tag(rt.store(), rt.config(), tag) {
                             trace_error(&e);
                         } else {
                             debug!("Adding tag worked");
@@ -162,12 +180,16 @@ fn alter(rt: &Runtime, path: StoreId, add: Option<Vec<Tag>>, rem: Option<Vec<Tag
         .map_err_trace_exit_unwrap(1);
 }
 
-fn list(path: StoreId, rt: &Runtime) {
+fn list(path: StoreId, rt: &Runtime, query: Option<&Query>) {
     let entry = match rt.store().get(path.clone()).map_err_trace_exit_unwrap(1) {
         Some(e) => e,
         None => warn_exit("No entry found.", 1),
     };
 
+    if !entry_matches(query, &entry).map_err_trace_exit_unwrap(1) {
+        return
+    }
+
     let scmd = rt.cli().subcommand_matches("list").unwrap(); // safe, we checked in main()
 
     let json_out = scmd.is_present("json");
@@ -212,6 +234,115 @@ fn list(path: StoreId, rt: &Runtime) {
         .map_err_trace_exit_unwrap(1);
 }
 
+fn registry(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("registry").unwrap(); // safe, we checked in main()
+
+    scmd.subcommand_name()
+        .map(|name| match name {
+            "add"  => registry_add(rt),
+            "list" => registry_list(rt),
+            "rm"   => registry_remove(rt),
+            other  => {
+                debug!("Unknown command");
+                let _ = rt.handle_unknown_subcommand("imag-tag-registry", other, rt.cli())
+                    .map_err_trace_exit_unwrap(1)
+                    .code()
+                    .map(::std::process::exit);
+            },
+        });
+}
+
+fn registry_add(rt: &Runtime) {
+    let scmd = rt.cli()
+        .subcommand_matches("registry")
+        .and_then(|scmd| scmd.subcommand_matches("add"))
+        .unwrap(); // safe, we checked in registry()
+
+    let name      = scmd.value_of("registry-add-name").map(String::from).unwrap(); // safed by clap
+    let location  = {
+        let mut entry = rt.store().create_tag(&name).map_err_trace_exit_unwrap(1);
+
+        if let Some(description) = scmd.value_of("registry-add-description") {
+            let _ = entry.set_description(description).map_err_trace_exit_unwrap(1);
+        }
+
+        if let Some(color) = scmd.value_of("registry-add-color") {
+            let _ = entry.set_color(color).map_err_trace_exit_unwrap(1);
+        }
+
+        entry.get_location().clone()
+    };
+
+    if let Some(aliases) = scmd.values_of("registry-add-alias") {
+        for alias in aliases {
+            let _ = rt
+                .store()
+                .add_tag_alias(&name, String::from(alias))
+                .map_err_trace_exit_unwrap(1);
+        }
+    }
+
+    let _ = rt
+        .report_touched(&location)
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn registry_list(rt: &Runtime) {
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    rt.store()
+        .all_tag_names()
+        .map_err_trace_exit_unwrap(1)
+        .map(|name| name.map_err_trace_exit_unwrap(1))
+        .for_each(|name| {
+            let entry = rt.store()
+                .get_tag_by_name(&name)
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| {
+                    error!("Tag '{}' vanished from the store", name);
+                    ::std::process::exit(1)
+                });
+
+            let color = entry.get_color().map_err_trace_exit_unwrap(1);
+            let shown = match color.as_ref().and_then(|c| colour_from_name(c)) {
+                Some(colour) if !rt.output_is_pipe() => colour.paint(name.clone()).to_string(),
+                _ => name.clone(),
+            };
+
+            let aliases     = entry.get_aliases().map_err_trace_exit_unwrap(1);
+            let description = entry.get_description().map_err_trace_exit_unwrap(1);
+
+            let _ = write!(outlock, "{}", shown).to_exit_code().unwrap_or_exit();
+
+            if !aliases.is_empty() {
+                let _ = write!(outlock, " ({})", aliases.join(", ")).to_exit_code().unwrap_or_exit();
+            }
+
+            if let Some(description) = description {
+                let _ = write!(outlock, ": {}", description).to_exit_code().unwrap_or_exit();
+            }
+
+            let _ = writeln!(outlock, "").to_exit_code().unwrap_or_exit();
+
+            let _ = rt
+                .report_touched(entry.get_location())
+                .map_err_trace_exit_unwrap(1);
+        })
+}
+
+fn registry_remove(rt: &Runtime) {
+    let scmd = rt.cli()
+        .subcommand_matches("registry")
+        .and_then(|scmd| scmd.subcommand_matches("rm"))
+        .unwrap(); // safe, we checked in registry()
+
+    for name in scmd.values_of("registry-rm-name").unwrap() { // safed by clap
+        info!("Removing tag '{}' from the registry", name);
+        let _ = rt.store().delete_tag(name).map_err_trace_exit_unwrap(1);
+    }
+}
+
 /// Get the tags which should be added from the commandline
 ///
 /// Returns none if the argument was not specified
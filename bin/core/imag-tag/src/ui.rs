@@ -26,6 +26,9 @@ use libimagstore::storeid::IntoStoreId;
 use libimagrt::runtime::IdPathProvider;
 use libimagerror::trace::MapErrTrace;
 use libimagentrytag::tag::is_tag;
+use libimaginteraction::format::is_colour_name;
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app.arg(Arg::with_name("id")
@@ -90,6 +93,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .help("Separated by string")
                         .value_name("SEP"))
+                   .arg(where_arg())
+                   .arg(saved_arg())
 
                    .group(ArgGroup::with_name("list-group")
                           .args(&[
@@ -101,6 +106,66 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                           .required(true))
                    )
 
+       .subcommand(SubCommand::with_name("registry")
+                   .about("Manage the tag registry")
+                   .version("0.1")
+                   .subcommand(SubCommand::with_name("add")
+                               .about("Register a tag")
+                               .version("0.1")
+                               .arg(Arg::with_name("registry-add-name")
+                                    .index(1)
+                                    .takes_value(true)
+                                    .required(true)
+                                    .multiple(false)
+                                    .value_name("TAG")
+                                    .validator(is_tag)
+                                    .help("The tag to register"))
+                               .arg(Arg::with_name("registry-add-description")
+                                    .long("description")
+                                    .short("d")
+                                    .takes_value(true)
+                                    .required(false)
+                                    .multiple(false)
+                                    .value_name("DESCRIPTION")
+                                    .help("A description of the tag"))
+                               .arg(Arg::with_name("registry-add-color")
+                                    .long("color")
+                                    .short("c")
+                                    .takes_value(true)
+                                    .required(false)
+                                    .multiple(false)
+                                    .value_name("COLOR")
+                                    .validator(is_colour_name)
+                                    .help("The color to display the tag with"))
+                               .arg(Arg::with_name("registry-add-alias")
+                                    .long("alias")
+                                    .short("a")
+                                    .takes_value(true)
+                                    .required(false)
+                                    .multiple(true)
+                                    .value_name("ALIAS")
+                                    .validator(is_tag)
+                                    .help("Aliases which resolve to this tag"))
+                              )
+
+                   .subcommand(SubCommand::with_name("list")
+                               .about("List all registered tags")
+                               .version("0.1"))
+
+                   .subcommand(SubCommand::with_name("rm")
+                               .about("Remove tags from the registry (entries keep their tags)")
+                               .version("0.1")
+                               .arg(Arg::with_name("registry-rm-name")
+                                    .index(1)
+                                    .takes_value(true)
+                                    .required(true)
+                                    .multiple(true)
+                                    .value_name("TAG")
+                                    .validator(is_tag)
+                                    .help("The tags to remove from the registry"))
+                              )
+                   )
+
 }
 
 pub struct PathProvider;
@@ -0,0 +1,725 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The state machine behind the interface
+//!
+//! Everything in here is independent of the terminal: keys go in through `App::handle()`, the
+//! drawing code reads the state through the accessor functions. This way the interface can be
+//! tested without a terminal.
+
+use std::collections::BTreeMap;
+
+use failure::Fallible as Result;
+use failure::err_msg;
+use termion::event::Key;
+use toml::Value;
+
+use libimagstore::store::Store;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrytag::tagable::Tagable;
+use libimagentrytag::registry::TagStore;
+use libimagentrycategory::entry::EntryCategory;
+use libimagentryannotation::annotateable::Annotateable;
+
+/// The screens of the interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    /// All collections in the store
+    Collections,
+
+    /// The entries of one collection, possibly filtered
+    Entries,
+
+    /// The internal links of one entry
+    Links,
+}
+
+/// What the input line is currently used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    /// Filtering the entries, which is applied while typing
+    Filter,
+
+    /// An action on the selected entry, which is applied when the input is finished
+    Entry(EntryInput),
+}
+
+/// The actions on the selected entry which take a name from the input line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryInput {
+    Tag,
+    Category,
+    Annotation,
+}
+
+impl InputKind {
+    pub fn prompt(&self) -> &'static str {
+        match *self {
+            InputKind::Filter                        => "Filter",
+            InputKind::Entry(EntryInput::Tag)        => "Add tag",
+            InputKind::Entry(EntryInput::Category)   => "Add category",
+            InputKind::Entry(EntryInput::Annotation) => "Annotation name",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub kind: InputKind,
+    pub buffer: String,
+}
+
+/// What the terminal loop has to do after a key was handled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Quit,
+
+    /// Leave the interface, open the entry in the editor and come back afterwards
+    Edit(StoreId),
+}
+
+pub struct App<'a> {
+    store: &'a Store,
+    config: Option<&'a Value>,
+    screen: Screen,
+
+    ids: Vec<StoreId>,
+    collections: Vec<(String, usize)>,
+    collection_cursor: usize,
+
+    collection: Option<String>,
+    filter: String,
+    entry_cursor: usize,
+
+    focused: Option<StoreId>,
+    links: Vec<StoreId>,
+    link_cursor: usize,
+    history: Vec<StoreId>,
+
+    input: Option<Input>,
+    status: Option<String>,
+}
+
+impl<'a> App<'a> {
+
+    /// Create the interface, `config` is used for the strict mode of the tag registry
+    pub fn new(store: &'a Store, config: Option<&'a Value>) -> Result<App<'a>> {
+        let mut app = App {
+            store: store,
+            config: config,
+            screen: Screen::Collections,
+            ids: vec![],
+            collections: vec![],
+            collection_cursor: 0,
+            collection: None,
+            filter: String::new(),
+            entry_cursor: 0,
+            focused: None,
+            links: vec![],
+            link_cursor: 0,
+            history: vec![],
+            input: None,
+            status: None,
+        };
+
+        app.refresh()?;
+        Ok(app)
+    }
+
+    /// Re-read the ids and the links of the focused entry from the store
+    pub fn refresh(&mut self) -> Result<()> {
+        let mut ids = self.store.entries()?.collect::<Result<Vec<StoreId>>>()?;
+        ids.sort();
+
+        let mut collections = BTreeMap::new();
+        for name in ids.iter().filter_map(collection_of) {
+            *collections.entry(name).or_insert(0) += 1;
+        }
+
+        self.ids         = ids;
+        self.collections = collections.into_iter().collect();
+
+        if let Some(focused) = self.focused.clone() {
+            self.load_links(focused)?;
+        }
+
+        self.clamp_cursors();
+        Ok(())
+    }
+
+    /// Show the entries of the collection `name`
+    pub fn open_collection(&mut self, name: &str) -> Result<()> {
+        match self.collections.iter().position(|&(ref c, _)| c == name) {
+            None      => Err(format_err!("No collection named '{}'", name)),
+            Some(pos) => {
+                self.collection_cursor = pos;
+                self.collection        = Some(String::from(name));
+                self.filter            = String::new();
+                self.entry_cursor      = 0;
+                self.screen            = Screen::Entries;
+                Ok(())
+            },
+        }
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub fn input(&self) -> Option<&Input> {
+        self.input.as_ref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_ref().map(|s| s.as_str())
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    pub fn title(&self) -> String {
+        match self.screen {
+            Screen::Collections => String::from("Collections"),
+            Screen::Entries     => {
+                let name = self.collection.as_ref().map(|c| c.as_str()).unwrap_or("");
+                if self.filter.is_empty() {
+                    format!("Collection '{}'", name)
+                } else {
+                    format!("Collection '{}', filter '{}'", name, self.filter)
+                }
+            },
+            Screen::Links       => {
+                let name = self.focused
+                    .as_ref()
+                    .map(StoreId::local_display_string)
+                    .unwrap_or_else(String::new);
+                format!("Links of '{}'", name)
+            },
+        }
+    }
+
+    /// The lines of the list on the current screen
+    pub fn items(&self) -> Vec<String> {
+        match self.screen {
+            Screen::Collections => self.collections
+                .iter()
+                .map(|&(ref name, count)| format!("{} ({})", name, count))
+                .collect(),
+            Screen::Entries => self.visible_entries()
+                .into_iter()
+                .map(StoreId::local_display_string)
+                .collect(),
+            Screen::Links => self.links
+                .iter()
+                .map(StoreId::local_display_string)
+                .collect(),
+        }
+    }
+
+    pub fn cursor(&self) -> usize {
+        match self.screen {
+            Screen::Collections => self.collection_cursor,
+            Screen::Entries     => self.entry_cursor,
+            Screen::Links       => self.link_cursor,
+        }
+    }
+
+    /// The entry under the cursor, which is shown in the preview and which actions work on
+    pub fn selected_entry(&self) -> Option<StoreId> {
+        match self.screen {
+            Screen::Collections => None,
+            Screen::Entries     => self.visible_entries().get(self.entry_cursor).map(|id| (*id).clone()),
+            Screen::Links       => self.links.get(self.link_cursor).cloned(),
+        }
+    }
+
+    /// Handle a key press
+    ///
+    /// Errors are not returned but shown in the status line, so a failing action does not end
+    /// the interface.
+    pub fn handle(&mut self, key: Key) -> Action {
+        let result = if self.input.is_some() {
+            self.handle_input(key)
+        } else {
+            self.status = None;
+            self.handle_key(key)
+        };
+
+        result.unwrap_or_else(|e| {
+            self.status = Some(format!("Error: {}", e));
+            Action::Continue
+        })
+    }
+
+    fn handle_key(&mut self, key: Key) -> Result<Action> {
+        match key {
+            Key::Char('q')                                   => return Ok(Action::Quit),
+            Key::Char('j') | Key::Down                       => self.move_cursor(1),
+            Key::Char('k') | Key::Up                         => self.move_cursor(-1),
+            Key::Char('g') | Key::Home                       => self.set_cursor(0),
+            Key::Char('G') | Key::End                        => {
+                let last = self.items().len().saturating_sub(1);
+                self.set_cursor(last)
+            },
+            Key::Char('\n') | Key::Char('l') | Key::Right    => self.enter()?,
+            Key::Char('h') | Key::Left | Key::Backspace | Key::Esc => self.back()?,
+            Key::Char('r')                                   => self.refresh()?,
+            Key::Char('/') if self.screen == Screen::Entries => self.start_input(InputKind::Filter),
+            Key::Char('t')                                   => self.start_entry_input(EntryInput::Tag),
+            Key::Char('c')                                   => self.start_entry_input(EntryInput::Category),
+            Key::Char('a')                                   => self.start_entry_input(EntryInput::Annotation),
+            Key::Char('e')                                   => match self.selected_entry() {
+                Some(id) => return Ok(Action::Edit(id)),
+                None     => self.status = Some(String::from("No entry selected")),
+            },
+            _ => {},
+        }
+
+        Ok(Action::Continue)
+    }
+
+    fn handle_input(&mut self, key: Key) -> Result<Action> {
+        let mut input = match self.input.take() {
+            Some(input) => input,
+            None        => return Ok(Action::Continue),
+        };
+
+        match key {
+            Key::Esc => {
+                if input.kind == InputKind::Filter {
+                    self.filter.clear();
+                }
+                return Ok(Action::Continue);
+            },
+            Key::Char('\n') => {
+                self.apply_input(input)?;
+                return Ok(Action::Continue);
+            },
+            Key::Backspace => { let _ = input.buffer.pop(); },
+            Key::Char(c)   => input.buffer.push(c),
+            _              => {},
+        }
+
+        if input.kind == InputKind::Filter {
+            self.filter       = input.buffer.clone();
+            self.entry_cursor = 0;
+        }
+
+        self.input = Some(input);
+        Ok(Action::Continue)
+    }
+
+    fn start_input(&mut self, kind: InputKind) {
+        let buffer = match kind {
+            InputKind::Filter => self.filter.clone(),
+            _                 => String::new(),
+        };
+
+        self.input = Some(Input { kind: kind, buffer: buffer });
+    }
+
+    fn start_entry_input(&mut self, kind: EntryInput) {
+        if self.selected_entry().is_some() {
+            self.start_input(InputKind::Entry(kind))
+        } else {
+            self.status = Some(String::from("No entry selected"));
+        }
+    }
+
+    fn apply_input(&mut self, input: Input) -> Result<()> {
+        let name = String::from(input.buffer.trim());
+        match input.kind {
+            InputKind::Filter                       => Ok(()),
+            InputKind::Entry(_) if name.is_empty()  => Ok(()),
+            InputKind::Entry(kind)                  => self.apply_entry_input(kind, name),
+        }
+    }
+
+    fn apply_entry_input(&mut self, kind: EntryInput, name: String) -> Result<()> {
+        let id = self.selected_entry().ok_or_else(|| err_msg("No entry selected"))?;
+        let status = {
+            let mut entry = self.get_entry(&id)?;
+
+            match kind {
+                EntryInput::Tag => {
+                    // Aliases are resolved, so the registered name is reported
+                    let tag = self.store.resolve_tag(&name)?.unwrap_or_else(|| name.clone());
+                    let _   = entry.add_tag(self.store, self.config, name)?;
+                    format!("Tagged '{}' with '{}'", id.local_display_string(), tag)
+                },
+                EntryInput::Category => {
+                    let _ = entry.add_category_checked(self.store, &name)?;
+                    format!("Added '{}' to category '{}'", id.local_display_string(), name)
+                },
+                EntryInput::Annotation => {
+                    let _ = entry.annotate(self.store, &name)?;
+                    format!("Annotated '{}' with '{}'", id.local_display_string(), name)
+                },
+            }
+        };
+
+        // Categories and annotations are links, so the lists might have changed
+        self.refresh()?;
+        self.status = Some(status);
+        Ok(())
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        match self.screen {
+            Screen::Collections => {
+                let name = self.collections.get(self.collection_cursor).map(|c| c.0.clone());
+                if let Some(name) = name {
+                    self.open_collection(&name)?;
+                }
+            },
+            Screen::Entries => if let Some(id) = self.selected_entry() {
+                self.history.clear();
+                self.show_links(id)?;
+            },
+            Screen::Links => if let Some(id) = self.selected_entry() {
+                self.show_links(id)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn back(&mut self) -> Result<()> {
+        match self.screen {
+            Screen::Collections => {},
+            Screen::Entries     => {
+                self.collection = None;
+                self.filter.clear();
+                self.screen     = Screen::Collections;
+            },
+            Screen::Links       => match self.history.pop() {
+                Some(previous) => {
+                    self.load_links(previous)?;
+                    self.link_cursor = 0;
+                },
+                None => {
+                    self.focused = None;
+                    self.links.clear();
+                    self.screen  = Screen::Entries;
+                },
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Show the links of `id`, remembering the currently focused entry for going back
+    fn show_links(&mut self, id: StoreId) -> Result<()> {
+        let previous = self.focused.take();
+        if let Err(e) = self.load_links(id) {
+            self.focused = previous;
+            return Err(e);
+        }
+
+        if let Some(previous) = previous {
+            self.history.push(previous);
+        }
+
+        self.screen      = Screen::Links;
+        self.link_cursor = 0;
+        Ok(())
+    }
+
+    fn load_links(&mut self, id: StoreId) -> Result<()> {
+        let links = self.get_entry(&id)?
+            .get_internal_links()?
+            .map(|link| link.get_store_id().clone())
+            .collect();
+
+        self.links   = links;
+        self.focused = Some(id);
+        Ok(())
+    }
+
+    fn get_entry(&self, id: &StoreId) -> Result<FileLockEntry<'a>> {
+        self.store
+            .get(id.clone())?
+            .ok_or_else(|| format_err!("Entry does not exist: {}", id.local_display_string()))
+    }
+
+    fn visible_entries(&self) -> Vec<&StoreId> {
+        let collection = match self.collection {
+            Some(ref c) => c,
+            None        => return vec![],
+        };
+        let filter = self.filter.to_lowercase();
+
+        self.ids
+            .iter()
+            .filter(|id| id.is_in_collection(&[collection]))
+            .filter(|id| id.local_display_string().to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn cursor_mut(&mut self) -> &mut usize {
+        match self.screen {
+            Screen::Collections => &mut self.collection_cursor,
+            Screen::Entries     => &mut self.entry_cursor,
+            Screen::Links       => &mut self.link_cursor,
+        }
+    }
+
+    fn set_cursor(&mut self, pos: usize) {
+        let len = self.items().len();
+        *self.cursor_mut() = ::std::cmp::min(pos, len.saturating_sub(1));
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let pos = self.cursor() as isize + delta;
+        self.set_cursor(if pos < 0 { 0 } else { pos as usize });
+    }
+
+    fn clamp_cursors(&mut self) {
+        let entries = self.visible_entries().len();
+        self.collection_cursor = ::std::cmp::min(self.collection_cursor, self.collections.len().saturating_sub(1));
+        self.entry_cursor      = ::std::cmp::min(self.entry_cursor, entries.saturating_sub(1));
+        self.link_cursor       = ::std::cmp::min(self.link_cursor, self.links.len().saturating_sub(1));
+    }
+
+}
+
+/// The collection an entry lives in, `None` for entries directly in the store root
+fn collection_of(id: &StoreId) -> Option<String> {
+    let mut components = id.local().components();
+    let first = components.next();
+
+    components
+        .next()
+        .and(first)
+        .and_then(|c| c.as_os_str().to_str().map(String::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use termion::event::Key;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentrylink::internal::InternalLinker;
+    use libimagentrytag::tagable::Tagable;
+
+    use super::*;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn id(s: &str) -> StoreId {
+        PathBuf::from(s).into_storeid().unwrap()
+    }
+
+    /// Two linked notes and one contact
+    fn fill(store: &Store) {
+        let mut a = store.create(id("notes/alpha")).unwrap();
+        let mut b = store.create(id("notes/beta")).unwrap();
+        let _     = store.create(id("contacts/carol")).unwrap();
+        a.add_internal_link(&mut b).unwrap();
+    }
+
+    fn type_str(app: &mut App, s: &str) {
+        for c in s.chars() {
+            assert_eq!(Action::Continue, app.handle(Key::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_lists_collections() {
+        let store = get_store();
+        fill(&store);
+        let app = App::new(&store, None).unwrap();
+
+        assert_eq!(Screen::Collections, app.screen());
+        assert_eq!(vec!["contacts (1)", "notes (2)"], app.items());
+        assert!(app.selected_entry().is_none());
+    }
+
+    #[test]
+    fn test_open_collection_and_filter() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+
+        app.handle(Key::Char('j'));
+        app.handle(Key::Char('\n'));
+        assert_eq!(Screen::Entries, app.screen());
+        assert_eq!(vec!["notes/alpha", "notes/beta"], app.items());
+
+        app.handle(Key::Char('/'));
+        type_str(&mut app, "BET");
+        assert_eq!(vec!["notes/beta"], app.items());
+
+        app.handle(Key::Char('\n'));
+        assert!(app.input().is_none());
+        assert_eq!(vec!["notes/beta"], app.items());
+        assert_eq!(Some(id("notes/beta")), app.selected_entry());
+
+        app.handle(Key::Char('/'));
+        app.handle(Key::Esc);
+        assert_eq!(vec!["notes/alpha", "notes/beta"], app.items());
+    }
+
+    #[test]
+    fn test_open_unknown_collection() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+        assert!(app.open_collection("wiki").is_err());
+        assert_eq!(Screen::Collections, app.screen());
+    }
+
+    #[test]
+    fn test_follow_links_and_back() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+        app.open_collection("notes").unwrap();
+
+        app.handle(Key::Char('\n'));
+        assert_eq!(Screen::Links, app.screen());
+        assert_eq!(vec!["notes/beta"], app.items());
+
+        app.handle(Key::Char('\n'));
+        assert_eq!(Screen::Links, app.screen());
+        assert_eq!("Links of 'notes/beta'", app.title());
+        assert_eq!(vec!["notes/alpha"], app.items());
+
+        app.handle(Key::Backspace);
+        assert_eq!("Links of 'notes/alpha'", app.title());
+
+        app.handle(Key::Backspace);
+        assert_eq!(Screen::Entries, app.screen());
+
+        app.handle(Key::Esc);
+        assert_eq!(Screen::Collections, app.screen());
+    }
+
+    #[test]
+    fn test_cursor_stays_in_bounds() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+
+        app.handle(Key::Up);
+        assert_eq!(0, app.cursor());
+        app.handle(Key::Char('G'));
+        assert_eq!(1, app.cursor());
+        app.handle(Key::Down);
+        assert_eq!(1, app.cursor());
+    }
+
+    #[test]
+    fn test_tag_entry() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+        app.open_collection("notes").unwrap();
+
+        app.handle(Key::Char('t'));
+        type_str(&mut app, "important");
+        app.handle(Key::Char('\n'));
+
+        assert!(app.input().is_none());
+        assert_eq!(Some("Tagged 'notes/alpha' with 'important'"), app.status());
+        let entry = store.get(id("notes/alpha")).unwrap().unwrap();
+        assert!(entry.has_tag("important").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_tag_is_reported() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+        app.open_collection("notes").unwrap();
+
+        app.handle(Key::Char('t'));
+        type_str(&mut app, "Not A Tag");
+        assert_eq!(Action::Continue, app.handle(Key::Char('\n')));
+
+        assert!(app.status().map(|s| s.starts_with("Error")).unwrap_or(false));
+        let entry = store.get(id("notes/alpha")).unwrap().unwrap();
+        assert!(entry.get_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unregistered_tag_is_rejected_in_strict_mode() {
+        let store = get_store();
+        fill(&store);
+        let config : Value = ::toml::from_str("[tag.registry]\nstrict = true").unwrap();
+        let mut app = App::new(&store, Some(&config)).unwrap();
+        app.open_collection("notes").unwrap();
+
+        app.handle(Key::Char('t'));
+        type_str(&mut app, "unknown");
+        app.handle(Key::Char('\n'));
+
+        assert!(app.status().map(|s| s.starts_with("Error")).unwrap_or(false));
+        let entry = store.get(id("notes/alpha")).unwrap().unwrap();
+        assert!(entry.get_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_annotate_entry() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+        app.open_collection("notes").unwrap();
+
+        app.handle(Key::Char('a'));
+        type_str(&mut app, "remark");
+        app.handle(Key::Char('\n'));
+
+        assert_eq!(Some("Annotated 'notes/alpha' with 'remark'"), app.status());
+
+        app.handle(Key::Esc);
+        assert_eq!(vec!["annotations (1)", "contacts (1)", "notes (2)"], app.items());
+
+        app.open_collection("notes").unwrap();
+        app.handle(Key::Char('\n'));
+        assert_eq!(vec!["annotations/remark", "notes/beta"], app.items());
+    }
+
+    #[test]
+    fn test_edit_and_quit() {
+        let store = get_store();
+        fill(&store);
+        let mut app = App::new(&store, None).unwrap();
+
+        assert_eq!(Action::Continue, app.handle(Key::Char('e')));
+        assert_eq!(Some("No entry selected"), app.status());
+
+        app.open_collection("contacts").unwrap();
+        assert_eq!(Action::Edit(id("contacts/carol")), app.handle(Key::Char('e')));
+        assert_eq!(Action::Quit, app.handle(Key::Char('q')));
+    }
+
+}
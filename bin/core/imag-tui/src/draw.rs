@@ -0,0 +1,146 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::cmp::{max, min};
+use std::io::Write;
+
+use failure::Fallible as Result;
+use termion::clear;
+use termion::cursor;
+use termion::style;
+
+use app::App;
+
+const HELP: &str = "q quit  j/k move  enter open  h back  / filter  t tag  c category  a annotate  e edit  r reload";
+
+/// Draw the whole interface
+///
+/// The first line shows the title of the screen, the last line is the status or input line. In
+/// between, the list of the current screen is shown on the left and the `preview` on the right.
+pub fn draw<W: Write>(out: &mut W, app: &App, preview: &[String], width: u16, height: u16) -> Result<()> {
+    let width  = width as usize;
+    let body   = (height as usize).saturating_sub(2);
+    let list   = min(width, max(20, width / 3));
+    let right  = width.saturating_sub(list + 1);
+    let items  = app.items();
+    let cursor = app.cursor();
+    let offset = (cursor + 1).saturating_sub(body);
+
+    write!(out, "{}{}{}{}{}",
+           clear::All,
+           cursor::Goto(1, 1),
+           style::Invert,
+           pad(&format!(" imag-tui: {}", app.title()), width),
+           style::Reset)?;
+
+    for row in 0..body {
+        write!(out, "{}", cursor::Goto(1, row as u16 + 2))?;
+
+        if let Some(item) = items.get(offset + row) {
+            if offset + row == cursor {
+                write!(out, "{}{}{}", style::Invert, pad(item, list), style::Reset)?;
+            } else {
+                write!(out, "{}", pad(item, list))?;
+            }
+        }
+
+        if right > 0 {
+            write!(out, "{}│", cursor::Goto(list as u16 + 1, row as u16 + 2))?;
+            if let Some(line) = preview.get(row) {
+                write!(out, " {}{}", truncate_ansi(line, right.saturating_sub(1)), style::Reset)?;
+            }
+        }
+    }
+
+    write!(out, "{}", cursor::Goto(1, height))?;
+    match app.input() {
+        Some(input) => {
+            let line = format!("{}: {}", input.kind.prompt(), input.buffer);
+            write!(out, "{}{}", pad(&line, width), cursor::Show)?;
+            write!(out, "{}", cursor::Goto(min(line.chars().count() + 1, max(width, 1)) as u16, height))?;
+        },
+        None => {
+            let line = app.status().unwrap_or(HELP);
+            write!(out, "{}{}", pad(line, width), cursor::Hide)?;
+        },
+    }
+
+    out.flush().map_err(From::from)
+}
+
+/// Cut `s` to `width` characters, filling up with spaces if it is shorter
+fn pad(s: &str, width: usize) -> String {
+    let mut line = s.chars().take(width).collect::<String>();
+    let len      = line.chars().count();
+    line.extend(::std::iter::repeat(' ').take(width - len));
+    line
+}
+
+/// Cut `s` to `width` visible characters
+///
+/// ANSI escape sequences, as the markdown viewer emits them for colors, do not count towards the
+/// width and are kept, so the cut line is still colored correctly.
+fn truncate_ansi(s: &str, width: usize) -> String {
+    let mut out     = String::new();
+    let mut visible = 0;
+    let mut chars   = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if visible < width {
+            out.push(c);
+            visible += 1;
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pad;
+    use super::truncate_ansi;
+
+    #[test]
+    fn test_pad() {
+        assert_eq!("abc  ", pad("abc", 5));
+        assert_eq!("abc", pad("abcdef", 3));
+        assert_eq!("", pad("abc", 0));
+    }
+
+    #[test]
+    fn test_truncate_ansi_plain() {
+        assert_eq!("abc", truncate_ansi("abcdef", 3));
+        assert_eq!("ab", truncate_ansi("ab", 3));
+    }
+
+    #[test]
+    fn test_truncate_ansi_keeps_escapes() {
+        let line = "\x1b[1mbold\x1b[0m text";
+        assert_eq!("\x1b[1mbo\x1b[0m", truncate_ansi(line, 2));
+        assert_eq!(line, truncate_ansi(line, 9));
+    }
+}
@@ -0,0 +1,160 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate failure;
+extern crate clap;
+extern crate termion;
+extern crate toml;
+
+extern crate libimagstore;
+#[macro_use] extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagentryview;
+extern crate libimagentrylink;
+extern crate libimagentrytag;
+extern crate libimagentrycategory;
+extern crate libimagentryannotation;
+extern crate libimagentryedit;
+
+use std::io::stdout;
+
+use failure::Fallible as Result;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagentryview::builtin::md::MarkdownViewer;
+use libimagentryview::viewer::Viewer;
+use libimagentryedit::edit::Edit;
+use libimagstore::storeid::StoreId;
+
+mod app;
+mod draw;
+mod ui;
+
+use app::Action;
+use app::App;
+
+fn main() {
+    let version = make_imag_version!();
+    let rt = generate_runtime_setup("imag-tui",
+                                    &version,
+                                    "Browse and work with the store in a terminal interface",
+                                    ui::build_ui);
+
+    let mut app = App::new(rt.store(), rt.config()).map_err_trace_exit_unwrap(1);
+
+    if let Some(collection) = rt.cli().value_of("collection") {
+        let _ = app.open_collection(collection).map_err_trace_exit_unwrap(1);
+    }
+
+    let _ = run(&rt, &mut app).map_err_trace_exit_unwrap(1);
+}
+
+/// The terminal loop
+///
+/// The terminal is in raw mode on the alternate screen as long as the interface is shown. For
+/// editing an entry, both are left, so the editor gets a normal terminal.
+fn run(rt: &Runtime, app: &mut App) -> Result<()> {
+    let viewer   = MarkdownViewer::new(rt);
+    let mut keys = termion::get_tty()?.keys();
+
+    loop {
+        let action = {
+            let stdout     = stdout();
+            let mut screen = AlternateScreen::from(stdout.lock().into_raw_mode()?);
+
+            loop {
+                let (width, height) = termion::terminal_size()?;
+                let preview = app
+                    .selected_entry()
+                    .map(|id| render_preview(rt, &viewer, id))
+                    .unwrap_or_else(Vec::new);
+
+                draw::draw(&mut screen, app, &preview, width, height)?;
+
+                let key = match keys.next() {
+                    Some(key) => key?,
+                    None      => break Action::Quit,
+                };
+
+                match app.handle(key) {
+                    Action::Continue => continue,
+                    other            => break other,
+                }
+            }
+        };
+
+        match action {
+            Action::Continue | Action::Quit => return Ok(()),
+            Action::Edit(id) => {
+                if let Err(e) = edit(rt, id) {
+                    app.set_status(format!("Error: {}", e));
+                }
+                let _ = app.refresh()?;
+            },
+        }
+    }
+}
+
+fn edit(rt: &Runtime, id: StoreId) -> Result<()> {
+    rt.store()
+        .get(id.clone())?
+        .ok_or_else(|| format_err!("Entry does not exist: {}", id.local_display_string()))?
+        .edit_content(rt)
+}
+
+/// Render the entry with the markdown viewer, line by line
+///
+/// Errors are rendered as well, the preview is no place to fail.
+fn render_preview(rt: &Runtime, viewer: &MarkdownViewer, id: StoreId) -> Vec<String> {
+    let mut buf = vec![];
+    let result  = rt
+        .store()
+        .get(id)
+        .and_then(|entry| match entry {
+            Some(entry) => viewer.view_entry(&entry, &mut buf),
+            None        => Err(format_err!("Entry does not exist")),
+        });
+
+    match result {
+        Ok(())  => String::from_utf8_lossy(&buf).lines().map(String::from).collect(),
+        Err(e)  => vec![format!("Error: {}", e)],
+    }
+}
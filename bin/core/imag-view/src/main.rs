@@ -42,7 +42,20 @@ extern crate toml;
 extern crate toml_query;
 extern crate failure;
 
+extern crate libimagentryannotation;
 extern crate libimagentryview;
+extern crate libimagentryquery;
+extern crate libimagentrymarkdown;
+#[cfg(feature = "viewer-mail")]
+extern crate libimagentryref;
+#[cfg(feature = "viewer-contact")]
+extern crate libimagcontact;
+#[cfg(feature = "viewer-habit")]
+extern crate libimaghabit;
+#[cfg(feature = "viewer-mail")]
+extern crate libimagmail;
+#[cfg(feature = "viewer-timetrack")]
+extern crate libimagtimetrack;
 extern crate libimagerror;
 #[macro_use] extern crate libimagrt;
 extern crate libimagstore;
@@ -58,7 +71,9 @@ use handlebars::Handlebars;
 use toml_query::read::TomlValueReadTypeExt;
 use failure::Error;
 use failure::err_msg;
+use failure::Fallible as Result;
 
+use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::iter::TraceIterator;
@@ -67,11 +82,22 @@ use libimagerror::exit::ExitUnwrap;
 use libimagentryview::builtin::stdout::StdoutViewer;
 use libimagentryview::builtin::md::MarkdownViewer;
 use libimagentryview::viewer::Viewer;
+use libimagentryview::registry::ViewerRegistry;
+use libimagentryview::pager::Pager;
+use libimagentryview::pager::Page;
+use libimagentryview::pager::header_line;
+use libimagentryview::pager::terminal_width;
+use libimagentryquery::cli::ids_from_saved_search;
+use libimagentrymarkdown::wikilink::backlinks;
+use libimagentrymarkdown::wikilink::StoreResolver;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
 use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
 
+mod margin;
 mod ui;
 use ui::build_ui;
+use margin::view_with_margin_notes;
 
 fn main() {
     let version = make_imag_version!();
@@ -80,9 +106,13 @@ fn main() {
                                      "View entries (readonly)",
                                      build_ui);
 
-    let view_header  = rt.cli().is_present("view-header");
-    let hide_content = rt.cli().is_present("not-view-content");
-    let entries      = rt.ids::<::ui::PathProvider>()
+    let view_header    = rt.cli().is_present("view-header");
+    let hide_content   = rt.cli().is_present("not-view-content");
+    let with_backlinks = rt.cli().is_present("backlinks");
+    let entries        = ids_from_saved_search(rt.cli(), rt.store())
+        .map_err_trace_exit_unwrap(1)
+        .map(Ok)
+        .unwrap_or_else(|| rt.ids::<::ui::PathProvider>())
         .map_err_trace_exit_unwrap(1)
         .into_iter()
         .map(Ok)
@@ -176,81 +206,165 @@ fn main() {
 
         drop(files);
     } else {
-        let out         = rt.stdout();
-        let mut outlock = out.lock();
-
         let basesep = if rt.cli().occurrences_of("seperator") != 0 { // checker for default value
             rt.cli().value_of("seperator").map(String::from)
         } else {
             None
         };
 
-        let mut sep_width = 80; // base width, automatically overridden by wrap width
+        // base width, automatically overridden by wrap width
+        let mut sep_width = terminal_width().unwrap_or(80);
 
-        // Helper to build the seperator with a base string `sep` and a `width`
-        let build_seperator = |sep: String, width: usize| -> String {
-            sep.repeat(width / sep.len())
+        let compile_md = rt.cli().is_present("compile-md");
+        let md_viewer  = if compile_md {
+            Some(MarkdownViewer::new(&rt))
+        } else {
+            None
         };
 
-        if rt.cli().is_present("compile-md") {
-            let viewer    = MarkdownViewer::new(&rt);
-            let seperator = basesep.map(|s| build_seperator(s, sep_width));
+        let mut viewer = StdoutViewer::new(view_header, !hide_content);
 
-            entries
-                .enumerate()
-                .for_each(|(n, entry)| {
-                    if n != 0 {
-                        seperator
-                            .as_ref()
-                            .map(|s| writeln!(outlock, "{}", s).to_exit_code().unwrap_or_exit());
-                    }
+        if rt.cli().occurrences_of("autowrap") != 0 {
+            let width = rt.cli().value_of("autowrap").unwrap(); // ensured by clap
+            let width = usize::from_str(width).unwrap_or_else(|e| {
+                error!("Failed to parse argument to number: autowrap = {:?}",
+                       rt.cli().value_of("autowrap").map(String::from));
+                error!("-> {:?}", e);
+                ::std::process::exit(1)
+            });
+
+            // Copying this value over, so that the seperator has the right len as well
+            sep_width = width;
+
+            viewer.wrap_at(width);
+        } else if rt.cli().is_present("autowrap-terminal") {
+            viewer.wrap_at(sep_width);
+        }
+
+        let with_annotations = rt.cli().is_present("annotations");
+        let registry         = viewer_registry(&rt);
 
+        // The viewers for the kinds of entries show neither the plain header nor the plain
+        // content, so they are not used if the user asked for either explicitly.
+        let use_kind_viewers = !(compile_md || rt.cli().is_present("raw") || view_header || hide_content);
+
+        let render = |entry: &FileLockEntry| -> Vec<u8> {
+            let mut buffer : Vec<u8> = vec![];
+
+            if let Some(ref md_viewer) = md_viewer {
+                md_viewer
+                    .view_entry(entry, &mut buffer)
+                    .map_err_trace_exit_unwrap(1);
+            } else if with_annotations {
+                view_with_margin_notes(rt.store(), entry, &mut buffer)
+                    .map_err_trace_exit_unwrap(1);
+            } else {
+                let viewed_by_kind = use_kind_viewers && registry
+                    .view_entry(entry, &mut buffer)
+                    .map_err_trace_exit_unwrap(1);
+
+                if !viewed_by_kind {
                     viewer
-                        .view_entry(&entry, &mut outlock)
+                        .view_entry(entry, &mut buffer)
                         .map_err_trace_exit_unwrap(1);
+                }
+            }
 
-                    rt.report_touched(entry.get_location())
-                        .map_err_trace_exit_unwrap(1);
-                });
-        } else {
-            let mut viewer = StdoutViewer::new(view_header, !hide_content);
-
-            if rt.cli().occurrences_of("autowrap") != 0 {
-                let width = rt.cli().value_of("autowrap").unwrap(); // ensured by clap
-                let width = usize::from_str(width).unwrap_or_else(|e| {
-                    error!("Failed to parse argument to number: autowrap = {:?}",
-                           rt.cli().value_of("autowrap").map(String::from));
-                    error!("-> {:?}", e);
-                    ::std::process::exit(1)
-                });
+            if with_backlinks {
+                view_backlinks(rt.store(), entry, &mut buffer)
+                    .map_err_trace_exit_unwrap(1);
+            }
+
+            rt.report_touched(entry.get_location())
+                .map_err_trace_exit_unwrap(1);
+
+            buffer
+        };
 
-                // Copying this value over, so that the seperator has the right len as well
-                sep_width = width;
+        if rt.cli().is_present("pager") && !rt.output_is_pipe() {
+            // The entries are rendered one by one while the pager is already showing them
+            let pages = entries.map(|entry| {
+                let content = String::from_utf8_lossy(&render(&entry)).into_owned();
+                Page::new(entry.get_location().to_string(), content)
+            });
 
-                viewer.wrap_at(width);
-            }
+            Pager::new(pages).show().map_err_trace_exit_unwrap(1);
+        } else {
+            let out         = rt.stdout();
+            let mut outlock = out.lock();
 
-            let seperator = basesep.map(|s| build_seperator(s, sep_width));
             entries
                 .enumerate()
                 .for_each(|(n, entry)| {
+                    let buffer = render(&entry);
+
                     if n != 0 {
-                        seperator
+                        basesep
                             .as_ref()
-                            .map(|s| writeln!(outlock, "{}", s).to_exit_code().unwrap_or_exit());
+                            .map(|sep| {
+                                let id = entry.get_location().to_string();
+                                writeln!(outlock, "{}", header_line(sep, &id, sep_width))
+                                    .to_exit_code()
+                                    .unwrap_or_exit()
+                            });
                     }
 
-                    viewer
-                        .view_entry(&entry, &mut outlock)
-                        .map_err_trace_exit_unwrap(1);
-
-                    rt.report_touched(entry.get_location())
-                        .map_err_trace_exit_unwrap(1);
+                    outlock.write_all(&buffer).to_exit_code().unwrap_or_exit();
                 });
         }
     }
 }
 
+/// Build the registry of viewers for the kinds of entries the domain libraries provide
+///
+/// Each of the viewers is behind a cargo feature, so imag-view can be built without the domain
+/// libraries.
+#[allow(unused_mut, unused_variables)] // if all viewer features are disabled
+fn viewer_registry<'a>(rt: &Runtime) -> ViewerRegistry<'a> {
+    let mut registry = ViewerRegistry::new();
+
+    #[cfg(feature = "viewer-contact")]
+    ::libimagcontact::viewer::register(&mut registry);
+
+    #[cfg(feature = "viewer-habit")]
+    ::libimaghabit::viewer::register(&mut registry);
+
+    #[cfg(feature = "viewer-timetrack")]
+    ::libimagtimetrack::viewer::register(&mut registry);
+
+    #[cfg(feature = "viewer-mail")]
+    register_mail_viewer(rt, &mut registry);
+
+    registry
+}
+
+#[cfg(feature = "viewer-mail")]
+fn register_mail_viewer(rt: &Runtime, registry: &mut ViewerRegistry) {
+    use libimagentryref::config::Config as RefConfig;
+
+    let ref_config = rt.config()
+        .map(RefConfig::from_config)
+        .unwrap_or_else(|| Ok(RefConfig::default()))
+        .map_err_trace_exit_unwrap(1);
+
+    ::libimagmail::viewer::register(registry, ref_config);
+}
+
+fn view_backlinks<W: Write>(store: &Store, entry: &FileLockEntry, out: &mut W) -> Result<()> {
+    let ids = backlinks(entry, store, &StoreResolver::new())?;
+
+    writeln!(out, "\nBacklinks:")?;
+    if ids.is_empty() {
+        writeln!(out, "  (none)")?;
+    }
+
+    for id in ids {
+        writeln!(out, "  {}", id)?;
+    }
+
+    Ok(())
+}
+
 fn create_tempfile_for<'a>(entry: &FileLockEntry<'a>, view_header: bool, hide_content: bool)
     -> (tempfile::NamedTempFile, String)
 {
@@ -0,0 +1,90 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagentryannotation::annotateable::Annotateable;
+use libimagentryannotation::annotation::Annotation;
+use libimagentryannotation::anchor::AnchoredAnnotation;
+
+use failure::Fallible as Result;
+
+struct MarginNote {
+    start: usize,
+    end:   usize,
+    text:  String,
+}
+
+/// View the content of `entry` with its anchored annotations as notes in the right margin
+///
+/// Annotations which are not anchored (or whose anchor got lost) are printed below the content.
+pub fn view_with_margin_notes<W: Write>(store: &Store, entry: &Entry, sink: &mut W) -> Result<()> {
+    let target       = entry.get_location().local_display_string();
+    let mut notes    = vec![];
+    let mut unplaced = vec![];
+
+    for annotation in entry.annotations(store)? {
+        let annotation = annotation?;
+        let summary    = format!("[{}] {}",
+                                 annotation.get_annotation_name()?,
+                                 annotation.get_content().lines().next().unwrap_or(""));
+
+        match annotation.get_anchor()? {
+            Some(ref anchor) if anchor.target() == &target && !anchor.is_lost() => {
+                notes.push(MarginNote {
+                    start: anchor.start(),
+                    end:   anchor.end(),
+                    text:  summary,
+                })
+            },
+            _ => unplaced.push(summary),
+        }
+    }
+
+    notes.sort_by_key(|n| n.start);
+
+    let lines = entry.get_content().lines().collect::<Vec<_>>();
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    for (i, line) in lines.iter().enumerate() {
+        let lineno = i + 1;
+        let margin = notes
+            .iter()
+            .filter(|n| n.start <= lineno && lineno <= n.end)
+            .map(|n| if n.start == lineno { n.text.clone() } else { String::from("┆") })
+            .collect::<Vec<_>>();
+
+        if margin.is_empty() {
+            let _ = writeln!(sink, "{}", line)?;
+        } else {
+            let _ = writeln!(sink, "{:width$} │ {}", line, margin.join(" "), width = width)?;
+        }
+    }
+
+    if !unplaced.is_empty() {
+        let _ = writeln!(sink, "")?;
+        for note in unplaced {
+            let _ = writeln!(sink, "{}", note)?;
+        }
+    }
+
+    Ok(())
+}
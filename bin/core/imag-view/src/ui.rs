@@ -25,6 +25,7 @@ use libimagstore::storeid::StoreId;
 use libimagstore::storeid::IntoStoreId;
 use libimagrt::runtime::IdPathProvider;
 use libimagerror::trace::MapErrTrace;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -36,6 +37,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
              .help("View these entries at this store path")
              .value_name("IDs"))
 
+        .arg(saved_arg().conflicts_with("id"))
+
         .arg(Arg::with_name("autowrap")
             .long("autowrap")
             .short("w")
@@ -46,6 +49,13 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
             .default_value("80")
             .validator(::libimagutil::cli_validators::is_integer)
             .help("Automatically wrap long lines. Has only an effect when using stdout as output."))
+        .arg(Arg::with_name("autowrap-terminal")
+            .long("autowrap-terminal")
+            .short("W")
+            .takes_value(false)
+            .required(false)
+            .help("Automatically wrap long lines at the width of the terminal. Has only an effect when using stdout as output.")
+            .conflicts_with("autowrap"))
 
         .arg(Arg::with_name("view-header")
             .long("header")
@@ -60,6 +70,13 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
             .required(false)
             .help("Do not view content"))
 
+        .arg(Arg::with_name("raw")
+            .long("raw")
+            .short("r")
+            .takes_value(false)
+            .required(false)
+            .help("Do not use the viewer for the kind of the entry (for example the contact card for contacts), but show the plain content"))
+
         .arg(Arg::with_name("compile-md")
             .long("compile")
             .short("c")
@@ -67,7 +84,35 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
             .required(false)
             .help("Do compile markdown to be nice")
             .conflicts_with("not-view-content")
-            .conflicts_with("autowrap")) // markdown viewer does not support wrapping
+            .conflicts_with("autowrap") // markdown viewer does not support wrapping
+            .conflicts_with("autowrap-terminal"))
+
+        .arg(Arg::with_name("annotations")
+            .long("annotations")
+            .short("a")
+            .takes_value(false)
+            .required(false)
+            .help("View anchored annotations as notes in the margin next to the content")
+            .conflicts_with("view-header")
+            .conflicts_with("not-view-content")
+            .conflicts_with("compile-md")
+            .conflicts_with("in"))
+
+        .arg(Arg::with_name("backlinks")
+            .long("backlinks")
+            .short("b")
+            .takes_value(false)
+            .required(false)
+            .help("View a list of the entries which link to the viewed entry in their content")
+            .conflicts_with("in"))
+
+        .arg(Arg::with_name("pager")
+            .long("pager")
+            .short("p")
+            .takes_value(false)
+            .required(false)
+            .help("Show the entries in a pager. Uses $PAGER if set, else a built-in pager which can navigate between the entries (n/p)")
+            .conflicts_with("in"))
 
         .arg(Arg::with_name("seperator")
             .long("seperate")
@@ -76,7 +121,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
             .takes_value(true)
             .value_name("SEPCHR")
             .default_value("-")
-            .help("Do seperate entries with a string if viewing multiple entries"))
+            .help("Do seperate entries with a line of this string and the id of the entry if viewing multiple entries"))
 
 
         .arg(Arg::with_name("in")
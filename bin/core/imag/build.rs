@@ -25,6 +25,9 @@ extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagentrytag;
+extern crate libimagentryquery;
+extern crate libimagentrytemplate;
+extern crate libimaginteraction;
 extern crate libimagutil;
 
 use clap::Shell;
@@ -83,9 +86,11 @@ gen_mods_buildui!(
     ("../../../bin/core/imag-category/src/ui.rs"    , imagcategory)    ,
     ("../../../bin/core/imag-diagnostics/src/ui.rs" , imagdiagnostics) ,
     ("../../../bin/core/imag-edit/src/ui.rs"        , imagedit)        ,
+    ("../../../bin/core/imag-export/src/ui.rs"      , imagexport)      ,
     ("../../../bin/core/imag-git/src/ui.rs"         , imaggit)         ,
     ("../../../bin/core/imag-gps/src/ui.rs"         , imaggps)         ,
     ("../../../bin/core/imag-grep/src/ui.rs"        , imaggrep)        ,
+    ("../../../bin/core/imag-header/src/ui.rs"      , imagheader)      ,
     ("../../../bin/core/imag-ids/src/ui.rs"         , imagids)         ,
     ("../../../bin/core/imag-init/src/ui.rs"        , imaginit)        ,
     ("../../../bin/core/imag-link/src/ui.rs"        , imaglink)        ,
@@ -93,6 +98,7 @@ gen_mods_buildui!(
     ("../../../bin/core/imag-ref/src/ui.rs"         , imagref)         ,
     ("../../../bin/core/imag-store/src/ui.rs"       , imagstore)       ,
     ("../../../bin/core/imag-tag/src/ui.rs"         , imagtag)         ,
+    ("../../../bin/core/imag-tui/src/ui.rs"         , imagtui)         ,
     ("../../../bin/core/imag-view/src/ui.rs"        , imagview)        ,
     ("../../../bin/domain/imag-bookmark/src/ui.rs"  , imagbookmark)    ,
     ("../../../bin/domain/imag-contact/src/ui.rs"   , imagcontact)     ,
@@ -101,6 +107,7 @@ gen_mods_buildui!(
     ("../../../bin/domain/imag-log/src/ui.rs"       , imaglog)         ,
     ("../../../bin/domain/imag-mail/src/ui.rs"      , imagmail)        ,
     ("../../../bin/domain/imag-notes/src/ui.rs"     , imagnotes)       ,
+    ("../../../bin/domain/imag-remind/src/ui.rs"    , imagremind)      ,
     ("../../../bin/domain/imag-timetrack/src/ui.rs" , imagtimetrack)   ,
     ("../../../bin/domain/imag-todo/src/ui.rs"      , imagtodo)        ,
     ("../../../bin/domain/imag-wiki/src/ui.rs"      , imagwiki)        ,
@@ -121,9 +128,11 @@ fn main() {
         .subcommand(build_subcommand!("diagnostics" , imagdiagnostics , version))
         .subcommand(build_subcommand!("diary"       , imagdiary       , version))
         .subcommand(build_subcommand!("edit"        , imagedit        , version))
+        .subcommand(build_subcommand!("export"      , imagexport      , version))
         .subcommand(build_subcommand!("git"         , imaggit         , version))
         .subcommand(build_subcommand!("gps"         , imaggps         , version))
         .subcommand(build_subcommand!("grep"        , imaggrep        , version))
+        .subcommand(build_subcommand!("header"      , imagheader      , version))
         .subcommand(build_subcommand!("habit"       , imaghabit       , version))
         .subcommand(build_subcommand!("ids"         , imagids         , version))
         .subcommand(build_subcommand!("init"        , imaginit        , version))
@@ -133,8 +142,10 @@ fn main() {
         .subcommand(build_subcommand!("mv"          , imagmv          , version))
         .subcommand(build_subcommand!("notes"       , imagnotes       , version))
         .subcommand(build_subcommand!("ref"         , imagref         , version))
+        .subcommand(build_subcommand!("remind"      , imagremind      , version))
         .subcommand(build_subcommand!("store"       , imagstore       , version))
         .subcommand(build_subcommand!("tag"         , imagtag         , version))
+        .subcommand(build_subcommand!("tui"         , imagtui         , version))
         .subcommand(build_subcommand!("timetrack"   , imagtimetrack   , version))
         .subcommand(build_subcommand!("todo"        , imagtodo        , version))
         .subcommand(build_subcommand!("view"        , imagview        , version))
@@ -44,6 +44,8 @@ extern crate libimagbookmark;
 #[macro_use] extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
+extern crate libimagentrylink;
+extern crate libimagentryquery;
 
 use std::io::Write;
 use std::process::exit;
@@ -60,6 +62,9 @@ use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagutil::debug_result::DebugResult;
+use libimagentrylink::external::Link;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 mod ui;
 
@@ -144,7 +149,9 @@ fn collection(rt: &Runtime) {
 }
 
 fn list(rt: &Runtime) {
-    let coll = get_collection_name(rt, "list", "collection");
+    let scmd  = rt.cli().subcommand_matches("list").unwrap(); // safe by main()
+    let coll  = get_collection_name(rt, "list", "collection");
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
 
     let collection = BookmarkCollectionStore::get(rt.store(), &coll)
         .map_err_trace_exit_unwrap(1)
@@ -156,10 +163,17 @@ fn list(rt: &Runtime) {
         .map_err_trace_exit_unwrap(1);
 
     collection
-        .links(rt.store())
+        .link_entries()
         .map_dbg_str("Listing...")
         .map_err_trace_exit_unwrap(1)
         .into_iter()
+        .filter_map(|link| rt.store().get(link.get_store_id().clone()).map_err_trace_exit_unwrap(1))
+        .filter(|entry| entry_matches(query.as_ref(), entry).map_err_trace_exit_unwrap(1))
+        .filter_map(|entry| match entry.get_link_uri_from_filelockentry() {
+            Ok(Some(url)) => Some(Ok(url)),
+            Ok(None)      => None,
+            Err(e)        => Some(Err(e)),
+        })
         .enumerate()
         .for_each(|(i, link)| match link {
             Ok(link) => writeln!(rt.stdout(), "{: >3}: {}", i, link).to_exit_code().unwrap_or_exit(),
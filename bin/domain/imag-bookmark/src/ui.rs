@@ -20,6 +20,8 @@
 use clap::{Arg, App, SubCommand};
 
 use libimagutil::cli_validators::*;
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -99,6 +101,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .multiple(true)
                         .value_name("TAGS")
                         .help("Filter links to contain these tags. When multiple tags are specified, all of them must be set for the link to match."))
+                   .arg(where_arg())
+                   .arg(saved_arg())
                    )
 
         .subcommand(SubCommand::with_name("collection")
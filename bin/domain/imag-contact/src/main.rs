@@ -52,6 +52,7 @@ extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimaginteraction;
 extern crate libimagentryedit;
+extern crate libimagentryquery;
 
 use std::process::exit;
 use std::path::PathBuf;
@@ -71,6 +72,8 @@ use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::iter::TraceIterator;
 use libimagcontact::store::ContactStore;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 use libimagcontact::contact::Contact;
 use libimagcontact::deser::DeserVcard;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
@@ -115,6 +118,7 @@ fn main() {
 fn list(rt: &Runtime) {
     let scmd        = rt.cli().subcommand_matches("list").unwrap();
     let list_format = get_contact_print_format("contact.list_format", rt, &scmd);
+    let query       = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
 
     let iterator = rt
         .store()
@@ -124,6 +128,7 @@ fn list(rt: &Runtime) {
         .trace_unwrap_exit(1)
         .map(|fle| fle.ok_or_else(|| Error::from(err_msg("StoreId not found".to_owned()))))
         .trace_unwrap_exit(1)
+        .filter(|fle| entry_matches(query.as_ref(), fle).map_err_trace_exit_unwrap(1))
         .map(|fle| {
             let _ = rt.report_touched(fle.get_location())
                 .map_err_trace_exit_unwrap(1);
@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("list")
@@ -44,6 +47,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .multiple(false)
                         .help("Print output as JSON"))
+                   .arg(where_arg())
+                   .arg(saved_arg())
                    )
 
         .subcommand(SubCommand::with_name("import")
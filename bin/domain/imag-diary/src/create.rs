@@ -27,6 +27,7 @@ use failure::err_msg;
 
 use libimagdiary::diary::Diary;
 use libimagentryedit::edit::Edit;
+use libimagentrytemplate::cli::apply_template_from_cli;
 use libimagrt::runtime::Runtime;
 use libimagerror::trace::MapErrTrace;
 use libimagutil::warn_exit::warn_exit;
@@ -45,6 +46,9 @@ pub fn create(rt: &Runtime) {
 
     let mut entry = create_entry(rt.store(), &diaryname, rt);
 
+    let _ = apply_template_from_cli(rt, rt.cli().subcommand_matches("create").unwrap(), &mut entry)
+        .map_err_trace_exit_unwrap(1);
+
     let _ = rt.report_touched(entry.get_location()).map_err_trace_exit_unwrap(1);
 
     let res = if rt.cli().subcommand_matches("create").unwrap().is_present("no-edit") {
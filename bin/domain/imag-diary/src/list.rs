@@ -30,6 +30,8 @@ use libimagutil::debug_result::*;
 use libimagdiary::diaryid::DiaryId;
 use libimagdiary::diaryid::FromStoreId;
 use libimagstore::storeid::IntoStoreId;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 use failure::Fallible as Result;
 
@@ -39,10 +41,22 @@ pub fn list(rt: &Runtime) {
     let diaryname = get_diary_name(rt)
         .unwrap_or_else(|| warn_exit("No diary selected. Use either the configuration file or the commandline option", 1));
 
+    let scmd  = rt.cli().subcommand_matches("list").unwrap(); // safe by clap
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
+
     let mut ids = Diary::entries(rt.store(), &diaryname)
         .map_dbg_str("Ok")
         .map_err_trace_exit_unwrap(1)
         .trace_unwrap_exit(1)
+        .filter(|id| match query {
+            None        => true,
+            Some(ref q) => rt
+                .store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .map(|entry| entry_matches(Some(q), &entry).map_err_trace_exit_unwrap(1))
+                .unwrap_or(false),
+        })
         .map(|id| DiaryId::from_storeid(&id))
         .collect::<Result<Vec<_>>>()
         .map_err_trace_exit_unwrap(1);
@@ -44,6 +44,8 @@ extern crate itertools;
 
 extern crate libimagdiary;
 extern crate libimagentryedit;
+extern crate libimagentryquery;
+extern crate libimagentrytemplate;
 extern crate libimagentryview;
 extern crate libimagerror;
 extern crate libimaginteraction;
@@ -19,6 +19,10 @@
 
 use clap::{Arg, ArgGroup, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+use libimagentrytemplate::cli::template_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
        .arg(Arg::with_name("diaryname")
@@ -42,6 +46,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .help("Do not edit after creating"))
 
+                   .arg(template_arg())
+
                    .arg(Arg::with_name("timed")
                         .long("timed")
                         .short("t")
@@ -79,7 +85,9 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list")
                    .about("List diary entries")
-                   .version("0.1"))
+                   .version("0.1")
+                   .arg(where_arg())
+                   .arg(saved_arg()))
 
         .subcommand(SubCommand::with_name("delete")
                    .about("Delete a diary entry")
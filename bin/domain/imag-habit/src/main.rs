@@ -49,6 +49,7 @@ extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimaginteraction;
+extern crate libimagentryquery;
 
 use std::io::Write;
 use std::process::exit;
@@ -71,6 +72,8 @@ use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 use libimaginteraction::ask::ask_bool;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 use libimagutil::debug_result::DebugResult;
 
 mod ui;
@@ -411,6 +414,9 @@ fn list(rt: &Runtime) {
         .map(|s| Cell::new(s))
         .collect::<Vec<Cell>>();
 
+    let scmd  = rt.cli().subcommand_matches("list").unwrap(); // safe by clap
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
+
     let mut empty = true;
     let mut table = Table::new();
     table.set_titles(Row::new(header));
@@ -431,6 +437,7 @@ fn list(rt: &Runtime) {
                 None
             },
         })
+        .filter(|h| entry_matches(query.as_ref(), h).map_err_trace_exit_unwrap(1))
         .enumerate()
         .for_each(|(i, e)| {
             let mut v = vec![format!("{}", i)];
@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("status")
@@ -114,6 +117,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .takes_value(false)
                         .help("List with details (how many instances)"))
+                   .arg(where_arg())
+                   .arg(saved_arg())
                    )
 
         .subcommand(SubCommand::with_name("show")
@@ -47,6 +47,7 @@ extern crate libimaglog;
 extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagdiary;
+extern crate libimagentryquery;
 
 use std::io::Write;
 
@@ -63,6 +64,8 @@ use libimagerror::exit::ExitCode;
 use libimagdiary::diary::Diary;
 use libimaglog::log::Log;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 mod ui;
 use ui::build_ui;
@@ -115,7 +118,8 @@ fn show(rt: &Runtime) {
     use libimagdiary::iter::DiaryEntryIterator;
     use libimagdiary::entry::DiaryEntry;
 
-    let scmd = rt.cli().subcommand_matches("show").unwrap(); // safed by main()
+    let scmd  = rt.cli().subcommand_matches("show").unwrap(); // safed by main()
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
     let iters : Vec<DiaryEntryIterator> = match scmd.values_of("show-name") {
         Some(values) => values
             .map(|diary_name| Diary::entries(rt.store(), diary_name).map_err_trace_exit_unwrap(1))
@@ -153,6 +157,7 @@ fn show(rt: &Runtime) {
             opt
         })
         .filter(|e| e.is_log().map_err_trace_exit_unwrap(1))
+        .filter(|e| entry_matches(query.as_ref(), e).map_err_trace_exit_unwrap(1))
         .map(|entry| (entry.diary_id().map_err_trace_exit_unwrap(1), entry))
         .sorted_by_key(|tpl| tpl.0.clone())
         .into_iter()
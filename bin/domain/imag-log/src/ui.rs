@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
        .arg(Arg::with_name("diaryname")
@@ -57,6 +60,9 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .conflicts_with("show-all")
                         .help("Show logs. Multiple possible (will be sorted by date, still)."))
 
+                   .arg(where_arg())
+                   .arg(saved_arg())
+
                 )
 
 }
@@ -40,12 +40,15 @@ extern crate failure;
 
 #[macro_use] extern crate libimagrt;
 extern crate libimagmail;
+#[macro_use] extern crate libimagentryref;
+extern crate libimagentryquery;
 extern crate libimagerror;
 extern crate libimagutil;
 
 use std::io::Write;
 
 use failure::Error;
+use failure::Fallible as Result;
 use failure::err_msg;
 
 use libimagerror::trace::{MapErrTrace, trace_error};
@@ -53,6 +56,12 @@ use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagmail::mail::Mail;
+use libimagmail::mail::MAIL_COLLECTION;
+use libimagmail::mail::MAIL_GENERATOR;
+use libimagentryref::config::Config as RefConfig;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagutil::info_result::*;
@@ -91,13 +100,31 @@ fn import_mail(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("import-mail").unwrap();
     let path = scmd.value_of("path").unwrap(); // enforced by clap
 
-    let mail = Mail::import_from_path(rt.store(), path)
+    let config = ref_config(rt);
+
+    let mail = match config.generator_for(MAIL_COLLECTION) {
+        Some(name) if name != MAIL_GENERATOR => {
+            with_generator!(name, import_mail_with(rt, path, &config, name))
+                .and_then(|mail| mail)
+        },
+        _ => Mail::import_from_path(rt.store(), path, &config),
+    };
+
+    let mail = mail
         .map_info_str("Ok")
         .map_err_trace_exit_unwrap(1);
 
     let _ = rt.report_touched(mail.fle().get_location()).map_err_trace_exit_unwrap(1);
 }
 
+/// Import the mail at `path` with the generator `RPG` named `generator`
+fn import_mail_with<'a, RPG>(rt: &'a Runtime, path: &str, config: &RefConfig, generator: &str)
+    -> Result<Mail<'a>>
+    where RPG: UniqueRefPathGenerator
+{
+    Mail::import_from_path_with::<RPG, _>(rt.store(), path, config, generator)
+}
+
 fn list(rt: &Runtime) {
     use failure::ResultExt;
 
@@ -150,6 +177,10 @@ fn list(rt: &Runtime) {
         let _ = rt.report_touched(m.fle().get_location()).map_err_trace_exit_unwrap(1);
     }
 
+    let ref_config = ref_config(rt);
+    let scmd       = rt.cli().subcommand_matches("list").unwrap(); // safe by clap
+    let query      = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
+
     let _ = rt.store()
         .entries()
         .map_err_trace_exit_unwrap(1)
@@ -161,7 +192,8 @@ fn list(rt: &Runtime) {
                 .context(err_msg("Ref handling error"))
                 .map_err(Error::from)
                 .map_err_trace_exit_unwrap(1)
-                .map(|fle| Mail::from_fle(fle).map_err_trace().ok())
+                .filter(|fle| entry_matches(query.as_ref(), fle).map_err_trace_exit_unwrap(1))
+                .map(|fle| Mail::from_fle(fle, &ref_config).map_err_trace().ok())
         })
         .filter_map(|e| e)
         .for_each(|m| list_mail(&rt, m));
@@ -173,3 +205,9 @@ fn mail_store(rt: &Runtime) {
     unimplemented!()
 }
 
+fn ref_config(rt: &Runtime) -> RefConfig {
+    rt.config()
+        .map(RefConfig::from_config)
+        .unwrap_or_else(|| Ok(RefConfig::default()))
+        .map_err_trace_exit_unwrap(1)
+}
@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("import-mail")
@@ -60,6 +63,9 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .short("P")
                          .help("Check whether the permissions of the referenced file changed"))
 
+                    .arg(where_arg())
+
+                    .arg(saved_arg())
                     )
 
         .subcommand(SubCommand::with_name("mail-store")
@@ -41,6 +41,8 @@ extern crate itertools;
 extern crate libimagnotes;
 #[macro_use] extern crate libimagrt;
 extern crate libimagentryedit;
+extern crate libimagentryquery;
+extern crate libimagentrytemplate;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimagstore;
@@ -51,6 +53,9 @@ use std::process::exit;
 use itertools::Itertools;
 
 use libimagentryedit::edit::Edit;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
+use libimagentrytemplate::cli::apply_template_from_cli;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
@@ -99,13 +104,16 @@ fn name_from_cli(rt: &Runtime, subcmd: &str) -> String {
 }
 
 fn create(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("create").unwrap(); // safed by main()
     let name = name_from_cli(rt, "create");
     let mut note = rt
         .store()
         .new_note(name.clone(), String::new())
         .map_err_trace_exit_unwrap(1);
 
-    if rt.cli().subcommand_matches("create").unwrap().is_present("edit") {
+    let _ = apply_template_from_cli(rt, scmd, &mut note).map_err_trace_exit_unwrap(1);
+
+    if scmd.is_present("edit") {
         let _ = note
             .edit_content(rt)
             .map_warn_err_str("Editing failed")
@@ -148,6 +156,9 @@ fn edit(rt: &Runtime) {
 fn list(rt: &Runtime) {
     use std::cmp::Ordering;
 
+    let scmd  = rt.cli().subcommand_matches("list").unwrap(); // safe by clap
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
+
     let _ = rt
         .store()
         .all_notes()
@@ -158,6 +169,7 @@ fn list(rt: &Runtime) {
             error!("Fatal: Nonexistent entry where entry should exist");
             exit(1)
         }))
+        .filter(|note| entry_matches(query.as_ref(), note).map_err_trace_exit_unwrap(1))
         .sorted_by(|note_a, note_b| if let (Ok(a), Ok(b)) = (note_a.get_name(), note_b.get_name()) {
             return a.cmp(&b)
         } else {
@@ -19,6 +19,10 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+use libimagentrytemplate::cli::template_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("create")
@@ -36,6 +40,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(true)
                         .help("Create Note with this name")
                         .value_name("NAME"))
+                   .arg(template_arg())
                    )
 
         .subcommand(SubCommand::with_name("delete")
@@ -61,6 +66,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list")
                    .about("List Notes")
-                   .version("0.1"))
+                   .version("0.1")
+                   .arg(where_arg())
+                   .arg(saved_arg()))
 
 }
@@ -0,0 +1,279 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use]
+extern crate log;
+extern crate failure;
+extern crate chrono;
+
+extern crate libimagentrydatetime;
+extern crate libimagnotification;
+#[macro_use] extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagstore;
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::process::exit;
+
+use chrono::Duration;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use failure::Error;
+use failure::Fallible as Result;
+
+use libimagentrydatetime::datetime::EntryDate;
+use libimagentrydatetime::recurrence::EntryRecurrence;
+use libimagentrydatetime::recurrence::Recurrence;
+use libimagentrydatetime::reminder::EntryReminder;
+use libimagentrydatetime::upcoming::UpcomingStore;
+use libimagnotification::notificator::Notificator;
+use libimagnotification::notificator::default::Notification;
+use libimagnotification::notificator::default::Urgency;
+use libimagrt::setup::generate_runtime_setup;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+
+mod ui;
+
+const DATE_DISPLAY_FMT : &str = "%Y-%m-%d %H:%M";
+
+fn main() {
+    let version = make_imag_version!();
+    let rt = generate_runtime_setup("imag-remind",
+                                    &version,
+                                    "Recurring dates and reminders for entries",
+                                    ui::build_ui);
+
+    rt.cli()
+        .subcommand_name()
+        .map(|name| {
+            match name {
+                "list"     => list(&rt),
+                "upcoming" => upcoming(&rt),
+                "fire"     => fire(&rt),
+                "add"      => add(&rt),
+                "remove"   => remove(&rt),
+                "recur"    => recur(&rt),
+                other      => {
+                    debug!("Unknown command");
+                    let _ = rt.handle_unknown_subcommand("imag-remind", other, rt.cli())
+                        .map_err_trace_exit_unwrap(1)
+                        .code()
+                        .map(::std::process::exit);
+                }
+            }
+        });
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+fn list(rt: &Runtime) {
+    let mut out = rt.stdout();
+    let mut due = rt.store()
+        .due_reminders(&now())
+        .map_err_trace_exit_unwrap(1)
+        .collect::<Result<Vec<_>>>()
+        .map_err_trace_exit_unwrap(1);
+    due.sort_by(|a, b| a.remind_at.cmp(&b.remind_at).then_with(|| a.id.cmp(&b.id)));
+
+    for reminder in due {
+        let _ = writeln!(out, "{}\t{} before\t{}",
+                         reminder.occurrence.format(DATE_DISPLAY_FMT),
+                         reminder.offset,
+                         reminder.id)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(&reminder.id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn upcoming(rt: &Runtime) {
+    let days = rt
+        .cli()
+        .subcommand_matches("upcoming")
+        .unwrap() // safed by main()
+        .value_of("days")
+        .unwrap() // safed by clap default value
+        .parse::<i64>()
+        .map_err(Error::from)
+        .map_err_trace_exit_unwrap(1);
+
+    let from = now();
+    let to   = from + Duration::days(days);
+    let mut out = rt.stdout();
+    let mut occurrences = rt.store()
+        .occurrences(&from, &to)
+        .map_err_trace_exit_unwrap(1)
+        .collect::<Result<Vec<_>>>()
+        .map_err_trace_exit_unwrap(1);
+    occurrences.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.id.cmp(&b.id)));
+
+    for occurrence in occurrences {
+        let _ = writeln!(out, "{}\t{}", occurrence.at.format(DATE_DISPLAY_FMT), occurrence.id)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(&occurrence.id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn fire(rt: &Runtime) {
+    let urgency = match rt.cli().subcommand_matches("fire").unwrap().value_of("urgency") {
+        Some("low")  => Urgency::Low,
+        Some("high") => Urgency::High,
+        _            => Urgency::Normal,
+    };
+
+    let now = now();
+    let due = rt.store().due_reminders(&now).map_err_trace_exit_unwrap(1);
+    let mut fired = BTreeSet::new();
+
+    for reminder in due {
+        let reminder     = reminder.map_err_trace_exit_unwrap(1);
+        let notification = Notification {
+            timeout: 0,
+            message: format!("{}", reminder.occurrence.format(DATE_DISPLAY_FMT)),
+            summary: String::from("imag reminder"),
+            urgency: urgency.clone(),
+        };
+
+        debug!("Firing {:?}", reminder);
+        let _ = notification.notify(&reminder.id).map_err_trace_exit_unwrap(1);
+        let _ = fired.insert(reminder.id);
+    }
+
+    for id in fired {
+        let _ = get_entry(rt, &id)
+            .set_reminded(now)
+            .map_err_trace_exit_unwrap(1);
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn add(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
+
+    for id in rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1) {
+        let mut entry = get_entry(rt, &id);
+
+        for offset in scmd.values_of("offset").unwrap() { // safed by clap
+            let added = entry.add_reminder(String::from(offset)).map_err_trace_exit_unwrap(1);
+            if !added {
+                info!("{} already has reminder '{}'", id, offset);
+            }
+        }
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn remove(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("remove").unwrap(); // safed by main()
+
+    for id in rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1) {
+        let mut entry = get_entry(rt, &id);
+
+        for offset in scmd.values_of("offset").unwrap() { // safed by clap
+            let removed = entry.remove_reminder(offset).map_err_trace_exit_unwrap(1);
+            if !removed {
+                warn!("{} has no reminder '{}'", id, offset);
+            }
+        }
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn recur(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("recur").unwrap(); // safed by main()
+
+    let parse_date = |name: &str| -> Option<NaiveDateTime> {
+        scmd.value_of(name).map(|s| parse_datetime(s).map_err_trace_exit_unwrap(1))
+    };
+
+    let start = parse_date("start");
+    let until = parse_date("until");
+
+    for id in rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1) {
+        let mut entry = get_entry(rt, &id);
+
+        if scmd.is_present("delete") {
+            let _ = entry.delete_recurrence().map_err_trace_exit_unwrap(1);
+        } else {
+            if let Some(start) = start {
+                let _ = entry.set_date(start).map_err_trace_exit_unwrap(1);
+            }
+
+            let spec       = String::from(scmd.value_of("spec").unwrap()); // safed by clap
+            let recurrence = Recurrence::new(spec, until).map_err_trace_exit_unwrap(1);
+            let _          = entry.set_recurrence(recurrence).map_err_trace_exit_unwrap(1);
+
+            if entry.first_occurrence().map_err_trace_exit_unwrap(1).is_none() {
+                warn!("{} has no date, it does not occur before one is set with --start", id);
+            }
+        }
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn get_entry<'a>(rt: &'a Runtime, id: &StoreId) -> FileLockEntry<'a> {
+    rt.store()
+        .get(id.clone())
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| { // if we have Ok(None)
+            error!("No such entry: {}", id);
+            exit(1)
+        })
+}
+
+/// Parse a date like "2018-10-20" (midnight) or "2018-10-20T09:00:00"
+fn parse_datetime(s: &str) -> Result<NaiveDateTime> {
+    s.parse::<NaiveDateTime>()
+        .or_else(|_| s.parse::<NaiveDate>().map(|d| d.and_hms(0, 0, 0)))
+        .map_err(Error::from)
+}
@@ -0,0 +1,175 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+
+use clap::{Arg, ArgMatches, App, SubCommand};
+
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+use libimagrt::runtime::IdPathProvider;
+use libimagerror::trace::MapErrTrace;
+
+pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+    app
+        .subcommand(SubCommand::with_name("list")
+                    .about("List the reminders which are due now")
+                    .version("0.1")
+                    )
+
+        .subcommand(SubCommand::with_name("upcoming")
+                    .about("List upcoming dates of all entries")
+                    .version("0.1")
+                    .arg(Arg::with_name("days")
+                         .long("days")
+                         .short("d")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .default_value("7")
+                         .help("How many days to look ahead")
+                         .value_name("DAYS"))
+                    )
+
+        .subcommand(SubCommand::with_name("fire")
+                    .about("Show a desktop notification for each reminder which is due now and mark it as fired")
+                    .version("0.1")
+                    .arg(Arg::with_name("urgency")
+                         .long("urgency")
+                         .short("u")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .possible_values(&["low", "normal", "high"])
+                         .default_value("normal")
+                         .help("The urgency of the notifications")
+                         .value_name("URGENCY"))
+                    )
+
+        .subcommand(SubCommand::with_name("add")
+                    .about("Add reminders to an entry")
+                    .version("0.1")
+                    .arg(Arg::with_name("entry")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The entry to add the reminders to")
+                         .value_name("ENTRY"))
+                    .arg(Arg::with_name("offset")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(true)
+                         .help("How long before each date of the entry to remind (eg: '15minutes', '2hours', '1days')")
+                         .value_name("OFFSET"))
+                    )
+
+        .subcommand(SubCommand::with_name("remove")
+                    .about("Remove reminders from an entry")
+                    .version("0.1")
+                    .arg(Arg::with_name("entry")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The entry to remove the reminders from")
+                         .value_name("ENTRY"))
+                    .arg(Arg::with_name("offset")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(true)
+                         .help("The reminders to remove")
+                         .value_name("OFFSET"))
+                    )
+
+        .subcommand(SubCommand::with_name("recur")
+                    .about("Let an entry recur")
+                    .version("0.1")
+                    .arg(Arg::with_name("entry")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The entry which should recur")
+                         .value_name("ENTRY"))
+                    .arg(Arg::with_name("spec")
+                         .index(2)
+                         .takes_value(true)
+                         .required_unless("delete")
+                         .multiple(false)
+                         .help("Spec how the entry should recur (eg: 'weekly', 'monthly', '5days', '12hours')")
+                         .value_name("SPEC"))
+                    .arg(Arg::with_name("start")
+                         .long("start")
+                         .short("s")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Set the date of the first occurrence (eg: '2018-10-20' or '2018-10-20T09:00:00'). Defaults to the date of the entry")
+                         .value_name("DATE"))
+                    .arg(Arg::with_name("until")
+                         .long("until")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Do not recur after this date")
+                         .value_name("DATE"))
+                    .arg(Arg::with_name("delete")
+                         .long("delete")
+                         .takes_value(false)
+                         .required(false)
+                         .multiple(false)
+                         .conflicts_with_all(&["spec", "start", "until"])
+                         .help("Remove the recurrence from the entry"))
+                    )
+}
+
+pub struct PathProvider;
+impl IdPathProvider for PathProvider {
+    fn get_ids(matches: &ArgMatches) -> Vec<StoreId> {
+        match matches.subcommand() {
+            ("add", Some(subm)) | ("remove", Some(subm)) | ("recur", Some(subm)) => {
+                let id = subm
+                    .value_of("entry")
+                    .map(PathBuf::from)
+                    .ok_or_else(|| {
+                        error!("No StoreId found");
+                        ::std::process::exit(1)
+                    })
+                    .unwrap()
+                    .into_storeid()
+                    .map_err_trace_exit_unwrap(1);
+
+                vec![id]
+            },
+
+            ("list", _) | ("upcoming", _) | ("fire", _) => {
+                error!("Command does not get IDs as input");
+                ::std::process::exit(1)
+            },
+
+            (other, _) => {
+                error!("Not a known command: {}", other);
+                ::std::process::exit(1)
+            }
+        }
+    }
+}
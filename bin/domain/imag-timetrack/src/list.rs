@@ -36,6 +36,9 @@ use libimagerror::iter::TraceIterator;
 use libimagstore::store::FileLockEntry;
 use libimagtimetrack::timetrackingstore::TimeTrackStore;
 use libimagtimetrack::timetracking::TimeTracking;
+use libimagentryquery::query::Query;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 use libimagrt::runtime::Runtime;
 
@@ -77,14 +80,16 @@ pub fn list(rt: &Runtime) -> i32 {
     let end   = gettime(&cmd, "end-time");
 
     let list_not_ended = cmd.is_present("list-not-ended");
+    let query          = query_from_cli(&cmd, rt.store()).map_err_trace_exit_unwrap(1);
 
-    list_impl(rt, start, end, list_not_ended)
+    list_impl(rt, start, end, list_not_ended, query)
 }
 
 pub fn list_impl(rt: &Runtime,
                  start: Option<NaiveDateTime>,
                  end: Option<NaiveDateTime>,
-                 list_not_ended: bool)
+                 list_not_ended: bool,
+                 query: Option<Query>)
     -> i32
 {
 
@@ -125,6 +130,7 @@ pub fn list_impl(rt: &Runtime,
         .map_err_trace_exit_unwrap(1)
         .trace_unwrap()
         .filter(|e| filter.filter(e))
+        .filter(|e| entry_matches(query.as_ref(), e).map_err_trace_exit_unwrap(1))
         .fold(Ok(table), |acc: Result<_>, e| {
             acc.and_then(|mut tab: Table| {
                 debug!("Processing {:?}", e.get_location());
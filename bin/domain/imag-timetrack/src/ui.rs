@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
        .subcommand(SubCommand::with_name("list")
@@ -46,6 +49,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .multiple(false)
                         .required(false)
                         .help("List not yet ended timetrackings even if after 'end-time'"))
+                   .arg(where_arg())
+                   .arg(saved_arg())
                    )
 
        .subcommand(SubCommand::with_name("start")
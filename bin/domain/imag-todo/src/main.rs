@@ -44,6 +44,7 @@ extern crate failure;
 #[macro_use] extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagtodo;
+extern crate libimagentryquery;
 
 use std::process::{Command, Stdio};
 use std::io::stdin;
@@ -53,6 +54,8 @@ use failure::Error;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagtodo::taskstore::TaskStore;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
@@ -118,6 +121,7 @@ fn list(rt: &Runtime) {
 
     let subcmd  = rt.cli().subcommand_matches("list").unwrap();
     let verbose = subcmd.is_present("verbose");
+    let query   = query_from_cli(subcmd, rt.store()).map_err_trace_exit_unwrap(1);
 
     // Helper for toml_query::read::TomlValueReadExt::read() return value, which does only
     // return Result<T> instead of Result<Option<T>>, which is a real inconvenience.
@@ -131,6 +135,7 @@ fn list(rt: &Runtime) {
             // filter out the ones were we can read the uuid
             let uuids : Vec<_> = iter.trace_unwrap_exit(1).filter_map(|storeid| {
                 match rt.store().retrieve(storeid) {
+                    Ok(ref fle) if !entry_matches(query.as_ref(), fle).map_err_trace_exit_unwrap(1) => None,
                     Ok(fle) => {
                         match fle.get_header().read_string("todo.uuid") {
                             Ok(Some(ref u)) => Some(u.clone()),
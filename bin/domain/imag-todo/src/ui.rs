@@ -19,6 +19,9 @@
 
 use clap::{Arg, App, ArgGroup, SubCommand};
 
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("tw-hook")
@@ -57,5 +60,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .required(false)
                          .help("Asks taskwarrior for all the details")
                         )
+                    .arg(where_arg())
+                    .arg(saved_arg())
                    )
 }
@@ -30,7 +30,10 @@ extern crate libimagstore;
 extern crate libimagwiki;
 extern crate libimagentryedit;
 extern crate libimagentrylink;
+extern crate libimagentrymarkdown;
+extern crate libimagentrytemplate;
 extern crate libimagutil;
+extern crate libimagentryquery;
 
 use std::io::Write;
 
@@ -41,7 +44,12 @@ use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagwiki::store::WikiStore;
-use libimagentryedit::edit::{Edit, EditHeader};
+use libimagstore::store::Entry;
+use libimagentryedit::edit::EditWithHook;
+use libimagentrymarkdown::wikilink::link_wiki_links;
+use libimagentrymarkdown::wikilink::StoreResolver;
+use libimagentryquery::cli::query_from_cli;
+use libimagentryquery::cli::entry_matches;
 
 mod ui;
 use ui::build_ui;
@@ -80,6 +88,7 @@ fn ids(rt: &Runtime, wiki_name: &str) {
     } else {
         String::from("")
     };
+    let query = query_from_cli(scmd, rt.store()).map_err_trace_exit_unwrap(1);
 
     let out         = rt.stdout();
     let mut outlock = out.lock();
@@ -94,6 +103,13 @@ fn ids(rt: &Runtime, wiki_name: &str) {
         .all_ids()
         .map_err_trace_exit_unwrap(1)
         .trace_unwrap_exit(1)
+        .filter(|id| query.is_none() || {
+            rt.store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .map(|entry| entry_matches(query.as_ref(), &entry).map_err_trace_exit_unwrap(1))
+                .unwrap_or(false)
+        })
         .for_each(|id| {
             let _ = writeln!(outlock, "{}{}", prefix, id)
                 .to_exit_code()
@@ -140,6 +156,7 @@ fn idof(rt: &Runtime, wiki_name: &str) {
 fn create(rt: &Runtime, wiki_name: &str) {
     use libimagwiki::entry::WikiEntry;
     use libimagutil::warn_result::WarnResult;
+    use libimagentrytemplate::cli::apply_template_from_cli;
 
     let scmd        = rt.cli().subcommand_matches("create").unwrap(); // safed by clap
     let name        = String::from(scmd.value_of("create-name").unwrap()); // safe by clap
@@ -155,11 +172,25 @@ fn create(rt: &Runtime, wiki_name: &str) {
 
     let mut entry = wiki.create_entry(name).map_err_trace_exit_unwrap(1);
 
+    let _ = apply_template_from_cli(rt, scmd, &mut entry).map_err_trace_exit_unwrap(1);
+
     if !scmd.is_present("create-noedit") {
+        let hook = |e: &mut Entry| {
+            link_wiki_links(e, rt.store(), &StoreResolver::new()).map(|missing| {
+                for link in missing {
+                    warn!("Wiki link target '{}' in {} does not exist", link.target, e.get_location());
+                }
+            })
+        };
+
         if scmd.is_present("create-editheader") {
-            let _ = entry.edit_header_and_content(rt).map_err_trace_exit_unwrap(1);
+            let _ = entry
+                .edit_header_and_content_with_hook(rt, hook)
+                .map_err_trace_exit_unwrap(1);
         } else {
-            let _ = entry.edit_content(rt).map_err_trace_exit_unwrap(1);
+            let _ = entry
+                .edit_content_with_hook(rt, hook)
+                .map_err_trace_exit_unwrap(1);
         }
     }
 
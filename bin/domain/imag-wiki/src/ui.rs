@@ -19,6 +19,10 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentrytemplate::cli::template_arg;
+use libimagentryquery::cli::where_arg;
+use libimagentryquery::cli::saved_arg;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .arg(Arg::with_name("wikiname")
@@ -39,7 +43,9 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(false)
                         .required(false)
                         .multiple(false)
-                        .help("Print full filepath")))
+                        .help("Print full filepath"))
+                   .arg(where_arg())
+                   .arg(saved_arg()))
 
         .subcommand(SubCommand::with_name("idof")
                    .about("List id of an entry in this wiki, if it exists")
@@ -96,6 +102,8 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .multiple(false)
                         .help("Print the store id after creating"))
+
+                   .arg(template_arg())
                    )
 
         .subcommand(SubCommand::with_name("create")
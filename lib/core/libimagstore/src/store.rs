@@ -326,6 +326,41 @@ impl Store {
             .map_err(Error::from)
     }
 
+    /// Check whether an entry exists, either in the internal cache or in the backend
+    ///
+    /// In contrast to `Store::get()`, this does not borrow the entry.
+    pub fn exists<S: IntoStoreId>(&self, id: S) -> Result<bool> {
+        let id = id.into_storeid()?.with_base(self.path().clone());
+
+        let in_cache = self.entries
+            .read()
+            .map(|map| map.contains_key(&id))
+            .map_err(|_| Error::from(EM::LockError))
+            .context(format_err!("ExistsCallError: {}", id))?;
+
+        Ok(in_cache || self.backend.exists(&id.clone().into_pathbuf()?)?)
+    }
+
+    /// Read an entry from the backend, bypassing the internal cache
+    ///
+    /// In contrast to `Store::get()`, this does not borrow the entry. It returns the entry as it is
+    /// persisted, which might differ from the cached version if the entry was changed in memory
+    /// or by another process.
+    pub fn read_persisted<S: IntoStoreId>(&self, id: S) -> Result<Option<Entry>> {
+        let id   = id.into_storeid()?.with_base(self.path().clone());
+        let path = id.clone().into_pathbuf()?;
+
+        if !self.backend.exists(&path)? {
+            return Ok(None)
+        }
+
+        self.backend
+            .new_instance(path)
+            .get_file_content(id.clone())
+            .context(format_err!("ReadPersistedCallError: {}", id))
+            .map_err(Error::from)
+    }
+
     /// Write (update) the `FileLockEntry` to disk
     ///
     /// # Return value
@@ -583,6 +618,8 @@ impl Store {
     ///
     /// So the link is _partly dangling_, so to say.
     ///
+    /// Links in the content of other entries are not touched either.
+    ///
     pub fn move_by_id(&self, old_id: StoreId, new_id: StoreId) -> Result<()> {
         let new_id = new_id.with_base(self.path().clone());
         let old_id = old_id.with_base(self.path().clone());
@@ -1076,6 +1113,38 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_store_read_persisted() {
+        let store = get_store();
+        assert!(store.read_persisted(PathBuf::from("test")).unwrap().is_none());
+
+        {
+            let mut entry = store.create(PathBuf::from("test")).unwrap();
+            *entry.get_content_mut() = String::from("persisted");
+        }
+
+        let mut entry = store.get(PathBuf::from("test")).unwrap().unwrap();
+        *entry.get_content_mut() = String::from("in memory");
+
+        let persisted = store.read_persisted(PathBuf::from("test")).unwrap().unwrap();
+        assert_eq!(persisted.get_content(), "persisted");
+    }
+
+    #[test]
+    fn test_store_exists() {
+        let store = get_store();
+
+        assert!(!store.exists(PathBuf::from("test-exists")).unwrap());
+        {
+            let _entry = store.create(PathBuf::from("test-exists")).unwrap();
+            assert!(store.exists(PathBuf::from("test-exists")).unwrap());
+        }
+        assert!(store.exists(PathBuf::from("test-exists")).unwrap());
+
+        assert!(store.delete(PathBuf::from("test-exists")).is_ok());
+        assert!(!store.exists(PathBuf::from("test-exists")).unwrap());
+    }
+
     #[test]
     fn test_store_get_create_get_delete_get() {
         let store = get_store();
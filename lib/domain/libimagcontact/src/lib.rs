@@ -49,6 +49,8 @@ extern crate serde;
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
 #[macro_use] extern crate libimagentryutil;
+#[cfg(feature = "viewer")]
+extern crate libimagentryview;
 
 module_entry_path_mod!("contact");
 
@@ -56,5 +58,7 @@ pub mod contact;
 pub mod iter;
 pub mod store;
 pub mod deser;
+#[cfg(feature = "viewer")]
+pub mod viewer;
 mod util;
 
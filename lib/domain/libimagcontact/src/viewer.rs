@@ -0,0 +1,119 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A viewer which renders contact entries as contact cards
+
+use std::io::Write;
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Entry;
+use libimagentryview::viewer::Viewer;
+use libimagentryview::registry::ViewerRegistry;
+
+use contact::Contact;
+use contact::IsContact;
+
+pub struct ContactViewer;
+
+impl Viewer for ContactViewer {
+
+    fn view_entry<W>(&self, e: &Entry, sink: &mut W) -> Result<()>
+        where W: Write
+    {
+        let card = e.deser()?;
+
+        let emails = card
+            .email()
+            .iter()
+            .map(|mail| mail.address.clone())
+            .collect::<Vec<_>>();
+
+        let fields = vec![
+            ("Name",         card.fullname().join(", ")),
+            ("Nickname",     card.nickname().join(", ")),
+            ("Organization", card.org().join(", ")),
+            ("Title",        card.title().join(", ")),
+            ("Email",        emails.join(", ")),
+            ("Phone",        card.tel().join(", ")),
+            ("Address",      card.adr().join("; ")),
+            ("Birthday",     card.bday().cloned().unwrap_or_default()),
+            ("Web",          card.url().join(", ")),
+            ("Note",         card.note().join("\n")),
+        ];
+
+        for (name, value) in fields {
+            if !value.is_empty() {
+                writeln!(sink, "{:<13} {}", format!("{}:", name), value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+}
+
+/// Register the `ContactViewer` for contact entries
+pub fn register(registry: &mut ViewerRegistry) {
+    registry.register_kind::<IsContact, _>(ContactViewer);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagentryview::viewer::Viewer;
+
+    use super::ContactViewer;
+    use store::ContactStore;
+
+    fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_view_contact() {
+        let store = get_store();
+        let vcard = [
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "UID:5d7b8a90-0a6f-4d3a-9c6e-2f2b1a3c4d5e",
+            "FN:Jane Doe",
+            "EMAIL:jane@example.com",
+            "TEL:+49 123 456",
+            "END:VCARD",
+        ].join("\r\n");
+
+        let entry = store.create_from_buf(&vcard).unwrap();
+
+        let mut buf = vec![];
+        ContactViewer.view_entry(&entry, &mut buf).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+
+        assert_eq!(output, concat!(
+            "Name:         Jane Doe\n",
+            "Email:        jane@example.com\n",
+            "Phone:        +49 123 456\n",
+        ));
+    }
+}
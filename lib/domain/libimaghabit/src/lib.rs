@@ -47,6 +47,8 @@ extern crate libimagerror;
 extern crate libimagentryedit;
 extern crate libimagentrylink;
 #[macro_use] extern crate libimagentryutil;
+#[cfg(feature = "viewer")]
+extern crate libimagentryview;
 extern crate libimagutil;
 
 module_entry_path_mod!("habit");
@@ -56,4 +58,6 @@ pub mod instance;
 pub mod iter;
 pub mod store;
 pub mod util;
+#[cfg(feature = "viewer")]
+pub mod viewer;
 
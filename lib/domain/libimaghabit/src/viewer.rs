@@ -0,0 +1,166 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Viewers for habit templates and habit instances
+
+use std::io::Write;
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Entry;
+use libimagentryview::viewer::Viewer;
+use libimagentryview::registry::ViewerRegistry;
+use libimagutil::date::date_to_string;
+
+use habit::HabitTemplate;
+use habit::IsHabitTemplate;
+use instance::HabitInstance;
+use instance::IsHabitInstance;
+
+/// Shows a habit template as table, together with the instances of the habit
+pub struct HabitTemplateViewer;
+
+impl Viewer for HabitTemplateViewer {
+
+    fn view_entry<W>(&self, e: &Entry, sink: &mut W) -> Result<()>
+        where W: Write
+    {
+        let next = e
+            .next_instance_date()?
+            .map(|date| date_to_string(&date))
+            .unwrap_or_else(|| String::from("-"));
+
+        let mut instances = e.linked_instances()?.collect::<Result<Vec<_>>>()?;
+        instances.sort();
+
+        writeln!(sink, "{:<10} {}", "Habit:", e.habit_name()?)?;
+        writeln!(sink, "{:<10} {}", "Comment:", e.habit_comment()?)?;
+        writeln!(sink, "{:<10} {}", "Since:", e.habit_basedate()?)?;
+        writeln!(sink, "{:<10} {}", "Until:", e.habit_until_date()?.unwrap_or_else(|| String::from("-")))?;
+        writeln!(sink, "{:<10} {}", "Every:", e.habit_recur_spec()?)?;
+        writeln!(sink, "{:<10} {}", "Next:", next)?;
+        writeln!(sink, "{:<10} {}", "Done:", instances.len())?;
+
+        for instance in instances {
+            writeln!(sink, "  {}", instance)?;
+        }
+
+        Ok(())
+    }
+
+}
+
+/// Shows a habit instance
+pub struct HabitInstanceViewer;
+
+impl Viewer for HabitInstanceViewer {
+
+    fn view_entry<W>(&self, e: &Entry, sink: &mut W) -> Result<()>
+        where W: Write
+    {
+        writeln!(sink, "{:<10} {}", "Habit:", e.get_template_name()?)?;
+        writeln!(sink, "{:<10} {}", "Done on:", date_to_string(&e.get_date()?))?;
+
+        let comment = e.get_comment()?;
+        if !comment.is_empty() {
+            writeln!(sink, "{:<10} {}", "Comment:", comment)?;
+        }
+
+        Ok(())
+    }
+
+}
+
+/// Register the viewers for habit templates and habit instances
+pub fn register(registry: &mut ViewerRegistry) {
+    registry
+        .register_kind::<IsHabitTemplate, _>(HabitTemplateViewer)
+        .register_kind::<IsHabitInstance, _>(HabitInstanceViewer);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use chrono::NaiveDate;
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Store;
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagentryview::viewer::Viewer;
+
+    use super::HabitTemplateViewer;
+    use super::HabitInstanceViewer;
+    use habit::builder::HabitBuilder;
+
+    fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn view<V: Viewer>(viewer: V, e: &Entry) -> String {
+        let mut buf = vec![];
+        viewer.view_entry(e, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_view_habit_template() {
+        let store = get_store();
+        let entry = HabitBuilder::default()
+            .with_name(String::from("run"))
+            .with_comment(String::from("every morning"))
+            .with_basedate(NaiveDate::from_ymd(2018, 1, 1))
+            .with_recurspec(String::from("weekly"))
+            .with_until(NaiveDate::from_ymd(2018, 1, 31))
+            .build(&store)
+            .unwrap();
+
+        let output = view(HabitTemplateViewer, &entry);
+        let lines  = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines, vec![
+            "Habit:     run",
+            "Comment:   every morning",
+            "Since:     2018-01-01",
+            "Until:     2018-01-31",
+            "Every:     weekly",
+            "Next:      -",
+            "Done:      0",
+        ]);
+    }
+
+    #[test]
+    fn test_view_habit_instance() {
+        let mut entry = Entry::new(PathBuf::from("habit/instance/run-2018-01-08").into_storeid().unwrap());
+        {
+            let header = entry.get_header_mut();
+            let _ = header.insert("habit.instance.name", Value::String(String::from("run"))).unwrap();
+            let _ = header.insert("habit.instance.date", Value::String(String::from("2018-01-08"))).unwrap();
+            let _ = header.insert("habit.instance.comment", Value::String(String::new())).unwrap();
+        }
+
+        let output = view(HabitInstanceViewer, &entry);
+        assert_eq!(output, "Habit:     run\nDone on:   2018-01-08\n");
+    }
+}
@@ -45,7 +45,12 @@ extern crate failure;
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagentryref;
+#[macro_use] extern crate libimagentryutil;
+#[cfg(feature = "viewer")]
+extern crate libimagentryview;
 
 pub mod iter;
 pub mod mail;
+#[cfg(feature = "viewer")]
+pub mod viewer;
 
@@ -25,10 +25,15 @@ use std::fs::OpenOptions;
 use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 use libimagstore::store::FileLockEntry;
+use libimagstore::store::Entry;
+use libimagentryref::config::Config as RefConfig;
 use libimagentryref::reference::Ref;
 use libimagentryref::refstore::RefStore;
 use libimagentryref::refstore::UniqueRefPathGenerator;
 use libimagerror::errors::ErrorMsg as EM;
+use libimagentryutil::isa::Is;
+use libimagentryutil::isa::IsKindHeaderPathProvider;
+use libimagentryview::registry::kind_flag;
 
 use email::MimeMessage;
 use email::results::ParsingResult as EmailParsingResult;
@@ -38,11 +43,32 @@ use failure::ResultExt;
 use failure::Error;
 use failure::err_msg;
 
+provide_kindflag_path!(pub IsMail, "mail.is_mail");
+
+/// The collection mails are imported into
+pub const MAIL_COLLECTION : &'static str = "mail";
+
+/// The generator name stored in mails imported with the built-in generator
+///
+/// The built-in generator hashes the "Subject", "From" and "To" headers of the mail.
+pub const MAIL_GENERATOR : &'static str = "mail";
+
+/// Check whether `e` is a mail
+///
+/// Mails which were imported before the kind flag was introduced do not have it, they are detected
+/// as refs in the "mail" collection.
+pub fn is_mail(e: &Entry) -> Result<bool> {
+    match kind_flag::<IsMail>(e)? {
+        Some(flag) => Ok(flag),
+        None       => Ok(e.get_location().is_in_collection(&[MAIL_COLLECTION]) && e.is_ref()?),
+    }
+}
+
 struct UniqueMailRefGenerator;
 impl UniqueRefPathGenerator for UniqueMailRefGenerator {
     /// The collection the `StoreId` should be created for
     fn collection() -> &'static str {
-        "mail"
+        MAIL_COLLECTION
     }
 
     /// A function which should generate a unique string for a Path
@@ -107,13 +133,28 @@ pub struct Mail<'a>(FileLockEntry<'a>, Buffer);
 
 impl<'a> Mail<'a> {
 
-    /// Imports a mail from the Path passed
-    pub fn import_from_path<P: AsRef<Path>>(store: &Store, p: P) -> Result<Mail> {
+    /// Imports a mail from the Path passed, with the built-in generator
+    pub fn import_from_path<'b, P>(store: &'b Store, p: P, config: &RefConfig) -> Result<Mail<'b>>
+        where P: AsRef<Path>
+    {
+        Mail::import_from_path_with::<UniqueMailRefGenerator, P>(store, p, config, MAIL_GENERATOR)
+    }
+
+    /// Imports a mail from the Path passed, with the generator `RPG` named `generator`
+    ///
+    /// The mail is put into the `MAIL_COLLECTION` either way.
+    pub fn import_from_path_with<'b, RPG, P>(store: &'b Store, p: P, config: &RefConfig, generator: &str)
+        -> Result<Mail<'b>>
+        where RPG: UniqueRefPathGenerator,
+              P: AsRef<Path>
+    {
         debug!("Importing Mail from path");
-        store.retrieve_ref::<UniqueMailRefGenerator, P>(p)
+        store.retrieve_ref_in::<RPG, P>(MAIL_COLLECTION, p, config)
+            .and_then(|mut reference| reference.set_isflag::<IsMail>().map(|_| reference))
+            .and_then(|mut reference| reference.set_generator(generator).map(|_| reference))
             .and_then(|reference| {
                 debug!("Build reference file: {:?}", reference);
-                reference.get_path()
+                reference.get_path(config)
                     .context(err_msg("Ref handling error"))
                     .map_err(Error::from)
                     .and_then(|path| File::open(path).context(EM::IO).map_err(Error::from))
@@ -130,21 +171,23 @@ impl<'a> Mail<'a> {
     }
 
     /// Opens a mail by the passed hash
-    pub fn open<S: AsRef<str>>(store: &Store, hash: S) -> Result<Option<Mail>> {
+    pub fn open<'b, S>(store: &'b Store, hash: S, config: &RefConfig) -> Result<Option<Mail<'b>>>
+        where S: AsRef<str>
+    {
         debug!("Opening Mail by Hash");
         store.get_ref::<UniqueMailRefGenerator, S>(hash)
             .context(err_msg("Fetch by hash error"))
             .context(err_msg("Fetch error"))
             .map_err(Error::from)
             .and_then(|o| match o {
-                Some(r) => Mail::from_fle(r).map(Some),
+                Some(r) => Mail::from_fle(r, config).map(Some),
                 None => Ok(None),
             })
     }
 
     /// Implement me as TryFrom as soon as it is stable
-    pub fn from_fle(fle: FileLockEntry<'a>) -> Result<Mail<'a>> {
-        fle.get_path()
+    pub fn from_fle(fle: FileLockEntry<'a>, config: &RefConfig) -> Result<Mail<'a>> {
+        fle.get_path(config)
             .context(err_msg("Ref handling error"))
             .map_err(Error::from)
             .and_then(|path| File::open(path).context(EM::IO).map_err(Error::from))
@@ -0,0 +1,93 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A viewer which shows the headers of mails
+
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+
+use email::MimeMessage;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+use libimagstore::store::Entry;
+use libimagentryref::config::Config as RefConfig;
+use libimagentryref::reference::Ref;
+use libimagentryview::viewer::Viewer;
+use libimagentryview::registry::ViewerRegistry;
+use libimagerror::errors::ErrorMsg as EM;
+
+use mail::is_mail;
+
+/// The mail headers which are shown, in this order
+const HEADERS : &'static [&'static str] = &["From", "To", "Cc", "Date", "Subject", "Message-ID"];
+
+pub struct MailViewer {
+    config: RefConfig,
+}
+
+impl MailViewer {
+    pub fn new(config: RefConfig) -> MailViewer {
+        MailViewer {
+            config: config,
+        }
+    }
+}
+
+impl Viewer for MailViewer {
+
+    fn view_entry<W>(&self, e: &Entry, sink: &mut W) -> Result<()>
+        where W: Write
+    {
+        let path = e.get_path(&self.config).context(err_msg("Ref handling error"))?;
+
+        let mut buffer = String::new();
+        let _ = File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut buffer))
+            .context(EM::IO)?;
+
+        let mail = MimeMessage::parse(&buffer)
+            .context(err_msg("Mail parsing error"))
+            .map_err(Error::from)?;
+
+        for name in HEADERS {
+            let value = mail.headers
+                .iter()
+                .filter(|hdr| hdr.name == *name)
+                .nth(0)
+                .and_then(|hdr| hdr.get_value().ok());
+
+            if let Some(value) = value {
+                writeln!(sink, "{:<11} {}", format!("{}:", name), value)?;
+            }
+        }
+
+        writeln!(sink, "{:<11} {}", "File:", path.display())?;
+        Ok(())
+    }
+
+}
+
+/// Register the `MailViewer` for mail entries
+pub fn register(registry: &mut ViewerRegistry, config: RefConfig) {
+    registry.register_for(is_mail, MailViewer::new(config));
+}
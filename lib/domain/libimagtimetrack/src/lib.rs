@@ -52,12 +52,17 @@ extern crate libimagstore;
 extern crate libimagentrydatetime;
 extern crate libimagentrytag;
 extern crate libimagerror;
+#[macro_use] extern crate libimagentryutil;
+#[cfg(feature = "viewer")]
+extern crate libimagentryview;
 
 mod constants;
 pub mod timetracking;
 pub mod timetrackingstore;
 pub mod iter;
 pub mod tag;
+#[cfg(feature = "viewer")]
+pub mod viewer;
 
 module_entry_path_mod!("timetrack");
 
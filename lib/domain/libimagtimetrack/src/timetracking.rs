@@ -28,6 +28,8 @@ use chrono::naive::NaiveDateTime;
 
 use libimagstore::store::Entry;
 use libimagerror::errors::ErrorMsg as EM;
+use libimagentryutil::isa::IsKindHeaderPathProvider;
+use libimagentryview::registry::kind_flag;
 
 use tag::TimeTrackingTag as TTT;
 use constants::*;
@@ -39,8 +41,16 @@ use toml_query::read::TomlValueReadTypeExt;
 use failure::Fallible as Result;
 use failure::Error;
 
+provide_kindflag_path!(pub IsTimeTracking, "timetrack.is_timetracking");
+
 pub trait TimeTracking {
 
+    /// Check whether this entry is a timetracking
+    ///
+    /// Timetrackings which were created before the kind flag was introduced do not have it, they
+    /// are detected by their tag.
+    fn is_timetracking(&self) -> Result<bool>;
+
     fn get_timetrack_tag(&self) -> Result<TTT>;
 
     fn set_start_datetime(&mut self, dt: NaiveDateTime) -> Result<()>;
@@ -61,6 +71,16 @@ pub trait TimeTracking {
 
 impl TimeTracking for Entry {
 
+    fn is_timetracking(&self) -> Result<bool> {
+        match kind_flag::<IsTimeTracking>(self)? {
+            Some(flag) => Ok(flag),
+            None       => self.get_header()
+                .read_string(DATE_TIME_TAG_HEADER_PATH)
+                .map(|tag| tag.is_some())
+                .map_err(Error::from),
+        }
+    }
+
     fn get_timetrack_tag(&self) -> Result<TTT> {
         self.get_header()
             .read_string(DATE_TIME_TAG_HEADER_PATH)
@@ -140,3 +160,39 @@ fn header_value_to_dt(val: Option<String>) -> Result<Option<NaiveDateTime>> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagentryutil::isa::Is;
+
+    use super::TimeTracking;
+    use super::IsTimeTracking;
+    use constants::DATE_TIME_TAG_HEADER_PATH;
+
+    fn entry() -> Entry {
+        Entry::new(PathBuf::from("timetrack/test").into_storeid().unwrap())
+    }
+
+    #[test]
+    fn test_is_timetracking_by_flag() {
+        let mut e = entry();
+        e.set_isflag::<IsTimeTracking>().unwrap();
+        assert!(e.is_timetracking().unwrap());
+    }
+
+    #[test]
+    fn test_is_timetracking_without_flag() {
+        let mut e = entry();
+        assert!(!e.is_timetracking().unwrap());
+
+        let tag = Value::String(String::from("work"));
+        e.get_header_mut().insert(DATE_TIME_TAG_HEADER_PATH, tag).unwrap();
+        assert!(e.is_timetracking().unwrap());
+    }
+}
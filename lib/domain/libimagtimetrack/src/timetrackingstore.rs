@@ -31,9 +31,11 @@ use failure::Error;
 use libimagstore::store::Store;
 use libimagstore::store::FileLockEntry;
 use libimagentrydatetime::datepath::compiler::DatePathCompiler;
+use libimagentryutil::isa::Is;
 
 use constants::*;
 use iter::get::TimeTrackingsGetIterator;
+use timetracking::IsTimeTracking;
 
 use tag::TimeTrackingTag as TTT;
 
@@ -76,6 +78,7 @@ impl<'a> TimeTrackStore<'a> for Store {
                 id
             })
             .and_then(|id| self.create(id))
+            .and_then(|mut fle| fle.set_isflag::<IsTimeTracking>().map(|_| fle))
             .and_then(|mut fle| {
                 let v = Value::String(ts.as_str().to_owned());
                 fle.get_header_mut()
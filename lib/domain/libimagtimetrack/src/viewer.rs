@@ -0,0 +1,137 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A viewer which shows a summary of a timetracking
+
+use std::io::Write;
+
+use chrono::Duration;
+use chrono::NaiveDateTime;
+use failure::Fallible as Result;
+
+use libimagstore::store::Entry;
+use libimagentryview::viewer::Viewer;
+use libimagentryview::registry::ViewerRegistry;
+
+use timetracking::TimeTracking;
+use constants::DATE_TIME_FORMAT;
+
+pub struct TimeTrackingViewer;
+
+impl Viewer for TimeTrackingViewer {
+
+    fn view_entry<W>(&self, e: &Entry, sink: &mut W) -> Result<()>
+        where W: Write
+    {
+        let start = e.get_start_datetime()?;
+        let end   = e.get_end_datetime()?;
+
+        let fmt = |dt: Option<NaiveDateTime>| {
+            dt.map(|dt| dt.format(DATE_TIME_FORMAT).to_string())
+                .unwrap_or_else(|| String::from("-"))
+        };
+
+        writeln!(sink, "{:<9} {}", "Tag:", e.get_timetrack_tag()?.as_str())?;
+        writeln!(sink, "{:<9} {}", "Start:", fmt(start))?;
+        writeln!(sink, "{:<9} {}", "End:", fmt(end))?;
+
+        if let (Some(start), Some(end)) = (start, end) {
+            writeln!(sink, "{:<9} {}", "Duration:", format_duration(end.signed_duration_since(start)))?;
+        } else if start.is_some() {
+            writeln!(sink, "{:<9} {}", "Duration:", "running")?;
+        }
+
+        Ok(())
+    }
+
+}
+
+fn format_duration(d: Duration) -> String {
+    let minutes = d.num_minutes();
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Register the `TimeTrackingViewer` for timetracking entries
+pub fn register(registry: &mut ViewerRegistry) {
+    registry.register_for(|e: &Entry| e.is_timetracking(), TimeTrackingViewer);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::Duration;
+    use chrono::NaiveDate;
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagentryview::viewer::Viewer;
+
+    use super::format_duration;
+    use super::TimeTrackingViewer;
+    use timetracking::TimeTracking;
+    use constants::DATE_TIME_TAG_HEADER_PATH;
+
+    fn entry() -> Entry {
+        let mut e = Entry::new(PathBuf::from("timetrack/test").into_storeid().unwrap());
+        let tag   = Value::String(String::from("work"));
+        e.get_header_mut().insert(DATE_TIME_TAG_HEADER_PATH, tag).unwrap();
+        e.set_start_datetime(NaiveDate::from_ymd(2018, 3, 1).and_hms(10, 0, 0)).unwrap();
+        e
+    }
+
+    fn view(e: &Entry) -> String {
+        let mut buf = vec![];
+        TimeTrackingViewer.view_entry(e, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(5)), "0h 05m");
+        assert_eq!(format_duration(Duration::minutes(135)), "2h 15m");
+    }
+
+    #[test]
+    fn test_view_finished_timetracking() {
+        let mut e = entry();
+        e.set_end_datetime(NaiveDate::from_ymd(2018, 3, 1).and_hms(12, 15, 0)).unwrap();
+
+        let output = view(&e);
+        let lines  = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "Tag:      work");
+        assert_eq!(lines[1], "Start:    2018-03-01T10:00:00");
+        assert_eq!(lines[2], "End:      2018-03-01T12:15:00");
+        assert_eq!(lines[3], "Duration: 2h 15m");
+    }
+
+    #[test]
+    fn test_view_running_timetracking() {
+        let output = view(&entry());
+        let lines  = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "End:      -");
+        assert_eq!(lines[3], "Duration: running");
+    }
+}
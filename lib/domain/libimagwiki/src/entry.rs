@@ -21,6 +21,8 @@ use libimagstore::store::Store;
 use libimagstore::store::Entry;
 use libimagentrymarkdown::processor::LinkProcessor;
 
+use wiki::WikiPageResolver;
+
 use failure::Fallible as Result;
 use failure::ResultExt;
 use failure::Error;
@@ -40,6 +42,7 @@ impl WikiEntry for Entry {
     /// * Internal targets creating = true
     /// * External link processing  = true
     /// * Processing of Refs        = true
+    /// * Wiki link processing      = true, page names are resolved in the wiki of this entry
     ///
     /// This is a convenience function for `WikiEntry::autolink_with_processor()`.
     ///
@@ -58,7 +61,13 @@ impl WikiEntry for Entry {
             .process_internal_links(true)
             .create_internal_targets(true)
             .process_external_links(true)
-            .process_refs(true);
+            .process_refs(true)
+            .process_wiki_links(true);
+
+        let processor = match WikiPageResolver::for_page(self.get_location()) {
+            Some(resolver) => processor.wiki_link_resolver(Box::new(resolver)),
+            None           => processor,
+        };
 
         self.autolink_with_processor(store, processor)
     }
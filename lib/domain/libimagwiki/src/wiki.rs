@@ -28,6 +28,9 @@ use libimagstore::storeid::IntoStoreId;
 use libimagstore::storeid::StoreId;
 use libimagstore::storeid::StoreIdIteratorWithStore;
 use libimagentrylink::internal::InternalLinker;
+use libimagentrymarkdown::wikilink::StoreResolver;
+use libimagentrymarkdown::wikilink::WikiLink;
+use libimagentrymarkdown::wikilink::WikiLinkResolver;
 
 use failure::Fallible as Result;
 use failure::Error;
@@ -105,6 +108,61 @@ impl<'a, 'b> Wiki<'a, 'b> {
         let sid   = ::module_path::ModuleEntryPath::new(path).into_storeid()?;
         self.0.delete(sid)
     }
+
+    /// Get a resolver for `[[wiki links]]` which resolves page names to pages of this wiki
+    pub fn link_resolver(&self) -> WikiPageResolver {
+        WikiPageResolver::new(String::from(self.1))
+    }
+}
+
+/// Resolves `[[Page Name]]` links to the page "Page Name" of a wiki
+///
+/// Targets containing a `/` are resolved as store ids, like `StoreResolver` does.
+#[derive(Debug)]
+pub struct WikiPageResolver(String);
+
+impl WikiPageResolver {
+    pub fn new(wiki_name: String) -> Self {
+        WikiPageResolver(wiki_name)
+    }
+
+    /// Get the resolver for the wiki the entry with `id` is a page of, if any
+    pub fn for_page(id: &StoreId) -> Option<Self> {
+        let mut components = id.local().components();
+
+        match (components.next(), components.next(), components.next()) {
+            (Some(module), Some(wiki), Some(_)) if module.as_os_str() == "wiki" => {
+                wiki.as_os_str().to_str().map(String::from).map(WikiPageResolver)
+            },
+            _ => None,
+        }
+    }
+}
+
+impl WikiLinkResolver for WikiPageResolver {
+
+    fn resolve(&self, link: &WikiLink, from: &StoreId, store: &Store) -> Result<Option<StoreId>> {
+        if link.is_id() {
+            return StoreResolver::new().resolve(link, from, store)
+        }
+
+        let id = self.new_target(link, from)?;
+        if store.exists(id.clone())? {
+            Ok(Some(id))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn new_target(&self, link: &WikiLink, from: &StoreId) -> Result<StoreId> {
+        if link.is_id() {
+            return StoreResolver::new().new_target(link, from)
+        }
+
+        let path = PathBuf::from(format!("{}/{}", self.0, link.target));
+        ::module_path::ModuleEntryPath::new(path).into_storeid()
+    }
+
 }
 
 pub struct WikiIdIterator<'a>(StoreIdIteratorWithStore<'a>, IdIsInWikiFilter<'a>);
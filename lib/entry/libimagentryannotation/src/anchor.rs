@@ -0,0 +1,449 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Anchoring annotations to a span of the content of the annotated entry
+//!
+//! An anchor is either a range of lines or a quoted piece of text in the content of the annotated
+//! entry. The anchored text is stored alongside the line numbers, so the anchor can be re-located
+//! after the content of the annotated entry was edited.
+
+use toml::Value;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagerror::errors::ErrorMsg as EM;
+
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
+
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+
+use annotateable::Annotateable;
+
+/// The minimal similarity (between 0.0 and 1.0) a span must have to the anchored text to be
+/// accepted as the new location of an anchor
+pub const FUZZY_MATCH_THRESHOLD : f64 = 0.6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorKind {
+    Lines,
+    Quote,
+}
+
+impl AnchorKind {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            AnchorKind::Lines => "lines",
+            AnchorKind::Quote => "quote",
+        }
+    }
+
+    fn from_str(s: &str) -> Result<AnchorKind> {
+        match s {
+            "lines" => Ok(AnchorKind::Lines),
+            "quote" => Ok(AnchorKind::Quote),
+            other   => Err(format_err!("Unknown anchor kind: '{}'", other)),
+        }
+    }
+}
+
+/// An anchor of an annotation in the content of the annotated entry
+///
+/// Line numbers start at 1, the range is inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    target: String,
+    kind:   AnchorKind,
+    start:  usize,
+    end:    usize,
+    text:   String,
+    lost:   bool,
+}
+
+impl Anchor {
+
+    /// Anchor to the lines `start` to `end` (inclusive, starting at 1) of `target`
+    pub fn for_lines(target: &Entry, start: usize, end: usize) -> Result<Anchor> {
+        let lines = target.get_content().lines().collect::<Vec<_>>();
+
+        if start == 0 || start > end || end > lines.len() {
+            return Err(format_err!("Line range {}-{} is not in the content of {} ({} lines)",
+                                   start, end, target.get_location(), lines.len()))
+        }
+
+        Ok(Anchor {
+            target: target.get_location().local_display_string(),
+            kind:   AnchorKind::Lines,
+            start:  start,
+            end:    end,
+            text:   lines[(start - 1)..end].join("\n"),
+            lost:   false,
+        })
+    }
+
+    /// Anchor to the first occurrence of `quote` in the content of `target`
+    pub fn for_quote(target: &Entry, quote: &str) -> Result<Anchor> {
+        let content = target.get_content();
+        let pos     = content
+            .find(quote)
+            .ok_or_else(|| format_err!("Text '{}' not found in {}", quote, target.get_location()))?;
+        let (start, end) = line_range_of(content, pos, quote);
+
+        Ok(Anchor {
+            target: target.get_location().local_display_string(),
+            kind:   AnchorKind::Quote,
+            start:  start,
+            end:    end,
+            text:   String::from(quote),
+            lost:   false,
+        })
+    }
+
+    /// The id of the annotated entry, as returned by `StoreId::local_display_string()`
+    pub fn target(&self) -> &String {
+        &self.target
+    }
+
+    pub fn kind(&self) -> &AnchorKind {
+        &self.kind
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The anchored text
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    /// Whether the anchored text could not be found anymore when re-locating the anchor
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    /// Find the anchored text in `content`
+    ///
+    /// The exact text is preferred, if it occurs more than once the occurrence closest to the old
+    /// location is taken. If the exact text cannot be found, the most similar span of lines is
+    /// taken if its similarity is at least `FUZZY_MATCH_THRESHOLD`. Otherwise the anchor is
+    /// returned unchanged, but marked as lost.
+    pub fn relocate(&self, content: &str) -> Anchor {
+        let mut new = self.clone();
+
+        if let Some((start, end)) = self.find_exact(content) {
+            new.start = start;
+            new.end   = end;
+            new.lost  = false;
+        } else if let Some((start, end)) = self.find_fuzzy(content) {
+            new.start = start;
+            new.end   = end;
+            new.lost  = false;
+
+            if self.kind == AnchorKind::Lines {
+                new.text = content.lines().collect::<Vec<_>>()[(start - 1)..end].join("\n");
+            }
+        } else {
+            new.lost = true;
+        }
+
+        new
+    }
+
+    fn find_exact(&self, content: &str) -> Option<(usize, usize)> {
+        let candidates = match self.kind {
+            AnchorKind::Quote => content
+                .match_indices(self.text.as_str())
+                .map(|(pos, _)| line_range_of(content, pos, &self.text))
+                .collect::<Vec<_>>(),
+
+            AnchorKind::Lines => {
+                let lines  = content.lines().collect::<Vec<_>>();
+                let wanted = self.text.lines().collect::<Vec<_>>();
+
+                if wanted.is_empty() || wanted.len() > lines.len() {
+                    return None
+                }
+
+                lines.windows(wanted.len())
+                    .enumerate()
+                    .filter(|&(_, window)| window == &wanted[..])
+                    .map(|(i, _)| (i + 1, i + wanted.len()))
+                    .collect::<Vec<_>>()
+            },
+        };
+
+        self.closest(candidates.into_iter())
+    }
+
+    fn find_fuzzy(&self, content: &str) -> Option<(usize, usize)> {
+        let lines = content.lines().collect::<Vec<_>>();
+        let len   = ::std::cmp::max(self.end + 1 - self.start, 1);
+
+        // Allow the span to have grown or shrunk by one line
+        let mut best : Option<(f64, (usize, usize))> = None;
+        for size in (::std::cmp::max(len, 2) - 1)..(len + 2) {
+            if size > lines.len() {
+                break
+            }
+
+            for (i, window) in lines.windows(size).enumerate() {
+                let range = (i + 1, i + size);
+                let sim   = similarity(&window.join("\n"), &self.text);
+
+                let better = match best {
+                    None                    => true,
+                    Some((best_sim, best_range)) => sim > best_sim ||
+                        (sim == best_sim && self.distance(range) < self.distance(best_range)),
+                };
+
+                if better {
+                    best = Some((sim, range));
+                }
+            }
+        }
+
+        best.and_then(|(sim, range)| if sim >= FUZZY_MATCH_THRESHOLD { Some(range) } else { None })
+    }
+
+    fn closest<I: Iterator<Item = (usize, usize)>>(&self, candidates: I) -> Option<(usize, usize)> {
+        candidates.min_by_key(|&range| self.distance(range))
+    }
+
+    fn distance(&self, range: (usize, usize)) -> usize {
+        if range.0 > self.start { range.0 - self.start } else { self.start - range.0 }
+    }
+
+}
+
+/// Get the line range (starting at 1, inclusive) of `text` at byte position `pos` in `content`
+fn line_range_of(content: &str, pos: usize, text: &str) -> (usize, usize) {
+    let start = content[..pos].matches('\n').count() + 1;
+    let end   = start + text.trim_right_matches('\n').matches('\n').count();
+    (start, end)
+}
+
+/// Similarity of two strings, between 0.0 and 1.0
+///
+/// Computed as the Sørensen–Dice coefficient of the character bigrams of the strings, ignoring
+/// whitespace.
+fn similarity(a: &str, b: &str) -> f64 {
+    fn bigrams(s: &str) -> Vec<(char, char)> {
+        let chars = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+
+    let a     = bigrams(a);
+    let mut b = bigrams(b);
+
+    if a.is_empty() || b.is_empty() {
+        return if a.is_empty() && b.is_empty() { 1.0 } else { 0.0 }
+    }
+
+    let total   = a.len() + b.len();
+    let mut hit = 0;
+    for bigram in a {
+        if let Some(pos) = b.iter().position(|other| *other == bigram) {
+            let _ = b.swap_remove(pos);
+            hit += 1;
+        }
+    }
+
+    (2 * hit) as f64 / total as f64
+}
+
+/// Functionality for annotations which are anchored to a span of the annotated entry
+pub trait AnchoredAnnotation {
+    fn get_anchor(&self) -> Result<Option<Anchor>>;
+    fn set_anchor(&mut self, anchor: &Anchor) -> Result<()>;
+
+    /// Re-locate the anchor of this annotation in `content`
+    ///
+    /// Returns whether the anchor was found. Does nothing if the annotation is not anchored.
+    fn relocate_anchor(&mut self, content: &str) -> Result<bool>;
+}
+
+impl AnchoredAnnotation for Entry {
+
+    fn get_anchor(&self) -> Result<Option<Anchor>> {
+        let header = self.get_header();
+        let target = match header.read_string("annotation.anchor.target").context(EM::EntryHeaderReadError)? {
+            Some(target) => target,
+            None         => return Ok(None),
+        };
+
+        let kind  = header.read_string("annotation.anchor.kind").context(EM::EntryHeaderReadError)?;
+        let start = header.read_int("annotation.anchor.start").context(EM::EntryHeaderReadError)?;
+        let end   = header.read_int("annotation.anchor.end").context(EM::EntryHeaderReadError)?;
+        let text  = header.read_string("annotation.anchor.text").context(EM::EntryHeaderReadError)?;
+        let lost  = header.read_bool("annotation.anchor.lost").context(EM::EntryHeaderReadError)?;
+
+        match (kind, start, end, text) {
+            (Some(kind), Some(start), Some(end), Some(text)) => Ok(Some(Anchor {
+                target: target,
+                kind:   AnchorKind::from_str(&kind)?,
+                start:  start as usize,
+                end:    end as usize,
+                text:   text,
+                lost:   lost.unwrap_or(false),
+            })),
+            _ => Err(Error::from(EM::EntryHeaderFieldMissing("annotation.anchor"))),
+        }
+    }
+
+    fn set_anchor(&mut self, anchor: &Anchor) -> Result<()> {
+        let header = self.get_header_mut();
+        let fields = vec![
+            ("annotation.anchor.target" , Value::String(anchor.target.clone())),
+            ("annotation.anchor.kind"   , Value::String(String::from(anchor.kind.as_str()))),
+            ("annotation.anchor.start"  , Value::Integer(anchor.start as i64)),
+            ("annotation.anchor.end"    , Value::Integer(anchor.end as i64)),
+            ("annotation.anchor.text"   , Value::String(anchor.text.clone())),
+            ("annotation.anchor.lost"   , Value::Boolean(anchor.lost)),
+        ];
+
+        for (path, value) in fields {
+            let _ = header.insert(path, value).context(EM::EntryHeaderWriteError)?;
+        }
+
+        Ok(())
+    }
+
+    fn relocate_anchor(&mut self, content: &str) -> Result<bool> {
+        match self.get_anchor()? {
+            None         => Ok(true),
+            Some(anchor) => {
+                let new = anchor.relocate(content);
+                if new != anchor {
+                    let _ = self.set_anchor(&new)?;
+                }
+                Ok(!new.is_lost())
+            },
+        }
+    }
+
+}
+
+/// Re-locate the anchors of all annotations which are anchored in `target`
+///
+/// Should be called after the content of `target` was edited.
+/// Returns the number of anchors which could not be found anymore.
+pub fn relocate_anchors(store: &Store, target: &Entry) -> Result<usize> {
+    let target_name = target.get_location().local_display_string();
+    let mut lost    = 0;
+
+    for annotation in target.annotations(store)? {
+        let mut annotation = annotation?;
+        let anchored_here  = annotation
+            .get_anchor()?
+            .map(|anchor| anchor.target() == &target_name)
+            .unwrap_or(false);
+
+        if anchored_here && !annotation.relocate_anchor(target.get_content())? {
+            lost += 1;
+        }
+    }
+
+    Ok(lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    use libimagstore::store::Store;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    const CONTENT : &'static str = "first line\nsecond line\nthe quick brown fox\njumps over the lazy dog\nlast line\n";
+
+    #[test]
+    fn test_anchor_for_lines_and_quote() {
+        let store      = get_store();
+        let mut target = store.create(PathBuf::from("target")).unwrap();
+        *target.get_content_mut() = String::from(CONTENT);
+
+        let lines = Anchor::for_lines(&target, 3, 4).unwrap();
+        assert_eq!("the quick brown fox\njumps over the lazy dog", lines.text());
+
+        let quote = Anchor::for_quote(&target, "lazy dog").unwrap();
+        assert_eq!((4, 4), (quote.start(), quote.end()));
+
+        assert!(Anchor::for_lines(&target, 4, 9).is_err());
+        assert!(Anchor::for_quote(&target, "not in there").is_err());
+    }
+
+    #[test]
+    fn test_relocate_exact_and_fuzzy() {
+        let store      = get_store();
+        let mut target = store.create(PathBuf::from("target")).unwrap();
+        *target.get_content_mut() = String::from(CONTENT);
+
+        let anchor = Anchor::for_lines(&target, 3, 4).unwrap();
+
+        // lines moved down
+        let moved = anchor.relocate(&format!("new line\n\n{}", CONTENT));
+        assert_eq!((5, 6, false), (moved.start(), moved.end(), moved.is_lost()));
+
+        // anchored text was slightly edited
+        let edited = anchor.relocate("first line\nsecond line\nthe quick red fox\njumps over the lazy dog\nlast line\n");
+        assert_eq!((3, 4, false), (edited.start(), edited.end(), edited.is_lost()));
+        assert_eq!("the quick red fox\njumps over the lazy dog", edited.text());
+
+        // anchored text was removed
+        let removed = anchor.relocate("first line\nsecond line\nlast line\n");
+        assert!(removed.is_lost());
+    }
+
+    #[test]
+    fn test_relocate_anchors_after_edit() {
+        let store = get_store();
+
+        {
+            let mut target = store.create(PathBuf::from("target")).unwrap();
+            *target.get_content_mut() = String::from(CONTENT);
+
+            let anchor   = Anchor::for_quote(&target, "lazy dog").unwrap();
+            let mut anno = target.annotate(&store, "anno").unwrap();
+            anno.set_anchor(&anchor).unwrap();
+        }
+
+        let mut target = store.get(PathBuf::from("target")).unwrap().unwrap();
+        *target.get_content_mut() = format!("prepended\n{}", CONTENT);
+
+        assert_eq!(0, relocate_anchors(&store, &target).unwrap());
+
+        let anno   = target.annotations(&store).unwrap().next().unwrap().unwrap();
+        let anchor = anno.get_anchor().unwrap().unwrap();
+        assert_eq!((5, 5), (anchor.start(), anchor.end()));
+    }
+}
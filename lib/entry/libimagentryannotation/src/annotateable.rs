@@ -18,6 +18,7 @@
 //
 
 use toml::Value;
+use chrono::Local;
 
 use libimagstore::store::Entry;
 use libimagstore::store::FileLockEntry;
@@ -37,6 +38,8 @@ use failure::Error;
 use failure::err_msg;
 
 use iter::*;
+use annotation::Annotation;
+use annotation::DATE_TIME_FORMAT;
 
 pub trait Annotateable {
     fn annotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>>;
@@ -53,15 +56,7 @@ impl Annotateable for Entry {
     fn annotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>> {
         use module_path::ModuleEntryPath;
         store.retrieve(ModuleEntryPath::new(ann_name).into_storeid()?)
-            .and_then(|mut anno| {
-                {
-                    let _ = anno.set_isflag::<IsAnnotation>()?;
-                    let _ = anno
-                        .get_header_mut()
-                        .insert("annotation.name", Value::String(String::from(ann_name)))?;
-                }
-                Ok(anno)
-            })
+            .and_then(|mut anno| init_annotation(&mut anno, ann_name).map(|_| anno))
             .and_then(|mut anno| {
                 anno.add_internal_link(self)
                     .context(err_msg("Linking error"))
@@ -102,3 +97,33 @@ impl Annotateable for Entry {
 
 }
 
+/// Create a new annotation entry, failing if an annotation with this name exists already
+///
+/// The new annotation is not linked to any entry.
+pub(crate) fn create_annotation<'a>(store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>> {
+    use module_path::ModuleEntryPath;
+    let mut anno = store
+        .create(ModuleEntryPath::new(ann_name).into_storeid()?)
+        .context(format!("Annotation exists already: '{}'", ann_name))?;
+
+    let _ = init_annotation(&mut anno, ann_name)?;
+    Ok(anno)
+}
+
+fn init_annotation(anno: &mut Entry, ann_name: &str) -> Result<()> {
+    let _ = anno.set_isflag::<IsAnnotation>()?;
+    let _ = anno
+        .get_header_mut()
+        .insert("annotation.name", Value::String(String::from(ann_name)))?;
+
+    if anno.get_created()?.is_none() {
+        let now = Local::now().naive_local().format(DATE_TIME_FORMAT).to_string();
+        let _   = anno
+            .get_header_mut()
+            .insert("annotation.created", Value::String(now))?;
+        let _   = anno.touch()?;
+    }
+
+    Ok(())
+}
+
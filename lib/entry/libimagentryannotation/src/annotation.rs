@@ -0,0 +1,314 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use chrono::NaiveDateTime;
+use chrono::Local;
+use toml::Value;
+
+use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagentrylink::internal::InternalLinker;
+use libimagerror::errors::ErrorMsg as EM;
+
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
+
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+use annotateable::Annotateable;
+use annotateable::create_annotation;
+
+pub const DATE_TIME_FORMAT : &'static str = "%Y-%m-%dT%H:%M:%S";
+
+/// Functionality for entries which are annotations
+pub trait Annotation {
+    fn get_annotation_name(&self) -> Result<String>;
+
+    fn get_author(&self) -> Result<Option<String>>;
+    fn set_author(&mut self, author: &str) -> Result<()>;
+
+    fn get_created(&self) -> Result<Option<NaiveDateTime>>;
+    fn get_modified(&self) -> Result<Option<NaiveDateTime>>;
+
+    /// Set the modification time of the annotation to now
+    fn touch(&mut self) -> Result<()>;
+
+    fn get_reply_to(&self) -> Result<Option<String>>;
+    fn reply<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>>;
+}
+
+impl Annotation for Entry {
+
+    fn get_annotation_name(&self) -> Result<String> {
+        self.get_header()
+            .read_string("annotation.name")
+            .context(EM::EntryHeaderReadError)?
+            .ok_or_else(|| Error::from(err_msg("Annotation name missing")))
+    }
+
+    fn get_author(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string("annotation.author")
+            .context(EM::EntryHeaderReadError)
+            .map_err(Error::from)
+    }
+
+    fn set_author(&mut self, author: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert("annotation.author", Value::String(String::from(author)))
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn get_created(&self) -> Result<Option<NaiveDateTime>> {
+        read_datetime(self, "annotation.created")
+    }
+
+    fn get_modified(&self) -> Result<Option<NaiveDateTime>> {
+        read_datetime(self, "annotation.modified")
+    }
+
+    fn touch(&mut self) -> Result<()> {
+        let now = Local::now().naive_local().format(DATE_TIME_FORMAT).to_string();
+        self.get_header_mut()
+            .insert("annotation.modified", Value::String(now))
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn get_reply_to(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string("annotation.reply_to")
+            .context(EM::EntryHeaderReadError)
+            .map_err(Error::from)
+    }
+
+    /// Reply to this annotation
+    ///
+    /// Creates a new annotation named `ann_name` for all entries this annotation annotates, which
+    /// refers to this annotation as the annotation it replies to. Fails if an annotation named
+    /// `ann_name` exists already.
+    fn reply<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>> {
+        let parent  = self.get_annotation_name()?;
+        let targets = self
+            .get_internal_links()?
+            .map(|link| link.get_store_id().clone())
+            .collect::<Vec<_>>();
+
+        let mut reply : Option<FileLockEntry<'a>> = None;
+        for id in targets {
+            let mut target = match store.get(id)? {
+                Some(target) => target,
+                None         => continue,
+            };
+
+            if target.is_annotation()? {
+                continue
+            }
+
+            let mut r = match reply {
+                None    => create_annotation(store, ann_name)?,
+                Some(r) => r,
+            };
+            let _ = r.add_internal_link(&mut target)?;
+            reply = Some(r);
+        }
+
+        let mut reply = reply.ok_or_else(|| format_err!("Annotation '{}' does not annotate any entry", parent))?;
+        let _ = reply
+            .get_header_mut()
+            .insert("annotation.reply_to", Value::String(parent))
+            .context(EM::EntryHeaderWriteError)?;
+
+        Ok(reply)
+    }
+
+}
+
+/// Sort annotations into threads
+///
+/// Annotations which do not reply to another annotation (or reply to an annotation which is not
+/// in `annotations`) are the roots of the threads. Each annotation is followed by its replies.
+/// Roots and replies are ordered chronologically by their creation time.
+///
+/// Returns the annotations together with their depth in the thread.
+pub fn sort_into_threads<'a>(annotations: Vec<FileLockEntry<'a>>) -> Result<Vec<(usize, FileLockEntry<'a>)>> {
+    let mut meta = vec![];
+    for anno in annotations.iter() {
+        meta.push((anno.get_annotation_name()?, anno.get_reply_to()?, anno.get_created()?));
+    }
+
+    let names = meta.iter().map(|m| m.0.clone()).collect::<Vec<_>>();
+
+    // Indices of the annotations which reply to `parent`, sorted by creation time
+    let children_of = |parent: Option<&String>| -> Vec<usize> {
+        let mut children = meta
+            .iter()
+            .enumerate()
+            .filter(|&(_, m)| match (parent, m.1.as_ref()) {
+                (None, None)            => true,
+                (None, Some(reply_to))  => !names.contains(reply_to),
+                (Some(p), Some(r))      => p == r,
+                (Some(_), None)         => false,
+            })
+            .map(|(i, m)| (i, m.2))
+            .collect::<Vec<_>>();
+
+        children.sort_by_key(|c| c.1);
+        children.into_iter().map(|c| c.0).collect()
+    };
+
+    let mut order   = vec![];
+    let mut pending = children_of(None).into_iter().rev().map(|i| (0, i)).collect::<Vec<_>>();
+
+    while let Some((depth, idx)) = pending.pop() {
+        if order.iter().any(|&(_, i)| i == idx) {
+            continue // reply cycle
+        }
+
+        order.push((depth, idx));
+        pending.extend(children_of(Some(&meta[idx].0)).into_iter().rev().map(|i| (depth + 1, i)));
+    }
+
+    let mut annotations = annotations.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order
+       .into_iter()
+       .filter_map(|(depth, idx)| annotations[idx].take().map(|a| (depth, a)))
+       .collect())
+}
+
+fn read_datetime(entry: &Entry, path: &str) -> Result<Option<NaiveDateTime>> {
+    match entry.get_header().read_string(path).context(EM::EntryHeaderReadError)? {
+        None    => Ok(None),
+        Some(s) => NaiveDateTime::parse_from_str(&s, DATE_TIME_FORMAT)
+            .map_err(Error::from)
+            .map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    use libimagstore::store::Store;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn set_created(entry: &mut Entry, s: &str) {
+        let _ = entry.get_header_mut()
+            .insert("annotation.created", Value::String(String::from(s)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reply_annotates_same_entry() {
+        let store = get_store();
+
+        let mut anno = {
+            let mut target = store.create(PathBuf::from("target")).unwrap();
+            target.annotate(&store, "first").unwrap()
+        };
+
+        let reply = anno.reply(&store, "second").unwrap();
+
+        assert_eq!(Some(String::from("first")), reply.get_reply_to().unwrap());
+        assert!(reply
+                .get_internal_links()
+                .unwrap()
+                .any(|l| l.get_store_id().local() == &PathBuf::from("target")));
+    }
+
+    #[test]
+    fn test_reply_does_not_reuse_existing_annotation() {
+        let store = get_store();
+
+        let mut anno = {
+            let mut target = store.create(PathBuf::from("target")).unwrap();
+            target.annotate(&store, "first").unwrap()
+        };
+
+        {
+            let mut other = store.create(PathBuf::from("other")).unwrap();
+            let _ = other.annotate(&store, "second").unwrap();
+        }
+
+        assert!(anno.reply(&store, "second").is_err());
+
+        let second = store.get(PathBuf::from("annotations/second")).unwrap().unwrap();
+        assert_eq!(None, second.get_reply_to().unwrap());
+        assert!(!second
+                .get_internal_links()
+                .unwrap()
+                .any(|l| l.get_store_id().local() == &PathBuf::from("target")));
+    }
+
+    #[test]
+    fn test_sort_into_threads() {
+        let store = get_store();
+
+        {
+            let mut a = {
+                let mut target = store.create(PathBuf::from("target")).unwrap();
+                target.annotate(&store, "a").unwrap()
+            };
+            set_created(&mut a, "2018-01-01T10:00:00");
+
+            let mut r = a.reply(&store, "a-reply").unwrap();
+            set_created(&mut r, "2018-01-03T10:00:00");
+        }
+
+        {
+            let mut target = store.get(PathBuf::from("target")).unwrap().unwrap();
+            let mut b      = target.annotate(&store, "b").unwrap();
+            set_created(&mut b, "2018-01-02T10:00:00");
+        }
+
+        let target      = store.get(PathBuf::from("target")).unwrap().unwrap();
+        let annotations = target
+            .annotations(&store)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let threads = sort_into_threads(annotations)
+            .unwrap()
+            .into_iter()
+            .map(|(depth, a)| (depth, a.get_annotation_name().unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+                   (0, String::from("a")),
+                   (1, String::from("a-reply")),
+                   (0, String::from("b")),
+        ], threads);
+    }
+}
@@ -37,9 +37,10 @@
     while_true,
 )]
 
+extern crate chrono;
 extern crate toml;
 extern crate toml_query;
-extern crate failure;
+#[macro_use] extern crate failure;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
@@ -48,7 +49,9 @@ extern crate libimagentrylink;
 
 module_entry_path_mod!("annotations");
 
+pub mod anchor;
 pub mod annotateable;
+pub mod annotation;
 pub mod annotation_fetcher;
 pub mod iter;
 
@@ -30,6 +30,7 @@ use failure::Fallible as Result;
 use failure::Error;
 use failure::err_msg;
 use store::CATEGORY_REGISTER_NAME_FIELD_PATH;
+use store::CATEGORY_REGISTER_PARENT_FIELD_PATH;
 use iter::CategoryEntryIterator;
 
 provide_kindflag_path!(pub IsCategory, "category.is_category");
@@ -37,6 +38,7 @@ provide_kindflag_path!(pub IsCategory, "category.is_category");
 pub trait Category {
     fn is_category(&self) -> Result<bool>;
     fn get_name(&self)    -> Result<String>;
+    fn get_parent_name(&self) -> Result<Option<String>>;
     fn get_entries<'a>(&self, store: &'a Store) -> Result<CategoryEntryIterator<'a>>;
 }
 
@@ -53,6 +55,14 @@ impl Category for Entry {
             .ok_or_else(|| Error::from(err_msg("Category name missing")))
     }
 
+    /// Get the name of the parent category, if this is a subcategory
+    fn get_parent_name(&self) -> Result<Option<String>> {
+        trace!("Getting parent category name of '{:?}'", self.get_location());
+        self.get_header()
+            .read_string(CATEGORY_REGISTER_PARENT_FIELD_PATH)
+            .map_err(Error::from)
+    }
+
     fn get_entries<'a>(&self, store: &'a Store) -> Result<CategoryEntryIterator<'a>> {
         trace!("Getting linked entries for category '{:?}'", self.get_location());
         let sit  = self.get_internal_links()?.map(|l| l.get_store_id().clone()).map(Ok);
@@ -44,18 +44,25 @@ pub trait EntryCategory {
 
     fn remove_category(&mut self) -> Result<()>;
 
+    fn add_category(&mut self, s: &str) -> Result<()>;
+
+    fn add_category_checked(&mut self, register: &CategoryStore, s: &str) -> Result<()>;
+
+    fn remove_category_value(&mut self, s: &str) -> Result<()>;
+
+    fn get_categories(&self) -> Result<Vec<String>>;
+
+    fn migrate_to_multiple_categories(&mut self) -> Result<bool>;
+
 }
 
 impl EntryCategory for Entry {
 
+    /// Set the category of the entry, replacing all other categories
     fn set_category(&mut self, s: &str) -> Result<()> {
         trace!("Setting category '{}' UNCHECKED", s);
-        self.get_header_mut()
-            .insert(&String::from("category.value"), Value::String(s.to_string()))
-            .map_err(Error::from)
-            .context(EM::EntryHeaderWriteError)
-            .map_err(Error::from)
-            .map(|_| ())
+        let _ = self.migrate_to_multiple_categories()?;
+        set_categories(self, vec![String::from(s)])
     }
 
     /// Check whether a category exists before setting it.
@@ -73,24 +80,25 @@ impl EntryCategory for Entry {
         Ok(())
     }
 
+    /// Get the (first) category of the entry
     fn get_category(&self) -> Result<String> {
         trace!("Getting category from '{}'", self.get_location());
-        self.get_header()
-            .read_string("category.value")?
+        self.get_categories()?
+            .into_iter()
+            .next()
             .ok_or_else(|| Error::from(err_msg("Category name missing")))
     }
 
+    /// Check whether the entry has a category
+    ///
+    /// Both the single-value header (`category.value`) and the multi-value header
+    /// (`category.values`) are considered.
     fn has_category(&self) -> Result<bool> {
         trace!("Has category? '{}'", self.get_location());
-        self.get_header()
-            .read("category.value")
-            .map_err(Error::from)
-            .context(EM::EntryHeaderReadError)
-            .map_err(Error::from)
-            .map(|x| x.is_some())
+        self.get_categories().map(|cs| !cs.is_empty())
     }
 
-    /// Remove the category setting
+    /// Remove all categories of the entry
     ///
     /// # Warning
     ///
@@ -99,12 +107,193 @@ impl EntryCategory for Entry {
     fn remove_category(&mut self) -> Result<()> {
         use toml_query::delete::TomlValueDeleteExt;
 
+        let _ = self.migrate_to_multiple_categories()?;
         self.get_header_mut()
-            .delete("category.value")
+            .delete("category.values")
             .map_err(Error::from)
             .context(EM::EntryHeaderWriteError)
             .map_err(Error::from)
             .map(|_| ())
     }
 
+    /// Add a category to the entry, without removing other categories
+    ///
+    /// If the entry has a single category set via `EntryCategory::set_category()`, it is migrated
+    /// to the multi-value header (`category.values`) first.
+    fn add_category(&mut self, s: &str) -> Result<()> {
+        trace!("Adding category '{}' UNCHECKED", s);
+        let _ = self.migrate_to_multiple_categories()?;
+
+        let mut categories = self.get_categories()?;
+        if !categories.iter().any(|c| c == s) {
+            categories.push(String::from(s));
+        }
+
+        set_categories(self, categories)
+    }
+
+    /// Check whether a category exists before adding it.
+    ///
+    /// This function should be used by default over EntryCategory::add_category()!
+    fn add_category_checked(&mut self, register: &CategoryStore, s: &str) -> Result<()> {
+        trace!("Adding category '{}' checked", s);
+        let mut category = register
+            .get_category_by_name(s)?
+            .ok_or_else(|| Error::from(err_msg("Category does not exist")))?;
+
+        let _ = self.add_category(s)?;
+        let _ = self.add_internal_link(&mut category)?;
+
+        Ok(())
+    }
+
+    /// Remove one category from the entry
+    ///
+    /// # Warning
+    ///
+    /// Like `EntryCategory::remove_category()`, this does _only_ remove the category from the
+    /// header, not the internal link to the category entry.
+    fn remove_category_value(&mut self, s: &str) -> Result<()> {
+        trace!("Removing category '{}' from '{}'", s, self.get_location());
+        let _ = self.migrate_to_multiple_categories()?;
+
+        let mut categories = self.get_categories()?;
+        categories.retain(|c| c != s);
+        set_categories(self, categories)
+    }
+
+    /// Get all categories of the entry
+    ///
+    /// Returns the categories from the multi-value header (`category.values`) as well as the one
+    /// from the single-value header (`category.value`), if set.
+    fn get_categories(&self) -> Result<Vec<String>> {
+        trace!("Getting categories from '{}'", self.get_location());
+        let mut categories = match self.get_header()
+            .read("category.values")
+            .map_err(Error::from)
+            .context(EM::EntryHeaderReadError)?
+        {
+            None => vec![],
+            Some(&Value::Array(ref values)) => values
+                .iter()
+                .map(|v| match *v {
+                    Value::String(ref s) => Ok(s.clone()),
+                    _ => Err(Error::from(EM::EntryHeaderTypeError)),
+                })
+                .collect::<Result<Vec<String>>>()?,
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError)),
+        };
+
+        if let Some(single) = self.get_header().read_string("category.value")? {
+            if !categories.contains(&single) {
+                categories.insert(0, single);
+            }
+        }
+
+        Ok(categories)
+    }
+
+    /// Move the single-value category header (`category.value`) to the multi-value header
+    /// (`category.values`)
+    ///
+    /// Returns whether something was migrated.
+    fn migrate_to_multiple_categories(&mut self) -> Result<bool> {
+        use toml_query::delete::TomlValueDeleteExt;
+
+        let single = match self.get_header().read_string("category.value")? {
+            None    => return Ok(false),
+            Some(s) => s,
+        };
+
+        trace!("Migrating category '{}' of '{}' to multi-value header", single, self.get_location());
+        let categories = self.get_categories()?; // includes `single`
+
+        let _ = self.get_header_mut()
+            .delete("category.value")
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)?;
+
+        set_categories(self, categories).map(|_| true)
+    }
+
+}
+
+fn set_categories(entry: &mut Entry, categories: Vec<String>) -> Result<()> {
+    let categories = categories.into_iter().map(Value::String).collect();
+    entry.get_header_mut()
+        .insert("category.values", Value::Array(categories))
+        .map_err(Error::from)
+        .context(EM::EntryHeaderWriteError)
+        .map_err(Error::from)
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    use libimagstore::store::Store;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_migrating_single_category() {
+        let store = get_store();
+        let mut e = store.create(PathBuf::from("a")).unwrap();
+
+        e.set_category("work").unwrap();
+        assert!(e.migrate_to_multiple_categories().unwrap());
+        assert!(!e.migrate_to_multiple_categories().unwrap());
+
+        assert!(e.get_header().read("category.value").unwrap().is_none());
+        assert_eq!(vec![String::from("work")], e.get_categories().unwrap());
+    }
+
+    #[test]
+    fn test_adding_and_removing_categories() {
+        let store = get_store();
+        let mut e = store.create(PathBuf::from("a")).unwrap();
+
+        e.set_category("work").unwrap();
+        e.add_category("finance").unwrap();
+        e.add_category("finance").unwrap();
+        assert_eq!(vec![String::from("work"), String::from("finance")], e.get_categories().unwrap());
+
+        e.remove_category_value("work").unwrap();
+        assert_eq!(vec![String::from("finance")], e.get_categories().unwrap());
+        assert!(e.has_category().unwrap());
+
+        e.remove_category_value("finance").unwrap();
+        assert!(!e.has_category().unwrap());
+    }
+
+    #[test]
+    fn test_accessors_on_migrated_entry() {
+        let store = get_store();
+        let mut e = store.create(PathBuf::from("a")).unwrap();
+
+        let _ = e.get_header_mut()
+            .insert("category.value", Value::String(String::from("work")))
+            .unwrap();
+        assert!(e.migrate_to_multiple_categories().unwrap());
+
+        assert_eq!("work", e.get_category().unwrap());
+
+        e.set_category("finance").unwrap();
+        assert_eq!("finance", e.get_category().unwrap());
+        assert_eq!(vec![String::from("finance")], e.get_categories().unwrap());
+        assert!(e.get_header().read("category.value").unwrap().is_none());
+
+        e.add_category("work").unwrap();
+        e.remove_category().unwrap();
+        assert!(!e.has_category().unwrap());
+        assert!(e.get_category().is_err());
+    }
 }
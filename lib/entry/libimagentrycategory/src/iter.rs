@@ -86,11 +86,40 @@ impl<'a> Iterator for CategoryNameIter<'a> {
     }
 }
 
-pub struct CategoryEntryIterator<'a>(&'a Store, StoreIdIterator, String);
+/// How an entry has to match the categories of a `CategoryEntryIterator`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CategoryMatch {
+    /// The entry has to be in at least one of the categories
+    Any,
+
+    /// The entry has to be in all of the categories
+    All,
+}
+
+/// Iterator over the entries of one or more categories
+///
+/// Only entries which match the categories (see `CategoryMatch`) are returned.
+pub struct CategoryEntryIterator<'a>(&'a Store, StoreIdIterator, Vec<String>, CategoryMatch);
 
 impl<'a> CategoryEntryIterator<'a> {
     pub(crate) fn new(store: &'a Store, sit: StoreIdIterator, name: String) -> Self {
-        CategoryEntryIterator(store, sit, name)
+        CategoryEntryIterator(store, sit, vec![name], CategoryMatch::Any)
+    }
+
+    pub(crate) fn new_with_names(store: &'a Store,
+                                 sit: StoreIdIterator,
+                                 names: Vec<String>,
+                                 matching: CategoryMatch)
+        -> Self
+    {
+        CategoryEntryIterator(store, sit, names, matching)
+    }
+
+    fn matches(&self, categories: &[String]) -> bool {
+        match self.3 {
+            CategoryMatch::Any => self.2.iter().any(|name| categories.contains(name)),
+            CategoryMatch::All => self.2.iter().all(|name| categories.contains(name)),
+        }
     }
 }
 
@@ -102,17 +131,17 @@ impl<'a> Iterator for CategoryEntryIterator<'a> {
             match next {
                 Err(e) => return Some(Err(e).map_err(Error::from)),
                 Ok(next) => {
-                    let getter = |next| -> Result<(String, FileLockEntry<'a>)> {
+                    let getter = |next| -> Result<(Vec<String>, FileLockEntry<'a>)> {
                         let entry = self.0
                             .get(next)?
                             .ok_or_else(|| Error::from(err_msg("Store read error")))?;
-                        Ok((entry.get_category()?, entry))
+                        Ok((entry.get_categories()?, entry))
                     };
 
                     match getter(next) {
-                        Err(e)     => return Some(Err(e)),
-                        Ok((c, e)) => {
-                            if c == self.2 {
+                        Err(e)      => return Some(Err(e)),
+                        Ok((cs, e)) => {
+                            if self.matches(&cs) {
                                 return Some(Ok(e))
                             // } else {
                             // continue
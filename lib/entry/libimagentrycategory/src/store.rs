@@ -18,6 +18,7 @@
 //
 
 use std::path::PathBuf;
+use std::collections::BTreeMap;
 
 use toml_query::insert::TomlValueInsertExt;
 use toml_query::read::TomlValueReadTypeExt;
@@ -26,6 +27,7 @@ use toml::Value;
 use libimagstore::store::Store;
 use libimagstore::store::FileLockEntry;
 use libimagstore::storeid::StoreId;
+use libimagstore::storeid::StoreIdIterator;
 use libimagentryutil::isa::Is;
 use libimagerror::errors::ErrorMsg as EM;
 
@@ -34,9 +36,13 @@ use failure::ResultExt;
 use failure::Error;
 use failure::err_msg;
 use iter::CategoryNameIter;
+use iter::CategoryEntryIterator;
+use iter::CategoryMatch;
 use category::IsCategory;
+use category::Category;
 
-pub const CATEGORY_REGISTER_NAME_FIELD_PATH : &'static str = "category.register.name";
+pub const CATEGORY_REGISTER_NAME_FIELD_PATH   : &'static str = "category.register.name";
+pub const CATEGORY_REGISTER_PARENT_FIELD_PATH : &'static str = "category.register.parent";
 
 /// Extension on the Store to make it a register for categories
 ///
@@ -53,6 +59,21 @@ pub trait CategoryStore {
 
     fn get_category_by_name(&self, name: &str) -> Result<Option<FileLockEntry>>;
 
+    fn set_category_parent(&self, name: &str, parent: Option<&str>) -> Result<()>;
+
+    fn get_subcategory_names(&self, name: &str) -> Result<Vec<String>>;
+
+    fn get_subcategory_map(&self) -> Result<BTreeMap<String, Vec<String>>>;
+
+    fn get_category_names_with_subcategories(&self, name: &str) -> Result<Vec<String>>;
+
+    fn get_entries_with_subcategories<'a>(&'a self, name: &str) -> Result<CategoryEntryIterator<'a>>;
+
+    fn rename_category(&self, old: &str, new: &str) -> Result<()>;
+
+    fn get_entries_in_categories<'a>(&'a self, names: &[&str], matching: CategoryMatch)
+        -> Result<CategoryEntryIterator<'a>>;
+
 }
 
 impl CategoryStore for Store {
@@ -87,12 +108,12 @@ impl CategoryStore for Store {
     /// Automatically removes all category settings from entries which are linked to this category.
     fn delete_category(&self, name: &str) -> Result<()> {
         use libimagentrylink::internal::InternalLinker;
-        use category::Category;
+        use entry::EntryCategory;
 
         trace!("Deleting category: '{}'", name);
         let sid = mk_category_storeid(self.path().clone(), name)?;
 
-        {
+        let parent = {
             let mut category = self.get(sid.clone())?
                 .ok_or_else(|| Error::from(err_msg("Category does not exist")))
                 .map_err(Error::from)?;
@@ -100,7 +121,15 @@ impl CategoryStore for Store {
             for entry in category.get_entries(self)? {
                 let mut entry = entry?;
                 let _         = category.remove_internal_link(&mut entry)?;
+                let _         = entry.remove_category_value(name)?;
             }
+
+            category.get_parent_name()?
+        };
+
+        // Subcategories are moved up to the parent of the deleted category
+        for child in self.get_subcategory_names(name)? {
+            let _ = self.set_category_parent(&child, parent.as_ref().map(String::as_ref))?;
         }
 
         self.delete(sid)
@@ -124,6 +153,194 @@ impl CategoryStore for Store {
             .context(err_msg("Store write error"))
             .map_err(Error::from)
     }
+
+    /// Set the parent of a category
+    ///
+    /// Passing `None` as parent makes the category a top-level category.
+    /// Fails if the parent does not exist or if the new parent is the category itself or one of
+    /// its subcategories.
+    fn set_category_parent(&self, name: &str, parent: Option<&str>) -> Result<()> {
+        use toml_query::delete::TomlValueDeleteExt;
+
+        trace!("Setting parent of category '{}' to '{:?}'", name, parent);
+        if let Some(parent) = parent {
+            if !self.category_exists(parent)? {
+                return Err(format_err!("Category does not exist: '{}'", parent))
+            }
+
+            // Walk up from the new parent, we must not find the category itself
+            let mut ancestor = Some(String::from(parent));
+            while let Some(anc) = ancestor {
+                if anc == name {
+                    return Err(format_err!("Cannot make '{}' a subcategory of itself", name))
+                }
+
+                ancestor = self.get_category_by_name(&anc)?
+                    .ok_or_else(|| format_err!("Category does not exist: '{}'", anc))?
+                    .get_parent_name()?;
+            }
+        }
+
+        let mut category = self.get_category_by_name(name)?
+            .ok_or_else(|| format_err!("Category does not exist: '{}'", name))?;
+
+        match parent {
+            Some(parent) => category
+                .get_header_mut()
+                .insert(CATEGORY_REGISTER_PARENT_FIELD_PATH, Value::String(String::from(parent)))
+                .context(EM::EntryHeaderWriteError)
+                .map_err(Error::from)
+                .map(|_| ()),
+
+            None => category
+                .get_header_mut()
+                .delete(CATEGORY_REGISTER_PARENT_FIELD_PATH)
+                .context(EM::EntryHeaderWriteError)
+                .map_err(Error::from)
+                .map(|_| ()),
+        }
+    }
+
+    /// Get the names of the direct subcategories of a category
+    fn get_subcategory_names(&self, name: &str) -> Result<Vec<String>> {
+        trace!("Getting subcategories of '{}'", name);
+        self.get_subcategory_map()
+            .map(|mut map| map.remove(name).unwrap_or_default())
+    }
+
+    /// Get the names of the direct subcategories of all categories, by the name of their parent
+    ///
+    /// Top-level categories are not included. Use this instead of `get_subcategory_names()` when
+    /// walking the category tree, as it reads each category only once.
+    fn get_subcategory_map(&self) -> Result<BTreeMap<String, Vec<String>>> {
+        trace!("Building subcategory map");
+        let mut map = BTreeMap::new();
+
+        for category in self.all_category_names()? {
+            let category = category?;
+            let parent   = self.get_category_by_name(&category)?
+                .ok_or_else(|| format_err!("Category does not exist: '{}'", category))?
+                .get_parent_name()?;
+
+            if let Some(parent) = parent {
+                map.entry(parent).or_insert_with(Vec::new).push(category);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Get the names of a category and all of its (transitive) subcategories
+    ///
+    /// The category itself is the first element. Does not check whether the category exists.
+    fn get_category_names_with_subcategories(&self, name: &str) -> Result<Vec<String>> {
+        trace!("Getting names of '{}' including subcategories", name);
+        let mut children = self.get_subcategory_map()?;
+        let mut names    = vec![];
+        let mut pending  = vec![String::from(name)];
+
+        while let Some(next) = pending.pop() {
+            if names.contains(&next) {
+                continue
+            }
+
+            pending.extend(children.remove(&next).unwrap_or_default());
+            names.push(next);
+        }
+
+        Ok(names)
+    }
+
+    /// Get all entries in a category and all of its (transitive) subcategories
+    fn get_entries_with_subcategories<'a>(&'a self, name: &str) -> Result<CategoryEntryIterator<'a>> {
+        use libimagentrylink::internal::InternalLinker;
+
+        trace!("Getting entries of '{}' including subcategories", name);
+        let names   = self.get_category_names_with_subcategories(name)?;
+        let mut ids = vec![];
+
+        for name in names.iter() {
+            let category = self.get_category_by_name(name)?
+                .ok_or_else(|| format_err!("Category does not exist: '{}'", name))?;
+
+            for link in category.get_internal_links()? {
+                ids.push(link.get_store_id().clone());
+            }
+        }
+
+        let sit = StoreIdIterator::new(Box::new(ids.into_iter().map(Ok)));
+        Ok(CategoryEntryIterator::new_with_names(self, sit, names, CategoryMatch::Any))
+    }
+
+    /// Rename a category
+    ///
+    /// All entries in the category are moved to the new category and all subcategories are
+    /// reparented to the new category.
+    fn rename_category(&self, old: &str, new: &str) -> Result<()> {
+        use libimagentrylink::internal::InternalLinker;
+        use entry::EntryCategory;
+
+        trace!("Renaming category '{}' to '{}'", old, new);
+        if self.category_exists(new)? {
+            return Err(format_err!("Category already exists: '{}'", new))
+        }
+
+        let children = self.get_subcategory_names(old)?;
+
+        {
+            let mut old_category = self.get_category_by_name(old)?
+                .ok_or_else(|| format_err!("Category does not exist: '{}'", old))?;
+            let mut new_category = self.create_category(new)?;
+
+            if let Some(parent) = old_category.get_parent_name()? {
+                let _ = new_category
+                    .get_header_mut()
+                    .insert(CATEGORY_REGISTER_PARENT_FIELD_PATH, Value::String(parent))?;
+            }
+
+            for entry in old_category.get_entries(self)? {
+                let mut entry = entry?;
+                let _ = old_category.remove_internal_link(&mut entry)?;
+
+                let _ = entry.remove_category_value(old)?;
+                let _ = entry.add_category(new)?;
+                let _ = new_category.add_internal_link(&mut entry)?;
+            }
+        }
+
+        for child in children {
+            let _ = self.set_category_parent(&child, Some(new))?;
+        }
+
+        let sid = mk_category_storeid(self.path().clone(), old)?;
+        self.delete(sid)
+    }
+
+    /// Get all entries which are in any or all (depending on `matching`) of the categories
+    fn get_entries_in_categories<'a>(&'a self, names: &[&str], matching: CategoryMatch)
+        -> Result<CategoryEntryIterator<'a>>
+    {
+        use libimagentrylink::internal::InternalLinker;
+
+        trace!("Getting entries in {:?} of categories {:?}", matching, names);
+        let mut ids = vec![];
+
+        for name in names {
+            let category = self.get_category_by_name(name)?
+                .ok_or_else(|| format_err!("Category does not exist: '{}'", name))?;
+
+            for link in category.get_internal_links()? {
+                ids.push(link.get_store_id().clone());
+            }
+        }
+
+        ids.sort();
+        ids.dedup();
+
+        let names = names.iter().map(|s| String::from(*s)).collect();
+        let sit   = StoreIdIterator::new(Box::new(ids.into_iter().map(Ok)));
+        Ok(CategoryEntryIterator::new_with_names(self, sit, names, matching))
+    }
 }
 
 #[cfg(test)]
@@ -209,6 +426,135 @@ mod tests {
             None        => assert!(false, "Header field not present"),
         }
     }
+
+    #[test]
+    fn test_subcategories() {
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("meetings").unwrap();
+        let _     = store.create_category("standup").unwrap();
+
+        assert!(store.set_category_parent("meetings", Some("work")).is_ok());
+        assert!(store.set_category_parent("standup", Some("meetings")).is_ok());
+
+        assert_eq!(vec![String::from("meetings")], store.get_subcategory_names("work").unwrap());
+        assert_eq!(vec![String::from("standup")], store.get_subcategory_names("meetings").unwrap());
+
+        let map = store.get_subcategory_map().unwrap();
+        assert_eq!(2, map.len());
+        assert_eq!(Some(&vec![String::from("meetings")]), map.get("work"));
+        assert_eq!(Some(&vec![String::from("standup")]), map.get("meetings"));
+
+        let names = store.get_category_names_with_subcategories("work").unwrap();
+        assert_eq!(vec![String::from("work"), String::from("meetings"), String::from("standup")], names);
+    }
+
+    #[test]
+    fn test_subcategory_cycles_are_rejected() {
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("meetings").unwrap();
+
+        assert!(store.set_category_parent("meetings", Some("work")).is_ok());
+        assert!(store.set_category_parent("work", Some("meetings")).is_err());
+        assert!(store.set_category_parent("work", Some("work")).is_err());
+    }
+
+    #[test]
+    fn test_entries_with_subcategories() {
+        use entry::EntryCategory;
+
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("meetings").unwrap();
+        let _     = store.set_category_parent("meetings", Some("work")).unwrap();
+
+        {
+            let mut e = store.create(PathBuf::from("a")).unwrap();
+            e.set_category_checked(&store, "work").unwrap();
+            let mut e = store.create(PathBuf::from("b")).unwrap();
+            e.set_category_checked(&store, "meetings").unwrap();
+        }
+
+        let mut ids = store.get_entries_with_subcategories("work")
+            .unwrap()
+            .map(|e| e.unwrap().get_location().clone().without_base().to_str().unwrap())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(vec![String::from("a"), String::from("b")], ids);
+    }
+
+    #[test]
+    fn test_renaming_category() {
+        use entry::EntryCategory;
+
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("meetings").unwrap();
+        let _     = store.set_category_parent("meetings", Some("work")).unwrap();
+
+        {
+            let mut e = store.create(PathBuf::from("a")).unwrap();
+            e.set_category_checked(&store, "work").unwrap();
+        }
+
+        assert!(store.rename_category("work", "job").is_ok());
+        assert!(!store.category_exists("work").unwrap());
+        assert!(store.category_exists("job").unwrap());
+
+        assert_eq!("job", store.get(PathBuf::from("a")).unwrap().unwrap().get_category().unwrap());
+        assert_eq!(vec![String::from("meetings")], store.get_subcategory_names("job").unwrap());
+    }
+
+    #[test]
+    fn test_deleting_category_removes_it_from_entries() {
+        use entry::EntryCategory;
+
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("finance").unwrap();
+
+        {
+            let mut e = store.create(PathBuf::from("a")).unwrap();
+            e.add_category_checked(&store, "work").unwrap();
+            e.add_category_checked(&store, "finance").unwrap();
+        }
+
+        assert!(store.delete_category("work").is_ok());
+
+        let categories = store.get(PathBuf::from("a")).unwrap().unwrap().get_categories().unwrap();
+        assert_eq!(vec![String::from("finance")], categories);
+    }
+
+    #[test]
+    fn test_entries_in_any_or_all_categories() {
+        use entry::EntryCategory;
+
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("finance").unwrap();
+
+        {
+            let mut e = store.create(PathBuf::from("a")).unwrap();
+            e.add_category_checked(&store, "work").unwrap();
+            e.add_category_checked(&store, "finance").unwrap();
+            let mut e = store.create(PathBuf::from("b")).unwrap();
+            e.add_category_checked(&store, "finance").unwrap();
+        }
+
+        let ids = |matching| {
+            let mut ids = store.get_entries_in_categories(&["work", "finance"], matching)
+                .unwrap()
+                .map(|e| e.unwrap().get_location().clone().without_base().to_str().unwrap())
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(vec![String::from("a"), String::from("b")], ids(CategoryMatch::Any));
+        assert_eq!(vec![String::from("a")], ids(CategoryMatch::All));
+    }
 }
 
 #[inline]
@@ -223,28 +569,18 @@ fn mk_category_storeid(base: PathBuf, s: &str) -> Result<StoreId> {
 
 #[inline]
 fn represents_category(store: &Store, sid: StoreId, name: &str) -> Result<bool> {
-    sid.exists()
-        .context(err_msg("Store id handling error"))
+    store.get(sid)
+        .context(err_msg("Store read error"))
         .map_err(Error::from)
-        .and_then(|bl| {
-            if bl {
-                store.get(sid)
-                    .context(err_msg("Store read error"))
-                    .map_err(Error::from)
-                    .and_then(|fle| {
-                        if let Some(fle) = fle {
-                            fle.get_header()
-                                .read_string(&String::from(CATEGORY_REGISTER_NAME_FIELD_PATH))
-                                .context(EM::EntryHeaderReadError)?
-                                .ok_or_else(|| Error::from(EM::EntryHeaderTypeError))
-                                .map(|s| s == name)
-                        } else {
-                            Ok(false)
-                        }
-                    })
+        .and_then(|fle| {
+            if let Some(fle) = fle {
+                fle.get_header()
+                    .read_string(&String::from(CATEGORY_REGISTER_NAME_FIELD_PATH))
+                    .context(EM::EntryHeaderReadError)?
+                    .ok_or_else(|| Error::from(EM::EntryHeaderTypeError))
+                    .map(|s| s == name)
             } else {
-                Ok(bl) // false
+                Ok(false)
             }
         })
 }
-
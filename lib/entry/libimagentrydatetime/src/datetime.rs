@@ -45,10 +45,10 @@ pub trait EntryDate {
 }
 
 lazy_static! {
-    static ref DATE_HEADER_LOCATION : &'static str              = "datetime.value";
-    static ref DATE_RANGE_START_HEADER_LOCATION : &'static str  = "datetime.range.start";
-    static ref DATE_RANGE_END_HEADER_LOCATION : &'static str    = "datetime.range.end";
-    static ref DATE_FMT : &'static str                          = "%Y-%m-%dT%H:%M:%S";
+    pub(crate) static ref DATE_HEADER_LOCATION : &'static str              = "datetime.value";
+    pub(crate) static ref DATE_RANGE_START_HEADER_LOCATION : &'static str  = "datetime.range.start";
+    static ref DATE_RANGE_END_HEADER_LOCATION : &'static str               = "datetime.range.end";
+    pub(crate) static ref DATE_FMT : &'static str                          = "%Y-%m-%dT%H:%M:%S";
 }
 
 impl EntryDate for Entry {
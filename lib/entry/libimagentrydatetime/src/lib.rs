@@ -38,10 +38,12 @@
 )]
 
 #[macro_use] extern crate lazy_static;
+#[macro_use] extern crate log;
 extern crate chrono;
 extern crate toml_query;
 extern crate toml;
-extern crate failure;
+#[macro_use] extern crate failure;
+extern crate kairos;
 
 extern crate libimagerror;
 extern crate libimagstore;
@@ -49,4 +51,7 @@ extern crate libimagstore;
 pub mod datepath;
 pub mod datetime;
 pub mod range;
+pub mod recurrence;
+pub mod reminder;
+pub mod upcoming;
 
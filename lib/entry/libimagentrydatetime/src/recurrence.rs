@@ -0,0 +1,454 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use toml::Value;
+use toml_query::delete::TomlValueDeleteExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use kairos::timetype::TimeType;
+use kairos::parser::parse;
+use kairos::parser::Parsed;
+
+use libimagstore::store::Entry;
+
+use failure::Error;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::err_msg;
+
+use datetime::DATE_HEADER_LOCATION;
+use datetime::DATE_RANGE_START_HEADER_LOCATION;
+use datetime::DATE_FMT;
+
+lazy_static! {
+    static ref RECURRENCE_SPEC_HEADER_LOCATION : &'static str  = "datetime.recurrence.spec";
+    static ref RECURRENCE_UNTIL_HEADER_LOCATION : &'static str = "datetime.recurrence.until";
+}
+
+/// How an entry recurs
+///
+/// The first occurrence is the date of the entry (`datetime.value`, or the start of
+/// `datetime.range`). From there, the entry recurs every `spec`, which is a kairos duration like
+/// "weekly" or "5days", up to and including `until`, if set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recurrence {
+    spec: String,
+    until: Option<NaiveDateTime>,
+}
+
+impl Recurrence {
+
+    /// Create a new recurrence, failing if `spec` is not a duration which moves time forward
+    pub fn new(spec: String, until: Option<NaiveDateTime>) -> Result<Recurrence> {
+        let reference = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
+        if shift(&reference, &spec, true)? <= reference {
+            return Err(format_err!("'{}' does not move time forward", spec));
+        }
+
+        Ok(Recurrence { spec: spec, until: until })
+    }
+
+    pub fn spec(&self) -> &str {
+        &self.spec
+    }
+
+    pub fn until(&self) -> Option<&NaiveDateTime> {
+        self.until.as_ref()
+    }
+
+}
+
+pub trait EntryRecurrence {
+
+    fn set_recurrence(&mut self, r: Recurrence) -> Result<()>;
+    fn get_recurrence(&self) -> Result<Option<Recurrence>>;
+    fn delete_recurrence(&mut self) -> Result<()>;
+
+    /// Get the date the entry occurs at first: `datetime.value` or, if not present,
+    /// `datetime.range.start`
+    fn first_occurrence(&self) -> Result<Option<NaiveDateTime>>;
+
+    /// Get all occurrences of the entry between `from` and `to`, both inclusive
+    ///
+    /// An entry without a date has no occurrences, an entry with a date but without a recurrence
+    /// occurs once. The n-th occurrence is the first occurrence plus n times the recurrence, so
+    /// the occurrences before `from` are skipped without computing them and a monthly recurrence
+    /// does not drift after a short month.
+    fn occurrences_between(&self, from: &NaiveDateTime, to: &NaiveDateTime)
+        -> Result<Vec<NaiveDateTime>>;
+
+}
+
+impl EntryRecurrence for Entry {
+
+    fn set_recurrence(&mut self, r: Recurrence) -> Result<()> {
+        let _ = self.get_header_mut()
+            .insert(&RECURRENCE_SPEC_HEADER_LOCATION, Value::String(r.spec))
+            .context("Error setting recurrence")?;
+
+        match r.until {
+            Some(until) => {
+                let until = until.format(&DATE_FMT).to_string();
+                self.get_header_mut()
+                    .insert(&RECURRENCE_UNTIL_HEADER_LOCATION, Value::String(until))
+                    .map(|_| ())
+                    .context("Error setting recurrence")
+                    .map_err(Error::from)
+            },
+            None => remove_header_key(self, "datetime.recurrence", "until").map(|_| ()),
+        }
+    }
+
+    fn get_recurrence(&self) -> Result<Option<Recurrence>> {
+        let spec = match self.get_header()
+            .read_string(&RECURRENCE_SPEC_HEADER_LOCATION)
+            .context("Error while reading recurrence")?
+        {
+            Some(spec) => spec,
+            None       => return Ok(None),
+        };
+
+        let until = match self.get_header()
+            .read_string(&RECURRENCE_UNTIL_HEADER_LOCATION)
+            .context("Error while reading recurrence")?
+        {
+            Some(until) => Some(until.parse::<NaiveDateTime>().context("Datetime parse error")?),
+            None        => None,
+        };
+
+        Recurrence::new(spec, until).map(Some)
+    }
+
+    fn delete_recurrence(&mut self) -> Result<()> {
+        remove_header_key(self, "datetime.recurrence", "spec")
+            .and_then(|_| remove_header_key(self, "datetime.recurrence", "until"))
+            .map(|_| ())
+    }
+
+    fn first_occurrence(&self) -> Result<Option<NaiveDateTime>> {
+        let hdr = self.get_header();
+        let date = match hdr.read_string(&DATE_HEADER_LOCATION).context("Error while reading date")? {
+            Some(date) => Some(date),
+            None       => hdr
+                .read_string(&DATE_RANGE_START_HEADER_LOCATION)
+                .context("Error while reading Datetime range")?,
+        };
+
+        match date {
+            Some(date) => date.parse::<NaiveDateTime>()
+                .context("Datetime parse error")
+                .map(Some)
+                .map_err(Error::from),
+            None => Ok(None),
+        }
+    }
+
+    fn occurrences_between(&self, from: &NaiveDateTime, to: &NaiveDateTime)
+        -> Result<Vec<NaiveDateTime>>
+    {
+        let start = match self.first_occurrence()? {
+            Some(start) => start,
+            None        => return Ok(vec![]),
+        };
+
+        let recurrence = match self.get_recurrence()? {
+            Some(recurrence) => recurrence,
+            None             => return Ok(if start >= *from && start <= *to {
+                vec![start]
+            } else {
+                vec![]
+            }),
+        };
+
+        let amount = parse_amount(recurrence.spec())?;
+        let end    = match recurrence.until() {
+            Some(until) if until < to => until,
+            _                         => to,
+        };
+
+        let mut occurrences = vec![];
+        let mut n           = first_occurrence_from(&start, &amount, recurrence.spec(), from)?;
+
+        loop {
+            let moment = nth_occurrence(&start, &amount, n)?;
+            if moment > *end {
+                break
+            }
+
+            occurrences.push(moment);
+            n += 1;
+        }
+
+        Ok(occurrences)
+    }
+
+}
+
+/// Parse `spec`, a kairos duration
+fn parse_amount(spec: &str) -> Result<TimeType> {
+    match parse(spec)? {
+        Parsed::TimeType(tt) => Ok(tt),
+        Parsed::Iterator(_)  => Err(format_err!("'{}' yields an iterator. Cannot use.", spec)),
+    }
+}
+
+/// Move `moment` forward (or backward) by `spec`, a kairos duration
+pub(crate) fn shift(moment: &NaiveDateTime, spec: &str, forward: bool) -> Result<NaiveDateTime> {
+    parse_amount(spec).and_then(|amount| shift_by(moment, amount, forward))
+}
+
+fn shift_by(moment: &NaiveDateTime, amount: TimeType, forward: bool) -> Result<NaiveDateTime> {
+    let base = TimeType::moment(*moment);
+    let shifted = if forward { base + amount } else { base - amount };
+
+    shifted.calculate()?
+        .get_moment()
+        .cloned()
+        .ok_or_else(|| err_msg("Shifting did not yield a moment"))
+}
+
+/// Get `start` plus `n` times `amount`
+///
+/// `n` times `amount` is summed up by doubling `amount`, so this takes log(n) steps.
+fn nth_occurrence(start: &NaiveDateTime, amount: &TimeType, n: u64) -> Result<NaiveDateTime> {
+    let mut total : Option<TimeType> = None;
+    let mut power = amount.clone();
+    let mut n     = n;
+
+    while n > 0 {
+        if n & 1 == 1 {
+            total = Some(match total {
+                Some(total) => (total + power.clone()).calculate()?,
+                None        => power.clone(),
+            });
+        }
+
+        n >>= 1;
+        if n > 0 {
+            power = (power.clone() + power).calculate()?;
+        }
+    }
+
+    match total {
+        Some(total) => shift_by(start, total, true),
+        None        => Ok(*start),
+    }
+}
+
+/// Get the number of the first occurrence at or after `from`
+///
+/// The number is estimated from the length of the first period. As periods can differ in length
+/// (months, years), the estimate is corrected afterwards.
+fn first_occurrence_from(start: &NaiveDateTime,
+                         amount: &TimeType,
+                         spec: &str,
+                         from: &NaiveDateTime)
+    -> Result<u64>
+{
+    let period = nth_occurrence(start, amount, 1)?.signed_duration_since(*start);
+    if period <= Duration::zero() {
+        return Err(format_err!("'{}' does not move time forward", spec));
+    }
+
+    if from <= start {
+        return Ok(0)
+    }
+
+    let elapsed = from.signed_duration_since(*start);
+    let mut n   = (elapsed.num_seconds() / ::std::cmp::max(period.num_seconds(), 1)) as u64;
+
+    while n > 0 && nth_occurrence(start, amount, n - 1)? >= *from {
+        n -= 1;
+    }
+
+    while nth_occurrence(start, amount, n)? < *from {
+        n += 1;
+    }
+
+    Ok(n)
+}
+
+/// Remove `key` from the header table at `table`, deleting the table if it is empty afterwards
+pub(crate) fn remove_header_key(entry: &mut Entry, table: &str, key: &str) -> Result<Option<Value>> {
+    let hdr = entry.get_header_mut();
+    let (removed, is_empty) = match hdr.read_mut(table).context("Error writing header")? {
+        Some(&mut Value::Table(ref mut t)) => {
+            let removed = t.remove(key);
+            (removed, t.is_empty())
+        },
+        _ => (None, false),
+    };
+
+    if is_empty {
+        let _ = hdr.delete(table).context("Error writing header")?;
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    use libimagstore::store::Store;
+
+    use datetime::EntryDate;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn ndt(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2018, 10, day).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    fn test_set_get_recurrence() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let recurrence = Recurrence::new(String::from("weekly"), Some(ndt(20, 0))).unwrap();
+        assert!(entry.set_recurrence(recurrence.clone()).is_ok());
+
+        let hdr_field = entry.get_header().read("datetime.recurrence.until");
+        assert_eq!(Some(&Value::String(String::from("2018-10-20T00:00:00"))), hdr_field.unwrap());
+
+        assert_eq!(Some(recurrence), entry.get_recurrence().unwrap());
+    }
+
+    #[test]
+    fn test_recurrence_must_move_forward() {
+        assert!(Recurrence::new(String::from("0days"), None).is_err());
+        assert!(Recurrence::new(String::from("today"), None).is_err());
+    }
+
+    #[test]
+    fn test_invalid_recurrence_in_header_is_rejected() {
+        use toml_query::insert::TomlValueInsertExt;
+
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let _ = entry.set_date(ndt(1, 9)).unwrap();
+        let _ = entry.get_header_mut()
+            .insert("datetime.recurrence.spec", Value::String(String::from("0days")))
+            .unwrap();
+
+        assert!(entry.get_recurrence().is_err());
+        assert!(entry.occurrences_between(&ndt(1, 0), &ndt(31, 0)).is_err());
+    }
+
+    #[test]
+    fn test_delete_recurrence() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let recurrence = Recurrence::new(String::from("weekly"), Some(ndt(20, 0))).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+        assert!(entry.delete_recurrence().is_ok());
+
+        assert!(entry.get_recurrence().unwrap().is_none());
+        assert!(entry.get_header().read("datetime.recurrence").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_occurrences_without_recurrence() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        assert!(entry.occurrences_between(&ndt(1, 0), &ndt(31, 0)).unwrap().is_empty());
+
+        let _ = entry.set_date(ndt(3, 9)).unwrap();
+        assert_eq!(vec![ndt(3, 9)], entry.occurrences_between(&ndt(1, 0), &ndt(31, 0)).unwrap());
+        assert!(entry.occurrences_between(&ndt(4, 0), &ndt(31, 0)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_occurrences_with_recurrence() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let _ = entry.set_date(ndt(1, 9)).unwrap();
+        let recurrence = Recurrence::new(String::from("weekly"), None).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+
+        let occurrences = entry.occurrences_between(&ndt(2, 0), &ndt(31, 0)).unwrap();
+        assert_eq!(vec![ndt(8, 9), ndt(15, 9), ndt(22, 9), ndt(29, 9)], occurrences);
+
+        let recurrence = Recurrence::new(String::from("weekly"), Some(ndt(16, 0))).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+
+        let occurrences = entry.occurrences_between(&ndt(1, 0), &ndt(31, 0)).unwrap();
+        assert_eq!(vec![ndt(1, 9), ndt(8, 9), ndt(15, 9)], occurrences);
+    }
+
+    #[test]
+    fn test_occurrences_far_after_start() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let _ = entry.set_date(NaiveDate::from_ymd(2010, 1, 15).and_hms(9, 0, 0)).unwrap();
+        let recurrence = Recurrence::new(String::from("monthly"), None).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+
+        let from = NaiveDate::from_ymd(2018, 2, 1).and_hms(0, 0, 0);
+        let to   = NaiveDate::from_ymd(2018, 4, 30).and_hms(0, 0, 0);
+        let expected = vec![
+            NaiveDate::from_ymd(2018, 2, 15).and_hms(9, 0, 0),
+            NaiveDate::from_ymd(2018, 3, 15).and_hms(9, 0, 0),
+            NaiveDate::from_ymd(2018, 4, 15).and_hms(9, 0, 0),
+        ];
+        assert_eq!(expected, entry.occurrences_between(&from, &to).unwrap());
+
+        let recurrence = Recurrence::new(String::from("3days"), None).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+
+        // 2018-02-01 is 2939 days, so 979 periods and 2 days after the start
+        let to = NaiveDate::from_ymd(2018, 2, 7).and_hms(0, 0, 0);
+        let expected = vec![
+            NaiveDate::from_ymd(2018, 2, 2).and_hms(9, 0, 0),
+            NaiveDate::from_ymd(2018, 2, 5).and_hms(9, 0, 0),
+        ];
+        assert_eq!(expected, entry.occurrences_between(&from, &to).unwrap());
+    }
+
+    #[test]
+    fn test_occurrences_from_range_start() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let _ = entry.set_date_range(ndt(5, 9), ndt(5, 10)).unwrap();
+        let recurrence = Recurrence::new(String::from("5days"), None).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+
+        let occurrences = entry.occurrences_between(&ndt(1, 0), &ndt(16, 0)).unwrap();
+        assert_eq!(vec![ndt(5, 9), ndt(10, 9), ndt(15, 9)], occurrences);
+    }
+
+}
+
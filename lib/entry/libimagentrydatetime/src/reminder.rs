@@ -0,0 +1,269 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+use libimagerror::errors::ErrorMsg as EM;
+
+use failure::Error;
+use failure::Fallible as Result;
+use failure::ResultExt;
+
+use datetime::DATE_FMT;
+use recurrence::EntryRecurrence;
+use recurrence::remove_header_key;
+use recurrence::shift;
+
+lazy_static! {
+    static ref REMINDERS_HEADER_LOCATION : &'static str = "datetime.reminders";
+    static ref REMINDED_HEADER_LOCATION : &'static str  = "datetime.reminded";
+}
+
+/// A reminder for one occurrence of an entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reminder {
+    pub id:         StoreId,
+    pub occurrence: NaiveDateTime,
+    pub remind_at:  NaiveDateTime,
+    pub offset:     String,
+}
+
+pub trait EntryReminder {
+
+    /// Remind `offset` before each occurrence of the entry
+    ///
+    /// `offset` is a kairos duration like "15minutes" or "1days". Returns false if the entry
+    /// already had this reminder.
+    fn add_reminder(&mut self, offset: String) -> Result<bool>;
+
+    fn get_reminders(&self) -> Result<Vec<String>>;
+
+    /// Remove a reminder, returns false if the entry did not have it
+    fn remove_reminder(&mut self, offset: &str) -> Result<bool>;
+
+    /// Get the time up to which the reminders of this entry were fired already
+    fn get_reminded(&self) -> Result<Option<NaiveDateTime>>;
+
+    fn set_reminded(&mut self, until: NaiveDateTime) -> Result<()>;
+
+    /// Get the reminders which are due at `now`
+    ///
+    /// A reminder is due if its time has come, the occurrence it reminds of is not over yet and it
+    /// was not fired already.
+    fn due_reminders(&self, now: &NaiveDateTime) -> Result<Vec<Reminder>>;
+
+}
+
+impl EntryReminder for Entry {
+
+    fn add_reminder(&mut self, offset: String) -> Result<bool> {
+        let reference = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
+        if shift(&reference, &offset, false)? > reference {
+            return Err(format_err!("Reminder '{}' would be after the occurrence", offset));
+        }
+
+        let mut reminders = self.get_reminders()?;
+        if reminders.contains(&offset) {
+            return Ok(false);
+        }
+        reminders.push(offset);
+
+        set_reminders(self, reminders).map(|_| true)
+    }
+
+    fn get_reminders(&self) -> Result<Vec<String>> {
+        match self.get_header()
+            .read(&REMINDERS_HEADER_LOCATION)
+            .context("Error while reading reminders")?
+        {
+            Some(&Value::Array(ref reminders)) => reminders
+                .iter()
+                .map(|r| r.as_str().map(String::from).ok_or_else(|| Error::from(EM::EntryHeaderTypeError)))
+                .collect(),
+            Some(_) => Err(Error::from(EM::EntryHeaderTypeError)),
+            None    => Ok(vec![]),
+        }
+    }
+
+    fn remove_reminder(&mut self, offset: &str) -> Result<bool> {
+        let mut reminders = self.get_reminders()?;
+        match reminders.iter().position(|r| r == offset) {
+            Some(pos) => {
+                let _ = reminders.remove(pos);
+            },
+            None => return Ok(false),
+        }
+
+        if reminders.is_empty() {
+            let _ = remove_header_key(self, "datetime", "reminders")?;
+            remove_header_key(self, "datetime", "reminded").map(|_| true)
+        } else {
+            set_reminders(self, reminders).map(|_| true)
+        }
+    }
+
+    fn get_reminded(&self) -> Result<Option<NaiveDateTime>> {
+        match self.get_header()
+            .read_string(&REMINDED_HEADER_LOCATION)
+            .context("Error while reading reminders")?
+        {
+            Some(reminded) => reminded
+                .parse::<NaiveDateTime>()
+                .context("Datetime parse error")
+                .map(Some)
+                .map_err(Error::from),
+            None => Ok(None),
+        }
+    }
+
+    fn set_reminded(&mut self, until: NaiveDateTime) -> Result<()> {
+        let until = until.format(&DATE_FMT).to_string();
+        self.get_header_mut()
+            .insert(&REMINDED_HEADER_LOCATION, Value::String(until))
+            .map(|_| ())
+            .context("Error setting reminders")
+            .map_err(Error::from)
+    }
+
+    fn due_reminders(&self, now: &NaiveDateTime) -> Result<Vec<Reminder>> {
+        let reminders = self.get_reminders()?;
+        if reminders.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let reminded = self.get_reminded()?;
+        let mut due  = vec![];
+
+        for offset in reminders {
+            let horizon = shift(now, &offset, true)?;
+
+            for occurrence in self.occurrences_between(now, &horizon)? {
+                let remind_at = shift(&occurrence, &offset, false)?;
+                let is_fired  = reminded.map(|r| remind_at <= r).unwrap_or(false);
+
+                if remind_at <= *now && !is_fired {
+                    due.push(Reminder {
+                        id:         self.get_location().clone(),
+                        occurrence: occurrence,
+                        remind_at:  remind_at,
+                        offset:     offset.clone(),
+                    });
+                }
+            }
+        }
+
+        due.sort_by(|a, b| a.remind_at.cmp(&b.remind_at));
+        Ok(due)
+    }
+
+}
+
+fn set_reminders(entry: &mut Entry, reminders: Vec<String>) -> Result<()> {
+    let reminders = reminders.into_iter().map(Value::String).collect();
+    entry.get_header_mut()
+        .insert(&REMINDERS_HEADER_LOCATION, Value::Array(reminders))
+        .map(|_| ())
+        .context("Error setting reminders")
+        .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    use libimagstore::store::Store;
+
+    use datetime::EntryDate;
+    use recurrence::Recurrence;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn ndt(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2018, 10, day).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    fn test_add_remove_reminder() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        assert!(entry.add_reminder(String::from("15minutes")).unwrap());
+        assert!(entry.add_reminder(String::from("1days")).unwrap());
+        assert!(!entry.add_reminder(String::from("1days")).unwrap());
+        assert_eq!(vec!["15minutes", "1days"], entry.get_reminders().unwrap());
+
+        assert!(entry.remove_reminder("15minutes").unwrap());
+        assert!(!entry.remove_reminder("15minutes").unwrap());
+        assert!(entry.remove_reminder("1days").unwrap());
+
+        assert!(entry.get_reminders().unwrap().is_empty());
+        assert!(entry.get_header().read("datetime").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_reminder() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        assert!(entry.add_reminder(String::from("today")).is_err());
+        assert!(entry.get_reminders().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        let _ = entry.set_date(ndt(1, 9)).unwrap();
+        let recurrence = Recurrence::new(String::from("weekly"), None).unwrap();
+        assert!(entry.set_recurrence(recurrence).is_ok());
+        assert!(entry.add_reminder(String::from("1days")).unwrap());
+
+        // the reminder for the occurrence at the 8th is due from the 7th, 9:00 on
+        assert!(entry.due_reminders(&ndt(7, 8)).unwrap().is_empty());
+
+        let due = entry.due_reminders(&ndt(7, 10)).unwrap();
+        assert_eq!(1, due.len());
+        assert_eq!(ndt(8, 9), due[0].occurrence);
+        assert_eq!(ndt(7, 9), due[0].remind_at);
+        assert_eq!("1days", due[0].offset);
+
+        assert!(entry.set_reminded(ndt(7, 10)).is_ok());
+        assert!(entry.due_reminders(&ndt(7, 11)).unwrap().is_empty());
+
+        // the next week, it is due again
+        assert_eq!(1, entry.due_reminders(&ndt(14, 10)).unwrap().len());
+    }
+
+}
+
@@ -0,0 +1,258 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::vec::IntoIter;
+
+use chrono::NaiveDateTime;
+
+use libimagstore::iter::get::StoreGetIterator;
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use failure::Fallible as Result;
+
+use recurrence::EntryRecurrence;
+use reminder::EntryReminder;
+use reminder::Reminder;
+
+/// One occurrence of an entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Occurrence {
+    pub id: StoreId,
+    pub at: NaiveDateTime,
+}
+
+/// Iterator over the occurrences of all entries in the store
+///
+/// Entries are read one after another. The occurrences of one entry are ordered by time, the
+/// entries are not ordered. Entries whose date or recurrence cannot be read are skipped with a
+/// warning.
+pub struct Occurrences<'a> {
+    entries: StoreGetIterator<'a>,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    current: IntoIter<Occurrence>,
+}
+
+impl<'a> Iterator for Occurrences<'a> {
+    type Item = Result<Occurrence>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(occurrence) = self.current.next() {
+                return Some(Ok(occurrence))
+            }
+
+            let entry = match next_entry(&mut self.entries) {
+                Some(Ok(entry)) => entry,
+                Some(Err(e))    => return Some(Err(e)),
+                None            => return None,
+            };
+
+            match entry.occurrences_between(&self.from, &self.to) {
+                Ok(occurrences) => {
+                    let id = entry.get_location().clone();
+                    self.current = occurrences
+                        .into_iter()
+                        .map(|at| Occurrence { id: id.clone(), at: at })
+                        .collect::<Vec<_>>()
+                        .into_iter();
+                },
+                Err(e) => warn!("Skipping '{}': {}", entry.get_location(), e),
+            }
+        }
+    }
+}
+
+/// Iterator over the due reminders of all entries in the store
+///
+/// Entries are read one after another. The reminders are not ordered. Entries whose date,
+/// recurrence or reminders cannot be read are skipped with a warning.
+pub struct DueReminders<'a> {
+    entries: StoreGetIterator<'a>,
+    now: NaiveDateTime,
+    current: IntoIter<Reminder>,
+}
+
+impl<'a> Iterator for DueReminders<'a> {
+    type Item = Result<Reminder>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(reminder) = self.current.next() {
+                return Some(Ok(reminder))
+            }
+
+            let entry = match next_entry(&mut self.entries) {
+                Some(Ok(entry)) => entry,
+                Some(Err(e))    => return Some(Err(e)),
+                None            => return None,
+            };
+
+            match entry.due_reminders(&self.now) {
+                Ok(due) => self.current = due.into_iter(),
+                Err(e)  => warn!("Skipping '{}': {}", entry.get_location(), e),
+            }
+        }
+    }
+}
+
+/// Get the next entry which exists
+fn next_entry<'a>(entries: &mut StoreGetIterator<'a>) -> Option<Result<FileLockEntry<'a>>> {
+    loop {
+        match entries.next() {
+            Some(Ok(Some(entry))) => return Some(Ok(entry)),
+            Some(Ok(None))        => continue,
+            Some(Err(e))          => return Some(Err(e)),
+            None                  => return None,
+        }
+    }
+}
+
+/// Extension on the Store to find upcoming dates and due reminders of all entries
+pub trait UpcomingStore {
+
+    /// Get the occurrences of all entries between `from` and `to`, both inclusive
+    fn occurrences<'a>(&'a self, from: &NaiveDateTime, to: &NaiveDateTime) -> Result<Occurrences<'a>>;
+
+    /// Get the reminders of all entries which are due at `now`
+    fn due_reminders<'a>(&'a self, now: &NaiveDateTime) -> Result<DueReminders<'a>>;
+
+}
+
+impl UpcomingStore for Store {
+
+    fn occurrences<'a>(&'a self, from: &NaiveDateTime, to: &NaiveDateTime) -> Result<Occurrences<'a>> {
+        Ok(Occurrences {
+            entries: self.entries()?.into_get_iter(),
+            from:    *from,
+            to:      *to,
+            current: vec![].into_iter(),
+        })
+    }
+
+    fn due_reminders<'a>(&'a self, now: &NaiveDateTime) -> Result<DueReminders<'a>> {
+        Ok(DueReminders {
+            entries: self.entries()?.into_get_iter(),
+            now:     *now,
+            current: vec![].into_iter(),
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    use datetime::EntryDate;
+    use recurrence::Recurrence;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn ndt(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2018, 10, day).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    fn test_occurrences_across_store() {
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("weekly")).unwrap();
+            let _ = entry.set_date(ndt(1, 9)).unwrap();
+            let recurrence = Recurrence::new(String::from("weekly"), None).unwrap();
+            assert!(entry.set_recurrence(recurrence).is_ok());
+            assert!(entry.add_reminder(String::from("1hours")).unwrap());
+        }
+
+        {
+            let mut entry = store.create(PathBuf::from("once")).unwrap();
+            let _ = entry.set_date(ndt(10, 12)).unwrap();
+            assert!(entry.add_reminder(String::from("1days")).unwrap());
+        }
+
+        let _ = store.create(PathBuf::from("undated")).unwrap();
+
+        let mut occurrences = store.occurrences(&ndt(2, 0), &ndt(16, 0))
+            .unwrap()
+            .map(|o| o.map(|o| (o.id.local().clone(), o.at)))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        occurrences.sort_by(|a, b| a.1.cmp(&b.1));
+
+        assert_eq!(vec![
+            (PathBuf::from("weekly"), ndt(8, 9)),
+            (PathBuf::from("once"), ndt(10, 12)),
+            (PathBuf::from("weekly"), ndt(15, 9)),
+        ], occurrences);
+
+        let due = store.due_reminders(&ndt(9, 12)).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(1, due.len());
+        assert_eq!(PathBuf::from("once"), *due[0].id.local());
+    }
+
+    #[test]
+    fn test_broken_entries_are_skipped() {
+        use toml::Value;
+        use toml_query::insert::TomlValueInsertExt;
+
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("broken")).unwrap();
+            let _ = entry.get_header_mut()
+                .insert("datetime.value", Value::String(String::from("not a date")))
+                .unwrap();
+            let _ = entry.get_header_mut()
+                .insert("datetime.reminders", Value::Array(vec![Value::String(String::from("1days"))]))
+                .unwrap();
+        }
+
+        {
+            let mut entry = store.create(PathBuf::from("once")).unwrap();
+            let _ = entry.set_date(ndt(10, 12)).unwrap();
+            assert!(entry.add_reminder(String::from("1days")).unwrap());
+        }
+
+        let occurrences = store.occurrences(&ndt(1, 0), &ndt(31, 0))
+            .unwrap()
+            .map(|o| o.unwrap().id.local().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![PathBuf::from("once")], occurrences);
+
+        let due = store.due_reminders(&ndt(9, 12))
+            .unwrap()
+            .map(|r| r.unwrap().id.local().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![PathBuf::from("once")], due);
+    }
+
+}
+
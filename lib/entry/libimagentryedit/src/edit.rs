@@ -17,15 +17,30 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
 use libimagrt::runtime::Runtime;
 use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimaginteraction::ask::ask_bool;
 
 use failure::Fallible as Result;
 use failure::Error;
 use failure::ResultExt;
 use failure::err_msg;
+use toml::Value;
 
 use libimagerror::errors::ErrorMsg as EM;
+use merge::Merge;
+use merge::merge3;
+use merge::merge_values;
+use merge::has_conflict_markers;
+
+/// Prefix of the lines which are used to report errors to the user inside the edited text
+const ERROR_COMMENT_PREFIX : &'static str = "# imag: ";
 
 pub trait Edit {
     fn edit_content(&mut self, rt: &Runtime) -> Result<()>;
@@ -36,6 +51,18 @@ pub trait EditHeader : Edit {
     fn edit_header_and_content(&mut self, rt: &Runtime) -> Result<()>;
 }
 
+/// Editing with a hook which is called if the content was changed
+///
+/// Lets the caller keep data which depends on the content up to date, for example the anchors of
+/// annotations or the links of `[[wiki links]]`.
+pub trait EditWithHook : EditHeader {
+    fn edit_content_with_hook<H>(&mut self, rt: &Runtime, hook: H) -> Result<()>
+        where H: FnOnce(&mut Entry) -> Result<()>;
+
+    fn edit_header_and_content_with_hook<H>(&mut self, rt: &Runtime, hook: H) -> Result<()>
+        where H: FnOnce(&mut Entry) -> Result<()>;
+}
+
 impl Edit for String {
 
     fn edit_content(&mut self, rt: &Runtime) -> Result<()> {
@@ -46,27 +73,112 @@ impl Edit for String {
 
 impl Edit for Entry {
 
+    /// Edit the content of the entry
+    ///
+    /// If the entry is changed on disk by another process while editing, the user is asked
+    /// whether the changes should be merged into the edited content and the changes to the header
+    /// on disk are merged into the header.
     fn edit_content(&mut self, rt: &Runtime) -> Result<()> {
-        edit_in_tmpfile(rt, self.get_content_mut())
-            .map(|_| ())
+        self.edit_content_with_hook(rt, |_| Ok(()))
     }
 
 }
 
 impl EditHeader for Entry {
 
+    /// Edit the header of the entry
+    ///
+    /// If the edited header cannot be parsed or does not verify, the editor is re-opened with the
+    /// error noted at the top of the text. If the entry is changed on disk by another process
+    /// while editing, the changes to the content on disk are merged into the content.
     fn edit_header(&mut self, rt: &Runtime) -> Result<()> {
-        let mut header = ::toml::ser::to_string_pretty(self.get_header())?;
-        let _          = edit_in_tmpfile(rt, &mut header)?;
-        let header     = ::toml::de::from_str(&header)?;
-        *self.get_header_mut() = header;
-        Ok(())
+        let header = ::toml::ser::to_string_pretty(self.get_header())?;
+        let edited = {
+            let original = &*self;
+            edit_guarded(rt,
+                         rt.store(),
+                         self.get_location(),
+                         header,
+                         ErrorPosition::Top,
+                         |entry| ::toml::ser::to_string_pretty(entry.get_header()).map_err(Error::from),
+                         |buffer| {
+                             let header : Value = ::toml::de::from_str(buffer)
+                                 .context(EM::TomlDeserError)?;
+
+                             let mut check = original.clone();
+                             *check.get_header_mut() = header.clone();
+                             check.verify().map(|_| header)
+                         })?
+        };
+
+        take_edited_header(self, edited)
     }
 
+    /// Edit header and content of the entry
+    ///
+    /// If the edited header cannot be parsed or does not verify, the editor is re-opened with the
+    /// error noted at the top of the header.
     fn edit_header_and_content(&mut self, rt: &Runtime) -> Result<()> {
-        let mut header_and_content = self.to_str()?;
-        let _                      = edit_in_tmpfile(rt, &mut header_and_content)?;
-        self.replace_from_buffer(&header_and_content).map_err(Error::from)
+        self.edit_header_and_content_with_hook(rt, |_| Ok(()))
+    }
+
+}
+
+impl EditWithHook for Entry {
+
+    /// Edit the content of the entry, see `Edit::edit_content()`
+    ///
+    /// Calls `hook` with the entry afterwards, if the content was changed.
+    fn edit_content_with_hook<H>(&mut self, rt: &Runtime, hook: H) -> Result<()>
+        where H: FnOnce(&mut Entry) -> Result<()>
+    {
+        let content = self.get_content().clone();
+        let edited  = edit_guarded(rt,
+                                   rt.store(),
+                                   self.get_location(),
+                                   content,
+                                   ErrorPosition::Top,
+                                   |entry| Ok(entry.get_content().clone()),
+                                   |buffer| Ok(String::from(buffer)))?;
+
+        if take_edited_content(self, edited) {
+            hook(self)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Edit header and content of the entry, see `EditHeader::edit_header_and_content()`
+    ///
+    /// Calls `hook` with the entry afterwards, if the content was changed.
+    fn edit_header_and_content_with_hook<H>(&mut self, rt: &Runtime, hook: H) -> Result<()>
+        where H: FnOnce(&mut Entry) -> Result<()>
+    {
+        let buffer = self.to_str()?;
+        let edited = {
+            let location = self.get_location();
+            edit_guarded(rt,
+                         rt.store(),
+                         location,
+                         buffer,
+                         ErrorPosition::BelowFirstSeparator,
+                         |entry| entry.to_str(),
+                         |buffer| {
+                             let entry = Entry::from_str(location.clone(), buffer)?;
+                             entry.verify().map(|_| entry)
+                         })?
+        };
+
+        let edited  = edited.value;
+        let changed = edited.get_content() != self.get_content();
+        *self.get_header_mut()  = edited.get_header().clone();
+        *self.get_content_mut() = edited.get_content().clone();
+
+        if changed {
+            hook(self)
+        } else {
+            Ok(())
+        }
     }
 
 }
@@ -89,3 +201,355 @@ pub fn edit_in_tmpfile(rt: &Runtime, s: &mut String) -> Result<()> {
         })
 }
 
+/// Put the edited content into `entry`, merging the changes to the header on disk
+///
+/// Header values changed in `entry` win over the ones changed on disk. Returns whether the
+/// content changed.
+fn take_edited_content(entry: &mut Entry, edited: Edited<String>) -> bool {
+    if let Some(persisted) = edited.persisted {
+        let base   = edited.original.map(|e| e.get_header().clone()).unwrap_or_else(empty_header);
+        let header = merge_values(&base, entry.get_header(), persisted.get_header());
+        *entry.get_header_mut() = header;
+    }
+
+    let changed = edited.value != *entry.get_content();
+    *entry.get_content_mut() = edited.value;
+    changed
+}
+
+/// Put the edited header into `entry`, merging the changes to the content on disk
+///
+/// Fails if the content was changed in `entry` and on disk in conflicting ways.
+fn take_edited_header(entry: &mut Entry, edited: Edited<Value>) -> Result<()> {
+    if let Some(persisted) = edited.persisted {
+        let base   = edited.original.map(|e| e.get_content().clone()).unwrap_or_default();
+        let merged = merge3(&base, entry.get_content(), persisted.get_content());
+        match merged {
+            Merge::Clean(content) => *entry.get_content_mut() = content,
+            Merge::Conflict(_)    => {
+                let id = entry.get_location();
+                return Err(format_err!("The content of {} was changed on disk while editing", id))
+            },
+        }
+    }
+
+    *entry.get_header_mut() = edited.value;
+    Ok(())
+}
+
+fn empty_header() -> Value {
+    Value::Table(Default::default())
+}
+
+/// Where errors are noted in the text when the editor is re-opened
+enum ErrorPosition {
+    /// At the top of the text
+    Top,
+
+    /// Below the first `---` line, which is the beginning of the header of an entry
+    BelowFirstSeparator,
+}
+
+/// The state of an entry on disk
+struct Snapshot {
+    fingerprint: u64,
+    part: String,
+    entry: Entry,
+}
+
+fn snapshot<P>(store: &Store, id: &StoreId, part: &P) -> Result<Option<Snapshot>>
+    where P: Fn(&Entry) -> Result<String>
+{
+    match store.read_persisted(id.clone())? {
+        None        => Ok(None),
+        Some(entry) => {
+            let mut hasher = DefaultHasher::new();
+            entry.to_str()?.hash(&mut hasher);
+
+            Ok(Some(Snapshot {
+                fingerprint: hasher.finish(),
+                part: part(&entry)?,
+                entry: entry,
+            }))
+        }
+    }
+}
+
+/// The result of a guarded edit
+struct Edited<T> {
+    /// The validated text
+    value: T,
+
+    /// The entry on disk when editing began
+    original: Option<Entry>,
+
+    /// The entry on disk, if it was changed by another process while editing
+    ///
+    /// The parts of the entry which were not edited have to be taken from here, so the changes
+    /// of the other process to them are not overwritten.
+    persisted: Option<Entry>,
+}
+
+/// The user who edits an entry
+///
+/// Abstracts the editor and the questions asked while editing, so `edit_guarded()` does not
+/// depend on a terminal.
+trait User {
+    /// Let the user edit `text`
+    fn edit(&self, text: &mut String) -> Result<()>;
+
+    /// Ask the user a yes/no question
+    fn ask(&self, question: &str, default: bool) -> Result<bool>;
+}
+
+impl<'a> User for Runtime<'a> {
+    fn edit(&self, text: &mut String) -> Result<()> {
+        edit_in_tmpfile(self, text)
+    }
+
+    /// Falls back to `default` if there is no input stream
+    fn ask(&self, question: &str, default: bool) -> Result<bool> {
+        match self.stdin() {
+            None            => Ok(default),
+            Some(mut input) => ask_bool(question, Some(default), &mut input, &mut self.stdout()),
+        }
+    }
+}
+
+/// Let `user` edit `text`, which is `part` of the entry `id`, until it passes `validate`
+///
+/// The persisted entry is checked before and after each editor run. If it changed, the user is
+/// asked whether to merge the changes into the edited text. If the text does not validate, the
+/// editor is re-opened with the error noted in the text, until the user empties the buffer.
+fn edit_guarded<T, P, V, U>(user: &U,
+                            store: &Store,
+                            id: &StoreId,
+                            mut text: String,
+                            position: ErrorPosition,
+                            part: P,
+                            validate: V)
+    -> Result<Edited<T>>
+    where P: Fn(&Entry) -> Result<String>,
+          V: Fn(&str) -> Result<T>,
+          U: User
+{
+    let mut before  = snapshot(store, id, &part)?;
+    let original    = before.as_ref().map(|snap| snap.entry.clone());
+    let mut changed = false;
+    let mut base    = before
+        .as_ref()
+        .map(|snap| snap.part.clone())
+        .unwrap_or_else(|| text.clone());
+
+    loop {
+        user.edit(&mut text)?;
+        let now = snapshot(store, id, &part)?;
+
+        if now.as_ref().map(|s| s.fingerprint) != before.as_ref().map(|s| s.fingerprint) {
+            warn!("{} was changed by another process while editing", id);
+            changed = true;
+
+            if let Some(ref theirs) = now {
+                if user.ask("Merge these changes into your version", true)? {
+                    text = match merge3(&base, &text, &theirs.part) {
+                        Merge::Clean(merged)    => merged,
+                        Merge::Conflict(merged) => {
+                            warn!("Merging resulted in conflicts");
+                            merged
+                        },
+                    };
+                } else {
+                    info!("Keeping your version, the other changes to it will be overwritten");
+                }
+
+                base = theirs.part.clone();
+            }
+
+            before = now;
+        }
+
+        if has_conflict_markers(&text) && user.ask("The text contains conflict markers. Edit again", true)? {
+            continue;
+        }
+
+        text = strip_error_comments(&text);
+        match validate(&text) {
+            Ok(t)  => {
+                let persisted = if changed {
+                    before.map(|snap| snap.entry)
+                } else {
+                    None
+                };
+
+                return Ok(Edited { value: t, original: original, persisted: persisted })
+            },
+            Err(e) => {
+                if text.trim().is_empty() {
+                    return Err(e).context(err_msg("Aborted editing after empty buffer")).map_err(Error::from);
+                }
+
+                error!("{}", e);
+                info!("Re-opening the editor, empty the buffer to abort");
+                text = add_error_comments(&text, &e, &position);
+            }
+        }
+    }
+}
+
+fn strip_error_comments(text: &str) -> String {
+    text.split('\n')
+        .filter(|line| !line.starts_with(ERROR_COMMENT_PREFIX))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn add_error_comments(text: &str, error: &Error, position: &ErrorPosition) -> String {
+    let comments = error
+        .iter_chain()
+        .flat_map(|cause| {
+            cause.to_string()
+                .lines()
+                .map(|line| format!("{}{}\n", ERROR_COMMENT_PREFIX, line))
+                .collect::<Vec<_>>()
+        })
+        .collect::<String>();
+
+    match *position {
+        ErrorPosition::Top                 => format!("{}{}", comments, text),
+        ErrorPosition::BelowFirstSeparator => match text.find("---\n") {
+            Some(pos) if pos == 0 || text[..pos].ends_with('\n') => {
+                let (head, tail) = text.split_at(pos + 4);
+                format!("{}{}{}", head, comments, tail)
+            },
+            _ => format!("{}{}", comments, text),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    /// Replaces the edited text with `text`, optionally changing the header on disk meanwhile
+    struct FakeUser<'a> {
+        store: &'a Store,
+        id: StoreId,
+        text: &'static str,
+        change_header: bool,
+    }
+
+    impl<'a> User for FakeUser<'a> {
+        fn edit(&self, text: &mut String) -> Result<()> {
+            *text = String::from(self.text);
+
+            if self.change_header {
+                let mut entry = self.store.get(self.id.clone())?.ok_or_else(|| err_msg("Missing entry"))?;
+                entry.get_header_mut()
+                    .as_table_mut()
+                    .ok_or_else(|| err_msg("Header is not a table"))?
+                    .insert(String::from("note"), Value::String(String::from("changed")));
+            }
+
+            Ok(())
+        }
+
+        fn ask(&self, _question: &str, default: bool) -> Result<bool> {
+            Ok(default)
+        }
+    }
+
+    fn edit_content_of(user: &FakeUser) -> Edited<String> {
+        edit_guarded(user,
+                     user.store,
+                     &user.id,
+                     String::from("old"),
+                     ErrorPosition::Top,
+                     |entry| Ok(entry.get_content().clone()),
+                     |buffer| Ok(String::from(buffer)))
+            .unwrap()
+    }
+
+    fn create_entry(store: &Store) -> StoreId {
+        let id = StoreId::new_baseless(PathBuf::from("test")).unwrap();
+        let mut entry = store.create(id.clone()).unwrap();
+        *entry.get_content_mut() = String::from("old");
+        id
+    }
+
+    #[test]
+    fn test_edit_without_concurrent_change() {
+        let store = get_store();
+        let user  = FakeUser { store: &store, id: create_entry(&store), text: "new", change_header: false };
+
+        let edited = edit_content_of(&user);
+        assert_eq!(edited.value, "new");
+        assert!(edited.persisted.is_none());
+    }
+
+    #[test]
+    fn test_header_changed_on_disk_during_content_edit() {
+        let store = get_store();
+        let user  = FakeUser { store: &store, id: create_entry(&store), text: "new", change_header: true };
+
+        let edited = edit_content_of(&user);
+        assert_eq!(edited.value, "new");
+
+        let persisted = edited.persisted.unwrap();
+        assert_eq!(persisted.get_header().get("note"), Some(&Value::String(String::from("changed"))));
+        assert_eq!(persisted.get_content(), "old");
+    }
+
+    #[test]
+    fn test_header_changes_in_memory_survive_concurrent_change() {
+        let store = get_store();
+        let id    = create_entry(&store);
+
+        // The entry as the caller holds it, with an unsaved header change
+        let mut entry = store.get(id.clone()).unwrap().unwrap().clone();
+        entry.get_header_mut()
+            .as_table_mut()
+            .unwrap()
+            .insert(String::from("author"), Value::String(String::from("me")));
+
+        let user   = FakeUser { store: &store, id: id, text: "new", change_header: true };
+        let edited = edit_content_of(&user);
+
+        assert!(take_edited_content(&mut entry, edited));
+        assert_eq!(entry.get_header().get("author"), Some(&Value::String(String::from("me"))));
+        assert_eq!(entry.get_header().get("note"), Some(&Value::String(String::from("changed"))));
+        assert_eq!(entry.get_content(), "new");
+    }
+
+    #[test]
+    fn test_error_comments_top() {
+        let error = err_msg("broken");
+        let text  = add_error_comments("a = 1\n", &error, &ErrorPosition::Top);
+
+        assert_eq!(text, "# imag: broken\na = 1\n");
+        assert_eq!(strip_error_comments(&text), "a = 1\n");
+    }
+
+    #[test]
+    fn test_error_comments_below_separator() {
+        let error = err_msg("broken");
+        let text  = add_error_comments("---\na = 1\n---\ncontent", &error, &ErrorPosition::BelowFirstSeparator);
+
+        assert_eq!(text, "---\n# imag: broken\na = 1\n---\ncontent");
+        assert_eq!(strip_error_comments(&text), "---\na = 1\n---\ncontent");
+    }
+
+    #[test]
+    fn test_strip_error_comments_keeps_other_comments() {
+        let text = "# a comment\n# imag: broken\na = 1";
+        assert_eq!(strip_error_comments(text), "# a comment\na = 1");
+    }
+}
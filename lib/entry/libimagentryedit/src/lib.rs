@@ -37,11 +37,14 @@
     while_true,
 )]
 
+#[macro_use] extern crate log;
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagrt;
 extern crate libimagutil;
+extern crate libimaginteraction;
 extern crate toml;
-extern crate failure;
+#[macro_use] extern crate failure;
 
 pub mod edit;
+pub mod merge;
@@ -0,0 +1,306 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A line based three-way merge, and one for TOML values
+//!
+//! Used to merge the changes of another process into an edited text or header.
+
+use toml::Value;
+
+pub const CONFLICT_MARKER_START : &'static str = "<<<<<<< edited";
+pub const CONFLICT_MARKER_SEP   : &'static str = "=======";
+pub const CONFLICT_MARKER_END   : &'static str = ">>>>>>> changed meanwhile";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Merge {
+    /// The changes were merged without conflicts
+    Clean(String),
+
+    /// The changes conflict, the text contains conflict markers
+    Conflict(String),
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`
+///
+/// Regions which were changed on both sides in different ways are marked with conflict markers,
+/// holding our version first and their version second.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Merge {
+    let base   = lines(base);
+    let ours   = lines(ours);
+    let theirs = lines(theirs);
+
+    let ours_matches   = lcs_matches(&base, &ours);
+    let theirs_matches = lcs_matches(&base, &theirs);
+
+    let mut result   = String::new();
+    let mut conflict = false;
+
+    // Start of the current chunk in base, ours and theirs
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    for i in 0..(base.len() + 1) {
+        // A line is stable if it is unchanged on both sides. The end of the texts is stable, too.
+        let stable = if i == base.len() {
+            Some((ours.len(), theirs.len()))
+        } else {
+            match (ours_matches[i], theirs_matches[i]) {
+                (Some(o), Some(t)) => Some((o, t)),
+                _                  => None,
+            }
+        };
+
+        if let Some((o_end, t_end)) = stable {
+            let base_chunk   = &base[b..i];
+            let ours_chunk   = &ours[o..o_end];
+            let theirs_chunk = &theirs[t..t_end];
+
+            if ours_chunk == base_chunk {
+                push_lines(&mut result, theirs_chunk);
+            } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+                push_lines(&mut result, ours_chunk);
+            } else {
+                conflict = true;
+                push_line(&mut result, CONFLICT_MARKER_START);
+                push_lines(&mut result, ours_chunk);
+                push_line(&mut result, CONFLICT_MARKER_SEP);
+                push_lines(&mut result, theirs_chunk);
+                push_line(&mut result, CONFLICT_MARKER_END);
+            }
+
+            if i < base.len() {
+                result.push_str(base[i]);
+            }
+
+            b = i + 1;
+            o = o_end + 1;
+            t = t_end + 1;
+        }
+    }
+
+    if conflict {
+        Merge::Conflict(result)
+    } else {
+        Merge::Clean(result)
+    }
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs` in a TOML value
+///
+/// Tables are merged key by key. Where both sides changed a value in different ways, our value is
+/// kept.
+pub fn merge_values(base: &Value, ours: &Value, theirs: &Value) -> Value {
+    if ours == base {
+        return theirs.clone()
+    }
+
+    if theirs == base {
+        return ours.clone()
+    }
+
+    match (base, ours, theirs) {
+        (&Value::Table(ref base), &Value::Table(ref ours), &Value::Table(ref theirs)) => {
+            let mut merged = ours.clone();
+
+            for (key, theirs_value) in theirs.iter() {
+                match (base.get(key), ours.get(key)) {
+                    (Some(base_value), Some(ours_value)) => {
+                        let value = merge_values(base_value, ours_value, theirs_value);
+                        let _     = merged.insert(key.clone(), value);
+                    },
+
+                    // Removed by us
+                    (Some(base_value), None) => if base_value != theirs_value {
+                        let _ = merged.insert(key.clone(), theirs_value.clone());
+                    },
+
+                    // Added by them
+                    (None, None) => {
+                        let _ = merged.insert(key.clone(), theirs_value.clone());
+                    },
+
+                    // Added on both sides, ours is kept
+                    (None, Some(ours_value)) => if let Value::Table(_) = *ours_value {
+                        let empty = Value::Table(Default::default());
+                        let value = merge_values(&empty, ours_value, theirs_value);
+                        let _     = merged.insert(key.clone(), value);
+                    },
+                }
+            }
+
+            // Removed by them
+            for (key, base_value) in base.iter() {
+                if !theirs.contains_key(key) && ours.get(key) == Some(base_value) {
+                    let _ = merged.remove(key);
+                }
+            }
+
+            Value::Table(merged)
+        },
+        _ => ours.clone(),
+    }
+}
+
+/// Check whether a text contains conflict markers from `merge3()`
+pub fn has_conflict_markers(text: &str) -> bool {
+    text.lines().any(|line| line == CONFLICT_MARKER_START || line == CONFLICT_MARKER_END)
+}
+
+/// Split a text into lines, keeping the line endings
+fn lines(text: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut rest  = text;
+
+    while let Some(pos) = rest.find('\n') {
+        lines.push(&rest[..pos + 1]);
+        rest = &rest[pos + 1..];
+    }
+
+    if !rest.is_empty() {
+        lines.push(rest);
+    }
+
+    lines
+}
+
+/// For each line of `a`, the index of the matching line in `b` in a longest common subsequence
+fn lcs_matches(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let (n, m)    = (a.len(), b.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                ::std::cmp::max(table[i + 1][j], table[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches    = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+fn push_lines(result: &mut String, lines: &[&str]) {
+    for line in lines {
+        result.push_str(line);
+    }
+}
+
+/// Push a marker line, making sure it starts on a line of its own
+fn push_line(result: &mut String, line: &str) {
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(line);
+    result.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &'static str = "one\ntwo\nthree\nfour\n";
+
+    #[test]
+    fn test_merge_unchanged() {
+        assert_eq!(merge3(BASE, BASE, BASE), Merge::Clean(String::from(BASE)));
+    }
+
+    #[test]
+    fn test_merge_one_side_changed() {
+        let ours = "zero\none\ntwo\nthree\nfour\n";
+        assert_eq!(merge3(BASE, ours, BASE), Merge::Clean(String::from(ours)));
+        assert_eq!(merge3(BASE, BASE, ours), Merge::Clean(String::from(ours)));
+    }
+
+    #[test]
+    fn test_merge_different_regions() {
+        let ours   = "one\nTWO\nthree\nfour\n";
+        let theirs = "one\ntwo\nthree\nfour\nfive\n";
+        assert_eq!(merge3(BASE, ours, theirs), Merge::Clean(String::from("one\nTWO\nthree\nfour\nfive\n")));
+    }
+
+    #[test]
+    fn test_merge_same_change() {
+        let both = "one\n2\nthree\nfour\n";
+        assert_eq!(merge3(BASE, both, both), Merge::Clean(String::from(both)));
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let ours   = "one\nTWO\nthree\nfour\n";
+        let theirs = "one\nzwei\nthree\nfour\n";
+        let merged = merge3(BASE, ours, theirs);
+
+        let expected = format!("one\n{}\nTWO\n{}\nzwei\n{}\nthree\nfour\n",
+                               CONFLICT_MARKER_START,
+                               CONFLICT_MARKER_SEP,
+                               CONFLICT_MARKER_END);
+
+        assert_eq!(merged, Merge::Conflict(expected.clone()));
+        assert!(has_conflict_markers(&expected));
+    }
+
+    #[test]
+    fn test_merge_conflict_without_trailing_newline() {
+        let ours   = "one\ntwo\nthree\nfour\nours";
+        let theirs = "one\ntwo\nthree\nfour\ntheirs";
+        let merged = merge3(BASE, ours, theirs);
+
+        let expected = format!("one\ntwo\nthree\nfour\n{}\nours\n{}\ntheirs\n{}\n",
+                               CONFLICT_MARKER_START,
+                               CONFLICT_MARKER_SEP,
+                               CONFLICT_MARKER_END);
+
+        assert_eq!(merged, Merge::Conflict(expected));
+    }
+
+    #[test]
+    fn test_no_conflict_markers() {
+        assert!(!has_conflict_markers(BASE));
+    }
+
+    fn value(s: &str) -> Value {
+        ::toml::de::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_merge_values() {
+        let base   = value("a = 1\nb = 2\nc = 3\n[t]\nx = 1\ny = 1");
+        let ours   = value("a = 10\nb = 2\nd = 4\n[t]\nx = 10\ny = 1");
+        let theirs = value("a = 11\nb = 20\ne = 5\n[t]\nx = 1\ny = 11");
+
+        let merged = merge_values(&base, &ours, &theirs);
+        assert_eq!(merged, value("a = 10\nb = 20\nd = 4\ne = 5\n[t]\nx = 10\ny = 11"));
+    }
+}
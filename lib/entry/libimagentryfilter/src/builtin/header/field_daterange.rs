@@ -0,0 +1,92 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use toml::Value;
+
+use libimagstore::store::Entry;
+
+use builtin::header::field_path::FieldPath;
+use builtin::header::field_predicate::FieldPredicate;
+use builtin::header::field_predicate::Predicate;
+use filters::failable::filter::FailableFilter;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+/// Parse a date or a date and time
+///
+/// Accepts `%Y-%m-%dT%H:%M:%S` and `%Y-%m-%d`. A date without time is the start of that day.
+pub fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(|d| d.and_hms(0, 0, 0)))
+}
+
+struct DateRangePred {
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+}
+
+impl Predicate for DateRangePred {
+
+    fn evaluate(&self, v: &Value) -> bool {
+        let dt = match *v {
+            Value::String(ref s)   => parse_datetime(s),
+            Value::Datetime(ref d) => parse_datetime(&d.to_string()),
+            _                      => None,
+        };
+
+        match dt {
+            Some(dt) => {
+                self.start.map(|start| start <= dt).unwrap_or(true) &&
+                    self.end.map(|end| dt <= end).unwrap_or(true)
+            },
+            None => false,
+        }
+    }
+
+}
+
+/// Check whether certain header field in a entry is a date (or date and time) in a range
+///
+/// Both ends of the range are inclusive, a missing end means the range is open to that side.
+pub struct FieldDateRange {
+    filter: FieldPredicate<DateRangePred>,
+}
+
+impl FieldDateRange {
+
+    pub fn new(path: FieldPath, start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> FieldDateRange {
+        FieldDateRange {
+            filter: FieldPredicate::new(path, Box::new(DateRangePred { start: start, end: end })),
+        }
+    }
+
+}
+
+impl FailableFilter<Entry> for FieldDateRange {
+    type Error = Error;
+
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        self.filter.filter(e)
+    }
+
+}
@@ -35,6 +35,7 @@
     while_true,
 )]
 
+extern crate chrono;
 extern crate filters;
 extern crate regex;
 extern crate semver;
@@ -44,6 +45,8 @@ extern crate failure;
 
 extern crate libimagstore;
 extern crate libimagentrytag;
+extern crate libimagentrycategory;
+extern crate libimagentrylink;
 extern crate libimagerror;
 
 // core functionality modules of the crate,
@@ -55,4 +58,6 @@ pub mod builtin;
 // these depend on other internal libraries than libimagstore and use the upper core modules for
 // their functionality
 
+pub mod category;
+pub mod link;
 pub mod tags;
@@ -0,0 +1,64 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+
+use filters::failable::filter::FailableFilter;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+/// Check whether an Entry has any internal links
+pub struct HasLinks;
+
+impl FailableFilter<Entry> for HasLinks {
+    type Error = Error;
+
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        e.get_internal_links().map(|mut links| links.next().is_some())
+    }
+
+}
+
+/// Check whether an Entry is linked to a certain other entry
+pub struct IsLinkedTo {
+    id: StoreId,
+}
+
+impl IsLinkedTo {
+
+    pub fn new(id: StoreId) -> IsLinkedTo {
+        IsLinkedTo {
+            id: id,
+        }
+    }
+
+}
+
+impl FailableFilter<Entry> for IsLinkedTo {
+    type Error = Error;
+
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        e.get_internal_links().map(|mut links| links.any(|l| *l.get_store_id() == self.id))
+    }
+
+}
+
@@ -21,6 +21,7 @@ use types::*;
 
 use libimagstore::store::Entry;
 
+use toml::Value;
 use toml_query::read::TomlValueReadExt;
 use toml_query::insert::TomlValueInsertExt;
 use toml_query::delete::TomlValueDeleteExt;
@@ -52,6 +53,12 @@ pub trait GPSEntry {
     ///
     fn remove_coordinates(&mut self) -> Result<Option<Result<Coordinates>>>;
 
+    /// Rewrite coordinates from the old header format, with integer degree, minutes and seconds,
+    /// to decimal degrees
+    ///
+    /// Returns whether the entry had coordinates in the old format.
+    fn migrate_coordinates(&mut self) -> Result<bool>;
+
 }
 
 impl GPSEntry for Entry {
@@ -73,22 +80,17 @@ impl GPSEntry for Entry {
     fn remove_coordinates(&mut self) -> Result<Option<Result<Coordinates>>> {
         let coordinates = self.get_coordinates();
 
-        let patterns = [
-            "gps.coordinates.latitude.degree",
-            "gps.coordinates.latitude.minutes",
-            "gps.coordinates.latitude.seconds",
-            "gps.coordinates.longitude.degree",
-            "gps.coordinates.longitude.minutes",
-            "gps.coordinates.longitude.seconds",
-            "gps.coordinates.latitude",
-            "gps.coordinates.longitude",
-            "gps.coordinates",
-            "gps",
-        ];
-
         let hdr = self.get_header_mut();
-        for pattern in patterns.iter() {
-            let _ = hdr.delete(pattern)
+        let gps_is_empty = match hdr.read_mut("gps").map_err(Error::from).context("Error writing header")? {
+            Some(&mut Value::Table(ref mut gps)) => {
+                let _ = gps.remove("coordinates");
+                gps.is_empty()
+            },
+            _ => false,
+        };
+
+        if gps_is_empty {
+            let _ = hdr.delete("gps")
                 .map_err(Error::from)
                 .context("Error writing header")?;
         }
@@ -99,6 +101,22 @@ impl GPSEntry for Entry {
             Err(e)         => Ok(Some(Err(e))),
         }
     }
+
+    fn migrate_coordinates(&mut self) -> Result<bool> {
+        let is_old_format = match self.get_header().read("gps.coordinates").map_err(Error::from)? {
+            Some(&Value::Table(ref t)) => t.values().any(Value::is_table),
+            _                          => false,
+        };
+
+        if !is_old_format {
+            return Ok(false)
+        }
+
+        match self.get_coordinates()? {
+            Some(c) => self.set_coordinates(c).map(|_| true),
+            None    => Ok(false),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -106,6 +124,10 @@ mod tests {
     use std::path::PathBuf;
     use std::sync::Arc;
 
+    use toml::Value;
+    use toml_query::read::TomlValueReadExt;
+    use toml_query::insert::TomlValueInsertExt;
+
     use libimagstore::store::Store;
 
     use entry::*;
@@ -129,8 +151,10 @@ mod tests {
         let mut entry = store.create(PathBuf::from("test_set_gps")).unwrap();
 
         let coordinates = Coordinates {
-            latitude: GPSValue::new(0, 0, 0),
-            longitude: GPSValue::new(0, 0, 0),
+            latitude: GPSValue::new(0, 0, 0.0),
+            longitude: GPSValue::new(0, 0, 0.0),
+            altitude: None,
+            accuracy: None,
         };
 
         let res = entry.set_coordinates(coordinates);
@@ -147,8 +171,10 @@ mod tests {
         let mut entry = store.create(PathBuf::from("test_setget_gps")).unwrap();
 
         let coordinates = Coordinates {
-            latitude: GPSValue::new(0, 0, 0),
-            longitude: GPSValue::new(0, 0, 0),
+            latitude: GPSValue::new(0, 0, 0.0),
+            longitude: GPSValue::new(0, 0, 0.0),
+            altitude: None,
+            accuracy: None,
         };
 
         let res = entry.set_coordinates(coordinates);
@@ -162,12 +188,54 @@ mod tests {
         assert!(coordinates.is_some());
         let coordinates = coordinates.unwrap();
 
-        assert_eq!(0, coordinates.longitude.degree);
-        assert_eq!(0, coordinates.longitude.minutes);
-        assert_eq!(0, coordinates.longitude.seconds);
-        assert_eq!(0, coordinates.latitude.degree);
-        assert_eq!(0, coordinates.latitude.minutes);
-        assert_eq!(0, coordinates.latitude.seconds);
+        assert_eq!(0, coordinates.longitude.degree());
+        assert_eq!(0, coordinates.longitude.minutes());
+        assert_eq!(0.0, coordinates.longitude.seconds());
+        assert_eq!(0, coordinates.latitude.degree());
+        assert_eq!(0, coordinates.latitude.minutes());
+        assert_eq!(0.0, coordinates.latitude.seconds());
+    }
+
+    #[test]
+    fn test_remove_gps() {
+        setup_logging();
+
+        let store = get_store();
+
+        let mut entry = store.create(PathBuf::from("test_remove_gps")).unwrap();
+
+        let coordinates = Coordinates::new(GPSValue::from_decimal(11.575), GPSValue::from_decimal(48.137))
+            .with_altitude(519.0);
+
+        assert!(entry.set_coordinates(coordinates.clone()).is_ok());
+
+        let removed = entry.remove_coordinates().unwrap().unwrap().unwrap();
+        assert_eq!(coordinates, removed);
+        assert!(entry.get_header().read("gps").unwrap().is_none());
+        assert!(entry.remove_coordinates().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrate_gps() {
+        setup_logging();
+
+        let store = get_store();
+
+        let mut entry = store.create(PathBuf::from("test_migrate_gps")).unwrap();
+
+        let old = "[longitude]\ndegree = 11\nminutes = 34\nseconds = 30\n\
+                   [latitude]\ndegree = -48\nminutes = 30\nseconds = 0\n";
+        let old = old.parse::<Value>().unwrap();
+        let _   = entry.get_header_mut().insert("gps.coordinates", old).unwrap();
+
+        assert!(entry.migrate_coordinates().unwrap());
+        assert!(!entry.migrate_coordinates().unwrap());
+
+        let longitude = entry.get_header().read("gps.coordinates.longitude").unwrap().unwrap();
+        assert!((longitude.as_float().unwrap() - 11.575).abs() < 1e-9);
+
+        let coordinates = entry.get_coordinates().unwrap().unwrap();
+        assert_eq!(-48.5, coordinates.latitude().decimal());
     }
 }
 
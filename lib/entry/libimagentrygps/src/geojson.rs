@@ -0,0 +1,103 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Export of located entries as GeoJSON (RFC 7946), for use in mapping tools
+
+use serde_json::Value as Json;
+
+use query::Located;
+
+/// Build a GeoJSON `FeatureCollection` with one `Point` feature per entry
+///
+/// The id of the entry is the `id` property of the feature, the accuracy (if known) the
+/// `accuracy` property. The altitude is the third position of the point, as GeoJSON specifies.
+pub fn to_geojson(located: &[Located]) -> Json {
+    let features = located.iter().map(feature).collect::<Vec<_>>();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+fn feature(located: &Located) -> Json {
+    let c = &located.coordinates;
+
+    let mut position = vec![c.longitude().decimal(), c.latitude().decimal()];
+    if let Some(altitude) = c.altitude() {
+        position.push(altitude);
+    }
+
+    let mut properties = json!({ "id": located.id.local_display_string() });
+    if let Some(accuracy) = c.accuracy() {
+        properties["accuracy"] = json!(accuracy);
+    }
+
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": position,
+        },
+        "properties": properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use libimagstore::storeid::IntoStoreId;
+
+    use query::Located;
+    use types::Coordinates;
+    use types::GPSValue;
+    use super::to_geojson;
+
+    #[test]
+    fn test_geojson() {
+        let located = vec![
+            Located {
+                id: PathBuf::from("notes/munich").into_storeid().unwrap(),
+                coordinates: Coordinates::new(GPSValue::from_decimal(11.575), GPSValue::from_decimal(48.137))
+                    .with_altitude(519.0)
+                    .with_accuracy(5.0),
+            },
+            Located {
+                id: PathBuf::from("notes/berlin").into_storeid().unwrap(),
+                coordinates: Coordinates::new(GPSValue::from_decimal(13.405), GPSValue::from_decimal(52.52)),
+            },
+        ];
+
+        let json = to_geojson(&located);
+
+        assert_eq!("FeatureCollection", json["type"]);
+        assert_eq!(2, json["features"].as_array().unwrap().len());
+
+        let munich = &json["features"][0];
+        assert_eq!("Point", munich["geometry"]["type"]);
+        assert_eq!(json!([11.575, 48.137, 519.0]), munich["geometry"]["coordinates"]);
+        assert_eq!("notes/munich", munich["properties"]["id"]);
+        assert_eq!(5.0, munich["properties"]["accuracy"]);
+
+        let berlin = &json["features"][1];
+        assert_eq!(json!([13.405, 52.52]), berlin["geometry"]["coordinates"]);
+        assert!(berlin["properties"].get("accuracy").is_none());
+    }
+}
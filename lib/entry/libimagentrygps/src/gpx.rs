@@ -0,0 +1,225 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Reading of GPX tracks
+
+use std::io::Read;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use failure::Fallible as Result;
+use xml::attribute::OwnedAttribute;
+use xml::reader::EventReader;
+use xml::reader::XmlEvent;
+
+use types::Coordinates;
+use types::GPSValue;
+
+/// A point of a track, with the time it was recorded at
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackPoint {
+    pub time:        DateTime<Utc>,
+    pub coordinates: Coordinates,
+}
+
+/// Read all track points (`trkpt`) from a GPX document, ordered by time
+///
+/// Points without a time are skipped, as they cannot be matched to anything. The elevation of a
+/// point is the altitude of its coordinates.
+pub fn read_track<R: Read>(reader: R) -> Result<Vec<TrackPoint>> {
+    let mut points  = vec![];
+    let mut current = None;
+    let mut field   = None;
+    let mut text    = String::new();
+
+    for event in EventReader::new(reader) {
+        match event.map_err(|e| format_err!("Error reading GPX: {}", e))? {
+            XmlEvent::StartElement { ref name, ref attributes, .. } if name.local_name == "trkpt" => {
+                current = Some(PointBuilder::new(attributes)?);
+            },
+            XmlEvent::StartElement { ref name, .. } if current.is_some() => {
+                field = Some(name.local_name.clone());
+                text.clear();
+            },
+            XmlEvent::Characters(ref s) if field.is_some() => text.push_str(s),
+            XmlEvent::EndElement { ref name } if name.local_name == "trkpt" => {
+                if let Some(point) = current.take().and_then(PointBuilder::build) {
+                    points.push(point);
+                }
+            },
+            XmlEvent::EndElement { ref name } => {
+                if let Some(ref mut point) = current {
+                    match name.local_name.as_str() {
+                        "ele"  => point.elevation = Some(parse_number(&text)?),
+                        "time" => point.time = Some(parse_time(&text)?),
+                        _      => {},
+                    }
+                }
+                field = None;
+            },
+            _ => {},
+        }
+    }
+
+    points.sort_by_key(|p| p.time);
+    Ok(points)
+}
+
+/// Find the point closest in time to `time`, if there is one not more than `max_offset` away
+///
+/// `points` must be ordered by time, as `read_track()` returns them.
+pub fn nearest_point<'a>(points: &'a [TrackPoint], time: &DateTime<Utc>, max_offset: Duration)
+    -> Option<&'a TrackPoint>
+{
+    let offset = |p: &TrackPoint| (p.time.signed_duration_since(*time)).num_milliseconds().abs();
+
+    let pos = match points.binary_search_by_key(time, |p| p.time) {
+        Ok(pos)  => return points.get(pos),
+        Err(pos) => pos,
+    };
+
+    let before = if pos > 0 { points.get(pos - 1) } else { None };
+    let after  = points.get(pos);
+
+    let nearest = match (before, after) {
+        (Some(b), Some(a)) => if offset(b) <= offset(a) { b } else { a },
+        (Some(p), None) | (None, Some(p)) => p,
+        (None, None) => return None,
+    };
+
+    if offset(nearest) <= max_offset.num_milliseconds() {
+        Some(nearest)
+    } else {
+        None
+    }
+}
+
+struct PointBuilder {
+    latitude:  GPSValue,
+    longitude: GPSValue,
+    elevation: Option<f64>,
+    time:      Option<DateTime<Utc>>,
+}
+
+impl PointBuilder {
+
+    fn new(attributes: &[OwnedAttribute]) -> Result<PointBuilder> {
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|a| a.name.local_name == key)
+                .map(|a| a.value.as_str())
+                .ok_or_else(|| format_err!("Track point without '{}'", key))
+        };
+
+        Ok(PointBuilder {
+            latitude:  GPSValue::parse_latitude(attribute("lat")?)?,
+            longitude: GPSValue::parse_longitude(attribute("lon")?)?,
+            elevation: None,
+            time:      None,
+        })
+    }
+
+    fn build(self) -> Option<TrackPoint> {
+        let coordinates = Coordinates::new(self.longitude, self.latitude);
+        let coordinates = match self.elevation {
+            Some(elevation) => coordinates.with_altitude(elevation),
+            None            => coordinates,
+        };
+
+        self.time.map(|time| TrackPoint { time: time, coordinates: coordinates })
+    }
+
+}
+
+fn parse_number(s: &str) -> Result<f64> {
+    s.trim().parse::<f64>().map_err(|_| format_err!("Not a number in GPX: '{}'", s))
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s.trim())
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| format_err!("Not a time in GPX: '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>Walk</name>
+    <trkseg>
+      <trkpt lat="48.1372" lon="11.5756">
+        <ele>519.5</ele>
+        <time>2018-10-01T10:00:00Z</time>
+      </trkpt>
+      <trkpt lat="48.1400" lon="11.5800">
+        <time>2018-10-01T12:10:00+02:00</time>
+      </trkpt>
+      <trkpt lat="48.1500" lon="11.5900">
+        <ele>520</ele>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>
+"#;
+
+    #[test]
+    fn test_read_track() {
+        let points = read_track(GPX.as_bytes()).unwrap();
+
+        assert_eq!(2, points.len());
+        assert_eq!(Utc.ymd(2018, 10, 1).and_hms(10, 0, 0), points[0].time);
+        assert_eq!(48.1372, points[0].coordinates.latitude().decimal());
+        assert_eq!(11.5756, points[0].coordinates.longitude().decimal());
+        assert_eq!(Some(519.5), points[0].coordinates.altitude());
+
+        assert_eq!(Utc.ymd(2018, 10, 1).and_hms(10, 10, 0), points[1].time);
+        assert_eq!(None, points[1].coordinates.altitude());
+    }
+
+    #[test]
+    fn test_read_invalid_track() {
+        assert!(read_track("<gpx><trk><trkseg><trkpt lat=\"95\" lon=\"0\"/></trkseg></trk></gpx>".as_bytes()).is_err());
+        assert!(read_track("<gpx><trk>".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_nearest_point() {
+        let points = read_track(GPX.as_bytes()).unwrap();
+        let at     = |h, m| Utc.ymd(2018, 10, 1).and_hms(h, m, 0);
+        let max    = Duration::minutes(15);
+
+        assert_eq!(Some(&points[0]), nearest_point(&points, &at(10, 0), max));
+        assert_eq!(Some(&points[0]), nearest_point(&points, &at(9, 50), max));
+        assert_eq!(Some(&points[0]), nearest_point(&points, &at(10, 4), max));
+        assert_eq!(Some(&points[1]), nearest_point(&points, &at(10, 6), max));
+        assert_eq!(Some(&points[1]), nearest_point(&points, &at(10, 25), max));
+        assert_eq!(None, nearest_point(&points, &at(10, 26), max));
+        assert_eq!(None, nearest_point(&points, &at(9, 44), max));
+        assert_eq!(None, nearest_point(&[], &at(10, 0), max));
+    }
+}
@@ -38,7 +38,12 @@
 extern crate toml;
 extern crate toml_query;
 #[macro_use] extern crate serde_derive;
-extern crate failure;
+#[macro_use] extern crate failure;
+#[macro_use] extern crate log;
+#[macro_use] extern crate serde_json;
+extern crate chrono;
+extern crate xml;
+extern crate exif;
 
 extern crate libimagstore;
 extern crate libimagerror;
@@ -47,5 +52,9 @@ extern crate libimagerror;
 extern crate env_logger;
 
 pub mod entry;
+pub mod geojson;
+pub mod gpx;
+pub mod photo;
+pub mod query;
 pub mod types;
 
@@ -0,0 +1,112 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Reading coordinates from the EXIF data of photos
+
+use std::io::BufRead;
+
+use exif::Reader;
+use exif::Tag;
+use exif::Value;
+use failure::Fallible as Result;
+
+use types::Coordinates;
+use types::GPSValue;
+
+/// Read the GPS coordinates from the EXIF data of an image
+///
+/// Returns `Ok(None)` if the image has no EXIF data or no GPS position in it.
+pub fn read_coordinates<R: BufRead>(reader: &mut R) -> Result<Option<Coordinates>> {
+    let exif = match Reader::new(reader) {
+        Ok(exif)                        => exif,
+        Err(::exif::Error::NotFound(_)) => return Ok(None),
+        Err(e)                          => return Err(format_err!("Error reading EXIF data: {}", e)),
+    };
+
+    let value = |tag| exif.get_field(tag, false).map(|field| &field.value);
+
+    let dms = |tag| match value(tag) {
+        Some(&Value::Rational(ref v)) if v.len() == 3 => Some([v[0].to_f64(), v[1].to_f64(), v[2].to_f64()]),
+        _ => None,
+    };
+
+    let reference = |tag| match value(tag) {
+        Some(&Value::Ascii(ref v)) => v.first().and_then(|s| s.first()).map(|b| *b as char),
+        _ => None,
+    };
+
+    let altitude = match (value(Tag::GPSAltitude), value(Tag::GPSAltitudeRef)) {
+        (Some(&Value::Rational(ref alt)), Some(&Value::Byte(ref r))) if !alt.is_empty() => {
+            // A reference of 1 means below sea level
+            Some(if r.first() == Some(&1) { -alt[0].to_f64() } else { alt[0].to_f64() })
+        },
+        (Some(&Value::Rational(ref alt)), _) if !alt.is_empty() => Some(alt[0].to_f64()),
+        _ => None,
+    };
+
+    match (dms(Tag::GPSLatitude), reference(Tag::GPSLatitudeRef),
+           dms(Tag::GPSLongitude), reference(Tag::GPSLongitudeRef))
+    {
+        (Some(lat), Some(lat_ref), Some(long), Some(long_ref)) => {
+            coordinates_from_exif(lat, lat_ref, long, long_ref, altitude).map(Some)
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Build coordinates from the EXIF representation: degree, minutes and seconds of latitude and
+/// longitude, each with their reference (the hemisphere letter), and the altitude in meters
+pub fn coordinates_from_exif(lat: [f64; 3], lat_ref: char, long: [f64; 3], long_ref: char, altitude: Option<f64>)
+    -> Result<Coordinates>
+{
+    let format = |dms: [f64; 3], r: char| format!("{} {} {}{}", dms[0], dms[1], dms[2], r);
+
+    let coordinates = Coordinates::new(GPSValue::parse_longitude(&format(long, long_ref))?,
+                                       GPSValue::parse_latitude(&format(lat, lat_ref))?);
+
+    Ok(match altitude {
+        Some(altitude) => coordinates.with_altitude(altitude),
+        None           => coordinates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_from_exif() {
+        let c = coordinates_from_exif([48.0, 8.0, 13.2], 'N', [11.0, 34.0, 30.0], 'E', Some(519.0)).unwrap();
+
+        assert!((c.latitude().decimal() - (48.0 + 8.0 / 60.0 + 13.2 / 3600.0)).abs() < 1e-9);
+        assert!((c.longitude().decimal() - 11.575).abs() < 1e-9);
+        assert_eq!(Some(519.0), c.altitude());
+
+        let c = coordinates_from_exif([33.0, 52.0, 0.0], 'S', [151.0, 12.0, 0.0], 'E', None).unwrap();
+        assert!(c.latitude().decimal() < 0.0);
+        assert!(c.longitude().decimal() > 0.0);
+        assert_eq!(None, c.altitude());
+    }
+
+    #[test]
+    fn test_coordinates_from_invalid_exif() {
+        assert!(coordinates_from_exif([48.0, 8.0, 0.0], 'E', [11.0, 0.0, 0.0], 'E', None).is_err());
+        assert!(coordinates_from_exif([48.0, 61.0, 0.0], 'N', [11.0, 0.0, 0.0], 'E', None).is_err());
+    }
+}
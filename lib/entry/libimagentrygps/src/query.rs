@@ -0,0 +1,280 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use entry::GPSEntry;
+use types::Coordinates;
+use types::GPSValue;
+
+/// An entry with its coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct Located {
+    pub id:          StoreId,
+    pub coordinates: Coordinates,
+}
+
+/// An area between two latitudes and two longitudes
+///
+/// If `west` is greater than `east`, the box spans the antimeridian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub south: GPSValue,
+    pub west:  GPSValue,
+    pub north: GPSValue,
+    pub east:  GPSValue,
+}
+
+impl BoundingBox {
+
+    pub fn new(south: GPSValue, west: GPSValue, north: GPSValue, east: GPSValue) -> BoundingBox {
+        BoundingBox { south: south, west: west, north: north, east: east }
+    }
+
+    pub fn contains(&self, c: &Coordinates) -> bool {
+        let lat  = c.latitude().decimal();
+        let long = c.longitude().decimal();
+
+        let in_latitude = self.south.decimal() <= lat && lat <= self.north.decimal();
+        let in_longitude = if self.west.decimal() <= self.east.decimal() {
+            self.west.decimal() <= long && long <= self.east.decimal()
+        } else {
+            self.west.decimal() <= long || long <= self.east.decimal()
+        };
+
+        in_latitude && in_longitude
+    }
+
+}
+
+/// Extension on the Store to query entries by their coordinates
+pub trait GPSStore {
+
+    /// All entries which have coordinates, ordered by id
+    ///
+    /// Entries with malformed coordinates are skipped with a warning.
+    fn located_entries(&self) -> Result<Vec<Located>>;
+
+    /// All entries within `radius` meters of `center`, the closest first, with their distance
+    fn entries_near(&self, center: &Coordinates, radius: f64) -> Result<Vec<(Located, f64)>>;
+
+    /// All entries within the bounding box, ordered by id
+    fn entries_within(&self, bbox: &BoundingBox) -> Result<Vec<Located>>;
+
+}
+
+impl GPSStore for Store {
+
+    fn located_entries(&self) -> Result<Vec<Located>> {
+        let mut located = vec![];
+
+        for id in self.entries()? {
+            let id = id?;
+            let coordinates = match self.get(id.clone())? {
+                Some(entry) => match entry.get_coordinates() {
+                    Ok(coordinates) => coordinates,
+                    Err(e)          => {
+                        warn!("Skipping '{}', malformed coordinates: {}", id, e);
+                        None
+                    },
+                },
+                None => None,
+            };
+
+            if let Some(coordinates) = coordinates {
+                located.push(Located { id: id, coordinates: coordinates });
+            }
+        }
+
+        located.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(located)
+    }
+
+    fn entries_near(&self, center: &Coordinates, radius: f64) -> Result<Vec<(Located, f64)>> {
+        let mut near = self.located_entries()?
+            .into_iter()
+            .map(|l| {
+                let distance = center.distance_to(&l.coordinates);
+                (l, distance)
+            })
+            .filter(|&(_, distance)| distance <= radius)
+            .collect::<Vec<_>>();
+
+        near.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        Ok(near)
+    }
+
+    fn entries_within(&self, bbox: &BoundingBox) -> Result<Vec<Located>> {
+        self.located_entries()
+            .map(|located| located.into_iter().filter(|l| bbox.contains(&l.coordinates)).collect())
+    }
+
+}
+
+/// Parse a distance like `500m`, `5km` or `5 km` into meters
+///
+/// A number without unit is taken as meters.
+pub fn parse_distance(s: &str) -> Result<f64> {
+    let s = s.trim();
+    let (number, factor) = if s.ends_with("km") {
+        (&s[..s.len() - 2], 1000.0)
+    } else if s.ends_with('m') {
+        (&s[..s.len() - 1], 1.0)
+    } else {
+        (s, 1.0)
+    };
+
+    f64::from_str(number.trim())
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .map(|n| n * factor)
+        .ok_or_else(|| format_err!("Not a distance: '{}'", s))
+}
+
+/// Format a distance in meters for humans, as meters below one kilometer
+pub fn format_distance(meters: f64) -> String {
+    if meters < 1000.0 {
+        format!("{:.0} m", meters)
+    } else {
+        format!("{:.2} km", meters / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use entry::GPSEntry;
+    use types::Coordinates;
+    use types::GPSValue;
+    use super::*;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn create(store: &Store, name: &str, coordinates: Option<&str>) {
+        let mut entry = store.create(PathBuf::from(name)).unwrap();
+        if let Some(c) = coordinates {
+            entry.set_coordinates(Coordinates::from_str(c).unwrap()).unwrap();
+        }
+    }
+
+    fn fill(store: &Store) {
+        create(store, "munich", Some("48.137, 11.575"));
+        create(store, "garching", Some("48.249, 11.651"));
+        create(store, "berlin", Some("52.520, 13.405"));
+        create(store, "nowhere", None);
+    }
+
+    fn names(located: &[Located]) -> Vec<String> {
+        located.iter().map(|l| l.id.local_display_string()).collect()
+    }
+
+    #[test]
+    fn test_located_entries() {
+        let store = get_store();
+        fill(&store);
+
+        let located = store.located_entries().unwrap();
+        assert_eq!(vec!["berlin", "garching", "munich"], names(&located));
+    }
+
+    #[test]
+    fn test_located_entries_skips_malformed_coordinates() {
+        use toml::Value;
+        use toml_query::insert::TomlValueInsertExt;
+
+        let store = get_store();
+        fill(&store);
+
+        {
+            let mut entry = store.create(PathBuf::from("broken")).unwrap();
+            let _ = entry.get_header_mut()
+                .insert("gps.coordinates", Value::String(String::from("somewhere")))
+                .unwrap();
+        }
+
+        let located = store.located_entries().unwrap();
+        assert_eq!(vec!["berlin", "garching", "munich"], names(&located));
+    }
+
+    #[test]
+    fn test_entries_near() {
+        let store = get_store();
+        fill(&store);
+
+        let center = Coordinates::from_str("48.14, 11.58").unwrap();
+        let near   = store.entries_near(&center, 20_000.0).unwrap();
+        let found  = near.iter().map(|&(ref l, _)| l.clone()).collect::<Vec<_>>();
+
+        assert_eq!(vec!["munich", "garching"], names(&found));
+        assert!(near[0].1 < near[1].1);
+        assert_eq!(PathBuf::from("munich").into_storeid().unwrap(), near[0].0.id);
+    }
+
+    #[test]
+    fn test_entries_within() {
+        let store = get_store();
+        fill(&store);
+
+        let bavaria = BoundingBox::new(GPSValue::from_decimal(47.0), GPSValue::from_decimal(9.0),
+                                       GPSValue::from_decimal(50.5), GPSValue::from_decimal(13.8));
+        assert_eq!(vec!["garching", "munich"], names(&store.entries_within(&bavaria).unwrap()));
+    }
+
+    #[test]
+    fn test_bounding_box_over_antimeridian() {
+        let bbox = BoundingBox::new(GPSValue::from_decimal(-10.0), GPSValue::from_decimal(170.0),
+                                    GPSValue::from_decimal(10.0), GPSValue::from_decimal(-170.0));
+
+        assert!(bbox.contains(&Coordinates::from_str("0, 175").unwrap()));
+        assert!(bbox.contains(&Coordinates::from_str("0, -175").unwrap()));
+        assert!(!bbox.contains(&Coordinates::from_str("0, 0").unwrap()));
+    }
+
+    #[test]
+    fn test_parse_distance() {
+        assert_eq!(5000.0, parse_distance("5km").unwrap());
+        assert_eq!(5500.0, parse_distance("5.5 km").unwrap());
+        assert_eq!(300.0, parse_distance("300m").unwrap());
+        assert_eq!(300.0, parse_distance("300").unwrap());
+        assert!(parse_distance("-1km").is_err());
+        assert!(parse_distance("far").is_err());
+    }
+
+    #[test]
+    fn test_format_distance() {
+        assert_eq!("300 m", format_distance(300.2));
+        assert_eq!("5.50 km", format_distance(5500.0));
+    }
+}
@@ -21,6 +21,7 @@ use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::str::FromStr;
 
 use toml::Value;
 use failure::Fallible as Result;
@@ -33,76 +34,114 @@ pub trait FromValue : Sized {
     fn from_value(v: &Value) -> Result<Self>;
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// One GPS value (latitude or longitude), in decimal degrees
+///
+/// Negative values are south of the equator or west of the prime meridian.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct GPSValue {
-    pub degree:  i64,
-    pub minutes: i64,
-    pub seconds: i64,
+    pub decimal: f64,
 }
 
 impl GPSValue {
 
-    pub fn new(d: i64, m: i64, s: i64) -> GPSValue {
-        GPSValue {
-            degree:  d,
-            minutes: m,
-            seconds: s
-        }
+    /// Build a value from degree, minutes and seconds
+    ///
+    /// The sign of the degree is the sign of the value, minutes and seconds are always counted
+    /// away from zero. Use `GPSValue::from_decimal()` for negative values with a degree of zero.
+    pub fn new(d: i64, m: i64, s: f64) -> GPSValue {
+        let abs = d.abs() as f64 + (m.abs() as f64) / 60.0 + s.abs() / 3600.0;
+        GPSValue::from_decimal(if d < 0 { -abs } else { abs })
+    }
+
+    pub fn from_decimal(decimal: f64) -> GPSValue {
+        GPSValue { decimal: decimal }
     }
 
+    pub fn decimal(&self) -> f64 {
+        self.decimal
+    }
+
+    /// The (signed) full degrees of the value
     pub fn degree(&self) -> i64 {
-        self.degree
+        let (d, _, _) = self.dms();
+        if self.decimal < 0.0 { -d } else { d }
     }
 
     pub fn minutes(&self) -> i64 {
-        self.minutes
+        self.dms().1
     }
 
-    pub fn seconds(&self) -> i64 {
-        self.seconds
+    pub fn seconds(&self) -> f64 {
+        self.dms().2
     }
 
-}
+    /// Parse a latitude, see `parse_value()` for the formats. Hemisphere letters must be N or S.
+    pub fn parse_latitude(s: &str) -> Result<GPSValue> {
+        parse_checked(s, ('N', 'S'), 90.0, "Latitude")
+    }
 
+    /// Parse a longitude, see `parse_value()` for the formats. Hemisphere letters must be E or W.
+    pub fn parse_longitude(s: &str) -> Result<GPSValue> {
+        parse_checked(s, ('E', 'W'), 180.0, "Longitude")
+    }
+
+    /// Format as degree, minutes and seconds with a hemisphere letter, like `48°8'13.2"N`
+    pub fn to_dms_string(&self, positive: char, negative: char) -> String {
+        let (d, m, s) = self.dms();
+        let hemisphere = if self.decimal < 0.0 { negative } else { positive };
+        format!("{}°{}'{}\"{}", d, m, s, hemisphere)
+    }
+
+    /// Unsigned degree, minutes and seconds, seconds rounded to milliseconds
+    fn dms(&self) -> (i64, i64, f64) {
+        let millis = (self.decimal.abs() * 3_600_000.0).round() as i64;
+        let d      = millis / 3_600_000;
+        let m      = (millis % 3_600_000) / 60_000;
+        let s      = (millis % 60_000) as f64 / 1000.0;
+        (d, m, s)
+    }
+
+}
 
 impl Into<Value> for GPSValue {
 
     fn into(self) -> Value {
-        let mut map = BTreeMap::new();
-        let _ = map.insert("degree".to_owned(),  Value::Integer(self.degree));
-        let _ = map.insert("minutes".to_owned(), Value::Integer(self.minutes));
-        let _ = map.insert("seconds".to_owned(), Value::Integer(self.seconds));
-        Value::Table(map)
+        Value::Float(self.decimal)
     }
 
 }
 
 impl FromValue for GPSValue {
+
+    /// Read a value from the header
+    ///
+    /// Besides the decimal degrees, this reads the old format, a table with integer `degree`,
+    /// `minutes` and `seconds`. See `GPSEntry::migrate_coordinates()` for rewriting it.
     fn from_value(v: &Value) -> Result<Self> {
-        let int_to_appropriate_width = |v: &Value| {
-            v.as_integer()
-             .ok_or_else(|| Error::from(EM::EntryHeaderTypeError))
+        let number = |v: &Value| {
+            match *v {
+                Value::Integer(i) => Ok(i as f64),
+                Value::Float(f)   => Ok(f),
+                _                 => Err(Error::from(EM::EntryHeaderTypeError)),
+            }
         };
 
         match *v {
             Value::Table(ref map) => {
-                Ok(GPSValue::new(
-                    map.get("degree")
-                        .ok_or_else(|| Error::from(err_msg("Degree missing")))
-                        .and_then(&int_to_appropriate_width)?,
-
-                    map
-                        .get("minutes")
-                        .ok_or_else(|| Error::from(err_msg("Minutes missing")))
-                        .and_then(&int_to_appropriate_width)?,
-
-                    map
-                        .get("seconds")
-                        .ok_or_else(|| Error::from(err_msg("Seconds missing")))
-                        .and_then(&int_to_appropriate_width)?
-                ))
-            }
-            _ => Err(Error::from(EM::EntryHeaderTypeError))
+                let get = |what: &'static str, ek: &'static str| {
+                    map.get(what)
+                        .ok_or_else(|| Error::from(err_msg(ek)))
+                        .and_then(&number)
+                };
+
+                let degree  = get("degree", "Degree missing")?;
+                let minutes = get("minutes", "Minutes missing")?;
+                let seconds = get("seconds", "Seconds missing")?;
+                let abs     = degree.abs() + minutes.abs() / 60.0 + seconds.abs() / 3600.0;
+
+                Ok(GPSValue::from_decimal(if degree < 0.0 { -abs } else { abs }))
+            },
+            _ => number(v).map(GPSValue::from_decimal),
         }
     }
 
@@ -110,15 +149,22 @@ impl FromValue for GPSValue {
 
 impl Display for GPSValue {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}° {}\" {}'", self.degree, self.minutes, self.seconds)
+        write!(f, "{}", self.decimal)
     }
 }
 
+/// The mean radius of the earth in meters
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
 /// Data-transfer type for transfering longitude-latitude-pairs
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// Altitude and accuracy are optional and in meters.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Coordinates {
     pub longitude: GPSValue,
     pub latitude:  GPSValue,
+    pub altitude:  Option<f64>,
+    pub accuracy:  Option<f64>,
 }
 
 impl Coordinates {
@@ -126,9 +172,21 @@ impl Coordinates {
         Coordinates {
             longitude: long,
             latitude:  lat,
+            altitude:  None,
+            accuracy:  None,
         }
     }
 
+    pub fn with_altitude(mut self, altitude: f64) -> Coordinates {
+        self.altitude = Some(altitude);
+        self
+    }
+
+    pub fn with_accuracy(mut self, accuracy: f64) -> Coordinates {
+        self.accuracy = Some(accuracy);
+        self
+    }
+
     pub fn longitude(&self) -> &GPSValue {
         &self.longitude
     }
@@ -136,6 +194,34 @@ impl Coordinates {
     pub fn latitude(&self) -> &GPSValue {
         &self.latitude
     }
+
+    pub fn altitude(&self) -> Option<f64> {
+        self.altitude
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        self.accuracy
+    }
+
+    /// The distance to `other` in meters, along the surface of the earth
+    ///
+    /// Uses the haversine formula, the altitude is not taken into account.
+    pub fn distance_to(&self, other: &Coordinates) -> f64 {
+        let lat1  = self.latitude.decimal().to_radians();
+        let lat2  = other.latitude.decimal().to_radians();
+        let dlat  = lat2 - lat1;
+        let dlong = (other.longitude.decimal() - self.longitude.decimal()).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlong / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+
+    /// Format as `48°8'13.2"N, 11°34'30"E`
+    pub fn to_dms_string(&self) -> String {
+        format!("{}, {}",
+                self.latitude.to_dms_string('N', 'S'),
+                self.longitude.to_dms_string('E', 'W'))
+    }
 }
 
 impl Into<Value> for Coordinates {
@@ -144,6 +230,12 @@ impl Into<Value> for Coordinates {
         let mut map = BTreeMap::new();
         let _ = map.insert("longitude".to_owned(), self.longitude.into());
         let _ = map.insert("latitude".to_owned(), self.latitude.into());
+        if let Some(altitude) = self.altitude {
+            let _ = map.insert("altitude".to_owned(), Value::Float(altitude));
+        }
+        if let Some(accuracy) = self.accuracy {
+            let _ = map.insert("accuracy".to_owned(), Value::Float(accuracy));
+        }
         Value::Table(map)
     }
 
@@ -160,19 +252,259 @@ impl FromValue for Coordinates {
                         .and_then(GPSValue::from_value)
                 };
 
-                Ok(Coordinates::new(
-                    get(t, "longitude", "Longitude missing")?,
-                    get(t, "latitude", "Latitude missing")?
-                ))
+                let optional = |what: &'static str| -> Result<Option<f64>> {
+                    match t.get(what) {
+                        None                     => Ok(None),
+                        Some(&Value::Integer(i)) => Ok(Some(i as f64)),
+                        Some(&Value::Float(f))   => Ok(Some(f)),
+                        Some(_)                  => Err(Error::from(EM::EntryHeaderTypeError)),
+                    }
+                };
+
+                Ok(Coordinates {
+                    longitude: get(t, "longitude", "Longitude missing")?,
+                    latitude:  get(t, "latitude", "Latitude missing")?,
+                    altitude:  optional("altitude")?,
+                    accuracy:  optional("accuracy")?,
+                })
             })
     }
 
 }
 
+impl FromStr for Coordinates {
+    type Err = Error;
+
+    /// Parse a pair of values, like `48.137, 11.575` or `48°8'13.2"N 11°34'30"E`
+    ///
+    /// Without hemisphere letters, the latitude comes first. With hemisphere letters on both
+    /// values, the order does not matter.
+    fn from_str(s: &str) -> Result<Self> {
+        let (first, second) = match s.find(',') {
+            Some(pos) => (&s[..pos], &s[pos + 1..]),
+            None      => {
+                let parts = s.split_whitespace().collect::<Vec<_>>();
+                if parts.len() != 2 {
+                    return Err(format_err!("Expected two values separated by a comma: '{}'", s))
+                }
+                (parts[0], parts[1])
+            },
+        };
+
+        let is_longitude = |v: &str| {
+            v.trim().chars().last().map(|c| "EeWw".contains(c)).unwrap_or(false)
+        };
+
+        let (lat, long) = if is_longitude(first) && !is_longitude(second) {
+            (second, first)
+        } else {
+            (first, second)
+        };
+
+        Ok(Coordinates::new(GPSValue::parse_longitude(long)?, GPSValue::parse_latitude(lat)?))
+    }
+}
+
 impl Display for Coordinates {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "longitude = {}\nlatitude = {}", self.longitude, self.latitude)
+        write!(f, "longitude = {}\nlatitude = {}", self.longitude, self.latitude)?;
+        if let Some(altitude) = self.altitude {
+            write!(f, "\naltitude = {}", altitude)?;
+        }
+        if let Some(accuracy) = self.accuracy {
+            write!(f, "\naccuracy = {}", accuracy)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse one GPS value into decimal degrees and the hemisphere letter, if there is one
+///
+/// Understood formats:
+///
+/// * Decimal degrees: `48.137` or `-11.575`
+/// * Degree, minutes and (fractional) seconds: `48°8'13.2"` or `48 8 13.2`
+/// * The old `imag-gps` format `<degree>.<minutes>.<seconds>`: `48.8.13`
+///
+/// All of them can be followed by a hemisphere letter (N, S, E or W). S and W negate the value.
+pub fn parse_value(s: &str) -> Result<(f64, Option<char>)> {
+    let s = s.trim();
+    let (s, hemisphere) = match s.chars().last() {
+        Some(c) if "NSEWnsew".contains(c) => (s[..s.len() - 1].trim(), c.to_uppercase().next()),
+        _                                 => (s, None),
+    };
+
+    let invalid = || format_err!("Not a GPS value: '{}'", s);
+
+    let is_decimal = s.chars().all(|c| c.is_digit(10) || c == '.' || c == '-' || c == '+')
+        && s.matches('.').count() <= 1;
+
+    let value = if is_decimal {
+        f64::from_str(s).map_err(|_| invalid())?
+    } else {
+        let parts = if s.contains(|c: char| c.is_whitespace() || !c.is_ascii()) || s.contains('\'') {
+            s.split(|c: char| !(c.is_digit(10) || c == '.' || c == '-' || c == '+'))
+                .filter(|p| !p.is_empty())
+                .collect::<Vec<_>>()
+        } else {
+            // <degree>.<minutes>.<seconds>
+            s.split('.').collect::<Vec<_>>()
+        };
+
+        if parts.is_empty() || parts.len() > 3 {
+            return Err(invalid())
+        }
+
+        let numbers = parts
+            .iter()
+            .map(|p| f64::from_str(p).map_err(|_| invalid()))
+            .collect::<Result<Vec<f64>>>()?;
+
+        let degree  = numbers[0];
+        let minutes = numbers.get(1).cloned().unwrap_or(0.0);
+        let seconds = numbers.get(2).cloned().unwrap_or(0.0);
+
+        if minutes < 0.0 || minutes >= 60.0 || seconds < 0.0 || seconds >= 60.0 {
+            return Err(format_err!("Minutes and seconds must be between 0 and 60: '{}'", s))
+        }
+
+        let abs = degree.abs() + minutes / 60.0 + seconds / 3600.0;
+        if parts[0].starts_with('-') { -abs } else { abs }
+    };
+
+    match hemisphere {
+        Some('S') | Some('W') if value < 0.0 => {
+            Err(format_err!("Negative value with hemisphere letter: '{}'", s))
+        },
+        Some('S') | Some('W') => Ok((-value, hemisphere)),
+        _                     => Ok((value, hemisphere)),
     }
 }
 
+fn parse_checked(s: &str, letters: (char, char), max: f64, what: &str) -> Result<GPSValue> {
+    let (value, hemisphere) = parse_value(s)?;
+
+    if let Some(h) = hemisphere {
+        if h != letters.0 && h != letters.1 {
+            return Err(format_err!("{} must have the hemisphere {} or {}: '{}'", what, letters.0, letters.1, s))
+        }
+    }
+
+    if !value.is_finite() || value.abs() > max {
+        return Err(format_err!("{} must be between -{} and {}: '{}'", what, max, max, s))
+    }
+
+    Ok(GPSValue::from_decimal(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use toml::Value;
+
+    use super::*;
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 1e-9, "{} != {}", expected, actual);
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_close(48.137, parse_value("48.137").unwrap().0);
+        assert_close(-11.575, parse_value(" -11.575 ").unwrap().0);
+        assert_close(48.0, parse_value("48").unwrap().0);
+    }
+
+    #[test]
+    fn test_parse_dms() {
+        let (value, hemisphere) = parse_value("48°8'13.2\"N").unwrap();
+        assert_close(48.0 + 8.0 / 60.0 + 13.2 / 3600.0, value);
+        assert_eq!(Some('N'), hemisphere);
+
+        assert_close(48.0 + 8.0 / 60.0 + 13.2 / 3600.0, parse_value("48 8 13.2").unwrap().0);
+        assert_close(-(11.0 + 30.0 / 60.0), parse_value("11°30'W").unwrap().0);
+        assert_close(-(11.0 + 30.0 / 60.0), parse_value("-11°30'").unwrap().0);
+    }
+
+    #[test]
+    fn test_parse_legacy_format() {
+        assert_close(48.0 + 8.0 / 60.0 + 13.0 / 3600.0, parse_value("48.8.13").unwrap().0);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_value("").is_err());
+        assert!(parse_value("foo").is_err());
+        assert!(parse_value("48°61'").is_err());
+        assert!(parse_value("-48S").is_err());
+        assert!(GPSValue::parse_latitude("91").is_err());
+        assert!(GPSValue::parse_latitude("48E").is_err());
+        assert!(GPSValue::parse_longitude("181").is_err());
+        assert!(GPSValue::parse_longitude("11N").is_err());
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let c = Coordinates::from_str("48.137, 11.575").unwrap();
+        assert_close(48.137, c.latitude().decimal());
+        assert_close(11.575, c.longitude().decimal());
+
+        let c = Coordinates::from_str("11°34'30\"E 48°8'13.2\"N").unwrap();
+        assert_close(48.0 + 8.0 / 60.0 + 13.2 / 3600.0, c.latitude().decimal());
+        assert_close(11.575, c.longitude().decimal());
+
+        assert!(Coordinates::from_str("48.137").is_err());
+        assert!(Coordinates::from_str("100, 11").is_err());
+    }
 
+    #[test]
+    fn test_dms_roundtrip() {
+        let v = GPSValue::new(48, 8, 13.2);
+        assert_eq!(48, v.degree());
+        assert_eq!(8, v.minutes());
+        assert_close(13.2, v.seconds());
+        assert_eq!("48°8'13.2\"N", v.to_dms_string('N', 'S'));
+
+        let v = GPSValue::from_decimal(-0.5);
+        assert_eq!(0, v.degree());
+        assert_eq!(30, v.minutes());
+        assert_eq!("0°30'0\"W", v.to_dms_string('E', 'W'));
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let c = Coordinates::new(GPSValue::from_decimal(11.575), GPSValue::from_decimal(48.137))
+            .with_altitude(519.0)
+            .with_accuracy(5.0);
+
+        let value: Value = c.clone().into();
+        assert_eq!(c, Coordinates::from_value(&value).unwrap());
+
+        let c = Coordinates::new(GPSValue::from_decimal(11.575), GPSValue::from_decimal(48.137));
+        let value: Value = c.clone().into();
+        assert!(value.as_table().unwrap().get("altitude").is_none());
+        assert_eq!(c, Coordinates::from_value(&value).unwrap());
+    }
+
+    #[test]
+    fn test_distance() {
+        let munich = Coordinates::from_str("48.137, 11.575").unwrap();
+        let berlin = Coordinates::from_str("52.520, 13.405").unwrap();
+
+        assert_close(0.0, munich.distance_to(&munich));
+        assert!((munich.distance_to(&berlin) - 504_000.0).abs() < 2_000.0);
+        assert_close(munich.distance_to(&berlin), berlin.distance_to(&munich));
+
+        // across the antimeridian
+        let west = Coordinates::from_str("0, 179.9").unwrap();
+        let east = Coordinates::from_str("0, -179.9").unwrap();
+        assert!(west.distance_to(&east) < 23_000.0);
+    }
+
+    #[test]
+    fn test_read_old_format() {
+        let value = Value::from_str("degree = -48\nminutes = 30\nseconds = 0").unwrap();
+        assert_close(-48.5, GPSValue::from_value(&value).unwrap().decimal());
+    }
+}
@@ -53,4 +53,6 @@ extern crate env_logger;
 pub mod html;
 pub mod link;
 pub mod processor;
+pub mod rewrite;
+pub mod wikilink;
 
@@ -23,9 +23,13 @@ use failure::Fallible as Result;
 use failure::ResultExt;
 use failure::Error;
 use link::extract_links;
+use wikilink::extract_wiki_links;
+use wikilink::StoreResolver;
+use wikilink::WikiLinkResolver;
 
 use libimagentrylink::external::ExternalLinker;
 use libimagentrylink::internal::InternalLinker;
+use libimagentryref::config::Config as RefConfig;
 use libimagentryref::refstore::RefStore;
 use libimagentryref::refstore::UniqueRefPathGenerator;
 use libimagentryref::generators::sha512::Sha512;
@@ -66,6 +70,8 @@ impl UniqueRefPathGenerator for UniqueMarkdownRefGenerator {
 ///  * Process external links (from store entry to URL)
 ///  * Process refs (from store entry to files on the filesystem and outside of the store)
 ///  (default: false)
+///  * Process wiki links (`[[Entry Name]]`, from store entry to store entry), resolved by a
+///    `WikiLinkResolver` (default: false)
 ///
 ///  # Note
 ///
@@ -75,7 +81,10 @@ pub struct LinkProcessor {
     process_internal_links: bool,
     create_internal_targets: bool,
     process_external_links: bool,
-    process_refs: bool
+    process_refs: bool,
+    ref_config: RefConfig,
+    process_wiki_links: bool,
+    wiki_link_resolver: Box<WikiLinkResolver>,
 }
 
 impl LinkProcessor {
@@ -115,6 +124,31 @@ impl LinkProcessor {
         self
     }
 
+    /// Set the base pathes refs are created relative to
+    ///
+    /// Defaults to no base pathes, so refs store the absolute path of the referenced file.
+    pub fn ref_config(mut self, config: RefConfig) -> Self {
+        self.ref_config = config;
+        self
+    }
+
+    /// Switch wiki link processing on/off
+    ///
+    /// Wiki links are `[[target]]` or `[[target|label]]`. Targets which cannot be resolved are
+    /// created if `LinkProcessor::create_internal_targets()` is set, else `process()` fails.
+    pub fn process_wiki_links(mut self, b: bool) -> Self {
+        self.process_wiki_links = b;
+        self
+    }
+
+    /// Set the resolver for the targets of wiki links
+    ///
+    /// Defaults to `StoreResolver`.
+    pub fn wiki_link_resolver(mut self, resolver: Box<WikiLinkResolver>) -> Self {
+        self.wiki_link_resolver = resolver;
+        self
+    }
+
     /// Process an Entry for its links
     ///
     /// # Warning
@@ -161,7 +195,8 @@ impl LinkProcessor {
                     trace!("URL.host_str() = {:?}", url.host_str());
                     let path = url.host_str().unwrap_or_else(|| url.path());
                     let path = PathBuf::from(path);
-                    let mut target = store.create_ref::<UniqueMarkdownRefGenerator, PathBuf>(path)?;
+                    let mut target = store
+                        .create_ref::<UniqueMarkdownRefGenerator, PathBuf>(path, &self.ref_config)?;
 
                     entry.add_internal_link(&mut target)?;
                 },
@@ -174,6 +209,29 @@ impl LinkProcessor {
             }
         }
 
+        if self.process_wiki_links {
+            let from = entry.get_location().clone();
+
+            for link in extract_wiki_links(entry.get_content()) {
+                trace!("Processing {:?}", link);
+                let id = match self.wiki_link_resolver.resolve(&link, &from, store)? {
+                    Some(id) => id,
+                    None => if self.create_internal_targets {
+                        self.wiki_link_resolver.new_target(&link, &from)?
+                    } else {
+                        return Err(format_err!("Cannot resolve wiki link target: {}", link.target))
+                    },
+                };
+
+                if id == from {
+                    continue
+                }
+
+                let mut target = store.retrieve(id)?;
+                let _ = entry.add_internal_link(&mut target)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -222,7 +280,10 @@ impl Default for LinkProcessor {
             process_internal_links: true,
             create_internal_targets: false,
             process_external_links: true,
-            process_refs: false
+            process_refs: false,
+            ref_config: RefConfig::default(),
+            process_wiki_links: false,
+            wiki_link_resolver: Box::new(StoreResolver::new()),
         }
     }
 }
@@ -565,5 +626,31 @@ mod tests {
         assert_eq!(2, store.entries().unwrap().collect::<Vec<_>>().len());
     }
 
-}
+    #[test]
+    fn test_process_wiki_links() {
+        setup_logging();
+        let store = get_store();
 
+        let mut base = store.create(PathBuf::from("wiki/test-3.1")).unwrap();
+        *base.get_content_mut() = format!("Links to [[test-3.2|another page]] and [[test-3.1]]");
+
+        let processor = LinkProcessor::default()
+            .process_internal_links(false)
+            .create_internal_targets(false)
+            .process_external_links(false)
+            .process_refs(false)
+            .process_wiki_links(true);
+
+        let result = processor.process(&mut base, &store);
+        assert!(result.is_err(), "Should be Err(_), target does not exist: {:?}", result);
+
+        let processor = processor.create_internal_targets(true);
+        let result = processor.process(&mut base, &store);
+        assert!(result.is_ok(), "Should be Ok(()): {:?}", result);
+
+        let base_links : Vec<_> = base.get_internal_links().unwrap().collect();
+        assert_eq!(1, base_links.len());
+        assert_eq!("wiki/test-3.2", base_links[0].to_str().unwrap());
+    }
+
+}
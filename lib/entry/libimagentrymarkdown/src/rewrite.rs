@@ -0,0 +1,294 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Rewriting of links in content, for example when the linked entry was moved
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use link::extract_links;
+use wikilink::extract_wiki_links;
+
+/// Rewrite the links to the entry `old` in `text`, the content of the entry `from`, so that they
+/// point to `new`
+///
+/// Markdown links (inline links and reference definitions, as found by `extract_links()`), wiki
+/// links to ids (`[[collection/id]]`) and wiki links to names (`[[id]]`) which refer to `old` from
+/// the collection of `from` are rewritten. Name links become links to the name of `new` if `new`
+/// is in the collection of `from`, else links to its id.
+///
+/// Returns `None` if `text` contains no link to `old`.
+pub fn rewrite_links(text: &str, from: &StoreId, old: &StoreId, new: &StoreId) -> Option<String> {
+    rewrite(text, from, old, new, false)
+}
+
+/// Rewrite the links to `old` in the content of the entries `ids` so that they point to `new`
+///
+/// Pass the entries which are linked with the moved entry, as an entry which refers to another one
+/// in its content is linked with it. Wiki links to the name of `old` are also rewritten if the name
+/// was resolved in the whole store, that is, if there is no entry with that name in the collection
+/// of the linking entry.
+///
+/// Returns the ids of the entries which were changed.
+pub fn rewrite_links_in<I>(store: &Store, ids: I, old: &StoreId, new: &StoreId) -> Result<Vec<StoreId>>
+    where I: IntoIterator<Item = StoreId>
+{
+    let mut touched = vec![];
+    for id in ids {
+        let mut entry = match store.get(id.clone())? {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        let names_anywhere = match name_of(old) {
+            Some(name) => !store.exists(sibling(&id, name))?,
+            None       => false,
+        };
+
+        if let Some(content) = rewrite(entry.get_content(), &id, old, new, names_anywhere) {
+            debug!("Rewriting links to {} in {}", old, id);
+            *entry.get_content_mut() = content;
+            let _ = store.update(&mut entry)?;
+            touched.push(id);
+        }
+    }
+
+    Ok(touched)
+}
+
+fn rewrite(text: &str, from: &StoreId, old: &StoreId, new: &StoreId, names_anywhere: bool)
+    -> Option<String>
+{
+    let new_target = new.local().display().to_string();
+
+    let markdown_targets = extract_links(text)
+        .into_iter()
+        .map(|link| link.link)
+        .filter(|target| points_to(target, old))
+        .collect::<BTreeSet<_>>();
+
+    let wiki_targets = extract_wiki_links(text)
+        .into_iter()
+        .filter(|link| link.is_id())
+        .map(|link| link.target)
+        .filter(|target| points_to(target, old))
+        .collect::<BTreeSet<_>>();
+
+    // A name refers to `old` if `old` is in the collection of `from`, or if the name was resolved
+    // in the whole store
+    let old_name = name_of(old)
+        .filter(|_| names_anywhere || collection_of(from) == collection_of(old));
+
+    let name_target = old_name.and_then(|name| {
+        let has_name_link = extract_wiki_links(text)
+            .into_iter()
+            .any(|link| !link.is_id() && link.target == name);
+
+        if has_name_link {
+            Some(name)
+        } else {
+            None
+        }
+    });
+
+    if markdown_targets.is_empty() && wiki_targets.is_empty() && name_target.is_none() {
+        return None
+    }
+
+    let mut result = String::from(text);
+    for target in markdown_targets.iter() {
+        result = replace_target(&result, "](", target, &new_target);
+        result = replace_target(&result, "]: ", target, &new_target);
+    }
+
+    for target in wiki_targets.iter() {
+        result = replace_wiki_target(&result, target, &new_target);
+    }
+
+    if let Some(name) = name_target {
+        let replacement = match name_of(new) {
+            Some(new_name) if collection_of(from) == collection_of(new) => String::from(new_name),
+            _ => new_target.clone(),
+        };
+
+        result = replace_wiki_target(&result, name, &replacement);
+    }
+
+    if result == text {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+fn points_to(target: &str, id: &StoreId) -> bool {
+    StoreId::new_baseless(PathBuf::from(target))
+        .map(|target| target == *id)
+        .unwrap_or(false)
+}
+
+/// The name of an entry, which is used for wiki links to it
+fn name_of(id: &StoreId) -> Option<&str> {
+    id.local().file_name().and_then(|n| n.to_str())
+}
+
+fn collection_of(id: &StoreId) -> Option<&Path> {
+    id.local().parent()
+}
+
+/// The local path of the entry `name` in the collection of `id`
+fn sibling(id: &StoreId, name: &str) -> PathBuf {
+    collection_of(id)
+        .map(|p| p.join(name))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Replace `target` with `replacement` where it directly follows `prefix` and ends at a link
+/// delimiter, so that other targets which start with `target` are not touched
+fn replace_target(text: &str, prefix: &str, target: &str, replacement: &str) -> String {
+    replace_target_with(text, prefix, target, replacement, |rest| {
+        rest.chars()
+            .next()
+            .map(|c| c == ')' || c == ']' || c == '|' || c.is_whitespace())
+            .unwrap_or(true)
+    })
+}
+
+/// Replace the target of the wiki links `[[target]]` and `[[target|label]]` with `replacement`
+fn replace_wiki_target(text: &str, target: &str, replacement: &str) -> String {
+    replace_target_with(text, "[[", target, replacement, |rest| {
+        rest.chars()
+            .find(|c| !c.is_whitespace())
+            .map(|c| c == ']' || c == '|')
+            .unwrap_or(false)
+    })
+}
+
+fn replace_target_with<F>(text: &str, prefix: &str, target: &str, replacement: &str, is_complete: F)
+    -> String
+    where F: Fn(&str) -> bool
+{
+    let pattern    = format!("{}{}", prefix, target);
+    let mut result = String::with_capacity(text.len());
+    let mut rest   = text;
+
+    while let Some(pos) = rest.find(&pattern) {
+        let end = pos + pattern.len();
+
+        result.push_str(&rest[..pos]);
+        result.push_str(prefix);
+        result.push_str(if is_complete(&rest[end..]) { replacement } else { target });
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let text = concat!(
+            "A [link](notes/old) and [another one](notes/old \"title\"), ",
+            "[[notes/old|wiki]] and [[notes/old]], [ref][1].\n",
+            "Not [this](notes/older) or [[old]].\n",
+            "\n",
+            "[1]: notes/old\n",
+        );
+
+        let expected = concat!(
+            "A [link](notes/new) and [another one](notes/new \"title\"), ",
+            "[[notes/new|wiki]] and [[notes/new]], [ref][1].\n",
+            "Not [this](notes/older) or [[old]].\n",
+            "\n",
+            "[1]: notes/new\n",
+        );
+
+        let result = rewrite_links(text, &id("diary/today"), &id("notes/old"), &id("notes/new"));
+        assert_eq!(result, Some(String::from(expected)));
+    }
+
+    #[test]
+    fn test_rewrite_links_without_links() {
+        let text = "No [link](notes/other) to the entry, just notes/old";
+        assert_eq!(rewrite_links(text, &id("notes/a"), &id("notes/old"), &id("notes/new")), None);
+    }
+
+    #[test]
+    fn test_rewrite_name_links() {
+        let text = "[[old]], [[old|label]], [[old ]], not [[old thing]] or [[older]]";
+        let from = id("notes/a");
+
+        let renamed = rewrite_links(text, &from, &id("notes/old"), &id("notes/new"));
+        assert_eq!(renamed, Some(String::from("[[new]], [[new|label]], [[new ]], not [[old thing]] or [[older]]")));
+
+        let moved = rewrite_links(text, &from, &id("notes/old"), &id("archive/old"));
+        assert_eq!(moved, Some(String::from(
+            "[[archive/old]], [[archive/old|label]], [[archive/old ]], not [[old thing]] or [[older]]"
+        )));
+
+        // From another collection, the name does not refer to `notes/old`
+        assert_eq!(rewrite_links(text, &id("diary/a"), &id("notes/old"), &id("notes/new")), None);
+    }
+
+    #[test]
+    fn test_rewrite_links_in() {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let fs    = InMemoryFileAbstraction::default();
+        let store = Store::new_with_backend(PathBuf::from("/"), &None, Arc::new(fs)).unwrap();
+
+        {
+            let mut linking = store.create(PathBuf::from("notes/linking")).unwrap();
+            *linking.get_content_mut() = String::from("See [there](notes/new)");
+        }
+        {
+            let mut by_name = store.create(PathBuf::from("diary/by-name")).unwrap();
+            *by_name.get_content_mut() = String::from("See [[new]]");
+        }
+        {
+            let mut not_linked = store.create(PathBuf::from("notes/other")).unwrap();
+            *not_linked.get_content_mut() = String::from("See [there](notes/new)");
+        }
+
+        let ids     = vec![id("notes/linking"), id("diary/by-name")];
+        let touched = rewrite_links_in(&store, ids, &id("notes/new"), &id("notes/newer")).unwrap();
+        assert_eq!(touched, vec![id("notes/linking"), id("diary/by-name")]);
+
+        let content = |s: &str| String::from(store.get(PathBuf::from(s)).unwrap().unwrap().get_content());
+        assert_eq!(content("notes/linking"), "See [there](notes/newer)");
+        assert_eq!(content("diary/by-name"), "See [[notes/newer]]");
+        assert_eq!(content("notes/other"), "See [there](notes/new)");
+    }
+}
@@ -0,0 +1,424 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Wiki-style links
+//!
+//! `[[Entry Name]]` links to an entry by its name, `[[collection/id|label]]` links to an entry by
+//! its id and shows a label instead of the target. How a target is resolved to an id is decided by
+//! a `WikiLinkResolver`.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use failure::Fallible as Result;
+
+use libimagentrylink::internal::InternalLinker;
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use link::extract_links;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    pub target: String,
+    pub label: Option<String>,
+}
+
+impl WikiLink {
+
+    /// The text which is shown for the link
+    pub fn title(&self) -> &str {
+        self.label.as_ref().unwrap_or(&self.target)
+    }
+
+    /// Whether the target is an id (`collection/id`) rather than a name
+    pub fn is_id(&self) -> bool {
+        self.target.contains('/')
+    }
+
+}
+
+/// Extract all `[[target]]` and `[[target|label]]` links from a text
+///
+/// Links cannot span multiple lines.
+pub fn extract_wiki_links(text: &str) -> Vec<WikiLink> {
+    let mut links = vec![];
+    let _ = split_wiki_links(text, |_, link| {
+        if let Some(link) = link {
+            links.push(link);
+        }
+        Ok(())
+    });
+
+    links
+}
+
+/// Replace all wiki links in a text with the string returned by `f` for the link
+pub fn replace_wiki_links<F>(text: &str, mut f: F) -> Result<String>
+    where F: FnMut(&WikiLink) -> Result<String>
+{
+    let mut result = String::with_capacity(text.len());
+    let _ = split_wiki_links(text, |before, link| {
+        result.push_str(before);
+        if let Some(link) = link {
+            result.push_str(&f(&link)?);
+        }
+        Ok(())
+    })?;
+
+    Ok(result)
+}
+
+/// Call `f` with the text before each wiki link and the link, and finally with the remaining text
+fn split_wiki_links<'a, F>(text: &'a str, mut f: F) -> Result<()>
+    where F: FnMut(&'a str, Option<WikiLink>) -> Result<()>
+{
+    let mut rest = text;
+    let mut done = 0; // bytes of `rest` which are plain text already
+
+    while let Some(start) = rest[done..].find("[[").map(|s| s + done) {
+        let after = &rest[start + 2..];
+        let end   = match after.find("]]") {
+            Some(end) => end,
+            None      => break,
+        };
+
+        let inner = &after[..end];
+        if inner.contains('\n') || inner.contains("[[") {
+            // Not a link, continue after the opening brackets
+            done = start + 2;
+            continue;
+        }
+
+        match parse_link(inner) {
+            Some(link) => {
+                let _ = f(&rest[..start], Some(link))?;
+                rest = &after[end + 2..];
+                done = 0;
+            },
+            None => done = start + 2 + end + 2,
+        }
+    }
+
+    f(rest, None)
+}
+
+fn parse_link(inner: &str) -> Option<WikiLink> {
+    let mut parts = inner.splitn(2, '|');
+    let target    = parts.next().map(str::trim).unwrap_or("");
+    let label     = parts.next().map(str::trim).filter(|l| !l.is_empty()).map(String::from);
+
+    if target.is_empty() {
+        None
+    } else {
+        Some(WikiLink {
+            target: String::from(target),
+            label: label,
+        })
+    }
+}
+
+/// Resolves the targets of wiki links to store ids
+pub trait WikiLinkResolver {
+
+    /// Resolve the target of a link in the entry `from` to the id of an existing entry
+    ///
+    /// Returns `None` if no entry matches.
+    fn resolve(&self, link: &WikiLink, from: &StoreId, store: &Store) -> Result<Option<StoreId>>;
+
+    /// The id of the entry which should be created for a link which cannot be resolved
+    fn new_target(&self, link: &WikiLink, from: &StoreId) -> Result<StoreId>;
+
+}
+
+/// The default resolver
+///
+/// * Targets with a `/` are ids relative to the store
+/// * Other targets are names of entries in the same collection as the linking entry. If there is
+///   none, the name is looked up in the whole store, where it must be unique.
+///
+/// For looking up names in the whole store, the resolver builds an index of the names of all
+/// entries once, on the first such lookup. Entries created or deleted afterwards are not in the
+/// index, so a resolver should only be used for one batch of links and only with one store.
+#[derive(Debug, Default)]
+pub struct StoreResolver {
+    names: RefCell<Option<BTreeMap<OsString, Vec<StoreId>>>>,
+}
+
+impl StoreResolver {
+
+    pub fn new() -> Self {
+        StoreResolver::default()
+    }
+
+    /// Get the ids of all entries with the file name `name`
+    fn ids_named(&self, name: &str, store: &Store) -> Result<Vec<StoreId>> {
+        if self.names.borrow().is_none() {
+            debug!("Building the index of entry names");
+            let mut names : BTreeMap<OsString, Vec<StoreId>> = BTreeMap::new();
+            for id in store.entries()? {
+                let id = id?;
+                if let Some(n) = id.local().file_name().map(OsString::from) {
+                    names.entry(n).or_insert_with(Vec::new).push(id);
+                }
+            }
+
+            *self.names.borrow_mut() = Some(names);
+        }
+
+        Ok(self.names
+            .borrow()
+            .as_ref()
+            .and_then(|names| names.get(&OsString::from(name)))
+            .cloned()
+            .unwrap_or_else(Vec::new))
+    }
+
+}
+
+impl WikiLinkResolver for StoreResolver {
+
+    fn resolve(&self, link: &WikiLink, from: &StoreId, store: &Store) -> Result<Option<StoreId>> {
+        let candidate = self.new_target(link, from)?;
+        if store.exists(candidate.clone())? {
+            return Ok(Some(candidate))
+        }
+
+        if link.is_id() {
+            return Ok(None)
+        }
+
+        let mut found = self.ids_named(&link.target, store)?;
+
+        match found.len() {
+            0 => Ok(None),
+            1 => Ok(found.pop()),
+            _ => Err(format_err!("Link target '{}' is ambiguous: {}", link.target, {
+                found.iter().map(|id| format!("{}", id)).collect::<Vec<_>>().join(", ")
+            })),
+        }
+    }
+
+    fn new_target(&self, link: &WikiLink, from: &StoreId) -> Result<StoreId> {
+        let path = if link.is_id() {
+            PathBuf::from(&link.target)
+        } else {
+            from.local()
+                .parent()
+                .map(|p| p.join(&link.target))
+                .unwrap_or_else(|| PathBuf::from(&link.target))
+        };
+
+        StoreId::new_baseless(path)
+    }
+
+}
+
+/// Link `entry` with the existing targets of the wiki links in its content
+///
+/// Unlike `LinkProcessor::process()`, this never creates entries and does not fail for links
+/// which cannot be resolved, but returns them.
+pub fn link_wiki_links(entry: &mut Entry, store: &Store, resolver: &WikiLinkResolver)
+    -> Result<Vec<WikiLink>>
+{
+    let from       = entry.get_location().clone();
+    let mut missed = vec![];
+
+    for link in extract_wiki_links(entry.get_content()) {
+        let id = match resolver.resolve(&link, &from, store)? {
+            Some(id) => id,
+            None     => {
+                missed.push(link);
+                continue
+            },
+        };
+
+        if id == from {
+            continue
+        }
+
+        if let Some(mut target) = store.get(id)? {
+            let _ = entry.add_internal_link(&mut target)?;
+        }
+    }
+
+    Ok(missed)
+}
+
+/// Find the entries which reference `entry` in their content
+///
+/// Internal links are bidirectional, so the candidates are the entries `entry` is linked with. A
+/// candidate is a backlink if its content contains a markdown link or a wiki link (resolved with
+/// `resolver`) to `entry`.
+pub fn backlinks(entry: &Entry, store: &Store, resolver: &WikiLinkResolver) -> Result<Vec<StoreId>> {
+    let id = entry.get_location();
+    let mut backlinks = vec![];
+
+    for link in entry.get_internal_links()? {
+        let candidate_id = link.get_store_id().clone();
+        if candidate_id == *id {
+            continue
+        }
+
+        let candidate = match store.get(candidate_id.clone())? {
+            Some(candidate) => candidate,
+            None            => continue,
+        };
+
+        let markdown_link = extract_links(candidate.get_content())
+            .into_iter()
+            .filter_map(|l| StoreId::new_baseless(PathBuf::from(&l.link)).ok())
+            .any(|target| target == *id);
+
+        let is_backlink = markdown_link || {
+            let mut found = false;
+            for link in extract_wiki_links(candidate.get_content()) {
+                if resolver.resolve(&link, &candidate_id, store)?.map(|t| t == *id).unwrap_or(false) {
+                    found = true;
+                    break;
+                }
+            }
+            found
+        };
+
+        if is_backlink {
+            backlinks.push(candidate_id);
+        }
+    }
+
+    Ok(backlinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn get_store() -> Store {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let fs = InMemoryFileAbstraction::default();
+        Store::new_with_backend(PathBuf::from("/"), &None, Arc::new(fs)).unwrap()
+    }
+
+    fn link(target: &str, label: Option<&str>) -> WikiLink {
+        WikiLink {
+            target: String::from(target),
+            label: label.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_extract_wiki_links() {
+        let text = "See [[Entry Name]] and [[notes/foo | the foo note]], not [[]] or [[broken\nlink]].";
+        assert_eq!(extract_wiki_links(text), vec![
+            link("Entry Name", None),
+            link("notes/foo", Some("the foo note")),
+        ]);
+    }
+
+    #[test]
+    fn test_extract_nested_brackets() {
+        assert_eq!(extract_wiki_links("[[a [[b]]"), vec![link("b", None)]);
+        assert_eq!(extract_wiki_links("[[a|]] [[unclosed"), vec![link("a", None)]);
+    }
+
+    #[test]
+    fn test_replace_wiki_links() {
+        let text   = "A [[b]] c [[d|e]] [[]] [[x\n]] f";
+        let result = replace_wiki_links(text, |l| Ok(format!("<{}>", l.title()))).unwrap();
+        assert_eq!(result, "A <b> c <e> [[]] [[x\n]] f");
+    }
+
+    #[test]
+    fn test_store_resolver() {
+        let store = get_store();
+        let _ = store.create(PathBuf::from("wiki/default/Other Page")).unwrap();
+        let _ = store.create(PathBuf::from("notes/unique")).unwrap();
+        let _ = store.create(PathBuf::from("notes/twice")).unwrap();
+        let _ = store.create(PathBuf::from("diary/twice")).unwrap();
+
+        let from     = StoreId::new_baseless(PathBuf::from("wiki/default/Page")).unwrap();
+        let resolver = StoreResolver::new();
+        let resolve  = |target: &str| resolver.resolve(&link(target, None), &from, &store);
+
+        let id = |s: &str| Some(StoreId::new_baseless(PathBuf::from(s)).unwrap());
+
+        assert_eq!(resolve("Other Page").unwrap(), id("wiki/default/Other Page"));
+        assert_eq!(resolve("notes/unique").unwrap(), id("notes/unique"));
+        assert_eq!(resolve("unique").unwrap(), id("notes/unique"));
+        assert_eq!(resolve("missing").unwrap(), None);
+        assert_eq!(resolve("notes/missing").unwrap(), None);
+        assert!(resolve("twice").is_err());
+    }
+
+    #[test]
+    fn test_link_wiki_links() {
+        let store = get_store();
+        let _ = store.create(PathBuf::from("notes/target")).unwrap();
+
+        let mut source = store.create(PathBuf::from("notes/source")).unwrap();
+        *source.get_content_mut() = String::from("See [[target]], [[source]] and [[missing]]");
+
+        let missed = link_wiki_links(&mut source, &store, &StoreResolver::new()).unwrap();
+        assert_eq!(missed, vec![link("missing", None)]);
+        assert!(!store.exists(StoreId::new_baseless(PathBuf::from("notes/missing")).unwrap()).unwrap());
+
+        let linked = source
+            .get_internal_links()
+            .unwrap()
+            .map(|l| l.get_store_id().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(linked, vec![StoreId::new_baseless(PathBuf::from("notes/target")).unwrap()]);
+    }
+
+    #[test]
+    fn test_backlinks() {
+        use processor::LinkProcessor;
+
+        let store = get_store();
+        let _ = store.create(PathBuf::from("notes/target")).unwrap();
+        let _ = store.create(PathBuf::from("notes/only-linked")).unwrap();
+
+        {
+            let mut source = store.create(PathBuf::from("notes/source")).unwrap();
+            *source.get_content_mut() = String::from("See [[target]]");
+            let processor = LinkProcessor::default()
+                .process_internal_links(false)
+                .process_external_links(false)
+                .process_refs(false)
+                .process_wiki_links(true);
+            processor.process(&mut source, &store).unwrap();
+        }
+
+        let mut target = store.get(PathBuf::from("notes/target")).unwrap().unwrap();
+        {
+            let mut only_linked = store.get(PathBuf::from("notes/only-linked")).unwrap().unwrap();
+            let _ = target.add_internal_link(&mut only_linked).unwrap();
+        }
+
+        let backlinks = backlinks(&target, &store, &StoreResolver::new()).unwrap();
+        let expected  = StoreId::new_baseless(PathBuf::from("notes/source")).unwrap();
+        assert_eq!(backlinks, vec![expected]);
+    }
+}
@@ -0,0 +1,96 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Helpers for binaries which filter entries with a query passed on the commandline
+
+use clap::Arg;
+use clap::ArgMatches;
+use filters::failable::filter::FailableFilter;
+use failure::Fallible as Result;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use query::Query;
+use saved::SavedSearchStore;
+use saved::is_saved_search;
+
+pub const WHERE_ARG_NAME: &'static str = "where";
+pub const SAVED_ARG_NAME: &'static str = "saved";
+
+/// The `--where <query>` argument
+pub fn where_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(WHERE_ARG_NAME)
+        .long("where")
+        .takes_value(true)
+        .required(false)
+        .multiple(false)
+        .value_name("QUERY")
+        .help("Only use entries matching this query. See the documentation of libimagentryquery for the query language.")
+}
+
+/// The `--saved <name>` argument, to use a saved search instead of a query
+pub fn saved_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(SAVED_ARG_NAME)
+        .long("saved")
+        .takes_value(true)
+        .required(false)
+        .multiple(false)
+        .value_name("NAME")
+        .conflicts_with(WHERE_ARG_NAME)
+        .help("Only use entries matching this saved search")
+}
+
+/// Compile the query passed with `--where` or the saved search passed with `--saved`, if any
+pub fn query_from_cli(matches: &ArgMatches, store: &Store) -> Result<Option<Query>> {
+    if let Some(name) = matches.value_of(SAVED_ARG_NAME) {
+        let q = store
+            .get_saved_search(name)?
+            .ok_or_else(|| format_err!("No saved search named '{}'", name))?;
+
+        return Query::compile(&q, store).map(Some)
+    }
+
+    match matches.value_of(WHERE_ARG_NAME) {
+        Some(q) => Query::compile(q, store).map(Some),
+        None    => Ok(None),
+    }
+}
+
+/// Run the saved search passed with `--saved`, if any
+///
+/// For commands which take ids, so the saved search can be used as source for the ids.
+pub fn ids_from_saved_search(matches: &ArgMatches, store: &Store) -> Result<Option<Vec<StoreId>>> {
+    match matches.value_of(SAVED_ARG_NAME) {
+        Some(name) => store.run_saved_search(name).map(Some),
+        None       => Ok(None),
+    }
+}
+
+/// Check whether an entry matches the query. Every entry matches if there is no query.
+///
+/// Saved searches never match a query, as with `SavedSearchStore::run_saved_search()`.
+pub fn entry_matches(query: Option<&Query>, entry: &Entry) -> Result<bool> {
+    match query {
+        Some(_) if is_saved_search(entry.get_location()) => Ok(false),
+        Some(q) => q.filter(entry),
+        None    => Ok(true),
+    }
+}
@@ -0,0 +1,705 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Parser for the query language
+
+use std::str;
+use std::str::FromStr;
+
+use nom::digit;
+use nom::multispace;
+use failure::Fallible as Result;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Unary {
+    Not
+}
+
+named!(unary_operator<Unary>, alt_complete!(
+    tag!("not") => { |_| { trace!("Unary::Not"); Unary::Not }}
+));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    OpMatches, // ~
+    OpIs,
+    OpIn,
+    OpEq,
+    OpNeq,
+    OpGte, // >=
+    OpLte, // <=
+    OpLt,  // <
+    OpGt,  // >
+}
+
+named!(compare_op<CompareOp>, alt_complete!(
+    tag!("~"  ) => { |_| { trace!("CompareOp::OpMatches"); CompareOp::OpMatches }} |
+    tag!("is" ) => { |_| { trace!("CompareOp::OpIs");  CompareOp::OpIs }} |
+    tag!("in" ) => { |_| { trace!("CompareOp::OpIn");  CompareOp::OpIn }} |
+    tag!("==" ) => { |_| { trace!("CompareOp::OpEq");  CompareOp::OpEq }} |
+    tag!("eq" ) => { |_| { trace!("CompareOp::OpEq");  CompareOp::OpEq }} |
+    tag!("!=" ) => { |_| { trace!("CompareOp::OpNeq"); CompareOp::OpNeq }} |
+    tag!("neq") => { |_| { trace!("CompareOp::OpNeq"); CompareOp::OpNeq }} |
+    tag!(">=" ) => { |_| { trace!("CompareOp::OpGte"); CompareOp::OpGte }} |
+    tag!("<=" ) => { |_| { trace!("CompareOp::OpLte"); CompareOp::OpLte }} |
+    tag!("<"  ) => { |_| { trace!("CompareOp::OpLt");  CompareOp::OpLt }}  |
+    tag!(">"  ) => { |_| { trace!("CompareOp::OpGt");  CompareOp::OpGt }}
+));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operator {
+    Or,
+    And,
+    Xor,
+}
+
+named!(operator<Operator>, alt_complete!(
+    tag!("or")      => { |_| { trace!("Operator::Or");  Operator::Or  }} |
+    tag!("and")     => { |_| { trace!("Operator::And"); Operator::And }} |
+    tag!("xor")     => { |_| { trace!("Operator::Xor"); Operator::Xor }}
+));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Function {
+    Length,
+    Keys,
+    Values,
+}
+
+named!(function<Function>, alt_complete!(
+    tag!("length") => { |_| { trace!("Function::Length"); Function::Length }} |
+    tag!("keys")   => { |_| { trace!("Function::Keys");   Function::Keys   }} |
+    tag!("values") => { |_| { trace!("Function::Values"); Function::Values }}
+));
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+}
+
+named!(int64<i64>, map_res!(map_res!(digit, str::from_utf8), i64::from_str));
+
+named!(signed_digits<(Option<&[u8]>, i64)>,
+    pair!(opt!(alt!(tag_s!("+") | tag_s!("-"))), int64)
+);
+named!(integer<i64>, do_parse!(tpl: signed_digits >> ({
+    let v = match tpl.0 {
+        Some(b"-") => -tpl.1,
+        _          => tpl.1,
+    };
+    trace!("integer = {:?}", v);
+    v
+})));
+
+named!(boolean<bool>, alt_complete!(
+    tag!("false") => { |_| { trace!("'false'"); false }} |
+    tag!("true")  => { |_| { trace!("'true'"); true }}
+));
+
+/// A string literal in double quotes
+///
+/// `\"` is a double quote inside the string. Any other backslash is kept as it is, together with
+/// the character following it, so regexes like `"\d+"` or `"\\"` do not need extra escaping.
+fn string(input: &[u8]) -> ::nom::IResult<&[u8], String> {
+    use nom::IResult;
+    use nom::ErrorKind;
+
+    if input.first() != Some(&b'"') {
+        return IResult::Error(error_position!(ErrorKind::Char, input));
+    }
+
+    let mut text    = Vec::new();
+    let mut escaped = false;
+
+    for (i, &c) in input.iter().enumerate().skip(1) {
+        if escaped {
+            if c != b'"' {
+                text.push(b'\\');
+            }
+            text.push(c);
+            escaped = false;
+        } else if c == b'\\' {
+            escaped = true;
+        } else if c == b'"' {
+            return match String::from_utf8(text) {
+                Ok(s) => {
+                    trace!("Parsed string: {:?}", s);
+                    IResult::Done(&input[i + 1..], s)
+                },
+                Err(_) => IResult::Error(error_position!(ErrorKind::MapRes, input)),
+            };
+        } else {
+            text.push(c);
+        }
+    }
+
+    IResult::Incomplete(::nom::Needed::Unknown)
+}
+
+named!(val<Value>, alt_complete!(
+    do_parse!(b: boolean >> ({
+        let v = Value::Boolean(b);
+        trace!("Value = {:?}", v);
+        v
+    })) |
+    do_parse!(number: integer >> ({
+        let v = Value::Integer(number);
+        trace!("Value = {:?}", v);
+        v
+    })) |
+    do_parse!(text: string >> ({
+        let v = Value::String(text);
+        trace!("Value = {:?}", v);
+        v
+    }))
+));
+
+named!(list_of_val<Vec<Value>>, do_parse!(
+        char!('[') >>
+        list: many0!(
+            do_parse!(
+                list: terminated!(val, opt!(char!(','))) >>
+                opt!(multispace) >>
+                (list)
+        )) >>
+        char!(']') >> (list)
+));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompareValue {
+    Value(Value),
+    Values(Vec<Value>)
+}
+
+named!(compare_value<CompareValue>, alt_complete!(
+    do_parse!(list: list_of_val >> (CompareValue::Values(list))) |
+    do_parse!(val: val >> (CompareValue::Value(val)))
+));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selector {
+    Direct(String),
+    Function(Function, String)
+}
+
+impl Selector {
+    pub fn selector_str(&self) -> &String {
+        match *self {
+            Selector::Direct(ref s)      => s,
+            Selector::Function(_, ref s) => s,
+        }
+    }
+    pub fn function(&self) -> Option<&Function> {
+        match *self {
+            Selector::Direct(_)          => None,
+            Selector::Function(ref f, _) => Some(f),
+        }
+    }
+}
+
+named!(selector_str<String>, do_parse!(
+    selector: take_till!(|s: u8| s == b' ') >> (String::from_utf8(selector.to_vec()).unwrap())
+));
+
+named!(bracketed,
+    delimited!(
+        tag!("("),
+        take_until!(")"),
+        tag!(")")
+    )
+);
+
+named!(selector<Selector>, alt_complete!(
+    do_parse!(fun: function >> sel: bracketed >> ({
+        let sel = Selector::Function(fun, String::from_utf8(sel.to_vec()).unwrap());
+        trace!("Building Selector object: {:?}", sel);
+        sel
+    })) |
+    do_parse!(sel: selector_str >> ({
+        let sel = Selector::Direct(sel);
+        trace!("Building Selector object: {:?}", sel);
+        sel
+    }))
+));
+
+/// A predicate on a header field
+#[derive(Debug, PartialEq, Eq)]
+pub struct Filter {
+    pub unary            : Option<Unary>,
+    pub selector         : Selector,
+    pub compare_operator : CompareOp,
+    pub compare_value    : CompareValue,
+}
+
+named!(filter<Filter>, do_parse!(
+        unary: opt!(terminated!(unary_operator, multispace)) >>
+        selec: selector >> opt!(multispace) >>
+        comop: compare_op >> opt!(multispace) >>
+        cmval: compare_value >>
+        ({
+            let f = Filter {
+                unary:              unary,
+                selector:           selec,
+                compare_operator:   comop,
+                compare_value:      cmval,
+            };
+
+            trace!("Building Filter object: {:?}", f);
+            f
+        })
+));
+
+/// A single element of a query, which can be combined with other terms by an `Operator`
+#[derive(Debug, PartialEq, Eq)]
+pub enum Term {
+    /// A predicate on a header field
+    Filter(Filter),
+
+    /// The entry has a tag
+    Tag(String),
+
+    /// The entry is in a category (or one of its subcategories)
+    Category(String),
+
+    /// The entry has any links (`None`) or is linked to the entry with this id
+    Linked(Option<String>),
+
+    /// The header field exists
+    Exists(String),
+
+    /// The content matches the regex
+    Content(String),
+
+    /// The header field is a date in the range. Both ends are inclusive and optional.
+    DateRange(String, Option<String>, Option<String>),
+
+    Not(Box<Term>),
+
+    /// A query in parentheses
+    Group(Box<Query>),
+}
+
+named!(quoted_argument<String>, do_parse!(
+        opt!(multispace) >> char!('(') >> opt!(multispace) >>
+        arg: string >>
+        opt!(multispace) >> char!(')') >>
+        (arg)
+));
+
+named!(tag_term<Term>, do_parse!(
+        tag!("tag") >> name: quoted_argument >> (Term::Tag(name))
+));
+
+named!(category_term<Term>, do_parse!(
+        tag!("category") >> name: quoted_argument >> (Term::Category(name))
+));
+
+named!(linked_term<Term>, do_parse!(
+        tag!("linked") >>
+        opt!(multispace) >> char!('(') >> opt!(multispace) >>
+        id: opt!(string) >>
+        opt!(multispace) >> char!(')') >>
+        (Term::Linked(id))
+));
+
+named!(exists_term<Term>, do_parse!(
+        tag!("exists") >> sel: bracketed >>
+        (Term::Exists(String::from_utf8_lossy(sel).trim().to_string()))
+));
+
+named!(content_term<Term>, do_parse!(
+        tag!("content") >> opt!(multispace) >> char!('~') >> opt!(multispace) >>
+        regex: string >>
+        (Term::Content(regex))
+));
+
+named!(date_range_term<Term>, do_parse!(
+        selec: selector_str >> multispace >>
+        tag!("in") >> opt!(multispace) >>
+        start: opt!(string) >> opt!(multispace) >>
+        tag!("..") >> opt!(multispace) >>
+        end: opt!(string) >>
+        (Term::DateRange(selec, start, end))
+));
+
+named!(term<Term>, alt_complete!(
+    do_parse!(tag!("not") >> multispace >> t: term >> (Term::Not(Box::new(t)))) |
+    do_parse!(
+        char!('(') >> opt!(multispace) >>
+        q: parse_query >>
+        opt!(multispace) >> char!(')') >>
+        (Term::Group(Box::new(q)))
+    ) |
+    tag_term |
+    category_term |
+    linked_term |
+    exists_term |
+    content_term |
+    date_range_term |
+    do_parse!(f: filter >> (Term::Filter(f)))
+));
+
+/// A parsed query
+///
+/// The terms are combined from left to right, there is no precedence between the operators.
+/// Parentheses can be used for grouping.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Query {
+    pub filter: Term,
+    pub next_filters: Vec<(Operator, Term)>,
+}
+
+named!(parse_query<Query>, do_parse!(
+        filt: term >>
+        next: many0!(do_parse!(opt!(multispace) >> op: operator >> multispace >> fil: term >> ((op, fil)))) >>
+        ({
+            let q = Query {
+                filter:       filt,
+                next_filters: next,
+            };
+
+            trace!("Building Query object: {:?}", q);
+
+            q
+        })
+));
+
+/// Parse a query
+pub fn parse(s: &str) -> Result<Query> {
+    match parse_query(s.as_bytes()) {
+        ::nom::IResult::Done(rest, q) => {
+            let rest = String::from_utf8_lossy(rest);
+            if rest.trim().is_empty() {
+                Ok(q)
+            } else {
+                Err(format_err!("Cannot parse query, unexpected input: '{}'", rest))
+            }
+        },
+        ::nom::IResult::Error(e) => {
+            Err(format_err!("Error during parsing the query: {:?}", e))
+        },
+        ::nom::IResult::Incomplete(needed) => {
+            Err(format_err!("Error during parsing the query, incomplete input. Needed: {:?}", needed))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_logging() {
+        let _ = ::env_logger::try_init();
+    }
+
+    #[test]
+    fn test_unary() {
+        assert_eq!(unary_operator(b"not").unwrap().1, Unary::Not);
+    }
+
+    #[test]
+    fn test_compare_op() {
+        assert_eq!(compare_op(b"is"  ).unwrap().1, CompareOp::OpIs );
+        assert_eq!(compare_op(b"in"  ).unwrap().1, CompareOp::OpIn );
+        assert_eq!(compare_op(b"=="  ).unwrap().1, CompareOp::OpEq );
+        assert_eq!(compare_op(b"eq"  ).unwrap().1, CompareOp::OpEq );
+        assert_eq!(compare_op(b"!="  ).unwrap().1, CompareOp::OpNeq);
+        assert_eq!(compare_op(b"neq" ).unwrap().1, CompareOp::OpNeq);
+        assert_eq!(compare_op(b">="  ).unwrap().1, CompareOp::OpGte);
+        assert_eq!(compare_op(b"<="  ).unwrap().1, CompareOp::OpLte);
+        assert_eq!(compare_op(b"<"   ).unwrap().1, CompareOp::OpLt );
+        assert_eq!(compare_op(b">"   ).unwrap().1, CompareOp::OpGt );
+    }
+
+    #[test]
+    fn test_operator() {
+        assert_eq!(operator(b"or").unwrap().1, Operator::Or  );
+        assert_eq!(operator(b"and").unwrap().1, Operator::And );
+        assert_eq!(operator(b"xor").unwrap().1, Operator::Xor );
+    }
+
+    #[test]
+    fn test_function() {
+        assert_eq!(function(b"length").unwrap().1, Function::Length );
+        assert_eq!(function(b"keys").unwrap().1, Function::Keys );
+        assert_eq!(function(b"values").unwrap().1, Function::Values );
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer(b"12").unwrap().1, 12);
+        assert_eq!(integer(b"11292").unwrap().1, 11292);
+        assert_eq!(integer(b"-12").unwrap().1, -12);
+        assert_eq!(integer(b"10101012").unwrap().1, 10101012);
+    }
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string(b"\"foo\"").unwrap().1, "foo");
+        assert_eq!(string(b"\"\"").unwrap().1, "");
+    }
+
+    #[test]
+    fn test_string_escaped_quote() {
+        assert_eq!(string(br#""say \"hi\"""#).unwrap().1, r#"say "hi""#);
+        assert_eq!(string(br#""a\"b" rest"#).unwrap(), (&b" rest"[..], String::from(r#"a"b"#)));
+    }
+
+    #[test]
+    fn test_string_keeps_other_backslashes() {
+        assert_eq!(string(br#""\d+""#).unwrap().1, r#"\d+"#);
+        assert_eq!(string(br#""foo\\""#).unwrap().1, r#"foo\\"#);
+    }
+
+    #[test]
+    fn test_query_with_escaped_quote() {
+        let q = parse(r#"content ~ "say \"hi\"" and tag("work")"#).unwrap();
+        assert_eq!(Term::Content(String::from(r#"say "hi""#)), q.filter);
+    }
+
+    #[test]
+    fn test_boolean() {
+        assert_eq!(boolean(b"false").unwrap().1, false);
+        assert_eq!(boolean(b"true").unwrap().1, true);
+    }
+
+    #[test]
+    fn test_val() {
+        assert_eq!(val(b"false").unwrap().1, Value::Boolean(false));
+        assert_eq!(val(b"true").unwrap().1, Value::Boolean(true));
+        assert_eq!(val(b"12").unwrap().1, Value::Integer(12));
+        assert_eq!(val(b"\"foobar\"").unwrap().1, Value::String(String::from("foobar")));
+    }
+
+    #[test]
+    fn test_list_of_val() {
+        setup_logging();
+        {
+            let list = list_of_val(b"[]");
+            debug!("list: {:?}", list);
+            let vals = list.unwrap().1;
+            assert_eq!(vals, vec![]);
+        }
+
+        {
+            let list = list_of_val(b"[1]");
+            debug!("list: {:?}", list);
+            let vals = list.unwrap().1;
+            assert_eq!(vals, vec![Value::Integer(1)]);
+        }
+
+        {
+            let list = list_of_val(b"[12,13]");
+            debug!("list: {:?}", list);
+            let vals = list.unwrap().1;
+            assert_eq!(vals, vec![Value::Integer(12), Value::Integer(13)]);
+        }
+
+        {
+            let vals = list_of_val(b"[\"foobar\",\"bazbaz\"]").unwrap().1;
+            let expt = vec![Value::String(String::from("foobar")),
+                            Value::String(String::from("bazbaz"))];
+            assert_eq!(vals, expt)
+        }
+
+        {
+            let vals = list_of_val(b"[\"1\", \"2\"]").unwrap().1;
+            let expt = vec![Value::String(String::from("1")),
+                            Value::String(String::from("2"))];
+            assert_eq!(vals, expt)
+        }
+    }
+
+    #[test]
+    fn test_selector_str() {
+        assert_eq!(selector_str(b"foo.bar baz").unwrap().1, String::from("foo.bar"));
+    }
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(selector(b"foo.bar baz").unwrap().1, Selector::Direct(String::from("foo.bar")));
+
+        assert_eq!(function(b"length").unwrap().1, Function::Length);
+
+        let exp = Selector::Function(Function::Length, String::from("foo.bar"));
+        assert_eq!(selector(b"length(foo.bar)").unwrap().1, exp);
+    }
+
+    #[test]
+    fn test_filter_1() {
+        setup_logging();
+        trace!("Setup worked");
+        let text = b"imag.header == 1";
+        let exp = Filter {
+            unary: None,
+            selector: Selector::Direct(String::from("imag.header")),
+            compare_operator: CompareOp::OpEq,
+            compare_value: CompareValue::Value(Value::Integer(1))
+        };
+
+        let parsed = filter(text);
+        trace!("{:?}", parsed);
+        assert_eq!(parsed.unwrap().1, exp);
+    }
+
+    #[test]
+    fn test_filter_2() {
+        setup_logging();
+        trace!("Setup worked");
+        let text = b"imag.header in [1, 2]";
+        let exp = Filter {
+            unary: None,
+            selector: Selector::Direct(String::from("imag.header")),
+            compare_operator: CompareOp::OpIn,
+            compare_value: CompareValue::Values(vec![Value::Integer(1), Value::Integer(2)])
+        };
+
+        let parsed = filter(text);
+        trace!("{:?}", parsed);
+        assert_eq!(parsed.unwrap().1, exp);
+    }
+
+    #[test]
+    fn test_filter_3() {
+        setup_logging();
+        trace!("Setup worked");
+        let text = b"length(imag.header) > 12";
+        let exp = Filter {
+            unary: None,
+            selector: Selector::Function(Function::Length, String::from("imag.header")),
+            compare_operator: CompareOp::OpGt,
+            compare_value: CompareValue::Value(Value::Integer(12))
+        };
+
+        let parsed = filter(text);
+        trace!("{:?}", parsed);
+        assert_eq!(parsed.unwrap().1, exp);
+    }
+
+    #[test]
+    fn test_query_1() {
+        setup_logging();
+        trace!("Setup worked");
+        let text = b"length(imag.header) > 12 or imag.foobar <= 125";
+
+        let filter_1 = Filter {
+            unary: None,
+            selector: Selector::Function(Function::Length, String::from("imag.header")),
+            compare_operator: CompareOp::OpGt,
+            compare_value: CompareValue::Value(Value::Integer(12))
+        };
+
+        let filter_2 = Filter {
+            unary: None,
+            selector: Selector::Direct(String::from("imag.foobar")),
+            compare_operator: CompareOp::OpLte,
+            compare_value: CompareValue::Value(Value::Integer(125))
+        };
+
+        let operator = Operator::Or;
+
+        let query = Query {
+            filter: Term::Filter(filter_1),
+            next_filters: vec![(operator, Term::Filter(filter_2))],
+        };
+
+        let parsed = parse_query(text);
+        trace!("{:?}", parsed);
+        assert_eq!(parsed.unwrap().1, query);
+    }
+
+    #[test]
+    fn test_query_2() {
+        setup_logging();
+        trace!("Setup worked");
+        let text = r#"imag.version == "0.7.0""#;
+
+        let filter_1 = Filter {
+            unary: None,
+            selector: Selector::Direct(String::from("imag.version")),
+            compare_operator: CompareOp::OpEq,
+            compare_value: CompareValue::Value(Value::String(String::from("0.7.0")))
+        };
+
+        let query = Query {
+            filter: Term::Filter(filter_1),
+            next_filters: vec![],
+        };
+
+        let parsed = parse_query(text.as_bytes());
+        trace!("{:?}", parsed);
+        assert_eq!(parsed.unwrap().1, query);
+    }
+
+    #[test]
+    fn test_compare_op_matches() {
+        assert_eq!(compare_op(b"~").unwrap().1, CompareOp::OpMatches);
+    }
+
+    #[test]
+    fn test_terms() {
+        setup_logging();
+        assert_eq!(term(br#"tag("work")"#).unwrap().1, Term::Tag(String::from("work")));
+        assert_eq!(term(br#"category( "project" )"#).unwrap().1,
+                   Term::Category(String::from("project")));
+        assert_eq!(term(b"linked()").unwrap().1, Term::Linked(None));
+        assert_eq!(term(br#"linked("notes/foo")"#).unwrap().1,
+                   Term::Linked(Some(String::from("notes/foo"))));
+        assert_eq!(term(b"exists(imag.version)").unwrap().1,
+                   Term::Exists(String::from("imag.version")));
+        assert_eq!(term(br#"content ~ "fo+""#).unwrap().1, Term::Content(String::from("fo+")));
+    }
+
+    #[test]
+    fn test_date_range() {
+        setup_logging();
+        let exp = Term::DateRange(String::from("datetime.value"),
+                                  Some(String::from("2018-01-01")),
+                                  Some(String::from("2018-01-31")));
+        assert_eq!(term(br#"datetime.value in "2018-01-01".."2018-01-31""#).unwrap().1, exp);
+
+        let exp = Term::DateRange(String::from("datetime.value"), None, Some(String::from("2018-01-31")));
+        assert_eq!(term(br#"datetime.value in .. "2018-01-31""#).unwrap().1, exp);
+    }
+
+    #[test]
+    fn test_not_and_group() {
+        setup_logging();
+        let q = parse(r#"not (tag("a") or tag("b")) and linked()"#).unwrap();
+
+        let group = Query {
+            filter: Term::Tag(String::from("a")),
+            next_filters: vec![(Operator::Or, Term::Tag(String::from("b")))],
+        };
+
+        let exp = Query {
+            filter: Term::Not(Box::new(Term::Group(Box::new(group)))),
+            next_filters: vec![(Operator::And, Term::Linked(None))],
+        };
+
+        assert_eq!(q, exp);
+    }
+
+    #[test]
+    fn test_parse_rejects_trailing_input() {
+        assert!(parse(r#"tag("a") tag("b")"#).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Compilation of parsed queries to `libimagentryfilter` filters
+
+use std::path::PathBuf;
+
+use chrono::Duration;
+use chrono::NaiveDateTime;
+use filters::filter::Filter as EntryFilter;
+use filters::failable::filter::FailableFilter;
+use regex::Regex;
+use toml::Value as TomlValue;
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrycategory::store::CategoryStore;
+use libimagentryfilter::builtin::content::grep::ContentGrep;
+use libimagentryfilter::builtin::header::field_daterange::FieldDateRange;
+use libimagentryfilter::builtin::header::field_daterange::parse_datetime;
+use libimagentryfilter::builtin::header::field_eq::FieldEq;
+use libimagentryfilter::builtin::header::field_exists::FieldExists;
+use libimagentryfilter::builtin::header::field_grep::FieldGrep;
+use libimagentryfilter::builtin::header::field_gt::FieldGt;
+use libimagentryfilter::builtin::header::field_istype::FieldIsType;
+use libimagentryfilter::builtin::header::field_istype::Type;
+use libimagentryfilter::builtin::header::field_lt::FieldLt;
+use libimagentryfilter::builtin::header::field_predicate::FieldPredicate;
+use libimagentryfilter::builtin::header::field_predicate::Predicate;
+use libimagentryfilter::category::HasAnyCategory;
+use libimagentryfilter::link::HasLinks;
+use libimagentryfilter::link::IsLinkedTo;
+use libimagentryfilter::tags::HasTag;
+
+use parser;
+use parser::CompareOp;
+use parser::CompareValue;
+use parser::Filter;
+use parser::Function;
+use parser::Operator;
+use parser::Term;
+use parser::Unary;
+use parser::Value;
+
+/// A compiled query, which can be used to filter entries
+pub struct Query(Node);
+
+impl Query {
+
+    /// Parse and compile a query
+    ///
+    /// The store is used to find the subcategories for `category()` terms.
+    pub fn compile(s: &str, store: &Store) -> Result<Query> {
+        parser::parse(s)
+            .and_then(|q| compile_query(q, store))
+            .map(Query)
+    }
+
+}
+
+impl FailableFilter<Entry> for Query {
+    type Error = Error;
+
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        self.0.filter(e)
+    }
+
+}
+
+/// The filters from `libimagentryfilter` a query compiles to
+enum Leaf {
+    Eq(FieldEq),
+    Lt(FieldLt),
+    Gt(FieldGt),
+    IsType(FieldIsType),
+    Exists(FieldExists),
+    Grep(FieldGrep),
+    DateRange(FieldDateRange),
+    Function(FieldPredicate<FunctionPredicate>),
+    Tag(HasTag),
+    Category(HasAnyCategory),
+    HasLinks(HasLinks),
+    LinkedTo(IsLinkedTo),
+    Content(ContentGrep),
+}
+
+impl Leaf {
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        match *self {
+            Leaf::Eq(ref f)        => FailableFilter::filter(f, e),
+            Leaf::Lt(ref f)        => FailableFilter::filter(f, e),
+            Leaf::Gt(ref f)        => FailableFilter::filter(f, e),
+            Leaf::IsType(ref f)    => FailableFilter::filter(f, e),
+            Leaf::Exists(ref f)    => FailableFilter::filter(f, e),
+            Leaf::Grep(ref f)      => FailableFilter::filter(f, e),
+            Leaf::DateRange(ref f) => FailableFilter::filter(f, e),
+            Leaf::Function(ref f)  => FailableFilter::filter(f, e),
+            Leaf::Category(ref f)  => FailableFilter::filter(f, e),
+            Leaf::HasLinks(ref f)  => FailableFilter::filter(f, e),
+            Leaf::LinkedTo(ref f)  => FailableFilter::filter(f, e),
+            Leaf::Tag(ref f)       => Ok(EntryFilter::filter(f, e)),
+            Leaf::Content(ref f)   => Ok(EntryFilter::filter(f, e)),
+        }
+    }
+}
+
+enum Node {
+    Leaf(Leaf),
+    Not(Box<Node>),
+    Any(Vec<Node>),
+    Op(Box<Node>, Operator, Box<Node>),
+}
+
+impl Node {
+    fn filter(&self, e: &Entry) -> Result<bool> {
+        match *self {
+            Node::Leaf(ref leaf) => leaf.filter(e),
+            Node::Not(ref node)  => node.filter(e).map(|b| !b),
+            Node::Any(ref nodes) => {
+                for node in nodes {
+                    if node.filter(e)? {
+                        return Ok(true)
+                    }
+                }
+                Ok(false)
+            },
+            Node::Op(ref left, ref op, ref right) => {
+                let left = left.filter(e)?;
+                match *op {
+                    Operator::And => if left { right.filter(e) } else { Ok(false) },
+                    Operator::Or  => if left { Ok(true) } else { right.filter(e) },
+                    Operator::Xor => right.filter(e).map(|right| left != right),
+                }
+            },
+        }
+    }
+}
+
+fn compile_query(q: parser::Query, store: &Store) -> Result<Node> {
+    let mut node = compile_term(q.filter, store)?;
+
+    for (op, term) in q.next_filters {
+        node = Node::Op(Box::new(node), op, Box::new(compile_term(term, store)?));
+    }
+
+    Ok(node)
+}
+
+fn compile_term(term: Term, store: &Store) -> Result<Node> {
+    let leaf = match term {
+        Term::Filter(f)    => return compile_filter(f),
+        Term::Not(t)       => return compile_term(*t, store).map(Box::new).map(Node::Not),
+        Term::Group(q)     => return compile_query(*q, store),
+        Term::Tag(t)       => Leaf::Tag(HasTag::new(t)),
+        Term::Category(c)  => Leaf::Category(HasAnyCategory::new(store.get_category_names_with_subcategories(&c)?)),
+        Term::Linked(None) => Leaf::HasLinks(HasLinks),
+        Term::Linked(Some(id)) => {
+            let id = StoreId::new_baseless(PathBuf::from(id))?;
+            Leaf::LinkedTo(IsLinkedTo::new(id))
+        },
+        Term::Exists(path) => Leaf::Exists(FieldExists::new(path)),
+        Term::Content(re)  => Leaf::Content(ContentGrep::new(&re[..])?),
+        Term::DateRange(path, start, end) => {
+            let start = match start {
+                Some(s) => Some(parse_bound(&s)?),
+                None    => None,
+            };
+
+            // A date without time as end includes the whole day
+            let end = match end {
+                Some(ref e) if !e.contains('T') => Some(parse_bound(e)? + Duration::seconds(86399)),
+                Some(ref e)                     => Some(parse_bound(e)?),
+                None                            => None,
+            };
+
+            Leaf::DateRange(FieldDateRange::new(path, start, end))
+        },
+    };
+
+    Ok(Node::Leaf(leaf))
+}
+
+fn compile_filter(f: Filter) -> Result<Node> {
+    let Filter { unary, selector, compare_operator, compare_value } = f;
+    let path = selector.selector_str().clone();
+
+    let node = match selector.function() {
+        Some(function) => compile_function(path, function, compare_operator, compare_value)?,
+        None => match (compare_operator, compare_value) {
+            (CompareOp::OpEq, CompareValue::Value(v)) => eq(path, v),
+
+            (CompareOp::OpNeq, CompareValue::Value(v)) => {
+                let exists = Node::Leaf(Leaf::Exists(FieldExists::new(path.clone())));
+                let not_eq = Node::Not(Box::new(eq(path, v)));
+                Node::Op(Box::new(exists), Operator::And, Box::new(not_eq))
+            },
+
+            // FieldLt matches if the passed value is lower than the header value, FieldGt the
+            // other way round
+            (CompareOp::OpGt, CompareValue::Value(v)) => {
+                Node::Leaf(Leaf::Lt(FieldLt::new(path, into_toml(v))))
+            },
+            (CompareOp::OpLt, CompareValue::Value(v)) => {
+                Node::Leaf(Leaf::Gt(FieldGt::new(path, into_toml(v))))
+            },
+            (CompareOp::OpGte, CompareValue::Value(v)) => Node::Any(vec![
+                Node::Leaf(Leaf::Lt(FieldLt::new(path.clone(), into_toml(v.clone())))),
+                eq(path, v),
+            ]),
+            (CompareOp::OpLte, CompareValue::Value(v)) => Node::Any(vec![
+                Node::Leaf(Leaf::Gt(FieldGt::new(path.clone(), into_toml(v.clone())))),
+                eq(path, v),
+            ]),
+
+            (CompareOp::OpIs, CompareValue::Value(v)) => {
+                Node::Leaf(Leaf::IsType(FieldIsType::new(path, type_of(&v))))
+            },
+
+            (CompareOp::OpIn, CompareValue::Value(v)) => eq(path, v),
+            (CompareOp::OpIn, CompareValue::Values(vs)) => Node::Any({
+                vs.into_iter().map(|v| eq(path.clone(), v)).collect()
+            }),
+
+            (CompareOp::OpMatches, CompareValue::Value(Value::String(re))) => {
+                Node::Leaf(Leaf::Grep(FieldGrep::new(path, Regex::new(&re)?)))
+            },
+            (CompareOp::OpMatches, _) => {
+                return Err(format_err!("'{}' can only be matched against a regex string", path))
+            },
+
+            (op, CompareValue::Values(_)) => {
+                return Err(format_err!("Cannot compare '{}' with multiple values using {:?}", path, op))
+            },
+        },
+    };
+
+    match unary {
+        Some(Unary::Not) => Ok(Node::Not(Box::new(node))),
+        None             => Ok(node),
+    }
+}
+
+fn compile_function(path: String, function: &Function, op: CompareOp, value: CompareValue)
+    -> Result<Node>
+{
+    let valid = match (function, &op, &value) {
+        (&Function::Length, &CompareOp::OpIn, &CompareValue::Values(_))                => true,
+        (&Function::Length, &CompareOp::OpIs, _)                                       => false,
+        (&Function::Length, &CompareOp::OpMatches, _)                                  => false,
+        (&Function::Length, _, &CompareValue::Value(Value::Integer(_)))                => true,
+        (&Function::Keys, &CompareOp::OpIn, _) | (&Function::Values, &CompareOp::OpIn, _) => true,
+        _                                                                              => false,
+    };
+
+    if !valid {
+        return Err(format_err!("Cannot use {:?} with {:?} {:?} on '{}'", function, op, value, path))
+    }
+
+    let function = match *function {
+        Function::Length => Function::Length,
+        Function::Keys   => Function::Keys,
+        Function::Values => Function::Values,
+    };
+
+    let pred = FunctionPredicate { function: function, op: op, value: value };
+    Ok(Node::Leaf(Leaf::Function(FieldPredicate::new(path, Box::new(pred)))))
+}
+
+/// Predicate for the `length()`, `keys()` and `values()` functions
+struct FunctionPredicate {
+    function: Function,
+    op: CompareOp,
+    value: CompareValue,
+}
+
+impl Predicate for FunctionPredicate {
+
+    fn evaluate(&self, v: &TomlValue) -> bool {
+        match self.function {
+            Function::Length => {
+                let len = match *v {
+                    TomlValue::Array(ref a)  => a.len() as i64,
+                    TomlValue::String(ref s) => s.len() as i64,
+                    _                        => 1,
+                };
+
+                match (&self.op, &self.value) {
+                    (&CompareOp::OpEq,  &CompareValue::Value(Value::Integer(i))) => len == i,
+                    (&CompareOp::OpNeq, &CompareValue::Value(Value::Integer(i))) => len != i,
+                    (&CompareOp::OpGt,  &CompareValue::Value(Value::Integer(i))) => len > i,
+                    (&CompareOp::OpLt,  &CompareValue::Value(Value::Integer(i))) => len < i,
+                    (&CompareOp::OpGte, &CompareValue::Value(Value::Integer(i))) => len >= i,
+                    (&CompareOp::OpLte, &CompareValue::Value(Value::Integer(i))) => len <= i,
+                    (&CompareOp::OpIn,  &CompareValue::Value(Value::Integer(i))) => len == i,
+                    (&CompareOp::OpIn,  &CompareValue::Values(ref vs)) => {
+                        vs.iter().any(|v| *v == Value::Integer(len))
+                    },
+                    _ => false,
+                }
+            },
+
+            Function::Keys => match *v {
+                TomlValue::Table(ref t) => t.keys().any(|k| self.contains(&TomlValue::String(k.clone()))),
+                _                       => false,
+            },
+
+            Function::Values => match *v {
+                TomlValue::Table(ref t) => t.values().any(|v| self.contains(v)),
+                _                       => false,
+            },
+        }
+    }
+
+}
+
+impl FunctionPredicate {
+    fn contains(&self, v: &TomlValue) -> bool {
+        match self.value {
+            CompareValue::Value(ref expected)   => into_toml(expected.clone()) == *v,
+            CompareValue::Values(ref expected) => expected.iter().any(|e| into_toml(e.clone()) == *v),
+        }
+    }
+}
+
+fn eq(path: String, v: Value) -> Node {
+    Node::Leaf(Leaf::Eq(FieldEq::new(path, into_toml(v))))
+}
+
+fn into_toml(v: Value) -> TomlValue {
+    match v {
+        Value::Boolean(b) => TomlValue::Boolean(b),
+        Value::Integer(i) => TomlValue::Integer(i),
+        Value::String(s)  => TomlValue::String(s),
+    }
+}
+
+fn type_of(v: &Value) -> Type {
+    match *v {
+        Value::Boolean(_) => Type::Boolean,
+        Value::Integer(_) => Type::Integer,
+        Value::String(_)  => Type::String,
+    }
+}
+
+fn parse_bound(s: &str) -> Result<NaiveDateTime> {
+    parse_datetime(s).ok_or_else(|| format_err!("Cannot parse date: '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Store;
+    use libimagentrycategory::entry::EntryCategory;
+
+    use super::*;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn create(store: &Store, id: &str, header: Vec<(&str, TomlValue)>) {
+        let mut entry = store.create(PathBuf::from(id)).unwrap();
+        for (path, value) in header {
+            let _ = entry.get_header_mut().insert(path, value).unwrap();
+        }
+    }
+
+    fn matching(store: &Store, query: &str, ids: &[&str]) -> Vec<String> {
+        let query = Query::compile(query, store).unwrap();
+        ids.iter()
+            .filter(|id| {
+                let entry = store.get(PathBuf::from(**id)).unwrap().unwrap();
+                FailableFilter::filter(&query, &entry).unwrap()
+            })
+            .map(|id| String::from(*id))
+            .collect()
+    }
+
+    fn strings(v: &[&str]) -> TomlValue {
+        TomlValue::Array(v.iter().map(|s| TomlValue::String(String::from(*s))).collect())
+    }
+
+    #[test]
+    fn test_gt_lt_compare_header_value_with_query_value() {
+        let store = get_store();
+        create(&store, "a", vec![("num", TomlValue::Integer(1))]);
+        create(&store, "b", vec![("num", TomlValue::Integer(2))]);
+        create(&store, "c", vec![("num", TomlValue::Integer(3))]);
+        let ids = ["a", "b", "c"];
+
+        assert_eq!(vec!["b", "c"], matching(&store, "num > 1", &ids));
+        assert_eq!(vec!["a"], matching(&store, "num < 2", &ids));
+        assert_eq!(vec!["b", "c"], matching(&store, "num >= 2", &ids));
+        assert_eq!(vec!["a", "b"], matching(&store, "num <= 2", &ids));
+    }
+
+    #[test]
+    fn test_neq_requires_the_field() {
+        let store = get_store();
+        create(&store, "a", vec![("num", TomlValue::Integer(1))]);
+        create(&store, "b", vec![("num", TomlValue::Integer(2))]);
+        create(&store, "c", vec![]);
+        let ids = ["a", "b", "c"];
+
+        assert_eq!(vec!["b"], matching(&store, "num != 1", &ids));
+        assert_eq!(vec!["b"], matching(&store, "num neq 1", &ids));
+    }
+
+    #[test]
+    fn test_functions() {
+        let store = get_store();
+        create(&store, "a", vec![
+            ("data.list", strings(&["x", "y"])),
+            ("data.table.k", TomlValue::String(String::from("v"))),
+        ]);
+        create(&store, "b", vec![
+            ("data.list", strings(&["x"])),
+            ("data.table.other", TomlValue::String(String::from("w"))),
+        ]);
+        let ids = ["a", "b"];
+
+        assert_eq!(vec!["a"], matching(&store, "length(data.list) == 2", &ids));
+        assert_eq!(vec!["b"], matching(&store, "length(data.list) < 2", &ids));
+        assert_eq!(vec!["a", "b"], matching(&store, "length(data.list) in [1, 2]", &ids));
+        assert_eq!(vec!["a"], matching(&store, "keys(data.table) in [\"k\"]", &ids));
+        assert_eq!(vec!["b"], matching(&store, "values(data.table) in [\"w\"]", &ids));
+
+        assert!(Query::compile("length(data.list) ~ \"x\"", &store).is_err());
+        assert!(Query::compile("keys(data.table) == \"k\"", &store).is_err());
+    }
+
+    #[test]
+    fn test_tag() {
+        let store = get_store();
+        create(&store, "a", vec![("tag.values", strings(&["work"]))]);
+        create(&store, "b", vec![("tag.values", strings(&["home"]))]);
+        let ids = ["a", "b"];
+
+        assert_eq!(vec!["a"], matching(&store, "tag(\"work\")", &ids));
+        assert_eq!(vec!["b"], matching(&store, "not tag(\"work\")", &ids));
+    }
+
+    #[test]
+    fn test_category_includes_subcategories() {
+        let store = get_store();
+        let _     = store.create_category("work").unwrap();
+        let _     = store.create_category("meetings").unwrap();
+        let _     = store.create_category("private").unwrap();
+        let _     = store.set_category_parent("meetings", Some("work")).unwrap();
+
+        for &(id, category) in [("a", "work"), ("b", "meetings"), ("c", "private")].iter() {
+            let mut entry = store.create(PathBuf::from(id)).unwrap();
+            entry.set_category_checked(&store, category).unwrap();
+        }
+        let ids = ["a", "b", "c"];
+
+        assert_eq!(vec!["a", "b"], matching(&store, "category(\"work\")", &ids));
+        assert_eq!(vec!["b"], matching(&store, "category(\"meetings\")", &ids));
+        assert_eq!(vec!["c"], matching(&store, "not category(\"work\")", &ids));
+    }
+
+}
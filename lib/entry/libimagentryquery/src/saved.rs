@@ -0,0 +1,227 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Saved searches
+//!
+//! A saved search is an entry `search/<name>` in the store, which holds a query string in
+//! `search.query`. Running a saved search yields the ids of all entries (outside of the `search`
+//! collection) which match the query, the same entries `cli::entry_matches()` accepts.
+
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadTypeExt;
+use filters::failable::filter::FailableFilter;
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+use libimagerror::errors::ErrorMsg as EM;
+
+use module_path::ModuleEntryPath;
+use query::Query;
+
+pub const SAVED_SEARCH_COLLECTION: &'static str = "search";
+
+/// Check whether the id is the id of a saved search
+///
+/// Saved searches are never results of a query, so they do not show up in the results of a query
+/// which happens to match their header.
+pub fn is_saved_search(id: &StoreId) -> bool {
+    id.is_in_collection(&[SAVED_SEARCH_COLLECTION])
+}
+
+pub trait SavedSearchStore<'a> {
+
+    /// Save a query under a name, overwriting an existing saved search with that name
+    ///
+    /// The query is compiled before it is saved, so only valid queries can be saved.
+    fn save_search(&'a self, name: &str, query: &str) -> Result<FileLockEntry<'a>>;
+
+    /// Get the query string of a saved search
+    fn get_saved_search(&'a self, name: &str) -> Result<Option<String>>;
+
+    /// Delete a saved search, failing if there is no saved search with that name
+    fn delete_saved_search(&'a self, name: &str) -> Result<()>;
+
+    /// Get the names of all saved searches
+    fn all_saved_search_names(&'a self) -> Result<Vec<String>>;
+
+    /// Run a saved search, failing if there is no saved search with that name
+    fn run_saved_search(&'a self, name: &str) -> Result<Vec<StoreId>>;
+
+}
+
+impl<'a> SavedSearchStore<'a> for Store {
+
+    fn save_search(&'a self, name: &str, query: &str) -> Result<FileLockEntry<'a>> {
+        let _ = Query::compile(query, self)?;
+
+        debug!("Saving search '{}': {}", name, query);
+        let mut entry = ModuleEntryPath::new(name)
+            .into_storeid()
+            .and_then(|id| self.retrieve(id))?;
+
+        {
+            let header = entry.get_header_mut();
+            let _ = header.insert("search.name", Value::String(String::from(name)))?;
+            let _ = header.insert("search.query", Value::String(String::from(query)))?;
+        }
+
+        Ok(entry)
+    }
+
+    fn get_saved_search(&'a self, name: &str) -> Result<Option<String>> {
+        match ModuleEntryPath::new(name).into_storeid().and_then(|id| self.get(id))? {
+            None        => Ok(None),
+            Some(entry) => entry
+                .get_header()
+                .read_string("search.query")
+                .map_err(Error::from)?
+                .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("search.query")))
+                .map(Some),
+        }
+    }
+
+    fn delete_saved_search(&'a self, name: &str) -> Result<()> {
+        ModuleEntryPath::new(name).into_storeid().and_then(|id| self.delete(id))
+    }
+
+    fn all_saved_search_names(&'a self) -> Result<Vec<String>> {
+        self.entries()?
+            .in_collection(SAVED_SEARCH_COLLECTION)
+            .map(|id| {
+                let id = id?;
+                id.local()
+                    .strip_prefix(SAVED_SEARCH_COLLECTION)
+                    .ok()
+                    .and_then(|n| n.to_str())
+                    .map(String::from)
+                    .ok_or_else(|| format_err!("Invalid saved search id: {}", id))
+            })
+            .collect()
+    }
+
+    fn run_saved_search(&'a self, name: &str) -> Result<Vec<StoreId>> {
+        let query = self
+            .get_saved_search(name)?
+            .ok_or_else(|| format_err!("No saved search named '{}'", name))
+            .and_then(|q| Query::compile(&q, self))?;
+
+        let mut ids = vec![];
+        for id in self.entries()? {
+            let id = id?;
+            if is_saved_search(&id) {
+                continue
+            }
+
+            if let Some(entry) = self.get(id.clone())? {
+                if query.filter(&entry)? {
+                    ids.push(id);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Store;
+
+    use super::*;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_save_and_get_search() {
+        let store = get_store();
+        let _     = store.save_search("open", "exists(todo.status)").unwrap();
+
+        assert_eq!(Some(String::from("exists(todo.status)")), store.get_saved_search("open").unwrap());
+        assert_eq!(None, store.get_saved_search("closed").unwrap());
+    }
+
+    #[test]
+    fn test_save_search_overwrites() {
+        let store = get_store();
+        let _     = store.save_search("open", "exists(todo.status)").unwrap();
+        let _     = store.save_search("open", "exists(todo.uuid)").unwrap();
+
+        assert_eq!(Some(String::from("exists(todo.uuid)")), store.get_saved_search("open").unwrap());
+    }
+
+    #[test]
+    fn test_save_invalid_query_fails() {
+        let store = get_store();
+        assert!(store.save_search("broken", "tag(").is_err());
+        assert_eq!(None, store.get_saved_search("broken").unwrap());
+    }
+
+    #[test]
+    fn test_list_and_delete_saved_searches() {
+        let store = get_store();
+        let _     = store.save_search("a", "exists(a)").unwrap();
+        let _     = store.save_search("b", "exists(b)").unwrap();
+
+        let mut names = store.all_saved_search_names().unwrap();
+        names.sort();
+        assert_eq!(vec![String::from("a"), String::from("b")], names);
+
+        store.delete_saved_search("a").unwrap();
+        assert_eq!(vec![String::from("b")], store.all_saved_search_names().unwrap());
+        assert!(store.delete_saved_search("a").is_err());
+    }
+
+    #[test]
+    fn test_run_saved_search_excludes_saved_searches() {
+        let store = get_store();
+        let _     = store.save_search("searches", "exists(search.query)").unwrap();
+
+        {
+            let mut entry = store.create(PathBuf::from("other")).unwrap();
+            let _ = entry
+                .get_header_mut()
+                .insert("search.query", Value::String(String::from("foo")))
+                .unwrap();
+        }
+        let _ = store.create(PathBuf::from("unrelated")).unwrap();
+
+        let ids = store.run_saved_search("searches").unwrap();
+        assert_eq!(1, ids.len());
+        assert!(!is_saved_search(&ids[0]));
+        assert!(ids[0].local().ends_with("other"));
+
+        assert!(store.run_saved_search("missing").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Configuration of refs
+//!
+//! # Base pathes
+//!
+//! A ref can either store the absolute path of the referenced file, or the name of a base path
+//! and the path relative to it. The base pathes are configured per machine in the
+//! `ref.basepathes` table of the imag configuration:
+//!
+//! ```toml
+//! [ref.basepathes]
+//! music = "/mnt/music"
+//! ```
+//!
+//! so a ref stays valid if the files it references live at another location on another machine.
+//!
+//! # Generators
+//!
+//! The `ref.generators` table names the `UniqueRefPathGenerator` which should be used for the refs
+//! of a collection:
+//!
+//! ```toml
+//! [ref.generators]
+//! ref = "partial-sha256"
+//! ```
+//!
+//! The names are not interpreted by this library, it is up to the user of the configuration to map
+//! them to generator types.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+use failure::Error;
+use failure::err_msg;
+
+/// The base pathes of this machine and the generators to use per collection
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    basepathes: BTreeMap<String, PathBuf>,
+    generators: BTreeMap<String, String>,
+}
+
+impl Config {
+
+    pub fn new(basepathes: BTreeMap<String, PathBuf>) -> Self {
+        Config {
+            basepathes: basepathes,
+            generators: BTreeMap::new(),
+        }
+    }
+
+    /// Set the generator names per collection
+    pub fn with_generators(mut self, generators: BTreeMap<String, String>) -> Self {
+        self.generators = generators;
+        self
+    }
+
+    /// Read the `ref.basepathes` and `ref.generators` tables of the imag configuration
+    ///
+    /// Missing tables are treated as empty.
+    pub fn from_config(config: &Value) -> Result<Config> {
+        let basepathes = read_string_table(config, "ref.basepathes")?
+            .into_iter()
+            .map(|(name, path)| (name, PathBuf::from(path)))
+            .collect();
+
+        let generators = read_string_table(config, "ref.generators")?;
+
+        Ok(Config::new(basepathes).with_generators(generators))
+    }
+
+    /// Get the directory a base path name points to
+    pub fn get(&self, name: &str) -> Option<&PathBuf> {
+        self.basepathes.get(name)
+    }
+
+    /// Get the name of the generator configured for a collection
+    pub fn generator_for(&self, collection: &str) -> Option<&str> {
+        self.generators.get(collection).map(String::as_str)
+    }
+
+    /// Get the names of the collections a generator is configured for
+    pub fn generator_collections(&self) -> Vec<&str> {
+        self.generators.keys().map(String::as_str).collect()
+    }
+
+    /// Split a path into the name of the base path it lives in and the path relative to it
+    ///
+    /// If the path is in several base pathes, the most specific (longest) one is used.
+    /// Returns `None` if the path is not in any configured base path.
+    pub fn split<P: AsRef<Path>>(&self, path: P) -> Option<(&str, PathBuf)> {
+        self.basepathes
+            .iter()
+            .filter_map(|(name, base)| {
+                path.as_ref()
+                    .strip_prefix(base)
+                    .ok()
+                    .map(|rel| (name, base, rel.to_path_buf()))
+            })
+            .max_by_key(|&(_, base, _)| base.components().count())
+            .map(|(name, _, rel)| (name.as_str(), rel))
+    }
+
+}
+
+fn read_string_table(config: &Value, path: &str) -> Result<BTreeMap<String, String>> {
+    match config.read(path).map_err(Error::from)? {
+        None                          => Ok(BTreeMap::new()),
+        Some(&Value::Table(ref tabl)) => tabl
+            .iter()
+            .map(|(key, value)| {
+                value.as_str()
+                    .map(|v| (key.clone(), String::from(v)))
+                    .ok_or_else(|| err_msg(format!("Type error: '{}.{}' must be a string", path, key)))
+            })
+            .collect(),
+        Some(_) => Err(err_msg(format!("Type error: '{}' must be a table", path))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::Config;
+
+    fn config() -> Config {
+        let mut map = BTreeMap::new();
+        map.insert(String::from("music"), PathBuf::from("/mnt/music"));
+        map.insert(String::from("live"), PathBuf::from("/mnt/music/live"));
+        Config::new(map)
+    }
+
+    #[test]
+    fn test_split_in_base() {
+        let c = config();
+        let res = c.split("/mnt/music/album/track.mp3");
+        assert_eq!(Some(("music", PathBuf::from("album/track.mp3"))), res);
+    }
+
+    #[test]
+    fn test_split_uses_most_specific_base() {
+        let c = config();
+        let res = c.split("/mnt/music/live/track.mp3");
+        assert_eq!(Some(("live", PathBuf::from("track.mp3"))), res);
+    }
+
+    #[test]
+    fn test_split_outside_base() {
+        let c = config();
+        assert_eq!(None, c.split("/home/user/track.mp3"));
+        assert_eq!(None, c.split("/mnt/musicfoo/track.mp3"));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = ::toml::from_str(r#"
+            [ref.basepathes]
+            music = "/mnt/music"
+
+            [ref.generators]
+            ref = "partial-sha256"
+        "#).unwrap();
+
+        let c = Config::from_config(&config).unwrap();
+        assert_eq!(Some(&PathBuf::from("/mnt/music")), c.get("music"));
+        assert_eq!(Some("partial-sha256"), c.generator_for("ref"));
+        assert_eq!(None, c.generator_for("mail"));
+        assert_eq!(vec!["ref"], c.generator_collections());
+    }
+}
@@ -33,6 +33,72 @@
 //! You must enable the appropriate crate feature to use any of the provided generators. With the
 //! `generators` feature, you only get the convenience macro `make_unique_ref_path_generator!()`.
 //!
+//! # Provided generators
+//!
+//! * `sha1`, `sha224`, `sha256`, `sha384`, `sha512`, `sha3`: Hash the full content of the file
+//! * `partial::PartialSha256`: Hash only the first `partial::PARTIAL_HASH_BYTES` bytes of the
+//!   file with SHA-256, which is considerably faster for huge (media) files
+//! * `path::PathHash`: Hash the canonicalized path of the file, not its content. The ref breaks
+//!   if the file is moved, but not if it is changed
+//! * `inode::InodeMtime`: Use the device, inode and modification time of the file (unix only).
+//!   The ref survives renaming the file on the same filesystem, but not changing it
+//!
+
+use failure::Error;
+use failure::err_msg;
+
+/// The name of the generator which is used if the configuration does not name one
+pub const DEFAULT_GENERATOR : &'static str = "sha512";
+
+/// The collection all provided generators put their refs in
+///
+/// Refs can be put into other collections with `RefStore::retrieve_ref_in()`.
+pub const GENERATOR_COLLECTION : &'static str = "ref";
+
+/// The error `with_generator!()` evaluates to if there is no generator named `name`
+pub fn unknown_generator(name: &str) -> Error {
+    err_msg(format!("Unknown generator: '{}'", name))
+}
+
+/// Call `$fun::<Generator>($args...)` with the provided generator named `$name`
+///
+/// The names are the ones of the list of provided generators above, for example "sha512" or
+/// "partial-sha256". Evaluates to `Ok` with the result of the call, or to the error from
+/// `unknown_generator()` if there is no generator with that name.
+///
+/// # Note
+///
+/// The calling crate must enable all generator features.
+///
+#[macro_export]
+macro_rules! with_generator {
+    ($name:expr, $fun:ident($($arg:expr),*)) => {{
+        use $crate::generators::sha1::Sha1;
+        use $crate::generators::sha224::Sha224;
+        use $crate::generators::sha256::Sha256;
+        use $crate::generators::sha384::Sha384;
+        use $crate::generators::sha512::Sha512;
+        use $crate::generators::sha3::Sha3;
+        use $crate::generators::partial::PartialSha256;
+        use $crate::generators::path::PathHash;
+        #[cfg(unix)]
+        use $crate::generators::inode::InodeMtime;
+
+        match $name {
+            "sha1"           => Ok($fun::<Sha1>($($arg),*)),
+            "sha224"         => Ok($fun::<Sha224>($($arg),*)),
+            "sha256"         => Ok($fun::<Sha256>($($arg),*)),
+            "sha384"         => Ok($fun::<Sha384>($($arg),*)),
+            "sha512"         => Ok($fun::<Sha512>($($arg),*)),
+            "sha3"           => Ok($fun::<Sha3>($($arg),*)),
+            "partial-sha256" => Ok($fun::<PartialSha256>($($arg),*)),
+            "path"           => Ok($fun::<PathHash>($($arg),*)),
+            #[cfg(unix)]
+            "inode-mtime"    => Ok($fun::<InodeMtime>($($arg),*)),
+            other            => Err($crate::generators::unknown_generator(other)),
+        }
+    }};
+}
 
 /// A convenience macro for wrapping a generator in a new one, reusing the functionality from the
 /// underlying generator
@@ -150,6 +216,9 @@ macro_rules! make_unique_ref_path_generator {
         feature = "generators-sha384",
         feature = "generators-sha512",
         feature = "generators-sha3",
+        feature = "generators-partial",
+        feature = "generators-path",
+        feature = "generators-inode",
         ))]
 mod base;
 
@@ -178,8 +247,8 @@ macro_rules! make_sha_mod {
                         .open(path)
                         .map_err(::failure::Error::from)
                         .and_then(|mut file| {
-                            let mut buffer = String::new();
-                            let _ = file.read_to_string(&mut buffer)?;
+                            let mut buffer = Vec::new();
+                            let _ = file.read_to_end(&mut buffer)?;
                             $hashingimpl(buffer)
                         })
                 }
@@ -210,7 +279,6 @@ macro_rules! make_sha_mod {
                                 }
                             }?;
 
-                            let buffer = String::from_utf8(buffer)?;
                             $hashingimpl(buffer)
                         })
                 }
@@ -223,11 +291,11 @@ macro_rules! make_sha_mod {
 
 #[cfg(feature = "generators-sha1")]
 make_sha_mod! {
-    sha1, Sha1, |buffer: String| {
+    sha1, Sha1, |buffer: Vec<u8>| {
         use sha1::{Sha1, Digest};
 
-        trace!("Hashing: '{:?}'", buffer);
-        let res = hex::encode(Sha1::digest(buffer.as_bytes()));
+        trace!("Hashing {} bytes", buffer.len());
+        let res = hex::encode(Sha1::digest(&buffer));
         trace!("Hash => '{:?}'", res);
 
         Ok(res)
@@ -236,41 +304,105 @@ make_sha_mod! {
 
 #[cfg(feature = "generators-sha224")]
 make_sha_mod! {
-    sha224, Sha224, |buffer: String| {
+    sha224, Sha224, |buffer: Vec<u8>| {
         use sha2::{Sha224, Digest};
-        Ok(hex::encode(Sha224::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha224::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha256")]
 make_sha_mod! {
-    sha256, Sha256, |buffer: String| {
+    sha256, Sha256, |buffer: Vec<u8>| {
         use sha2::{Sha256, Digest};
-        Ok(hex::encode(Sha256::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha256::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha384")]
 make_sha_mod! {
-    sha384, Sha384, |buffer: String| {
+    sha384, Sha384, |buffer: Vec<u8>| {
         use sha2::{Sha384, Digest};
-        Ok(hex::encode(Sha384::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha384::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha512")]
 make_sha_mod! {
-    sha512, Sha512, |buffer: String| {
+    sha512, Sha512, |buffer: Vec<u8>| {
         use sha2::{Sha512, Digest};
-        Ok(hex::encode(Sha512::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha512::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha3")]
 make_sha_mod! {
-    sha3, Sha3, |buffer: String| {
+    sha3, Sha3, |buffer: Vec<u8>| {
         use sha3::{Sha3_256, Digest};
-        Ok(hex::encode(Sha3_256::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha3_256::digest(&buffer)))
+    }
+}
+
+#[cfg(feature = "generators-partial")]
+pub mod partial {
+    use std::path::Path;
+
+    use generators::sha256::Sha256;
+
+    /// The number of bytes `PartialSha256` hashes
+    pub const PARTIAL_HASH_BYTES : usize = 1024 * 1024;
+
+    make_unique_ref_path_generator! (
+        pub PartialSha256
+        over generators::base::Base
+        => with collection name "ref"
+        => |path| Sha256::hash_n_bytes(path, PARTIAL_HASH_BYTES)
+    );
+}
+
+#[cfg(feature = "generators-path")]
+pub mod path {
+    use std::path::Path;
+
+    use hex;
+    use failure::Fallible as Result;
+    use failure::err_msg;
+
+    make_unique_ref_path_generator! (
+        pub PathHash
+        over generators::base::Base
+        => with collection name "ref"
+        => |path| hash_path(path)
+    );
+
+    fn hash_path<A: AsRef<Path>>(path: A) -> Result<String> {
+        use sha2::{Sha256, Digest};
+
+        let path = path.as_ref().canonicalize()?;
+        let path = path
+            .to_str()
+            .ok_or_else(|| err_msg(format!("Path is not valid UTF-8: {}", path.display())))?;
+
+        Ok(hex::encode(Sha256::digest(path.as_bytes())))
     }
 }
 
+#[cfg(all(unix, feature = "generators-inode"))]
+pub mod inode {
+    use std::path::Path;
+
+    use failure::Fallible as Result;
+
+    make_unique_ref_path_generator! (
+        pub InodeMtime
+        over generators::base::Base
+        => with collection name "ref"
+        => |path| hash_inode(path)
+    );
+
+    fn hash_inode<A: AsRef<Path>>(path: A) -> Result<String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let md = ::std::fs::metadata(path)?;
+        Ok(format!("{:x}-{:x}-{:x}", md.dev(), md.ino(), md.mtime()))
+    }
+}
@@ -49,6 +49,7 @@ extern crate failure;
 
 module_entry_path_mod!("ref");
 
+pub mod config;
 pub mod reference;
 pub mod refstore;
 
@@ -60,6 +61,7 @@ extern crate sha1;
     feature = "generators-sha256",
     feature = "generators-sha384",
     feature = "generators-sha512",
+    feature = "generators-path",
 ))]
 extern crate sha2;
 
@@ -73,6 +75,7 @@ extern crate sha3;
     feature = "generators-sha384",
     feature = "generators-sha512",
     feature = "generators-sha3",
+    feature = "generators-path",
 ))]
 extern crate hex;
 
@@ -19,9 +19,14 @@
 
 //! The Ref object is a helper over the link functionality, so one is able to create references to
 //! files outside of the imag store.
+//!
+//! A ref either stores the absolute path of the referenced file in `ref.path`, or - if the file
+//! lives in one of the base pathes from the `Config` - the name of the base path in `ref.base` and
+//! the path relative to it in `ref.relative`.
 
 use std::path::Path;
 use std::path::PathBuf;
+use std::collections::BTreeMap;
 
 use libimagentryutil::isa::Is;
 use libimagentryutil::isa::IsKindHeaderPathProvider;
@@ -30,11 +35,14 @@ use libimagerror::errors::ErrorMsg as EM;
 
 use toml::Value;
 use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
 use toml_query::delete::TomlValueDeleteExt;
 use toml_query::insert::TomlValueInsertExt;
 use failure::Fallible as Result;
 use failure::Error;
+use failure::err_msg;
 
+use config::Config;
 use refstore::UniqueRefPathGenerator;
 
 pub trait Ref {
@@ -47,32 +55,80 @@ pub trait Ref {
     /// Does not need a `UniqueRefPathGenerator` as it reads the hash stored in the header
     fn get_hash(&self) -> Result<&str>;
 
+    /// Get the name of the generator the ref was created with, if it was stored
+    ///
+    /// The name is whatever the creating application passed to `Ref::set_generator()`, this crate
+    /// does not interpret it.
+    fn get_generator(&self) -> Result<Option<String>>;
+
+    /// Store the name of the generator the ref was created with in `ref.generator`
+    fn set_generator(&mut self, name: &str) -> Result<()>;
+
     /// Make this object a ref
-    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P) -> Result<()>;
+    ///
+    /// If `path` is in one of the base pathes from `config`, the ref is stored relative to that
+    /// base path.
+    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P, config: &Config) -> Result<()>;
 
     /// Get the referenced path.
     ///
     /// Does not need a `UniqueRefPathGenerator` as it reads the path stored in the header.
-    fn get_path(&self) -> Result<PathBuf>;
+    /// Refs relative to a base path are resolved with the base pathes from `config`.
+    fn get_path(&self, config: &Config) -> Result<PathBuf>;
+
+    /// Set the referenced path, leaving the stored hash untouched
+    fn set_path<P: AsRef<Path>>(&mut self, path: P, config: &Config) -> Result<()>;
 
     /// Check whether the referenced file still matches its hash
-    fn hash_valid<RPG: UniqueRefPathGenerator>(&self) -> Result<bool>;
+    fn hash_valid<RPG: UniqueRefPathGenerator>(&self, config: &Config) -> Result<bool>;
+
+    /// Try to re-find the referenced file among the passed candidate paths
+    ///
+    /// Each candidate is hashed with the `UniqueRefPathGenerator`. The first candidate whose hash
+    /// equals the stored hash becomes the new referenced path and is returned. Candidates which
+    /// cannot be hashed are skipped.
+    ///
+    /// Returns `None` if no candidate matched.
+    ///
+    /// When relocating many refs against the same candidates, hash them once with
+    /// `hash_candidates()` and use `relocate_by_hash()` instead.
+    fn relocate<RPG, I, P>(&mut self, candidates: I, config: &Config) -> Result<Option<PathBuf>>
+        where RPG: UniqueRefPathGenerator,
+              I: IntoIterator<Item = P>,
+              P: AsRef<Path>
+    {
+        let hashes = hash_candidates::<RPG, _, _>(candidates);
+        self.relocate_by_hash(&hashes, config)
+    }
+
+    /// Try to re-find the referenced file in a map of precomputed `hash -> path` pairs
+    ///
+    /// If the stored hash is in `hashes`, the path becomes the new referenced path and is
+    /// returned. Returns `None` otherwise.
+    fn relocate_by_hash(&mut self, hashes: &BTreeMap<String, PathBuf>, config: &Config)
+        -> Result<Option<PathBuf>>;
+
+    /// Convert an absolute ref into a ref relative to a base path
+    ///
+    /// Returns whether the ref was converted, which is only the case if it stores an absolute path
+    /// that is in one of the base pathes from `config`.
+    fn make_portable(&mut self, config: &Config) -> Result<bool>;
 
     fn remove_ref(&mut self) -> Result<()>;
 
     /// Alias for `r.fs_link_exists() && r.deref().is_file()`
-    fn is_ref_to_file(&self) -> Result<bool> {
-        self.get_path().map(|p| p.is_file())
+    fn is_ref_to_file(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| p.is_file())
     }
 
     /// Alias for `r.fs_link_exists() && r.deref().is_dir()`
-    fn is_ref_to_dir(&self) -> Result<bool> {
-        self.get_path().map(|p| p.is_dir())
+    fn is_ref_to_dir(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| p.is_dir())
     }
 
     /// Alias for `!Ref::fs_link_exists()`
-    fn is_dangling(&self) -> Result<bool> {
-        self.get_path().map(|p| !p.exists())
+    fn is_dangling(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| !p.exists())
     }
 
 }
@@ -96,49 +152,257 @@ impl Ref for Entry {
             })
     }
 
-    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P) -> Result<()> {
-        let path_str : String = path
-            .as_ref()
-            .to_str()
-            .map(String::from)
-            .ok_or_else(|| EM::UTF8Error)?;
+    fn get_generator(&self) -> Result<Option<String>> {
+        self.get_header().read_string("ref.generator").map_err(Error::from)
+    }
 
-        let _   = self.set_isflag::<IsRef>()?;
-        let hdr = self.get_header_mut();
-        hdr.insert("ref.path", Value::String(String::from(path_str)))?;
-        hdr.insert("ref.hash", Value::String(hash))?;
+    fn set_generator(&mut self, name: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert("ref.generator", Value::String(String::from(name)))
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P, config: &Config) -> Result<()> {
+        let _ = self.set_isflag::<IsRef>()?;
+        let _ = self.set_path(path, config)?;
+        let _ = self.get_header_mut().insert("ref.hash", Value::String(hash))?;
 
         Ok(())
     }
 
-    fn get_path(&self) -> Result<PathBuf> {
-        self.get_header()
-            .read("ref.path")
+    fn get_path(&self, config: &Config) -> Result<PathBuf> {
+        let hdr = self.get_header();
+
+        if let Some(path) = hdr.read_string("ref.path").map_err(Error::from)? {
+            return Ok(PathBuf::from(path))
+        }
+
+        let base = hdr
+            .read_string("ref.base")
             .map_err(Error::from)?
-            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.path")))
-            .and_then(|v| {
-                v.as_str()
-                    .ok_or_else(|| EM::EntryHeaderTypeError2("ref.path", "string"))
-                    .map_err(Error::from)
-            })
-            .map(PathBuf::from)
+            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.path")))?;
+
+        let relative = hdr
+            .read_string("ref.relative")
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.relative")))?;
+
+        config
+            .get(&base)
+            .map(|basepath| basepath.join(relative))
+            .ok_or_else(|| err_msg(format!("Base path '{}' is not configured", base)))
     }
 
-    fn hash_valid<RPG: UniqueRefPathGenerator>(&self) -> Result<bool> {
-        self.get_path()
-            .map(PathBuf::from)
-            .map_err(Error::from)
+    fn set_path<P: AsRef<Path>>(&mut self, path: P, config: &Config) -> Result<()> {
+        let hdr = self.get_header_mut();
+        let _   = hdr.delete("ref.path")?;
+        let _   = hdr.delete("ref.base")?;
+        let _   = hdr.delete("ref.relative")?;
+
+        match config.split(path.as_ref()) {
+            Some((base, relative)) => {
+                let relative = relative.to_str().map(String::from).ok_or_else(|| EM::UTF8Error)?;
+                debug!("Storing ref relative to base path '{}': {}", base, relative);
+                let _ = hdr.insert("ref.base", Value::String(String::from(base)))?;
+                let _ = hdr.insert("ref.relative", Value::String(relative))?;
+            },
+            None => {
+                let path = path.as_ref().to_str().map(String::from).ok_or_else(|| EM::UTF8Error)?;
+                let _    = hdr.insert("ref.path", Value::String(path))?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn hash_valid<RPG: UniqueRefPathGenerator>(&self, config: &Config) -> Result<bool> {
+        self.get_path(config)
             .and_then(|pb| RPG::unique_hash(pb))
             .and_then(|h| Ok(h == self.get_hash()?))
     }
 
+    fn relocate_by_hash(&mut self, hashes: &BTreeMap<String, PathBuf>, config: &Config)
+        -> Result<Option<PathBuf>>
+    {
+        let path = match hashes.get(self.get_hash()?) {
+            Some(path) => path.clone(),
+            None       => return Ok(None),
+        };
+
+        debug!("Found '{}' for hash '{}'", path.display(), self.get_hash()?);
+        let _ = self.set_path(&path, config)?;
+        Ok(Some(path))
+    }
+
+    fn make_portable(&mut self, config: &Config) -> Result<bool> {
+        let path = match self.get_header().read_string("ref.path").map_err(Error::from)? {
+            Some(path) => PathBuf::from(path),
+            None       => return Ok(false), // already relative to a base path
+        };
+
+        if config.split(&path).is_none() {
+            return Ok(false)
+        }
+
+        self.set_path(path, config).map(|_| true)
+    }
+
     fn remove_ref(&mut self) -> Result<()> {
         let hdr = self.get_header_mut();
         let _   = hdr.delete("ref.hash")?;
         let _   = hdr.delete("ref.path")?;
+        let _   = hdr.delete("ref.base")?;
+        let _   = hdr.delete("ref.relative")?;
+        let _   = hdr.delete("ref.generator")?;
         let _   = hdr.delete("ref")?;
         Ok(())
     }
 
 }
 
+/// Hash the candidate paths with the `UniqueRefPathGenerator`, for `Ref::relocate_by_hash()`
+///
+/// If several candidates have the same hash, the first one is kept. Candidates which cannot be
+/// hashed are skipped.
+pub fn hash_candidates<RPG, I, P>(candidates: I) -> BTreeMap<String, PathBuf>
+    where RPG: UniqueRefPathGenerator,
+          I: IntoIterator<Item = P>,
+          P: AsRef<Path>
+{
+    let mut hashes = BTreeMap::new();
+
+    for candidate in candidates {
+        let candidate = candidate.as_ref();
+        match RPG::unique_hash(candidate) {
+            Ok(h)  => {
+                let _ = hashes.entry(h).or_insert_with(|| candidate.to_path_buf());
+            },
+            Err(e) => debug!("Skipping '{}': {:?}", candidate.display(), e),
+        }
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+
+    use super::*;
+    use config::Config;
+    use refstore::RefStore;
+    use refstore::UniqueRefPathGenerator;
+
+    use failure::Fallible as Result;
+    use failure::err_msg;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    /// Hashes a path by its file name, so the tests do not need files on disk
+    struct FileName;
+
+    impl UniqueRefPathGenerator for FileName {
+        fn unique_hash<A: AsRef<Path>>(path: A) -> Result<String> {
+            path.as_ref()
+                .file_name()
+                .and_then(|n| n.to_str())
+                .filter(|n| !n.starts_with("unhashable"))
+                .map(String::from)
+                .ok_or_else(|| err_msg("Cannot hash"))
+        }
+    }
+
+    fn config() -> Config {
+        let mut map = BTreeMap::new();
+        map.insert(String::from("music"), PathBuf::from("/mnt/music"));
+        Config::new(map)
+    }
+
+    #[test]
+    fn test_relocate_finds_matching_candidate() {
+        let store     = get_store();
+        let config    = config();
+        let mut entry = store.create_ref::<FileName, _>("/old/track.mp3", &config).unwrap();
+
+        let candidates = vec!["/new/other.mp3", "/mnt/music/track.mp3"];
+        let res = entry.relocate::<FileName, _, _>(candidates, &config).unwrap();
+
+        assert_eq!(Some(PathBuf::from("/mnt/music/track.mp3")), res);
+        assert_eq!(PathBuf::from("/mnt/music/track.mp3"), entry.get_path(&config).unwrap());
+        assert_eq!("track.mp3", entry.get_hash().unwrap());
+    }
+
+    #[test]
+    fn test_relocate_without_match_keeps_path() {
+        let store     = get_store();
+        let config    = config();
+        let mut entry = store.create_ref::<FileName, _>("/old/track.mp3", &config).unwrap();
+
+        let candidates = vec!["/new/other.mp3", "/new/unhashable.mp3"];
+        let res = entry.relocate::<FileName, _, _>(candidates, &config).unwrap();
+
+        assert_eq!(None, res);
+        assert_eq!(PathBuf::from("/old/track.mp3"), entry.get_path(&config).unwrap());
+    }
+
+    #[test]
+    fn test_hash_candidates_keeps_first_and_skips_unhashable() {
+        let candidates = vec!["/a/track.mp3", "/b/track.mp3", "/c/unhashable.mp3"];
+        let hashes     = hash_candidates::<FileName, _, _>(candidates);
+
+        assert_eq!(1, hashes.len());
+        assert_eq!(Some(&PathBuf::from("/a/track.mp3")), hashes.get("track.mp3"));
+    }
+
+    #[test]
+    fn test_relocate_by_hash_relocates_several_refs() {
+        let store   = get_store();
+        let config  = config();
+        let mut one = store.create_ref::<FileName, _>("/old/one.mp3", &config).unwrap();
+        let mut two = store.create_ref::<FileName, _>("/old/two.mp3", &config).unwrap();
+
+        let hashes = hash_candidates::<FileName, _, _>(vec!["/new/two.mp3", "/new/one.mp3"]);
+
+        assert_eq!(Some(PathBuf::from("/new/one.mp3")), one.relocate_by_hash(&hashes, &config).unwrap());
+        assert_eq!(Some(PathBuf::from("/new/two.mp3")), two.relocate_by_hash(&hashes, &config).unwrap());
+    }
+
+    #[test]
+    fn test_generator_is_stored_and_removed() {
+        let store     = get_store();
+        let config    = config();
+        let mut entry = store.create_ref::<FileName, _>("/old/track.mp3", &config).unwrap();
+        assert_eq!(None, entry.get_generator().unwrap());
+
+        entry.set_generator("file-name").unwrap();
+        assert_eq!(Some(String::from("file-name")), entry.get_generator().unwrap());
+
+        entry.remove_ref().unwrap();
+        assert_eq!(None, entry.get_generator().unwrap());
+    }
+
+    #[test]
+    fn test_ref_in_other_collection() {
+        let store  = get_store();
+        let config = config();
+
+        {
+            let entry = store.retrieve_ref_in::<FileName, _>("photo", "/old/img.jpg", &config).unwrap();
+            assert_eq!(&PathBuf::from("photo/img.jpg"), entry.get_location().local());
+            assert!(entry.is_ref().unwrap());
+        }
+
+        assert!(store.get_ref_in("photo", "img.jpg").unwrap().is_some());
+        assert!(store.get_ref::<FileName, _>("img.jpg").unwrap().is_none());
+    }
+}
@@ -24,6 +24,7 @@ use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 
+use config::Config;
 use reference::Ref;
 
 use failure::Fallible as Result;
@@ -67,7 +68,8 @@ pub trait UniqueRefPathGenerator {
 /// The StoreId which is generated is then used to carry out the actual action (reading, creating
 /// ...).
 /// If a entry is created, header information is set (that it is a ref, the hash which was just
-/// generated and the path of the referenced file)
+/// generated and the path of the referenced file, relative to a base path from the `Config` if
+/// possible)
 ///
 /// # Details
 ///
@@ -78,11 +80,18 @@ pub trait UniqueRefPathGenerator {
 ///   a hint.
 /// * The `UniqueRefPathGenerator` is a functor which does not carry state.
 ///
+/// The `*_in()` variants put the ref into the passed collection instead of the one of the
+/// `UniqueRefPathGenerator`, so one generator can be used for refs in several collections.
+///
 pub trait RefStore<'a> {
 
     fn get_ref<RPG: UniqueRefPathGenerator, H: AsRef<str>>(&'a self, hash: H) -> Result<Option<FileLockEntry<'a>>>;
-    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A) -> Result<FileLockEntry<'a>>;
-    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A) -> Result<FileLockEntry<'a>>;
+    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
+    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
+
+    fn get_ref_in<H: AsRef<str>>(&'a self, collection: &str, hash: H) -> Result<Option<FileLockEntry<'a>>>;
+    fn create_ref_in<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, collection: &str, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
+    fn retrieve_ref_in<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, collection: &str, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
 
 }
 
@@ -91,7 +100,25 @@ impl<'a> RefStore<'a> for Store {
     fn get_ref<RPG: UniqueRefPathGenerator, H: AsRef<str>>(&'a self, hash: H)
         -> Result<Option<FileLockEntry<'a>>>
     {
-        let sid = StoreId::new_baseless(PathBuf::from(format!("{}/{}", RPG::collection(), hash.as_ref())))
+        self.get_ref_in(RPG::collection(), hash)
+    }
+
+    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config)
+        -> Result<FileLockEntry<'a>>
+    {
+        self.create_ref_in::<RPG, A>(RPG::collection(), path, config)
+    }
+
+    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config)
+        -> Result<FileLockEntry<'a>>
+    {
+        self.retrieve_ref_in::<RPG, A>(RPG::collection(), path, config)
+    }
+
+    fn get_ref_in<H: AsRef<str>>(&'a self, collection: &str, hash: H)
+        -> Result<Option<FileLockEntry<'a>>>
+    {
+        let sid = StoreId::new_baseless(PathBuf::from(format!("{}/{}", collection, hash.as_ref())))
             .map_err(Error::from)?;
 
         debug!("Getting: {:?}", sid);
@@ -99,30 +126,33 @@ impl<'a> RefStore<'a> for Store {
             .map_err(Error::from)
     }
 
-    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A)
+    fn create_ref_in<RPG, A>(&'a self, collection: &str, path: A, config: &Config)
         -> Result<FileLockEntry<'a>>
+        where RPG: UniqueRefPathGenerator,
+              A: AsRef<Path>
     {
         let hash     = RPG::unique_hash(&path)?;
-        let pathbuf  = PathBuf::from(format!("{}/{}", RPG::collection(), hash));
+        let pathbuf  = PathBuf::from(format!("{}/{}", collection, hash));
         let sid      = StoreId::new_baseless(pathbuf.clone())?;
 
         debug!("Creating: {:?}", sid);
         self.create(sid)
             .map_err(Error::from)
             .and_then(|mut fle| {
-                fle.make_ref(hash, path)?;
+                fle.make_ref(hash, path, config)?;
                 Ok(fle)
             })
     }
 
-    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A)
+    fn retrieve_ref_in<RPG, A>(&'a self, collection: &str, path: A, config: &Config)
         -> Result<FileLockEntry<'a>>
+        where RPG: UniqueRefPathGenerator,
+              A: AsRef<Path>
     {
-        match self.get_ref::<RPG, String>(RPG::unique_hash(path.as_ref())?)? {
+        match self.get_ref_in(collection, RPG::unique_hash(path.as_ref())?)? {
             Some(r) => Ok(r),
-            None    => self.create_ref::<RPG, A>(path),
+            None    => self.create_ref_in::<RPG, A>(collection, path, config),
         }
     }
 
 }
-
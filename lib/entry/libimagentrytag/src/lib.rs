@@ -47,9 +47,13 @@ extern crate toml_query;
 extern crate filters;
 #[macro_use] extern crate failure;
 
-extern crate libimagstore;
+#[macro_use] extern crate libimagstore;
 extern crate libimagerror;
+#[macro_use] extern crate libimagentryutil;
 
 pub mod tag;
 pub mod tagable;
+pub mod registry;
+
+module_entry_path_mod!("tag");
 
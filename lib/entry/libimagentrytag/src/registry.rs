@@ -0,0 +1,509 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The tag registry
+//!
+//! Tags are plain strings in the header of an entry. The registry is an optional collection of
+//! entries in the store (`tag/<name>`), one for each known tag, which holds meta information
+//! about the tag: a description, a display color and a list of aliases.
+//!
+//! With the registry, typos can be detected: `Tagable::add_tag_checked()` rejects tags which are
+//! not registered, `Tagable::add_tag()` and `Tagable::set_tags()` do so if `tag.registry.strict` is
+//! set in the configuration.
+
+use std::path::PathBuf;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::delete::TomlValueDeleteExt;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::StoreId;
+use libimagstore::storeid::StoreIdIterator;
+use libimagentryutil::isa::Is;
+use libimagentryutil::isa::IsKindHeaderPathProvider;
+use libimagerror::errors::ErrorMsg as EM;
+
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+use tag::{Tag, TagSlice};
+use tag::is_tag_str;
+
+pub const TAG_REGISTER_NAME_FIELD_PATH        : &'static str = "tag.register.name";
+pub const TAG_REGISTER_DESCRIPTION_FIELD_PATH : &'static str = "tag.register.description";
+pub const TAG_REGISTER_COLOR_FIELD_PATH       : &'static str = "tag.register.color";
+pub const TAG_REGISTER_ALIASES_FIELD_PATH     : &'static str = "tag.register.aliases";
+
+provide_kindflag_path!(pub IsTag, "tag.is_tag");
+
+/// Extension on the Store to make it a register for tags
+///
+/// The register writes one entry per tag to the `tag` collection in the store.
+pub trait TagStore {
+
+    fn tag_exists(&self, name: TagSlice) -> Result<bool>;
+
+    fn create_tag<'a>(&'a self, name: TagSlice) -> Result<FileLockEntry<'a>>;
+
+    fn delete_tag(&self, name: TagSlice) -> Result<()>;
+
+    fn all_tag_names(&self) -> Result<TagNameIter>;
+
+    fn get_tag_by_name(&self, name: TagSlice) -> Result<Option<FileLockEntry>>;
+
+    fn resolve_tag(&self, name: TagSlice) -> Result<Option<Tag>>;
+
+    fn add_tag_alias(&self, name: TagSlice, alias: Tag) -> Result<()>;
+
+}
+
+impl TagStore for Store {
+
+    /// Check whether a tag is registered
+    ///
+    /// Aliases are not considered here, use `TagStore::resolve_tag()` for that.
+    fn tag_exists(&self, name: TagSlice) -> Result<bool> {
+        trace!("Tag exists? '{}'", name);
+        let sid = mk_tag_storeid(self.path().clone(), name)?;
+        represents_tag(self, sid, name)
+    }
+
+    /// Register a tag
+    ///
+    /// Fails if the tag is already registered, either by name or as alias of another tag.
+    fn create_tag<'a>(&'a self, name: TagSlice) -> Result<FileLockEntry<'a>> {
+        trace!("Registering tag: '{}'", name);
+        let _ = is_tag_str(&String::from(name)).map_err(|s| format_err!("{}", s))?;
+
+        if let Some(existing) = self.resolve_tag(name)? {
+            return Err(format_err!("Tag '{}' is already registered as '{}'", name, existing))
+        }
+
+        let sid       = mk_tag_storeid(self.path().clone(), name)?;
+        let mut entry = self.create(sid)?;
+
+        entry.set_isflag::<IsTag>()?;
+
+        let _ = entry
+            .get_header_mut()
+            .insert(TAG_REGISTER_NAME_FIELD_PATH, Value::String(String::from(name)))?;
+
+        trace!("Registering tag worked: '{}'", name);
+        Ok(entry)
+    }
+
+    /// Remove a tag from the registry
+    ///
+    /// # Warning
+    ///
+    /// This does _only_ remove the registry entry. Entries which are tagged with this tag keep
+    /// their tag.
+    fn delete_tag(&self, name: TagSlice) -> Result<()> {
+        trace!("Deleting tag: '{}'", name);
+        if !self.tag_exists(name)? {
+            return Err(format_err!("Tag '{}' is not registered", name))
+        }
+
+        let sid = mk_tag_storeid(self.path().clone(), name)?;
+        self.delete(sid)
+    }
+
+    /// Get all registered tag names
+    fn all_tag_names(&self) -> Result<TagNameIter> {
+        trace!("Getting all registered tag names");
+        Ok(TagNameIter::new(self, self.entries()?.in_collection("tag").without_store()))
+    }
+
+    /// Get a registered tag by its name
+    ///
+    /// Returns the FileLockEntry which represents the tag in the registry.
+    fn get_tag_by_name(&self, name: TagSlice) -> Result<Option<FileLockEntry>> {
+        trace!("Getting tag by name: '{}'", name);
+        let sid = mk_tag_storeid(self.path().clone(), name)?;
+
+        self.get(sid)
+            .context(err_msg("Store read error"))
+            .map_err(Error::from)
+    }
+
+    /// Resolve a tag name or alias to the name of the registered tag
+    ///
+    /// Returns `None` if neither a tag nor an alias with this name is registered.
+    fn resolve_tag(&self, name: TagSlice) -> Result<Option<Tag>> {
+        trace!("Resolving tag: '{}'", name);
+        if self.tag_exists(name)? {
+            return Ok(Some(String::from(name)))
+        }
+
+        for tagname in self.all_tag_names()? {
+            let tagname = tagname?;
+            let entry   = self
+                .get_tag_by_name(&tagname)?
+                .ok_or_else(|| Error::from(err_msg("Store read error")))?;
+
+            if entry.get_aliases()?.iter().any(|alias| alias == name) {
+                return Ok(Some(tagname))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Add an alias to a registered tag
+    ///
+    /// Fails if the alias is already registered, either as tag or as alias of another tag.
+    fn add_tag_alias(&self, name: TagSlice, alias: Tag) -> Result<()> {
+        trace!("Adding alias '{}' to tag '{}'", alias, name);
+        let _ = is_tag_str(&alias).map_err(|s| format_err!("{}", s))?;
+
+        if let Some(existing) = self.resolve_tag(&alias)? {
+            if existing != name || alias == name {
+                return Err(format_err!("Cannot use '{}' as alias, it is already registered as '{}'",
+                                       alias, existing))
+            }
+        }
+
+        let mut entry = self
+            .get_tag_by_name(name)?
+            .ok_or_else(|| format_err!("Tag '{}' is not registered", name))?;
+
+        let mut aliases = entry.get_aliases()?;
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+
+        set_aliases(&mut entry, aliases)
+    }
+
+}
+
+/// Accessors for the meta information of an entry in the tag registry
+pub trait RegisteredTag {
+    fn is_registered_tag(&self) -> Result<bool>;
+    fn get_tag_name(&self) -> Result<Tag>;
+
+    fn get_description(&self) -> Result<Option<String>>;
+    fn set_description(&mut self, description: &str) -> Result<()>;
+
+    fn get_color(&self) -> Result<Option<String>>;
+    fn set_color(&mut self, color: &str) -> Result<()>;
+    fn unset_color(&mut self) -> Result<()>;
+
+    fn get_aliases(&self) -> Result<Vec<Tag>>;
+    fn remove_alias(&mut self, alias: TagSlice) -> Result<()>;
+}
+
+impl RegisteredTag for Entry {
+
+    fn is_registered_tag(&self) -> Result<bool> {
+        self.is::<IsTag>()
+    }
+
+    fn get_tag_name(&self) -> Result<Tag> {
+        trace!("Getting tag name of '{:?}'", self.get_location());
+        self.get_header()
+            .read_string(TAG_REGISTER_NAME_FIELD_PATH)
+            .map_err(Error::from)?
+            .ok_or_else(|| Error::from(err_msg("Tag name missing")))
+    }
+
+    fn get_description(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string(TAG_REGISTER_DESCRIPTION_FIELD_PATH)
+            .map_err(Error::from)
+            .context(EM::EntryHeaderReadError)
+            .map_err(Error::from)
+    }
+
+    fn set_description(&mut self, description: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert(TAG_REGISTER_DESCRIPTION_FIELD_PATH, Value::String(String::from(description)))
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn get_color(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string(TAG_REGISTER_COLOR_FIELD_PATH)
+            .map_err(Error::from)
+            .context(EM::EntryHeaderReadError)
+            .map_err(Error::from)
+    }
+
+    /// Set the display color of the tag
+    ///
+    /// The color is not validated here, but should be the name of one of the color helpers from
+    /// `libimaginteraction::format` (for example "red" or "blue").
+    fn set_color(&mut self, color: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert(TAG_REGISTER_COLOR_FIELD_PATH, Value::String(String::from(color)))
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn unset_color(&mut self) -> Result<()> {
+        self.get_header_mut()
+            .delete(TAG_REGISTER_COLOR_FIELD_PATH)
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn get_aliases(&self) -> Result<Vec<Tag>> {
+        match self.get_header()
+            .read(TAG_REGISTER_ALIASES_FIELD_PATH)
+            .map_err(Error::from)
+            .context(EM::EntryHeaderReadError)?
+        {
+            None => Ok(vec![]),
+            Some(&Value::Array(ref aliases)) => aliases
+                .iter()
+                .map(|alias| match *alias {
+                    Value::String(ref s) => Ok(s.clone()),
+                    _ => Err(Error::from(EM::EntryHeaderTypeError)),
+                })
+                .collect(),
+            Some(_) => Err(Error::from(EM::EntryHeaderTypeError)),
+        }
+    }
+
+    fn remove_alias(&mut self, alias: TagSlice) -> Result<()> {
+        let mut aliases = self.get_aliases()?;
+        aliases.retain(|a| a != alias);
+        set_aliases(self, aliases)
+    }
+
+}
+
+/// Iterator for registered tag names
+///
+/// Iterates over Result<Tag>
+pub struct TagNameIter<'a>(&'a Store, StoreIdIterator);
+
+impl<'a> TagNameIter<'a> {
+
+    pub(crate) fn new(store: &'a Store, sidit: StoreIdIterator) -> TagNameIter<'a> {
+        TagNameIter(store, sidit)
+    }
+
+}
+
+impl<'a> Iterator for TagNameIter<'a> {
+    type Item = Result<Tag>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(sid) = self.1.next() {
+            match sid {
+                Err(e) => return Some(Err(e)),
+                Ok(sid) => {
+                    if sid.is_in_collection(&["tag"]) {
+                        let func = |store: &Store| {
+                            store
+                                .get(sid)?
+                                .ok_or_else(|| Error::from(err_msg("Store read error")))?
+                                .get_tag_name()
+                        };
+
+                        return Some(func(&self.0))
+                    }
+                },
+            } // else continue
+        }
+
+        None
+    }
+}
+
+/// Check whether the tag registry is configured to be strict
+///
+/// In strict mode, only tags which are registered (or aliases of registered tags) can be added to
+/// entries.
+pub fn is_strict(config: Option<&Value>) -> Result<bool> {
+    match config {
+        Some(cfg) => cfg
+            .read_bool("tag.registry.strict")
+            .map_err(Error::from)
+            .context(err_msg("Error reading tag.registry.strict from the configuration"))
+            .map(|strict| strict.unwrap_or(false))
+            .map_err(Error::from),
+        None => Ok(false),
+    }
+}
+
+fn set_aliases(entry: &mut Entry, aliases: Vec<Tag>) -> Result<()> {
+    let aliases = aliases.into_iter().map(Value::String).collect();
+    entry.get_header_mut()
+        .insert(TAG_REGISTER_ALIASES_FIELD_PATH, Value::Array(aliases))
+        .map_err(Error::from)
+        .context(EM::EntryHeaderWriteError)
+        .map_err(Error::from)
+        .map(|_| ())
+}
+
+#[inline]
+fn mk_tag_storeid(base: PathBuf, s: TagSlice) -> Result<StoreId> {
+    use libimagstore::storeid::IntoStoreId;
+    ::module_path::ModuleEntryPath::new(s)
+        .into_storeid()
+        .map(|id| id.with_base(base))
+        .context(err_msg("Store id handling error"))
+        .map_err(Error::from)
+}
+
+#[inline]
+fn represents_tag(store: &Store, sid: StoreId, name: TagSlice) -> Result<bool> {
+    match store.get(sid).context(err_msg("Store read error"))? {
+        Some(fle) => fle.get_tag_name().map(|s| s == name),
+        None      => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use tagable::Tagable;
+
+    use libimagstore::store::Store;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_non_existing_tag_exists() {
+        let exists = get_store().tag_exists("nonexistent");
+
+        assert!(exists.is_ok(), format!("Expected Ok(_), got: {:?}", exists));
+        assert!(!exists.unwrap());
+    }
+
+    #[test]
+    fn test_creating_tag_creates_store_entry() {
+        let store = get_store();
+
+        {
+            let res = store.create_tag("exampletag");
+            assert!(res.is_ok(), format!("Expected Ok(_), got: {:?}", res));
+        }
+
+        let tag = store.get(PathBuf::from("tag/exampletag"));
+        assert!(tag.is_ok(), format!("Expected Ok(_), got: {:?}", tag));
+
+        let tag = tag.unwrap();
+        assert!(tag.is_some());
+        assert_eq!("exampletag", tag.unwrap().get_tag_name().unwrap());
+    }
+
+    #[test]
+    fn test_creating_tag_twice_fails() {
+        let store = get_store();
+        let _     = store.create_tag("exampletag").unwrap();
+
+        assert!(store.create_tag("exampletag").is_err());
+    }
+
+    #[test]
+    fn test_resolving_alias() {
+        let store = get_store();
+
+        let _ = store.create_tag("exampletag").unwrap();
+        store.add_tag_alias("exampletag", String::from("ex")).unwrap();
+
+        assert_eq!(Some(String::from("exampletag")), store.resolve_tag("exampletag").unwrap());
+        assert_eq!(Some(String::from("exampletag")), store.resolve_tag("ex").unwrap());
+        assert_eq!(None, store.resolve_tag("exmaple").unwrap());
+    }
+
+    #[test]
+    fn test_checked_tagging_rejects_unknown_tags() {
+        let store = get_store();
+
+        let _ = store.create_tag("exampletag").unwrap();
+        store.add_tag_alias("exampletag", String::from("ex")).unwrap();
+
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        assert!(entry.add_tag_checked(&store, String::from("exmaple")).is_err());
+        assert!(entry.add_tag_checked(&store, String::from("ex")).is_ok());
+        assert_eq!(vec![String::from("exampletag")], entry.get_tags().unwrap());
+    }
+
+    #[test]
+    fn test_configured_tagging_respects_strict_mode() {
+        use toml::from_str;
+
+        let store = get_store();
+
+        let _ = store.create_tag("exampletag").unwrap();
+        store.add_tag_alias("exampletag", String::from("ex")).unwrap();
+
+        let strict     : Value = from_str("[tag.registry]\nstrict = true").unwrap();
+        let not_strict : Value = from_str("[tag.registry]\nstrict = false").unwrap();
+
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+
+        assert!(entry.add_tag(&store, Some(&strict), String::from("unknown")).is_err());
+        assert!(entry.add_tag(&store, Some(&strict), String::from("ex")).is_ok());
+        assert!(entry.add_tag(&store, Some(&not_strict), String::from("unknown")).is_ok());
+        assert!(entry.add_tag(&store, None, String::from("other")).is_ok());
+
+        let mut tags = entry.get_tags().unwrap();
+        tags.sort();
+        assert_eq!(vec!["exampletag", "other", "unknown"], tags);
+
+        let tags = vec![String::from("ex"), String::from("unknown")];
+        assert!(entry.set_tags(&store, Some(&strict), &tags).is_err());
+        assert!(entry.set_tags(&store, Some(&not_strict), &tags).is_ok());
+        assert_eq!(vec!["exampletag", "unknown"], entry.get_tags().unwrap());
+    }
+
+    #[test]
+    fn test_alias_must_not_collide() {
+        let store = get_store();
+        let _     = store.create_tag("exampletag").unwrap();
+        let _     = store.create_tag("other").unwrap();
+
+        assert!(store.add_tag_alias("exampletag", String::from("ex")).is_ok());
+        assert!(store.add_tag_alias("exampletag", String::from("ex")).is_ok());
+        assert!(store.add_tag_alias("exampletag", String::from("exampletag")).is_err());
+        assert!(store.add_tag_alias("exampletag", String::from("other")).is_err());
+        assert!(store.add_tag_alias("other", String::from("ex")).is_err());
+        assert!(store.add_tag_alias("missing", String::from("mi")).is_err());
+
+        let aliases = store.get_tag_by_name("exampletag").unwrap().unwrap().get_aliases().unwrap();
+        assert_eq!(vec!["ex"], aliases);
+    }
+}
@@ -31,15 +31,18 @@ use failure::Fallible as Result;
 use failure::err_msg;
 use tag::{Tag, TagSlice};
 use tag::is_tag_str;
+use registry::TagStore;
+use registry::is_strict;
 
 use toml::Value;
 
 pub trait Tagable {
 
     fn get_tags(&self) -> Result<Vec<Tag>>;
-    fn set_tags(&mut self, ts: &[Tag]) -> Result<()>;
+    fn set_tags(&mut self, register: &TagStore, config: Option<&Value>, ts: &[Tag]) -> Result<()>;
 
-    fn add_tag(&mut self, t: Tag) -> Result<()>;
+    fn add_tag(&mut self, register: &TagStore, config: Option<&Value>, t: Tag) -> Result<()>;
+    fn add_tag_checked(&mut self, register: &TagStore, t: Tag) -> Result<()>;
     fn remove_tag(&mut self, t: Tag) -> Result<()>;
 
     fn has_tag(&self, t: TagSlice) -> Result<bool>;
@@ -84,34 +87,34 @@ impl Tagable for Value {
             .unwrap_or(Ok(vec![]))
     }
 
-    fn set_tags(&mut self, ts: &[Tag]) -> Result<()> {
-        if ts.iter().any(|tag| !is_tag_str(tag).is_ok()) {
-            let not_tag = ts.iter().filter(|t| !is_tag_str(t).is_ok()).next().unwrap();
-            return Err(format_err!("Not a tag: '{}'", not_tag));
-        }
-
-        let a = ts.iter().unique().map(|t| Value::String(t.clone())).collect();
-        debug!("Setting tags = {:?}", a);
-        self.insert("tag.values", Value::Array(a))
-            .map(|_| ())
-            .map_err(|_| Error::from(EM::EntryHeaderWriteError))
+    /// Set the tags of the entry, honoring `tag.registry.strict` from the configuration
+    ///
+    /// Aliases are resolved to the name of the registered tag. In strict mode, unknown tags are
+    /// rejected and no tag is set.
+    fn set_tags(&mut self, register: &TagStore, config: Option<&Value>, ts: &[Tag]) -> Result<()> {
+        let strict = is_strict(config)?;
+        let tags   = ts.iter()
+            .map(|t| resolve(register, strict, t.clone()))
+            .collect::<Result<Vec<Tag>>>()?;
+
+        set_tags_unchecked(self, &tags)
     }
 
-    fn add_tag(&mut self, t: Tag) -> Result<()> {
-        if !is_tag_str(&t).map(|_| true)
-            .map_err(|s| format_err!("{}", s))
-            .context(err_msg("Not a tag"))?
-        {
-            return Err(format_err!("Not a tag: '{}'", t));
-        }
+    /// Add a tag, honoring `tag.registry.strict` from the configuration
+    ///
+    /// In strict mode, this is `add_tag_checked()`. Otherwise aliases are resolved and unknown tags
+    /// are added as they are.
+    fn add_tag(&mut self, register: &TagStore, config: Option<&Value>, t: Tag) -> Result<()> {
+        let tag = resolve(register, is_strict(config)?, t)?;
+        add_tag_unchecked(self, tag)
+    }
 
-        self.get_tags()
-            .map(|mut tags| {
-                debug!("Pushing tag = {:?} to list = {:?}", t, tags);
-                tags.push(t);
-                self.set_tags(&tags.into_iter().unique().collect::<Vec<_>>()[..])
-            })
-            .map(|_| ())
+    /// Check whether a tag is registered before adding it.
+    ///
+    /// Aliases are resolved to the name of the registered tag. Unknown tags are rejected.
+    fn add_tag_checked(&mut self, register: &TagStore, t: Tag) -> Result<()> {
+        let tag = resolve(register, true, t)?;
+        add_tag_unchecked(self, tag)
     }
 
     fn remove_tag(&mut self, t: Tag) -> Result<()> {
@@ -126,7 +129,7 @@ impl Tagable for Value {
         self.get_tags()
             .map(|mut tags| {
                 tags.retain(|tag| tag.clone() != t);
-                self.set_tags(&tags[..])
+                set_tags_unchecked(self, &tags[..])
             })
             .map(|_| ())
     }
@@ -165,12 +168,16 @@ impl Tagable for Entry {
         self.get_header().get_tags()
     }
 
-    fn set_tags(&mut self, ts: &[Tag]) -> Result<()> {
-        self.get_header_mut().set_tags(ts)
+    fn set_tags(&mut self, register: &TagStore, config: Option<&Value>, ts: &[Tag]) -> Result<()> {
+        self.get_header_mut().set_tags(register, config, ts)
+    }
+
+    fn add_tag(&mut self, register: &TagStore, config: Option<&Value>, t: Tag) -> Result<()> {
+        self.get_header_mut().add_tag(register, config, t)
     }
 
-    fn add_tag(&mut self, t: Tag) -> Result<()> {
-        self.get_header_mut().add_tag(t)
+    fn add_tag_checked(&mut self, register: &TagStore, t: Tag) -> Result<()> {
+        self.get_header_mut().add_tag_checked(register, t)
     }
 
     fn remove_tag(&mut self, t: Tag) -> Result<()> {
@@ -187,3 +194,41 @@ impl Tagable for Entry {
 
 }
 
+/// Resolve `t` through the registry
+///
+/// Aliases are resolved to the name of the registered tag. Unknown tags are rejected if `strict`
+/// is set and kept as they are otherwise.
+fn resolve(register: &TagStore, strict: bool, t: Tag) -> Result<Tag> {
+    match register.resolve_tag(&t)? {
+        Some(tag)       => Ok(tag),
+        None if strict  => Err(format_err!("Tag is not registered: '{}'", t)),
+        None            => Ok(t),
+    }
+}
+
+fn set_tags_unchecked(header: &mut Value, ts: &[Tag]) -> Result<()> {
+    if ts.iter().any(|tag| !is_tag_str(tag).is_ok()) {
+        let not_tag = ts.iter().filter(|t| !is_tag_str(t).is_ok()).next().unwrap();
+        return Err(format_err!("Not a tag: '{}'", not_tag));
+    }
+
+    let a = ts.iter().unique().map(|t| Value::String(t.clone())).collect();
+    debug!("Setting tags = {:?}", a);
+    header.insert("tag.values", Value::Array(a))
+        .map(|_| ())
+        .map_err(|_| Error::from(EM::EntryHeaderWriteError))
+}
+
+fn add_tag_unchecked(header: &mut Value, t: Tag) -> Result<()> {
+    if !is_tag_str(&t).map(|_| true)
+        .map_err(|s| format_err!("{}", s))
+        .context(err_msg("Not a tag"))?
+    {
+        return Err(format_err!("Not a tag: '{}'", t));
+    }
+
+    let mut tags = header.get_tags()?;
+    debug!("Pushing tag = {:?} to list = {:?}", t, tags);
+    tags.push(t);
+    set_tags_unchecked(header, &tags.into_iter().unique().collect::<Vec<_>>()[..])
+}
@@ -0,0 +1,112 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Helpers for binaries which create entries from templates
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use clap::Arg;
+use clap::ArgMatches;
+use toml_query::read::TomlValueReadTypeExt;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+use libimagrt::runtime::Runtime;
+use libimagstore::store::Entry;
+use libimaginteraction::ask::ask_string;
+
+use store::TemplateStore;
+use template::Template;
+use template::default_data;
+
+pub const TEMPLATE_ARG_NAME: &'static str = "template";
+
+/// The `--template <name>` argument
+pub fn template_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(TEMPLATE_ARG_NAME)
+        .long("template")
+        .takes_value(true)
+        .required(false)
+        .multiple(false)
+        .value_name("TEMPLATE")
+        .help("Use this template for the new entry: an entry 'template/<TEMPLATE>' or a file '<TEMPLATE>.md' in the template directory")
+}
+
+/// The directory with template files, `template.directory` in the configuration or
+/// `<runtimepath>/templates`
+pub fn template_directory(rt: &Runtime) -> Result<PathBuf> {
+    let configured = match rt.config() {
+        Some(config) => config.read_string("template.directory").map_err(Error::from)?,
+        None         => None,
+    };
+
+    Ok(configured.map(PathBuf::from).unwrap_or_else(|| rt.rtp().join("templates")))
+}
+
+/// Find a template by name, in the store first and then in the template directory
+pub fn find_template(rt: &Runtime, name: &str) -> Result<Template> {
+    if let Some(template) = rt.store().get_template(name)? {
+        return Ok(template)
+    }
+
+    let path = template_directory(rt)?.join(format!("{}.md", name));
+    debug!("Looking for template file {}", path.display());
+    if !path.exists() {
+        return Err(format_err!("No template '{}' in the store or at {}", name, path.display()))
+    }
+
+    let mut buf = String::new();
+    let _ = File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut buf))
+        .context(format_err!("Cannot read template file {}", path.display()))?;
+
+    Template::from_str(name, &buf)
+}
+
+/// Apply the template passed with `--template` to the entry, if any
+///
+/// The variables of the template are asked for interactively.
+/// Returns whether a template was applied.
+pub fn apply_template_from_cli(rt: &Runtime, matches: &ArgMatches, entry: &mut Entry) -> Result<bool> {
+    let name = match matches.value_of(TEMPLATE_ARG_NAME) {
+        Some(name) => name,
+        None       => return Ok(false),
+    };
+
+    let template = find_template(rt, name)?;
+    let mut data = default_data(entry.get_location());
+
+    if !template.prompts().is_empty() {
+        let mut input  = rt
+            .stdin()
+            .ok_or_else(|| err_msg("No input stream, cannot ask for the values of the template"))?;
+        let mut output = rt.stdout();
+
+        for (var, question) in template.prompts() {
+            let answer = ask_string(question, None, true, false, None, "> ", &mut input, &mut output)?;
+            let _ = data.insert(var.clone(), String::from(answer.trim_right_matches('\n')));
+        }
+    }
+
+    template.apply(entry, &data).map(|_| true)
+}
@@ -0,0 +1,94 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Templates in the `template` collection of the store
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+
+use module_path::ModuleEntryPath;
+use template::Template;
+
+pub const TEMPLATE_COLLECTION: &'static str = "template";
+
+pub trait TemplateStore<'a> {
+
+    /// Get the template `template/<name>`
+    fn get_template(&'a self, name: &str) -> Result<Option<Template>>;
+
+    fn all_template_names(&'a self) -> Result<Vec<String>>;
+
+}
+
+impl<'a> TemplateStore<'a> for Store {
+
+    fn get_template(&'a self, name: &str) -> Result<Option<Template>> {
+        match ModuleEntryPath::new(name).into_storeid().and_then(|id| self.get(id))? {
+            Some(entry) => Template::from_entry(name, &entry).map(Some),
+            None        => Ok(None),
+        }
+    }
+
+    fn all_template_names(&'a self) -> Result<Vec<String>> {
+        self.entries()?
+            .in_collection(TEMPLATE_COLLECTION)
+            .map(|id| {
+                let id = id?;
+                id.local()
+                    .strip_prefix(TEMPLATE_COLLECTION)
+                    .ok()
+                    .and_then(|n| n.to_str())
+                    .map(String::from)
+                    .ok_or_else(|| format_err!("Invalid template id: {}", id))
+            })
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_get_template() {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let fs    = InMemoryFileAbstraction::default();
+        let store = Store::new_with_backend(PathBuf::from("/"), &None, Arc::new(fs)).unwrap();
+
+        {
+            let mut entry = store.create(PathBuf::from("template/todo")).unwrap();
+            *entry.get_content_mut() = String::from("- [ ] {{name}}");
+        }
+
+        let template = store.get_template("todo").unwrap().unwrap();
+        let (_, content) = template.render(&vec![
+            (String::from("name"), String::from("write tests")),
+        ].into_iter().collect()).unwrap();
+
+        assert_eq!(content, "- [ ] write tests");
+        assert!(store.get_template("missing").unwrap().is_none());
+        assert_eq!(store.all_template_names().unwrap(), vec![String::from("todo")]);
+    }
+}
@@ -0,0 +1,290 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::Local;
+use handlebars::Handlebars;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+use libimagerror::errors::ErrorMsg as EM;
+
+/// A template for new entries
+///
+/// The header of a template (besides the `imag` and `template` sections) holds the defaults for
+/// the header of new entries, its content is the skeleton for their content. String values in the
+/// header and the content are handlebars templates.
+///
+/// `template.prompts` is a table of variables which are asked for when the template is applied,
+/// mapping the variable names to the questions:
+///
+/// ```toml
+/// [template.prompts]
+/// title = "Title of the meeting"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Template {
+    name: String,
+    header: Value,
+    content: String,
+    prompts: BTreeMap<String, String>,
+}
+
+impl Template {
+
+    /// Build a template from an entry of the `template` collection
+    pub fn from_entry(name: &str, entry: &Entry) -> Result<Template> {
+        Template::from_parts(name, entry.get_header().clone(), entry.get_content().clone())
+    }
+
+    /// Build a template from a template file, which has the same format as an entry in the store
+    pub fn from_str(name: &str, s: &str) -> Result<Template> {
+        let id    = StoreId::new_baseless(PathBuf::from("template").join(name))?;
+        let entry = Entry::from_str(id, s).context(format_err!("Cannot parse template '{}'", name))?;
+        Template::from_entry(name, &entry)
+    }
+
+    fn from_parts(name: &str, mut header: Value, content: String) -> Result<Template> {
+        let prompts = match header.read("template.prompts")? {
+            None                        => BTreeMap::new(),
+            Some(&Value::Table(ref t)) => t
+                .iter()
+                .map(|(var, question)| {
+                    question
+                        .as_str()
+                        .map(|q| (var.clone(), String::from(q)))
+                        .ok_or_else(|| Error::from(EM::EntryHeaderTypeError2("template.prompts", "table of strings")))
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError2("template.prompts", "table"))),
+        };
+
+        match header {
+            Value::Table(ref mut t) => {
+                let _ = t.remove("imag");
+                let _ = t.remove("template");
+            },
+            _ => return Err(Error::from(EM::EntryHeaderTypeError)),
+        }
+
+        Ok(Template {
+            name: String::from(name),
+            header: header,
+            content: content,
+            prompts: prompts,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The variables which have to be asked for, with the questions to ask
+    pub fn prompts(&self) -> &BTreeMap<String, String> {
+        &self.prompts
+    }
+
+    /// Render the header defaults and the content skeleton with `data`
+    pub fn render(&self, data: &BTreeMap<String, String>) -> Result<(Value, String)> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(::handlebars::no_escape);
+
+        let header  = render_value(&handlebars, &self.header, data)?;
+        let content = render_str(&handlebars, &self.content, data)?;
+        Ok((header, content))
+    }
+
+    /// Apply the template to an entry
+    ///
+    /// Header fields which are not yet set in the entry are set to the rendered defaults of the
+    /// template. The content is set to the rendered skeleton if the entry has no content yet.
+    pub fn apply(&self, entry: &mut Entry, data: &BTreeMap<String, String>) -> Result<()> {
+        debug!("Applying template '{}' to {}", self.name, entry.get_location());
+        let (header, content) = self.render(data)?;
+
+        merge_defaults(entry.get_header_mut(), header);
+
+        if entry.get_content().trim().is_empty() {
+            *entry.get_content_mut() = content;
+        } else {
+            debug!("Entry has content already, not using the content of the template");
+        }
+
+        Ok(())
+    }
+
+}
+
+/// The variables which are available in all templates
+///
+/// * `id`: The id of the new entry
+/// * `name`: The last part of the id
+/// * `date`, `time` and `datetime`: The current date and time (`YYYY-MM-DD`, `HH:MM`,
+///   `YYYY-MM-DDTHH:MM:SS`)
+/// * `user`: The `USER` environment variable
+pub fn default_data(id: &StoreId) -> BTreeMap<String, String> {
+    let now      = Local::now().naive_local();
+    let mut data = BTreeMap::new();
+
+    data.insert(String::from("id"), format!("{}", id));
+    data.insert(String::from("name"), id
+        .local()
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(String::new));
+    data.insert(String::from("date"), now.format("%Y-%m-%d").to_string());
+    data.insert(String::from("time"), now.format("%H:%M").to_string());
+    data.insert(String::from("datetime"), now.format("%Y-%m-%dT%H:%M:%S").to_string());
+    data.insert(String::from("user"), ::std::env::var("USER").unwrap_or_else(|_| String::new()));
+
+    data
+}
+
+fn render_str(handlebars: &Handlebars, template: &str, data: &BTreeMap<String, String>) -> Result<String> {
+    handlebars
+        .render_template(template, data)
+        .map_err(Error::from)
+        .context(format_err!("Cannot render template: '{}'", template))
+        .map_err(Error::from)
+}
+
+fn render_value(handlebars: &Handlebars, value: &Value, data: &BTreeMap<String, String>) -> Result<Value> {
+    match *value {
+        Value::String(ref s) => render_str(handlebars, s, data).map(Value::String),
+        Value::Array(ref a)  => a
+            .iter()
+            .map(|v| render_value(handlebars, v, data))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+        Value::Table(ref t)  => t
+            .iter()
+            .map(|(k, v)| render_value(handlebars, v, data).map(|v| (k.clone(), v)))
+            .collect::<Result<BTreeMap<_, _>>>()
+            .map(Value::Table),
+        ref other            => Ok(other.clone()),
+    }
+}
+
+/// Insert the values of `defaults` which are not in `header`, recursing into tables
+fn merge_defaults(header: &mut Value, defaults: Value) {
+    match (header, defaults) {
+        (&mut Value::Table(ref mut header), Value::Table(defaults)) => {
+            for (key, default) in defaults {
+                if header.contains_key(&key) {
+                    let _ = header.get_mut(&key).map(|value| merge_defaults(value, default));
+                } else {
+                    let _ = header.insert(key, default);
+                }
+            }
+        },
+        _ => {
+            // The value is set already, keep it
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use toml::from_str as toml_from_str;
+
+    const TEMPLATE: &'static str = r#"---
+[imag]
+version = "0.10.0"
+
+[template.prompts]
+topic = "What is the meeting about?"
+
+[meeting]
+topic = "{{topic}}"
+attendees = ["{{user}}"]
+important = false
+---
+# Meeting: {{topic}}
+
+{{date}}
+"#;
+
+    fn data() -> BTreeMap<String, String> {
+        let mut data = BTreeMap::new();
+        data.insert(String::from("topic"), String::from("Budget"));
+        data.insert(String::from("user"), String::from("jane"));
+        data.insert(String::from("date"), String::from("2018-10-01"));
+        data
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let template = Template::from_str("meeting", TEMPLATE).unwrap();
+
+        assert_eq!(template.name(), "meeting");
+        assert_eq!(template.prompts().get("topic").map(String::as_str), Some("What is the meeting about?"));
+        assert!(template.header.read("imag").unwrap().is_none());
+        assert!(template.header.read("template").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template          = Template::from_str("meeting", TEMPLATE).unwrap();
+        let (header, content) = template.render(&data()).unwrap();
+
+        let expected : Value = toml_from_str(r#"
+            [meeting]
+            topic = "Budget"
+            attendees = ["jane"]
+            important = false
+        "#).unwrap();
+
+        assert_eq!(header, expected);
+        assert_eq!(content, "# Meeting: Budget\n\n2018-10-01\n");
+    }
+
+    #[test]
+    fn test_apply_keeps_existing_values() {
+        let template  = Template::from_str("meeting", TEMPLATE).unwrap();
+        let id        = StoreId::new_baseless(PathBuf::from("meetings/budget")).unwrap();
+        let mut entry = Entry::from_str(id, "---\n[imag]\nversion = \"0.10.0\"\n\n[meeting]\nimportant = true\n---\nNotes\n").unwrap();
+
+        template.apply(&mut entry, &data()).unwrap();
+
+        let header = entry.get_header();
+        assert_eq!(header.read("meeting.important").unwrap(), Some(&Value::Boolean(true)));
+        assert_eq!(header.read("meeting.topic").unwrap(), Some(&Value::String(String::from("Budget"))));
+        assert_eq!(header.read("imag.version").unwrap(), Some(&Value::String(String::from("0.10.0"))));
+        assert_eq!(entry.get_content(), "Notes\n");
+    }
+
+    #[test]
+    fn test_default_data() {
+        let id   = StoreId::new_baseless(PathBuf::from("notes/foo")).unwrap();
+        let data = default_data(&id);
+
+        assert_eq!(data.get("id").map(String::as_str), Some("notes/foo"));
+        assert_eq!(data.get("name").map(String::as_str), Some("foo"));
+        assert!(data.contains_key("date"));
+    }
+}
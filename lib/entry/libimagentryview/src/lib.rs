@@ -37,9 +37,12 @@
     while_true,
 )]
 
+#[macro_use] extern crate log;
 extern crate toml;
+extern crate toml_query;
 extern crate textwrap;
-extern crate failure;
+extern crate termion;
+#[macro_use] extern crate failure;
 
 #[cfg(feature = "markdownviewer")]
 extern crate mdcat;
@@ -55,6 +58,15 @@ extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagentryedit;
 
+#[cfg(test)]
+#[macro_use]
+extern crate libimagentryutil;
+
+#[cfg(not(test))]
+extern crate libimagentryutil;
+
 pub mod builtin;
+pub mod pager;
+pub mod registry;
 pub mod viewer;
 
@@ -0,0 +1,418 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A pager for viewing many entries
+//!
+//! The pages (one per entry) are either piped to the `$PAGER`, or shown in a built-in pager,
+//! which can be used to navigate between the entries with the keyboard. The pages are taken from
+//! an iterator as they are needed, so the first entry is shown before all entries are rendered.
+
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+
+const HELP : &'static str = "q: quit  j/k: scroll  space/b: page  n/p: next/previous entry";
+
+/// Get the width of the terminal, if the output is a terminal
+pub fn terminal_width() -> Option<usize> {
+    ::termion::terminal_size().ok().map(|(width, _)| width as usize)
+}
+
+/// Build a line `width` wide from `sep`, with `title` in it, to be put above an entry
+pub fn header_line(sep: &str, title: &str, width: usize) -> String {
+    let mut line = format!("{}{} {} ", sep, sep, title);
+
+    if !sep.is_empty() {
+        let sep_len = sep.chars().count();
+        while line.chars().count() + sep_len <= width {
+            line.push_str(sep);
+        }
+    }
+
+    line
+}
+
+/// An entry to be shown in the pager
+pub struct Page {
+    title: String,
+    content: String,
+}
+
+impl Page {
+    pub fn new(title: String, content: String) -> Page {
+        Page {
+            title: title,
+            content: content,
+        }
+    }
+
+    fn lines(&self, width: usize) -> Vec<String> {
+        self.content
+            .lines()
+            .flat_map(|line| {
+                let wrapped = ::textwrap::wrap(line, width);
+                if wrapped.is_empty() {
+                    vec![String::new()]
+                } else {
+                    wrapped.into_iter().map(|l| l.into_owned()).collect()
+                }
+            })
+            .collect()
+    }
+
+    /// The page with a header line
+    fn render(&self, width: usize) -> String {
+        format!("{}\n{}\n", header_line("-", &self.title, width), self.content)
+    }
+}
+
+/// Shows the pages from an iterator
+pub struct Pager<I>
+    where I: Iterator<Item = Page>
+{
+    source: I,
+
+    /// The pages which were taken from `source` so far
+    pages: Vec<Page>,
+    exhausted: bool,
+}
+
+impl<I> Pager<I>
+    where I: Iterator<Item = Page>
+{
+
+    pub fn new(source: I) -> Pager<I> {
+        Pager {
+            source: source,
+            pages: vec![],
+            exhausted: false,
+        }
+    }
+
+    /// Show the pages with the `$PAGER`, or with the built-in pager if it is not set
+    pub fn show(self) -> Result<()> {
+        match ::std::env::var("PAGER") {
+            Ok(ref command) if !command.trim().is_empty() => self.show_with(command),
+            _                                              => self.show_builtin(),
+        }
+    }
+
+    /// Take pages from the source until page `n` is available
+    ///
+    /// Returns whether there is a page `n`.
+    fn load(&mut self, n: usize) -> bool {
+        while self.pages.len() <= n && !self.exhausted {
+            match self.source.next() {
+                Some(page) => self.pages.push(page),
+                None       => self.exhausted = true,
+            }
+        }
+
+        self.pages.len() > n
+    }
+
+    fn show_with(mut self, command: &str) -> Result<()> {
+        debug!("Calling pager: {}", command);
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .context(format_err!("Failed to call pager: {}", command))?;
+
+        {
+            let stdin = child.stdin
+                .as_mut()
+                .ok_or_else(|| Error::from(err_msg("Cannot write to pager")))?;
+
+            let width = terminal_width().unwrap_or(80);
+            for (n, page) in self.source.by_ref().enumerate() {
+                let text = if n == 0 {
+                    page.render(width)
+                } else {
+                    format!("\n{}", page.render(width))
+                };
+
+                if let Err(e) = stdin.write_all(text.as_bytes()) {
+                    // The user might have quit the pager before reading everything
+                    debug!("Writing to pager failed: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        let status = child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format_err!("Pager exited with {}", status))
+        }
+    }
+
+    fn show_builtin(mut self) -> Result<()> {
+        if !self.load(0) {
+            return Ok(())
+        }
+
+        // The keys are read from the terminal directly, as stdin might be the list of ids
+        let tty       = ::termion::get_tty().context(err_msg("Cannot open terminal"))?;
+        let (w, h)    = ::termion::terminal_size()?;
+        let width     = w as usize;
+        let mut lines = vec![self.pages[0].lines(width)];
+        let mut state = PagerState::new(vec![lines[0].len()], (h as usize).saturating_sub(2));
+
+        let stdout     = ::std::io::stdout();
+        let mut screen = AlternateScreen::from(stdout.lock().into_raw_mode()?);
+        write!(screen, "{}", ::termion::cursor::Hide)?;
+
+        let mut keys = tty.keys();
+        loop {
+            self.draw(&mut screen, &state, &lines[state.page], width)?;
+
+            match keys.next() {
+                Some(Ok(key)) => {
+                    // The next page is only rendered when the user navigates to it
+                    let next = state.page + 1;
+                    if is_next_page_key(&key) && next == lines.len() && self.load(next) {
+                        let page_lines = self.pages[next].lines(width);
+                        state.add_page(page_lines.len());
+                        lines.push(page_lines);
+                    }
+
+                    if !state.handle(key) {
+                        break
+                    }
+                },
+                Some(Err(e)) => {
+                    write!(screen, "{}", ::termion::cursor::Show)?;
+                    return Err(Error::from(e))
+                },
+                None => break,
+            }
+        }
+
+        write!(screen, "{}", ::termion::cursor::Show)?;
+        screen.flush().map_err(Error::from)
+    }
+
+    fn draw<W: Write>(&self, screen: &mut W, state: &PagerState, lines: &[String], width: usize) -> Result<()> {
+        use termion::clear;
+        use termion::cursor::Goto;
+        use termion::style;
+
+        // The number of pages is only known after all were taken from the source
+        let count = if self.exhausted {
+            self.pages.len().to_string()
+        } else {
+            format!("{}+", self.pages.len())
+        };
+
+        let title = format!("[{}/{}] {}", state.page + 1, count, self.pages[state.page].title);
+        write!(screen, "{}{}{}{}{}", clear::All, Goto(1, 1), style::Invert, truncate(&title, width), style::Reset)?;
+
+        for (n, line) in lines.iter().skip(state.offset).take(state.height).enumerate() {
+            write!(screen, "{}{}", Goto(1, n as u16 + 2), truncate(line, width))?;
+        }
+
+        let status = format!("{}  ({}/{} lines)",
+                             HELP,
+                             ::std::cmp::min(state.offset + state.height, lines.len()),
+                             lines.len());
+        write!(screen, "{}{}{}{}", Goto(1, state.height as u16 + 2), style::Invert, truncate(&status, width), style::Reset)?;
+        screen.flush().map_err(Error::from)
+    }
+
+}
+
+fn is_next_page_key(key: &Key) -> bool {
+    match *key {
+        Key::Char('n') | Key::Right => true,
+        _                           => false,
+    }
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+/// Position in the pages of the built-in pager
+struct PagerState {
+    /// The number of lines of each page
+    page_lengths: Vec<usize>,
+
+    /// The number of lines which fit on the screen
+    height: usize,
+
+    page: usize,
+    offset: usize,
+}
+
+impl PagerState {
+
+    fn new(page_lengths: Vec<usize>, height: usize) -> PagerState {
+        PagerState {
+            page_lengths: page_lengths,
+            height: ::std::cmp::max(height, 1),
+            page: 0,
+            offset: 0,
+        }
+    }
+
+    /// Add a page with `length` lines
+    fn add_page(&mut self, length: usize) {
+        self.page_lengths.push(length);
+    }
+
+    fn max_offset(&self) -> usize {
+        self.page_lengths[self.page].saturating_sub(self.height)
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        self.offset = ::std::cmp::min(self.offset + n, self.max_offset());
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        self.offset = self.offset.saturating_sub(n);
+    }
+
+    fn goto_page(&mut self, page: usize) {
+        if page < self.page_lengths.len() {
+            self.page   = page;
+            self.offset = 0;
+        }
+    }
+
+    /// Handle a key, returns false if the pager should quit
+    fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => return false,
+
+            Key::Char('j') | Key::Down | Key::Char('\n') => self.scroll_down(1),
+            Key::Char('k') | Key::Up                     => self.scroll_up(1),
+            Key::Char(' ') | Key::PageDown               => {
+                let height = self.height;
+                self.scroll_down(height)
+            },
+            Key::Char('b') | Key::PageUp => {
+                let height = self.height;
+                self.scroll_up(height)
+            },
+            Key::Char('g') | Key::Home => self.offset = 0,
+            Key::Char('G') | Key::End  => self.offset = self.max_offset(),
+
+            ref key if is_next_page_key(key) => {
+                let next = self.page + 1;
+                self.goto_page(next)
+            },
+            Key::Char('p') | Key::Left => if self.page > 0 {
+                let prev = self.page - 1;
+                self.goto_page(prev)
+            },
+
+            _ => {},
+        }
+
+        true
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use termion::event::Key;
+    use super::*;
+
+    #[test]
+    fn test_header_line() {
+        assert_eq!(header_line("-", "foo", 12), "-- foo -----");
+        assert_eq!(header_line("", "foo", 12), " foo ");
+    }
+
+    #[test]
+    fn test_page_lines_are_wrapped() {
+        let page = Page::new(String::from("foo"), String::from("aaa bbb\n\nccc"));
+        assert_eq!(page.lines(4), vec!["aaa", "bbb", "", "ccc"]);
+    }
+
+    #[test]
+    fn test_pages_are_taken_when_needed() {
+        use std::cell::Cell;
+
+        let taken = Cell::new(0);
+        let pages = (0..3).map(|n| {
+            taken.set(taken.get() + 1);
+            Page::new(format!("{}", n), String::new())
+        });
+        let mut pager = Pager::new(pages);
+
+        assert!(pager.load(0));
+        assert_eq!(taken.get(), 1);
+        assert!(pager.load(0));
+        assert_eq!(taken.get(), 1);
+
+        assert!(pager.load(2));
+        assert_eq!(taken.get(), 3);
+        assert!(!pager.exhausted);
+
+        assert!(!pager.load(3));
+        assert!(pager.exhausted);
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let mut state = PagerState::new(vec![10], 4);
+        assert!(state.handle(Key::Char('j')));
+        assert_eq!(state.offset, 1);
+
+        state.handle(Key::Char(' '));
+        state.handle(Key::Char(' '));
+        assert_eq!(state.offset, 6);
+
+        state.handle(Key::Char('g'));
+        assert_eq!(state.offset, 0);
+        state.handle(Key::Char('k'));
+        assert_eq!(state.offset, 0);
+
+        assert!(!state.handle(Key::Char('q')));
+    }
+
+    #[test]
+    fn test_navigating_entries() {
+        let mut state = PagerState::new(vec![10, 2], 4);
+        state.handle(Key::Char('G'));
+        state.handle(Key::Char('n'));
+        assert_eq!((state.page, state.offset), (1, 0));
+
+        state.handle(Key::Char('n'));
+        assert_eq!(state.page, 1);
+
+        state.handle(Key::Char('p'));
+        state.handle(Key::Char('p'));
+        assert_eq!(state.page, 0);
+    }
+}
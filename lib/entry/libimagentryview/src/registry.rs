@@ -0,0 +1,275 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A registry of viewers for the kinds of entries
+//!
+//! Domain libraries provide viewers for their kind of entries (for example a contact card for
+//! contact entries), which are registered in a `ViewerRegistry`. The registry then picks the right
+//! viewer for each entry.
+
+use std::io::Write;
+use std::marker::PhantomData;
+
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+
+use libimagstore::store::Entry;
+use libimagentryutil::isa::Is;
+use libimagentryutil::isa::IsKindHeaderPathProvider;
+
+use viewer::Viewer;
+
+/// A viewer which can be put into a `ViewerRegistry`
+///
+/// `Viewer` is generic over the sink and thus cannot be used as trait object, this trait is.
+pub trait EntryViewer {
+
+    /// Check whether this viewer is responsible for viewing `e`
+    fn handles(&self, e: &Entry) -> Result<bool>;
+
+    fn view(&self, e: &Entry, sink: &mut Write) -> Result<()>;
+
+}
+
+/// A `EntryViewer` for entries of the kind `K`, as detected by `libimagentryutil::isa::Is`
+pub struct KindViewer<K, V>
+    where K: IsKindHeaderPathProvider,
+          V: Viewer
+{
+    viewer: V,
+    _kind: PhantomData<K>,
+}
+
+impl<K, V> KindViewer<K, V>
+    where K: IsKindHeaderPathProvider,
+          V: Viewer
+{
+    pub fn new(viewer: V) -> KindViewer<K, V> {
+        KindViewer {
+            viewer: viewer,
+            _kind: PhantomData,
+        }
+    }
+}
+
+impl<K, V> EntryViewer for KindViewer<K, V>
+    where K: IsKindHeaderPathProvider,
+          V: Viewer
+{
+
+    fn handles(&self, e: &Entry) -> Result<bool> {
+        kind_flag::<K>(e).map(|flag| flag.unwrap_or(false))
+    }
+
+    fn view(&self, e: &Entry, mut sink: &mut Write) -> Result<()> {
+        self.viewer.view_entry(e, &mut sink)
+    }
+
+}
+
+/// A `EntryViewer` for the entries for which the function `F` returns `true`
+///
+/// Used for kinds of entries which cannot be detected by a kind flag alone, for example because
+/// entries were created before the flag was introduced.
+pub struct PredicateViewer<F, V>
+    where F: Fn(&Entry) -> Result<bool>,
+          V: Viewer
+{
+    predicate: F,
+    viewer: V,
+}
+
+impl<F, V> PredicateViewer<F, V>
+    where F: Fn(&Entry) -> Result<bool>,
+          V: Viewer
+{
+    pub fn new(predicate: F, viewer: V) -> PredicateViewer<F, V> {
+        PredicateViewer {
+            predicate: predicate,
+            viewer: viewer,
+        }
+    }
+}
+
+impl<F, V> EntryViewer for PredicateViewer<F, V>
+    where F: Fn(&Entry) -> Result<bool>,
+          V: Viewer
+{
+
+    fn handles(&self, e: &Entry) -> Result<bool> {
+        (self.predicate)(e)
+    }
+
+    fn view(&self, e: &Entry, mut sink: &mut Write) -> Result<()> {
+        self.viewer.view_entry(e, &mut sink)
+    }
+
+}
+
+/// Read the kind flag `K` of `e`
+///
+/// Returns `None` if the entry does not have the flag at all, which is the case for entries of
+/// other kinds.
+pub fn kind_flag<K>(e: &Entry) -> Result<Option<bool>>
+    where K: IsKindHeaderPathProvider
+{
+    if e.get_header().read(K::kindflag_header_location())?.is_none() {
+        return Ok(None)
+    }
+
+    e.is::<K>().map(Some)
+}
+
+/// A list of `EntryViewer`s
+///
+/// If multiple viewers handle an entry, the one registered first is used.
+pub struct ViewerRegistry<'a> {
+    viewers: Vec<Box<EntryViewer + 'a>>,
+}
+
+impl<'a> ViewerRegistry<'a> {
+
+    pub fn new() -> ViewerRegistry<'a> {
+        ViewerRegistry {
+            viewers: vec![],
+        }
+    }
+
+    pub fn register<V>(&mut self, viewer: V) -> &mut ViewerRegistry<'a>
+        where V: EntryViewer + 'a
+    {
+        self.viewers.push(Box::new(viewer));
+        self
+    }
+
+    /// Register `viewer` for the entries of the kind `K`
+    pub fn register_kind<K, V>(&mut self, viewer: V) -> &mut ViewerRegistry<'a>
+        where K: IsKindHeaderPathProvider + 'a,
+              V: Viewer + 'a
+    {
+        self.register(KindViewer::<K, V>::new(viewer))
+    }
+
+    /// Register `viewer` for the entries for which `predicate` returns `true`
+    pub fn register_for<F, V>(&mut self, predicate: F, viewer: V) -> &mut ViewerRegistry<'a>
+        where F: Fn(&Entry) -> Result<bool> + 'a,
+              V: Viewer + 'a
+    {
+        self.register(PredicateViewer::new(predicate, viewer))
+    }
+
+    /// Get the viewer for `e`, if any
+    pub fn viewer_for(&self, e: &Entry) -> Result<Option<&EntryViewer>> {
+        for viewer in self.viewers.iter() {
+            if viewer.handles(e)? {
+                return Ok(Some(viewer.as_ref()))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// View `e` with the viewer for it
+    ///
+    /// Returns `false` if no viewer handles `e`, so the caller can fall back to some other viewer.
+    pub fn view_entry(&self, e: &Entry, sink: &mut Write) -> Result<bool> {
+        match self.viewer_for(e)? {
+            Some(viewer) => viewer.view(e, sink).map(|_| true),
+            None         => Ok(false),
+        }
+    }
+
+}
+
+impl<'a> Default for ViewerRegistry<'a> {
+    fn default() -> ViewerRegistry<'a> {
+        ViewerRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::io::Write;
+
+    use failure::Fallible as Result;
+
+    use libimagstore::store::Entry;
+    use libimagstore::storeid::IntoStoreId;
+    use libimagentryutil::isa::Is;
+    use libimagentryutil::isa::IsKindHeaderPathProvider;
+
+    use viewer::Viewer;
+    use super::ViewerRegistry;
+
+    provide_kindflag_path!(IsTestKind, "test.is_test");
+
+    struct TestViewer;
+
+    impl Viewer for TestViewer {
+        fn view_entry<W>(&self, _: &Entry, sink: &mut W) -> Result<()>
+            where W: Write
+        {
+            write!(sink, "test kind").map_err(From::from)
+        }
+    }
+
+    fn entry(name: &str) -> Entry {
+        Entry::new(PathBuf::from(name).into_storeid().unwrap())
+    }
+
+    #[test]
+    fn test_registry_picks_kind_viewer() {
+        let mut registry = ViewerRegistry::new();
+        registry.register_kind::<IsTestKind, _>(TestViewer);
+
+        let mut e = entry("kind");
+        e.set_isflag::<IsTestKind>().unwrap();
+
+        let mut out = vec![];
+        assert!(registry.view_entry(&e, &mut out).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), "test kind");
+    }
+
+    #[test]
+    fn test_registry_picks_predicate_viewer() {
+        let mut registry = ViewerRegistry::new();
+        registry.register_for(|e: &Entry| Ok(e.get_content() == "legacy"), TestViewer);
+
+        let mut e = entry("legacy");
+        *e.get_content_mut() = String::from("legacy");
+
+        let mut out = vec![];
+        assert!(registry.view_entry(&e, &mut out).unwrap());
+        assert_eq!(String::from_utf8(out).unwrap(), "test kind");
+
+        let mut out = vec![];
+        assert!(!registry.view_entry(&entry("other"), &mut out).unwrap());
+    }
+
+    #[test]
+    fn test_registry_ignores_other_entries() {
+        let mut registry = ViewerRegistry::new();
+        registry.register_kind::<IsTestKind, _>(TestViewer);
+
+        let mut out = vec![];
+        assert!(!registry.view_entry(&entry("other"), &mut out).unwrap());
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Configuration of the full-text index
+//!
+//! The `grep.index` table of the imag configuration configures which header fields are indexed
+//! besides the content, and where the index is stored:
+//!
+//! ```toml
+//! [grep.index]
+//! header_fields = ["note.name", "habit.template.name"]
+//! path = "/home/user/.imag/index/fulltext.json"
+//! ```
+
+use std::path::PathBuf;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+use failure::Error;
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    header_fields: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl Config {
+
+    pub fn new(header_fields: Vec<String>) -> Self {
+        Config {
+            header_fields: header_fields,
+            path: None,
+        }
+    }
+
+    /// Read the `grep.index` table of the imag configuration
+    ///
+    /// A missing table results in the default configuration.
+    pub fn from_config(config: &Value) -> Result<Config> {
+        let header_fields = match config.read("grep.index.header_fields").map_err(Error::from)? {
+            None => vec![],
+            Some(&Value::Array(ref fields)) => fields
+                .iter()
+                .map(|f| match *f {
+                    Value::String(ref s) => Ok(s.clone()),
+                    _ => Err(format_err!("Type error: 'grep.index.header_fields' must contain strings")),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => return Err(format_err!("Type error: 'grep.index.header_fields' must be an array")),
+        };
+
+        let path = match config.read("grep.index.path").map_err(Error::from)? {
+            None                        => None,
+            Some(&Value::String(ref s)) => Some(PathBuf::from(s)),
+            Some(_) => return Err(format_err!("Type error: 'grep.index.path' must be a string")),
+        };
+
+        Ok(Config {
+            header_fields: header_fields,
+            path: path,
+        })
+    }
+
+    /// The header fields which are indexed besides the content
+    pub fn header_fields(&self) -> &[String] {
+        &self.header_fields
+    }
+
+    /// The configured path of the index file, if any
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml::de::from_str;
+
+    #[test]
+    fn test_from_config() {
+        let config : Value = from_str(r#"
+            [grep.index]
+            header_fields = ["note.name"]
+            path = "/tmp/index.json"
+        "#).unwrap();
+
+        let config = Config::from_config(&config).unwrap();
+        assert_eq!(config.header_fields(), &[String::from("note.name")]);
+        assert_eq!(config.path(), Some(&PathBuf::from("/tmp/index.json")));
+    }
+
+    #[test]
+    fn test_from_empty_config() {
+        let config : Value = from_str("").unwrap();
+        let config = Config::from_config(&config).unwrap();
+        assert!(config.header_fields().is_empty());
+        assert!(config.path().is_none());
+    }
+
+    #[test]
+    fn test_from_config_type_error() {
+        let config : Value = from_str("[grep.index]\nheader_fields = \"note.name\"").unwrap();
+        assert!(Config::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,647 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The inverted index
+//!
+//! The index is lazy: writing an entry to the store does not update it. `Index::update()` brings
+//! it up to date with the store, which imag-grep does before each search.
+//!
+//! The index maps each term to the entries it occurs in, with the positions of the term in the
+//! content and the number of occurrences in the indexed header fields. For each entry, the
+//! modification time of its file and a fingerprint of its text is stored, so `Index::update()`
+//! neither has to read the entries whose file was not modified nor re-index the entries whose text
+//! did not change since the last update.
+//!
+//! The header fields the index was built with are part of the index and of the fingerprints, so
+//! changing `grep.index.header_fields` re-indexes all entries.
+//!
+//! Results are ranked with BM25. Matches in header fields weigh more than matches in the content.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::fs::create_dir_all;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+use failure::Error;
+use failure::ResultExt;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use config::Config;
+use query::Clause;
+use query::edit_distance;
+use tokenizer::terms;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// How much more a match in a header field counts than a match in the content
+const HEADER_WEIGHT: f64 = 2.0;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    documents: BTreeMap<String, Document>,
+    terms: BTreeMap<String, BTreeMap<String, Postings>>,
+
+    /// The header fields the documents were indexed with
+    #[serde(default)]
+    header_fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    fingerprint: String,
+
+    /// The modification time of the file of the entry when it was indexed, if known
+    #[serde(default)]
+    modified: Option<(u64, u32)>,
+
+    /// Number of terms in the content
+    length: usize,
+
+    /// The distinct terms of the document, for removing it from the index
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Postings {
+    content: Vec<usize>,
+    header: usize,
+}
+
+/// What `Index::update()` did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// A search result
+#[derive(Debug)]
+pub struct Hit {
+    /// The id of the entry, relative to the store
+    pub id: String,
+    pub score: f64,
+
+    /// The positions of the matched terms in the content, as passed to `snippet::snippet()`
+    pub positions: BTreeSet<usize>,
+}
+
+impl Index {
+
+    /// Load the index from a file. A missing file results in an empty index.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Index> {
+        let path = path.as_ref();
+        if !path.exists() {
+            debug!("No index at {}, starting with an empty one", path.display());
+            return Ok(Index::default())
+        }
+
+        File::open(path)
+            .map_err(Error::from)
+            .and_then(|f| ::serde_json::from_reader(f).map_err(Error::from))
+            .context(format_err!("Failed to load the index from {}", path.display()))
+            .map_err(Error::from)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            let _ = create_dir_all(parent)?;
+        }
+
+        File::create(path)
+            .map_err(Error::from)
+            .and_then(|f| ::serde_json::to_writer(f, self).map_err(Error::from))
+            .context(format_err!("Failed to save the index to {}", path.display()))
+            .map_err(Error::from)
+    }
+
+    /// Bring the index up to date with the store
+    ///
+    /// Entries whose file was not modified since they were indexed are not read, entries which did
+    /// not change are not re-indexed, entries which do not exist anymore are removed from the
+    /// index. If the indexed header fields changed, all entries are re-indexed.
+    pub fn update(&mut self, store: &Store, config: &Config) -> Result<UpdateStats> {
+        let mut stats = UpdateStats::default();
+        let mut seen  = BTreeSet::new();
+        let same_fields = self.header_fields.as_slice() == config.header_fields();
+
+        for id in store.entries()? {
+            let id  = id?;
+            let key = id_key(&id)?;
+            let _   = seen.insert(key.clone());
+
+            let modified = modification_time(store, &id);
+            let not_modified = same_fields
+                && modified.is_some()
+                && self.documents.get(&key).map(|d| d.modified == modified).unwrap_or(false);
+
+            if not_modified {
+                trace!("Not modified: {}", key);
+                stats.unchanged += 1;
+                continue
+            }
+
+            let entry = match store.get(id.clone())? {
+                Some(entry) => entry,
+                None        => continue,
+            };
+
+            match self.documents.get(&key).map(|d| d.fingerprint == fingerprint(&entry, config)) {
+                Some(true)  => stats.unchanged += 1,
+                Some(false) => stats.updated += 1,
+                None        => stats.added += 1,
+            }
+
+            let _ = self.index_entry(&entry, config)?;
+            if let Some(doc) = self.documents.get_mut(&key) {
+                doc.modified = modified;
+            }
+        }
+
+        self.header_fields = config.header_fields().to_vec();
+
+        let removed = self
+            .documents
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in removed {
+            self.remove(&key);
+            stats.removed += 1;
+        }
+
+        Ok(stats)
+    }
+
+    /// Index a single entry
+    ///
+    /// Returns false if the entry did not change since it was indexed.
+    pub fn index_entry(&mut self, entry: &Entry, config: &Config) -> Result<bool> {
+        let key         = id_key(entry.get_location())?;
+        let fingerprint = fingerprint(entry, config);
+
+        if self.documents.get(&key).map(|d| d.fingerprint == fingerprint).unwrap_or(false) {
+            return Ok(false)
+        }
+
+        trace!("Indexing {}", key);
+        self.remove(&key);
+
+        let mut postings : BTreeMap<String, Postings> = BTreeMap::new();
+        let content = terms(entry.get_content());
+        let length  = content.len();
+
+        for (pos, term) in content.into_iter().enumerate() {
+            postings.entry(term).or_insert_with(Postings::default).content.push(pos);
+        }
+
+        for field in config.header_fields() {
+            for text in header_texts(entry.get_header(), field)? {
+                for term in terms(&text) {
+                    postings.entry(term).or_insert_with(Postings::default).header += 1;
+                }
+            }
+        }
+
+        let doc_terms = postings.keys().cloned().collect();
+        for (term, p) in postings {
+            let _ = self.terms.entry(term).or_insert_with(BTreeMap::new).insert(key.clone(), p);
+        }
+
+        let _ = self.documents.insert(key, Document {
+            fingerprint: fingerprint,
+            modified: None,
+            length: length,
+            terms: doc_terms,
+        });
+
+        Ok(true)
+    }
+
+    /// Remove an entry from the index, by its id relative to the store
+    pub fn remove(&mut self, key: &str) {
+        if let Some(doc) = self.documents.remove(key) {
+            for term in doc.terms {
+                let now_empty = match self.terms.get_mut(&term) {
+                    Some(docs) => {
+                        let _ = docs.remove(key);
+                        docs.is_empty()
+                    },
+                    None => false,
+                };
+
+                if now_empty {
+                    let _ = self.terms.remove(&term);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Search the index
+    ///
+    /// All clauses have to match. If collections are passed, only entries in these collections
+    /// are searched. The hits are sorted by score, best first.
+    pub fn search(&self, clauses: &[Clause], collections: Option<&[&str]>) -> Vec<Hit> {
+        let in_scope = |key: &str| collections
+            .map(|cs| cs.iter().any(|c| Path::new(key).starts_with(c)))
+            .unwrap_or(true);
+
+        let mut hits : Option<BTreeMap<&str, Hit>> = None;
+        let avg_length = self.avg_length();
+
+        for clause in clauses {
+            let matches = self.match_clause(clause, avg_length);
+
+            hits = Some(match hits {
+                None => matches
+                    .into_iter()
+                    .filter(|&(key, _)| in_scope(key))
+                    .map(|(key, m)| (key, Hit {
+                        id: String::from(key),
+                        score: m.score,
+                        positions: m.positions,
+                    }))
+                    .collect(),
+
+                Some(hits) => hits
+                    .into_iter()
+                    .filter_map(|(key, mut hit)| matches.get(key).map(|m| {
+                        hit.score += m.score;
+                        hit.positions.extend(m.positions.iter().cloned());
+                        (key, hit)
+                    }))
+                    .collect(),
+            });
+        }
+
+        let mut hits = hits
+            .map(|h| h.into_iter().map(|(_, hit)| hit).collect::<Vec<_>>())
+            .unwrap_or_else(Vec::new);
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(::std::cmp::Ordering::Equal)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        hits
+    }
+
+    fn match_clause(&self, clause: &Clause, avg_length: f64) -> BTreeMap<&str, Match> {
+        match *clause {
+            Clause::Term(ref term) => self.match_term(term, 1.0, avg_length),
+
+            Clause::Fuzzy(ref term, distance) => {
+                let mut result : BTreeMap<&str, Match> = BTreeMap::new();
+
+                for candidate in self.terms.keys() {
+                    let d = edit_distance(term, candidate);
+                    if d > distance {
+                        continue
+                    }
+
+                    // Exact matches weigh more than near matches
+                    for (key, m) in self.match_term(candidate, 1.0 / (1.0 + d as f64), avg_length) {
+                        let entry = result.entry(key).or_insert_with(Match::default);
+                        entry.score = entry.score.max(m.score);
+                        entry.positions.extend(m.positions);
+                    }
+                }
+
+                result
+            },
+
+            Clause::Phrase(ref phrase) => self.match_phrase(phrase, avg_length),
+        }
+    }
+
+    fn match_term(&self, term: &str, weight: f64, avg_length: f64) -> BTreeMap<&str, Match> {
+        let docs = match self.terms.get(term) {
+            Some(docs) => docs,
+            None       => return BTreeMap::new(),
+        };
+
+        let idf = self.idf(docs.len());
+        docs.iter()
+            .map(|(key, p)| {
+                let score = self.bm25(key, p.content.len(), idf, avg_length)
+                    + HEADER_WEIGHT * idf * p.header as f64;
+
+                (key.as_str(), Match {
+                    score: weight * score,
+                    positions: p.content.iter().cloned().collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn match_phrase(&self, phrase: &[String], avg_length: f64) -> BTreeMap<&str, Match> {
+        if phrase.is_empty() {
+            return BTreeMap::new()
+        }
+
+        let postings = phrase
+            .iter()
+            .map(|t| self.terms.get(t))
+            .collect::<Option<Vec<_>>>();
+
+        let postings = match postings {
+            Some(p) => p,
+            None    => return BTreeMap::new(),
+        };
+
+        let idf : f64 = postings.iter().map(|docs| self.idf(docs.len())).sum();
+        let mut result = BTreeMap::new();
+
+        for (key, first) in postings[0].iter() {
+            let mut positions = BTreeSet::new();
+            let mut count     = 0;
+
+            'starts: for start in first.content.iter() {
+                for (offset, docs) in postings.iter().enumerate().skip(1) {
+                    let found = docs
+                        .get(key)
+                        .map(|p| p.content.binary_search(&(start + offset)).is_ok())
+                        .unwrap_or(false);
+
+                    if !found {
+                        continue 'starts;
+                    }
+                }
+
+                count += 1;
+                positions.extend(*start..(start + phrase.len()));
+            }
+
+            if count > 0 {
+                let _ = result.insert(key.as_str(), Match {
+                    score: self.bm25(key, count, idf, avg_length),
+                    positions: positions,
+                });
+            }
+        }
+
+        result
+    }
+
+    fn idf(&self, doc_freq: usize) -> f64 {
+        let n  = self.documents.len() as f64;
+        let df = doc_freq as f64;
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    /// The average number of terms in the content of the documents, for `bm25()`
+    fn avg_length(&self) -> f64 {
+        if self.documents.is_empty() {
+            1.0
+        } else {
+            let sum : usize = self.documents.values().map(|d| d.length).sum();
+            (sum as f64 / self.documents.len() as f64).max(1.0)
+        }
+    }
+
+    fn bm25(&self, key: &str, term_freq: usize, idf: f64, avg_length: f64) -> f64 {
+        if term_freq == 0 {
+            return 0.0
+        }
+
+        let length = self.documents.get(key).map(|d| d.length).unwrap_or(0) as f64;
+        let tf     = term_freq as f64;
+
+        idf * (tf * (BM25_K1 + 1.0)) / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * length / avg_length))
+    }
+
+}
+
+#[derive(Debug, Default)]
+struct Match {
+    score: f64,
+    positions: BTreeSet<usize>,
+}
+
+fn id_key(id: &StoreId) -> Result<String> {
+    id.local()
+        .to_str()
+        .map(String::from)
+        .ok_or_else(|| format_err!("Id is not valid UTF-8: {:?}", id))
+}
+
+/// The modification time of the file of an entry, if the store is on the filesystem
+fn modification_time(store: &Store, id: &StoreId) -> Option<(u64, u32)> {
+    store.path()
+        .join(id.local())
+        .metadata()
+        .and_then(|md| md.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+}
+
+/// A fingerprint of the header and content of an entry and the indexed header fields
+///
+/// Uses the hasher of the standard library, so the fingerprints may change with the Rust version,
+/// which only results in a complete re-index.
+fn fingerprint(entry: &Entry, config: &Config) -> String {
+    let mut hasher = DefaultHasher::new();
+    config.header_fields().hash(&mut hasher);
+    format!("{}", entry.get_header()).hash(&mut hasher);
+    entry.get_content().hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// The texts in a header field: a string, or the strings in an array
+fn header_texts(header: &Value, field: &str) -> Result<Vec<String>> {
+    Ok(match header.read(field).map_err(Error::from)? {
+        Some(&Value::String(ref s)) => vec![s.clone()],
+        Some(&Value::Array(ref a))  => a.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+        _                           => vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use toml_query::insert::TomlValueInsertExt;
+
+    use super::*;
+    use query::parse;
+
+    use libimagstore::store::Store;
+
+    fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn create(store: &Store, id: &str, content: &str) {
+        let mut entry = store.create(PathBuf::from(id)).unwrap();
+        *entry.get_content_mut() = String::from(content);
+    }
+
+    fn ids(hits: Vec<Hit>) -> Vec<String> {
+        hits.into_iter().map(|h| h.id).collect()
+    }
+
+    #[test]
+    fn test_update_incrementally() {
+        let store  = get_store();
+        let config = Config::default();
+        let mut index = Index::default();
+
+        create(&store, "notes/a", "foo bar");
+        create(&store, "notes/b", "baz");
+
+        let stats = index.update(&store, &config).unwrap();
+        assert_eq!(stats, UpdateStats { added: 2, updated: 0, removed: 0, unchanged: 0 });
+
+        {
+            let mut b = store.get(PathBuf::from("notes/b")).unwrap().unwrap();
+            *b.get_content_mut() = String::from("foo");
+        }
+        store.delete(PathBuf::from("notes/a")).unwrap();
+        create(&store, "notes/c", "quux");
+
+        let stats = index.update(&store, &config).unwrap();
+        assert_eq!(stats, UpdateStats { added: 1, updated: 1, removed: 1, unchanged: 0 });
+        assert_eq!(index.len(), 2);
+
+        let hits = index.search(&parse("foo").unwrap(), None);
+        assert_eq!(ids(hits), vec!["notes/b"]);
+        assert!(index.search(&parse("bar").unwrap(), None).is_empty());
+    }
+
+    #[test]
+    fn test_ranking() {
+        let store  = get_store();
+        let mut index = Index::default();
+
+        create(&store, "notes/a", "foo bar baz quux");
+        create(&store, "notes/b", "foo foo foo foo");
+        create(&store, "notes/c", "bar");
+
+        let _ = index.update(&store, &Config::default()).unwrap();
+        let hits = index.search(&parse("foo").unwrap(), None);
+        assert_eq!(ids(hits), vec!["notes/b", "notes/a"]);
+    }
+
+    #[test]
+    fn test_phrase() {
+        let store  = get_store();
+        let mut index = Index::default();
+
+        create(&store, "notes/a", "the quick brown fox");
+        create(&store, "notes/b", "brown quick");
+
+        let _ = index.update(&store, &Config::default()).unwrap();
+        let hits = index.search(&parse("\"quick brown\"").unwrap(), None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "notes/a");
+        assert_eq!(hits[0].positions.iter().cloned().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_fuzzy_and_all_clauses() {
+        let store  = get_store();
+        let mut index = Index::default();
+
+        create(&store, "notes/a", "meeting with alice");
+        create(&store, "notes/b", "meting with bob");
+        create(&store, "notes/c", "lunch with alice");
+
+        let _ = index.update(&store, &Config::default()).unwrap();
+        let hits = index.search(&parse("meeting~").unwrap(), None);
+        assert_eq!(ids(hits), vec!["notes/a", "notes/b"]);
+
+        let hits = index.search(&parse("meeting~ alice").unwrap(), None);
+        assert_eq!(ids(hits), vec!["notes/a"]);
+    }
+
+    #[test]
+    fn test_collections_and_header_fields() {
+        let store  = get_store();
+        let mut index = Index::default();
+        let config = Config::new(vec![String::from("note.name")]);
+
+        create(&store, "notes/a", "nothing");
+        create(&store, "diary/b", "nothing");
+        {
+            let mut a = store.get(PathBuf::from("notes/a")).unwrap().unwrap();
+            a.get_header_mut()
+                .insert("note.name", Value::String(String::from("Shopping list")))
+                .unwrap();
+        }
+
+        let _ = index.update(&store, &config).unwrap();
+        assert_eq!(ids(index.search(&parse("shopping").unwrap(), None)), vec!["notes/a"]);
+        assert_eq!(index.search(&parse("nothing").unwrap(), Some(&["diary"][..])).len(), 1);
+    }
+
+    #[test]
+    fn test_changed_header_fields_reindex() {
+        let store  = get_store();
+        let mut index = Index::default();
+
+        create(&store, "notes/a", "nothing");
+        {
+            let mut a = store.get(PathBuf::from("notes/a")).unwrap().unwrap();
+            a.get_header_mut()
+                .insert("note.name", Value::String(String::from("Shopping list")))
+                .unwrap();
+        }
+
+        let _ = index.update(&store, &Config::default()).unwrap();
+        assert!(index.search(&parse("shopping").unwrap(), None).is_empty());
+
+        let config = Config::new(vec![String::from("note.name")]);
+        let stats  = index.update(&store, &config).unwrap();
+        assert_eq!(stats, UpdateStats { added: 0, updated: 1, removed: 0, unchanged: 0 });
+        assert_eq!(ids(index.search(&parse("shopping").unwrap(), None)), vec!["notes/a"]);
+
+        let stats = index.update(&store, &config).unwrap();
+        assert_eq!(stats, UpdateStats { added: 0, updated: 0, removed: 0, unchanged: 1 });
+    }
+}
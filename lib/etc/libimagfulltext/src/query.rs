@@ -0,0 +1,175 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Search queries
+//!
+//! A query is a list of clauses, separated by whitespace. All clauses have to match:
+//!
+//! * `word` matches entries containing the term
+//! * `"some words"` matches entries containing the terms in exactly this order
+//! * `word~` and `word~2` match entries containing a term with an edit distance of at most 1
+//!   (or 2) to the word
+
+use failure::Fallible as Result;
+
+/// The default edit distance for `word~`
+pub const DEFAULT_FUZZY_DISTANCE: usize = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Clause {
+    Term(String),
+    Phrase(Vec<String>),
+    Fuzzy(String, usize),
+}
+
+/// Parse a search query
+pub fn parse(s: &str) -> Result<Vec<Clause>> {
+    let mut clauses = vec![];
+    let mut chars   = s.chars().peekable();
+
+    loop {
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            let _ = chars.next();
+        }
+
+        match chars.peek().cloned() {
+            None      => break,
+            Some('"') => {
+                let _ = chars.next();
+                let phrase = chars.by_ref().take_while(|c| *c != '"').collect::<String>();
+                push_terms(&mut clauses, ::tokenizer::terms(&phrase));
+            },
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = chars.peek().cloned() {
+                    if c.is_whitespace() || c == '"' {
+                        break
+                    }
+                    word.push(c);
+                    let _ = chars.next();
+                }
+
+                clauses.push(parse_word(&word)?);
+            },
+        }
+    }
+
+    let clauses = clauses.into_iter().filter(|c| *c != Clause::Phrase(vec![])).collect::<Vec<_>>();
+
+    if clauses.is_empty() {
+        Err(format_err!("Empty search query: '{}'", s))
+    } else {
+        Ok(clauses)
+    }
+}
+
+fn parse_word(word: &str) -> Result<Clause> {
+    if let Some(pos) = word.rfind('~') {
+        let distance = match &word[pos + 1..] {
+            ""   => DEFAULT_FUZZY_DISTANCE,
+            dist => dist
+                .parse::<usize>()
+                .map_err(|_| format_err!("Invalid edit distance in '{}'", word))?,
+        };
+
+        let mut terms = ::tokenizer::terms(&word[..pos]);
+        return match terms.len() {
+            1 => Ok(Clause::Fuzzy(terms.remove(0), distance)),
+            _ => Err(format_err!("Fuzzy search needs exactly one term: '{}'", word)),
+        }
+    }
+
+    let mut clauses = vec![];
+    push_terms(&mut clauses, ::tokenizer::terms(word));
+    Ok(clauses.pop().unwrap_or_else(|| Clause::Phrase(vec![])))
+}
+
+/// A single term is a term clause, multiple terms (like "foo-bar") are a phrase
+fn push_terms(clauses: &mut Vec<Clause>, mut terms: Vec<String>) {
+    match terms.len() {
+        0 => {},
+        1 => clauses.push(Clause::Term(terms.remove(0))),
+        _ => clauses.push(Clause::Phrase(terms)),
+    }
+}
+
+/// The Levenshtein distance between two terms
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b : Vec<char> = b.chars().collect();
+    let mut prev : Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let v = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+            cur.push(v);
+        }
+        prev = cur;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(s: &str) -> String {
+        String::from(s)
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(parse("Foo bar").unwrap(), vec![Clause::Term(s("foo")), Clause::Term(s("bar"))]);
+    }
+
+    #[test]
+    fn test_parse_phrase() {
+        assert_eq!(parse("foo \"Bar baz\"").unwrap(), vec![
+            Clause::Term(s("foo")),
+            Clause::Phrase(vec![s("bar"), s("baz")]),
+        ]);
+        assert_eq!(parse("foo-bar").unwrap(), vec![Clause::Phrase(vec![s("foo"), s("bar")])]);
+    }
+
+    #[test]
+    fn test_parse_fuzzy() {
+        assert_eq!(parse("foo~ bar~2").unwrap(), vec![
+            Clause::Fuzzy(s("foo"), 1),
+            Clause::Fuzzy(s("bar"), 2),
+        ]);
+        assert!(parse("foo~x").is_err());
+        assert!(parse("foo-bar~").is_err());
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(parse("").is_err());
+        assert!(parse("  \"\" ").is_err());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("foo", "foo"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("straße", "strasse"), 2);
+    }
+}
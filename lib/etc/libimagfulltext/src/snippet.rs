@@ -0,0 +1,101 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Snippets of the content around the matches of a search
+
+use std::collections::BTreeSet;
+
+use tokenizer::tokenize;
+
+/// Build a snippet of the content around the first matched term
+///
+/// `positions` are the term positions of the matches (see `index::Hit`), `context` is the number
+/// of terms shown before and after the first match. Matched terms are passed through `highlight`.
+/// Line breaks are replaced by spaces, so the snippet fits on one line.
+///
+/// Returns `None` if there is no match in the content (for example if only header fields
+/// matched).
+pub fn snippet<F>(content: &str, positions: &BTreeSet<usize>, context: usize, highlight: F)
+    -> Option<String>
+    where F: Fn(&str) -> String
+{
+    let tokens = tokenize(content);
+    let first  = positions.iter().cloned().find(|p| *p < tokens.len())?;
+    let start  = first.saturating_sub(context);
+    let end    = (first + context).min(tokens.len() - 1);
+
+    let mut result = String::new();
+    if start > 0 {
+        result.push_str("...");
+    }
+
+    let mut last_end = tokens[start].start;
+    for pos in start..(end + 1) {
+        let token = &tokens[pos];
+        result.push_str(&content[last_end..token.start]);
+
+        let text = &content[token.start..token.end];
+        if positions.contains(&pos) {
+            result.push_str(&highlight(text));
+        } else {
+            result.push_str(text);
+        }
+
+        last_end = token.end;
+    }
+
+    if end + 1 < tokens.len() {
+        result.push_str("...");
+    }
+
+    Some(result.replace(|c| c == '\n' || c == '\r', " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(ps: &[usize]) -> BTreeSet<usize> {
+        ps.iter().cloned().collect()
+    }
+
+    fn mark(s: &str) -> String {
+        format!("[{}]", s)
+    }
+
+    #[test]
+    fn test_snippet() {
+        let content = "one two three\nfour, five six seven";
+        let s = snippet(content, &positions(&[3]), 2, mark);
+        assert_eq!(s, Some(String::from("...two three [four], five six...")));
+    }
+
+    #[test]
+    fn test_snippet_at_borders() {
+        let content = "one two three";
+        assert_eq!(snippet(content, &positions(&[0, 2]), 5, mark),
+                   Some(String::from("[one] two [three]")));
+    }
+
+    #[test]
+    fn test_snippet_without_match() {
+        assert_eq!(snippet("one two", &positions(&[]), 2, mark), None);
+        assert_eq!(snippet("one two", &positions(&[7]), 2, mark), None);
+    }
+}
@@ -0,0 +1,93 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Splitting text into terms
+
+/// A term in a text, with its byte offsets
+#[derive(Debug, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split a text into lowercased terms
+///
+/// A term is a run of alphanumeric characters, everything else separates terms.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut start  = None;
+
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None)     => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push(token(text, s, i));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+
+    if let Some(s) = start {
+        tokens.push(token(text, s, text.len()));
+    }
+
+    tokens
+}
+
+/// Split a text into lowercased terms, without offsets
+pub fn terms(text: &str) -> Vec<String> {
+    tokenize(text).into_iter().map(|t| t.term).collect()
+}
+
+fn token(text: &str, start: usize, end: usize) -> Token {
+    Token {
+        term: text[start..end].to_lowercase(),
+        start: start,
+        end: end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("Hello, World! foo-bar");
+        assert_eq!(tokens, vec![
+            Token { term: String::from("hello"), start: 0,  end: 5 },
+            Token { term: String::from("world"), start: 7,  end: 12 },
+            Token { term: String::from("foo"),   start: 14, end: 17 },
+            Token { term: String::from("bar"),   start: 18, end: 21 },
+        ]);
+    }
+
+    #[test]
+    fn test_tokenize_unicode() {
+        assert_eq!(terms("Über straße"), vec!["über", "straße"]);
+    }
+
+    #[test]
+    fn test_tokenize_empty() {
+        assert!(tokenize("").is_empty());
+        assert!(tokenize(" ,.- ").is_empty());
+    }
+}
@@ -103,6 +103,28 @@ fn colorize(color: Colour, h: &Helper, output: &mut Output) -> Result<(), Render
     Ok(())
 }
 
+/// Get the `Colour` for the name of one of the color helpers ("black", "blue", ...)
+pub fn colour_from_name(name: &str) -> Option<Colour> {
+    match name {
+        "black"  => Some(Colour::Black),
+        "blue"   => Some(Colour::Blue),
+        "cyan"   => Some(Colour::Cyan),
+        "green"  => Some(Colour::Green),
+        "purple" => Some(Colour::Purple),
+        "red"    => Some(Colour::Red),
+        "white"  => Some(Colour::White),
+        "yellow" => Some(Colour::Yellow),
+        _        => None,
+    }
+}
+
+/// validator which can be used by clap to validate that a string is a valid color name
+pub fn is_colour_name(s: String) -> Result<(), String> {
+    colour_from_name(&s)
+        .map(|_| ())
+        .ok_or_else(|| format!("The string '{}' is not a valid color", s))
+}
+
 #[derive(Clone, Copy)]
 pub struct UnderlineHelper;
 
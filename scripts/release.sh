@@ -48,6 +48,7 @@ CRATES=(
     ./bin/domain/imag-todo
     ./bin/domain/imag-log
     ./bin/domain/imag-wiki
+    ./bin/domain/imag-remind
     ./bin/core/imag-ref
     ./bin/core/imag-gps
     ./bin/core/imag-diagnostics
@@ -60,6 +61,9 @@ CRATES=(
     ./bin/core/imag-view
     ./bin/core/imag-init
     ./bin/core/imag-edit
+    ./bin/core/imag-export
+    ./bin/core/imag-header
+    ./bin/core/imag-tui
     ./bin/core/imag-ids
     ./bin/core/imag-git
     ./bin/core/imag-category